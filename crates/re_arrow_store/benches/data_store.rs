@@ -5,8 +5,8 @@ use arrow2::array::UnionArray;
 use criterion::{criterion_group, criterion_main, Criterion};
 
 use re_arrow_store::{
-    DataStore, DataStoreConfig, GarbageCollectionTarget, LatestAtQuery, RangeQuery, TimeInt,
-    TimeRange,
+    DataStore, DataStoreConfig, GarbageCollectionOptions, GarbageCollectionTarget, LatestAtQuery,
+    RangeQuery, TimeInt, TimeRange,
 };
 use re_log_types::{
     component_types::{InstanceKey, Rect2D},
@@ -276,7 +276,11 @@ fn gc(c: &mut Criterion) {
         let store = insert_table(Default::default(), InstanceKey::name(), &table);
         b.iter(|| {
             let mut store = store.clone();
-            let (_, stats_diff) = store.gc(GarbageCollectionTarget::DropAtLeastFraction(1.0 / 3.0));
+            let (_, stats_diff) = store.gc(GarbageCollectionOptions {
+                target: GarbageCollectionTarget::DropAtLeastFraction(1.0 / 3.0),
+                protect_latest_at: false,
+                protected_paths: Vec::new(),
+            });
             stats_diff
         });
     });
@@ -294,8 +298,11 @@ fn gc(c: &mut Criterion) {
             );
             b.iter(|| {
                 let mut store = store.clone();
-                let (_, stats_diff) =
-                    store.gc(GarbageCollectionTarget::DropAtLeastFraction(1.0 / 3.0));
+                let (_, stats_diff) = store.gc(GarbageCollectionOptions {
+                    target: GarbageCollectionTarget::DropAtLeastFraction(1.0 / 3.0),
+                    protect_latest_at: false,
+                    protected_paths: Vec::new(),
+                });
                 stats_diff
             });
         });
@@ -8,7 +8,11 @@
 //! * See [`DataStore`] for an overview of the core data structures.
 //! * See [`DataStore::latest_at`] and [`DataStore::range`] for the documentation of the public
 //!   read APIs.
+//!   [`DataStore::latest_at_multi`] and [`DataStore::range_multi`] filter on several timelines
+//!   at once.
 //! * See [`DataStore::insert_row`] for the documentation of the public write APIs.
+//! * See [`DataStore::spill`] for moving cold data to disk rather than purging it.
+//! * See [`DataStore::range_scalar_summaries`] for cheaply summarizing large scalar series.
 //!
 //! ## Feature flags
 #![doc = document_features::document_features!()]
@@ -17,12 +21,18 @@
 mod arrow_util;
 mod store;
 mod store_arrow;
+mod store_dedup;
 mod store_dump;
+mod store_export;
 mod store_format;
 mod store_gc;
+mod store_import;
 mod store_read;
 mod store_sanity;
+mod store_spill;
 mod store_stats;
+mod store_subscriber;
+mod store_summary;
 mod store_write;
 
 #[cfg(feature = "polars")]
@@ -36,14 +46,28 @@ pub mod test_util;
 
 pub use self::arrow_util::ArrayExt;
 pub use self::store::{DataStore, DataStoreConfig};
-pub use self::store_gc::GarbageCollectionTarget;
-pub use self::store_read::{LatestAtQuery, RangeQuery};
+pub use self::store_export::{ExportError, ExportGrouping, ExportResult, ExportTable};
+pub use self::store_gc::{GarbageCollectionOptions, GarbageCollectionTarget};
+#[cfg(feature = "csv")]
+pub use self::store_import::import_csv;
+#[cfg(feature = "parquet")]
+pub use self::store_import::import_parquet;
+pub use self::store_import::{
+    import_arrow_ipc, import_chunks, import_file, import_to_log_msgs, ComponentColumns,
+    EntityPathSource, ImportConfig, ImportError, ImportFormat, ImportResult, TimelineColumn,
+};
+pub use self::store_read::{LatestAtQuery, MultiLatestAtQuery, MultiRangeQuery, RangeQuery};
+pub use self::store_spill::{SpillError, SpillOptions, SpillResult, SpillTarget, SpilledColumns};
 pub use self::store_stats::{DataStoreRowStats, DataStoreStats};
+pub use self::store_subscriber::{
+    StoreEvent, StoreEventKind, StoreSubscriber, StoreSubscriberHandle,
+};
+pub use self::store_summary::ScalarSummary;
 pub use self::store_write::{WriteError, WriteResult};
 
 pub(crate) use self::store::{
-    ClusterCellCache, DataTypeRegistry, IndexedBucket, IndexedBucketInner, IndexedTable,
-    MetadataRegistry, PersistentIndexedTable,
+    ClusterCellCache, DataTypeRegistry, DedupCellCache, IndexedBucket, IndexedBucketInner,
+    IndexedTable, MetadataRegistry, PersistentIndexedTable,
 };
 
 // Re-exports
@@ -1,5 +1,5 @@
 use std::collections::BTreeMap;
-use std::sync::atomic::AtomicU64;
+use std::sync::{atomic::AtomicU64, Arc};
 
 use ahash::HashMap;
 use arrow2::datatypes::DataType;
@@ -12,6 +12,11 @@ use re_log_types::{
     NumInstancesVec, RowId, RowIdVec, SizeBytes, TimeInt, TimePoint, TimeRange, Timeline,
 };
 
+use crate::{
+    store_spill::{PagedInColumns, SpillDir},
+    DataStoreRowStats, ScalarSummary, SpilledColumns, StoreSubscriber, StoreSubscriberHandle,
+};
+
 // --- Data store ---
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -47,6 +52,18 @@ pub struct DataStoreConfig {
     ///
     /// Enabled by default in debug builds.
     pub enable_typecheck: bool,
+
+    /// Cells whose total size (stack + heap) is at least this many bytes get deduplicated on
+    /// insertion: all identical cells, as determined by their contents, share a single copy of
+    /// the data.
+    ///
+    /// This keeps memory usage flat when the same large, static data (meshes, annotation
+    /// contexts, ...) is logged over and over again, at the cost of hashing every such cell on
+    /// insertion.
+    /// Use `u64::MAX` to disable deduplication altogether.
+    ///
+    /// See [`Self::DEFAULT`] for defaults.
+    pub dedup_min_size_bytes: u64,
 }
 
 impl Default for DataStoreConfig {
@@ -66,6 +83,9 @@ impl DataStoreConfig {
         indexed_bucket_num_rows: 512,
         store_insert_ids: cfg!(debug_assertions),
         enable_typecheck: cfg!(debug_assertions),
+        // NOTE: Below that, cells are mostly made of their datatype and array metadata, which
+        // aren't deduplicated anyway, so hashing them isn't worth it.
+        dedup_min_size_bytes: 512,
     };
 }
 
@@ -153,6 +173,42 @@ impl std::ops::DerefMut for ClusterCellCache {
     }
 }
 
+impl ClusterCellCache {
+    /// Was this cell auto-generated by the store, rather than logged as part of a row?
+    ///
+    /// Auto-generated cells are all shallow clones of the ones in this cache.
+    #[inline]
+    pub(crate) fn is_generated(&self, cell: &DataCell) -> bool {
+        self.get(&cell.num_instances()).map_or(false, |generated| {
+            std::sync::Arc::ptr_eq(&generated.inner, &cell.inner)
+        })
+    }
+}
+
+/// Content-addressed storage for the cells that get deduplicated on insertion, see
+/// [`DataStoreConfig::dedup_min_size_bytes`].
+///
+/// All the tables that hold a cell identical to one of the cells in there hold a shallow clone of
+/// it instead, so that its data lives in memory only once.
+#[derive(Debug, Default, Clone)]
+pub struct DedupCellCache {
+    /// All deduplicated cells, by content hash.
+    ///
+    /// There's more than one cell per hash only in case of collisions.
+    pub cells: IntMap<u64, SmallVec<[DataCell; 1]>>,
+
+    /// The content hash of every deduplicated cell, and the number of times it's referenced by
+    /// the tables of the store, keyed by the address of the cell.
+    pub num_refs: IntMap<usize, (u64, u64)>,
+
+    /// How many bytes deduplication saves, i.e. the size of every deduplicated cell times the
+    /// number of its references beyond the first.
+    ///
+    /// Tables account for the full size of every cell they reference, deduplicated or not: this
+    /// is how much of that is accounted for more than once.
+    pub saved_size_bytes: u64,
+}
+
 // ---
 
 /// A complete data store: covers all timelines, all entities, everything.
@@ -199,6 +255,11 @@ pub struct DataStore {
     /// so that they can be properly deduplicated on insertion.
     pub(crate) cluster_cell_cache: ClusterCellCache,
 
+    /// Holds the data of the cells that are deduplicated on insertion.
+    ///
+    /// See [`DataStoreConfig::dedup_min_size_bytes`].
+    pub(crate) dedup_cell_cache: DedupCellCache,
+
     /// All temporal [`IndexedTable`]s for all entities on all timelines.
     ///
     /// See also [`Self::timeless_tables`].
@@ -217,6 +278,27 @@ pub struct DataStore {
 
     /// Monotonically increasing ID for GCs.
     pub(crate) gc_id: u64,
+
+    /// Monotonically increasing ID for changes to the data of any entity.
+    pub(crate) generation: u64,
+
+    /// The [`Self::generation`] at which the data of each entity last changed.
+    ///
+    /// See [`Self::entity_generation`].
+    pub(crate) entity_generations: IntMap<EntityPathHash, u64>,
+
+    /// Notified of every change to the contents of the store.
+    ///
+    /// See [`Self::register_subscriber`].
+    pub(crate) subscribers: Vec<(StoreSubscriberHandle, Box<dyn StoreSubscriber>)>,
+
+    pub(crate) next_subscriber_handle: u32,
+
+    /// Where the spilled buckets of this store and its clones are written to, created on the
+    /// first spill.
+    ///
+    /// See [`Self::spill`].
+    pub(crate) spill_dir: Option<Arc<SpillDir>>,
 }
 
 impl Clone for DataStore {
@@ -227,6 +309,7 @@ impl Clone for DataStore {
             type_registry: self.type_registry.clone(),
             metadata_registry: self.metadata_registry.clone(),
             cluster_cell_cache: self.cluster_cell_cache.clone(),
+            dedup_cell_cache: self.dedup_cell_cache.clone(),
             tables: self.tables.clone(),
             timeless_tables: self.timeless_tables.clone(),
             insert_id: self.insert_id,
@@ -235,6 +318,12 @@ impl Clone for DataStore {
                 .load(std::sync::atomic::Ordering::Relaxed)
                 .into(),
             gc_id: self.gc_id,
+            generation: self.generation,
+            entity_generations: self.entity_generations.clone(),
+            // Subscribers are tied to the store they've been registered with.
+            subscribers: Vec::new(),
+            next_subscriber_handle: self.next_subscriber_handle,
+            spill_dir: self.spill_dir.clone(),
         }
     }
 }
@@ -246,6 +335,7 @@ impl DataStore {
             cluster_key,
             config,
             cluster_cell_cache: Default::default(),
+            dedup_cell_cache: Default::default(),
             metadata_registry: Default::default(),
             type_registry: Default::default(),
             tables: Default::default(),
@@ -253,6 +343,11 @@ impl DataStore {
             insert_id: 0,
             query_id: AtomicU64::new(0),
             gc_id: 0,
+            generation: 0,
+            entity_generations: Default::default(),
+            subscribers: Vec::new(),
+            next_subscriber_handle: 0,
+            spill_dir: None,
         }
     }
 
@@ -304,6 +399,24 @@ impl DataStore {
         oldest_time_per_timeline
     }
 
+    /// Returns a number that changes every time the data of the given entity changes, be it
+    /// because of an insertion or a garbage collection.
+    ///
+    /// Useful to know when to invalidate cached query results, see e.g. `re_query::QueryCache`.
+    #[inline]
+    pub fn entity_generation(&self, ent_path_hash: &EntityPathHash) -> u64 {
+        self.entity_generations
+            .get(ent_path_hash)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn bump_entity_generation(&mut self, ent_path_hash: EntityPathHash) {
+        self.generation += 1;
+        self.entity_generations
+            .insert(ent_path_hash, self.generation);
+    }
+
     /// Returns a read-only iterator over the raw indexed tables.
     ///
     /// Do _not_ use this to try and assert the internal state of the datastore.
@@ -332,6 +445,7 @@ fn datastore_internal_repr() {
             indexed_bucket_num_rows: 0,
             store_insert_ids: true,
             enable_typecheck: true,
+            dedup_min_size_bytes: u64::MAX,
         },
     );
 
@@ -399,6 +513,13 @@ pub struct IndexedTable {
     /// This is a best-effort approximation, adequate for most purposes (stats,
     /// triggering GCs, ...).
     pub buckets_size_bytes: u64,
+
+    /// The number of cells and the size of the component data stored in this table, per
+    /// component, across all of its buckets.
+    ///
+    /// Kept up to date on insertion and garbage collection, see
+    /// [`crate::DataStoreStats::components`].
+    pub components_stats: IntMap<ComponentName, DataStoreRowStats>,
 }
 
 impl IndexedTable {
@@ -413,6 +534,7 @@ impl IndexedTable {
             all_components: Default::default(),
             buckets_num_rows: 0,
             buckets_size_bytes,
+            components_stats: Default::default(),
         }
     }
 }
@@ -497,7 +619,25 @@ pub struct IndexedBucketInner {
     /// triggering GCs, ...).
     ///
     /// We cache this because there can be many, many buckets.
+    ///
+    /// Component data that was spilled to disk still counts towards this (see [`Self::spilled`]).
     pub size_bytes: u64,
+
+    /// Set when the component data of this bucket has been spilled to disk, in which case
+    /// [`Self::columns`] is empty until it gets paged back in.
+    ///
+    /// The control columns always stay in memory. See [`DataStore::spill`].
+    pub spilled: Option<SpilledColumns>,
+
+    /// Set when the component data of this bucket has been paged back in from disk and hasn't
+    /// been modified since, in which case it can be spilled again without writing anything.
+    pub(crate) paged_in: Option<PagedInColumns>,
+
+    /// The [`ScalarSummary`] of each component that has been summarized so far.
+    ///
+    /// These are computed lazily, see [`IndexedBucket::scalar_summary`], and dropped whenever the
+    /// rows of the bucket change.
+    pub scalar_summaries: IntMap<ComponentName, ScalarSummary>,
 }
 
 impl Default for IndexedBucketInner {
@@ -511,6 +651,9 @@ impl Default for IndexedBucketInner {
             col_num_instances: Default::default(),
             columns: Default::default(),
             size_bytes: 0, // NOTE: computed below
+            spilled: None,
+            paged_in: None,
+            scalar_summaries: Default::default(),
         };
         this.compute_size_bytes();
         this
@@ -558,18 +701,36 @@ pub struct PersistentIndexedTable {
     /// The cells are optional since not all rows will have data for every single component
     /// (i.e. the table is sparse).
     pub columns: IntMap<ComponentName, DataCellColumn>,
+
+    /// The size of both the control & component data stored in this table, heap only, in bytes.
+    ///
+    /// This is a best-effort approximation, adequate for most purposes (stats,
+    /// triggering GCs, ...).
+    ///
+    /// We cache this because computing it requires going through every single cell.
+    pub size_bytes: u64,
+
+    /// The number of cells and the size of the component data stored in this table, per
+    /// component.
+    ///
+    /// Kept up to date on insertion and deletion, see [`crate::DataStoreStats::components`].
+    pub components_stats: IntMap<ComponentName, DataStoreRowStats>,
 }
 
 impl PersistentIndexedTable {
     pub fn new(cluster_key: ComponentName, ent_path: EntityPath) -> Self {
-        Self {
+        let mut this = Self {
             cluster_key,
             ent_path,
             col_insert_id: Default::default(),
             col_row_id: Default::default(),
             col_num_instances: Default::default(),
             columns: Default::default(),
-        }
+            size_bytes: 0, // NOTE: computed below
+            components_stats: Default::default(),
+        };
+        this.size_bytes = this.size_bytes_uncached();
+        this
     }
 
     pub fn is_empty(&self) -> bool {
@@ -24,6 +24,8 @@ impl IndexedBucket {
     pub fn serialize(&self) -> DataTableResult<(Schema, Chunk<Box<dyn Array>>)> {
         crate::profile_function!();
 
+        self.page_in_if_needed();
+
         let Self {
             timeline,
             cluster_key,
@@ -39,6 +41,9 @@ impl IndexedBucket {
             col_num_instances,
             columns,
             size_bytes: _,
+            spilled: _,
+            paged_in: _,
+            scalar_summaries: _,
         } = &*inner.read();
 
         serialize(
@@ -72,6 +77,8 @@ impl PersistentIndexedTable {
             col_row_id,
             col_num_instances,
             columns,
+            size_bytes: _,
+            components_stats: _,
         } = self;
 
         serialize(
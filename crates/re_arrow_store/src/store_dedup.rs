@@ -0,0 +1,292 @@
+use std::hash::{BuildHasher as _, Hash as _, Hasher};
+use std::sync::Arc;
+
+use arrow2::{
+    array::{
+        Array, BinaryArray, BooleanArray, FixedSizeBinaryArray, FixedSizeListArray, ListArray,
+        PrimitiveArray, StructArray, UnionArray, Utf8Array,
+    },
+    datatypes::{PhysicalType, PrimitiveType},
+    types::NativeType,
+};
+use re_log_types::{DataCell, DataCellRow, DataRow, SizeBytes as _};
+
+use crate::{store::DedupCellCache, DataStore};
+
+// ---
+
+impl DataStore {
+    /// Deduplicates the cells of the given row that are big enough for it to be worth it, see
+    /// [`crate::DataStoreConfig::dedup_min_size_bytes`].
+    ///
+    /// The row is about to be inserted into `num_tables` tables, each of which will reference its
+    /// deduplicated cells.
+    ///
+    /// Returns `None` if there was nothing to deduplicate.
+    pub(crate) fn dedup_row(&mut self, row: &DataRow, num_tables: u64) -> Option<DataRow> {
+        let min_size_bytes = self.config.dedup_min_size_bytes;
+        let is_dedupable = |cell: &DataCell| cell.total_size_bytes() >= min_size_bytes;
+
+        if !row.cells().iter().any(is_dedupable) {
+            return None;
+        }
+
+        crate::profile_function!();
+
+        let DataRow {
+            row_id,
+            timepoint,
+            entity_path,
+            num_instances,
+            cells,
+        } = row;
+
+        let cells = cells
+            .iter()
+            .map(|cell| {
+                if is_dedupable(cell) {
+                    self.dedup_cell_cache.insert(cell, num_tables)
+                } else {
+                    cell.clone() // shallow
+                }
+            })
+            .collect();
+
+        Some(DataRow {
+            row_id: *row_id,
+            timepoint: timepoint.clone(),
+            entity_path: entity_path.clone(),
+            num_instances: *num_instances,
+            cells: DataCellRow(cells),
+        })
+    }
+
+    /// Releases the references that a table held to the given cells, for those that are
+    /// deduplicated.
+    pub(crate) fn release_cells<'a>(&mut self, cells: impl IntoIterator<Item = &'a DataCell>) {
+        for cell in cells {
+            self.dedup_cell_cache.release(cell);
+        }
+    }
+}
+
+impl DedupCellCache {
+    /// The number of distinct deduplicated cells.
+    #[inline]
+    pub fn num_cells(&self) -> u64 {
+        self.num_refs.len() as _
+    }
+
+    /// Is this one of the deduplicated cells, i.e. a shallow clone of one of the cells in here?
+    #[inline]
+    pub(crate) fn is_deduplicated(&self, cell: &DataCell) -> bool {
+        self.num_refs.contains_key(&cell_address(cell))
+    }
+
+    /// Returns the deduplicated cell that holds the same data as `cell`, adding it to the cache
+    /// first if needed, and accounts for `num_refs` more references to it.
+    pub(crate) fn insert(&mut self, cell: &DataCell, num_refs: u64) -> DataCell {
+        // Cells that come straight out of this very cache don't even need to be hashed.
+        if let Some((_, cur_num_refs)) = self.num_refs.get_mut(&cell_address(cell)) {
+            *cur_num_refs += num_refs;
+            self.saved_size_bytes += num_refs * cell.total_size_bytes();
+            return cell.clone(); // shallow
+        }
+
+        let hash = hash_cell(cell);
+        let candidates = self.cells.entry(hash).or_default();
+        let existing = candidates
+            .iter()
+            .find(|deduped| has_same_data(deduped, cell))
+            .cloned(); // shallow
+        let is_new = existing.is_none();
+        let deduped = existing.unwrap_or_else(|| {
+            candidates.push(cell.clone() /* shallow */);
+            cell.clone() // shallow
+        });
+
+        self.num_refs
+            .entry(cell_address(&deduped))
+            .or_insert((hash, 0))
+            .1 += num_refs;
+
+        // NOTE: The first reference to a cell is the one that actually holds its data.
+        let num_saved_refs = if is_new { num_refs - 1 } else { num_refs };
+        self.saved_size_bytes += num_saved_refs * deduped.total_size_bytes();
+
+        deduped
+    }
+
+    /// Accounts for one less reference to the given cell, dropping it from the cache once it
+    /// isn't referenced anymore.
+    ///
+    /// Does nothing if the cell isn't deduplicated in the first place.
+    pub(crate) fn release(&mut self, cell: &DataCell) {
+        let address = cell_address(cell);
+        let Some((hash, num_refs)) = self.num_refs.get_mut(&address) else {
+            return;
+        };
+
+        *num_refs = num_refs.saturating_sub(1);
+        if *num_refs > 0 {
+            self.saved_size_bytes -= cell.total_size_bytes();
+            return;
+        }
+
+        let hash = *hash;
+        self.num_refs.remove(&address);
+        if let Some(candidates) = self.cells.get_mut(&hash) {
+            candidates.retain(|deduped| cell_address(deduped) != address);
+            if candidates.is_empty() {
+                self.cells.remove(&hash);
+            }
+        }
+    }
+}
+
+/// Identifies a deduplicated cell within the cache: all its copies point to the same data.
+#[inline]
+pub(crate) fn cell_address(cell: &DataCell) -> usize {
+    Arc::as_ptr(&cell.inner) as usize
+}
+
+#[inline]
+fn has_same_data(lhs: &DataCell, rhs: &DataCell) -> bool {
+    lhs.component_name() == rhs.component_name() && lhs.as_arrow_ref() == rhs.as_arrow_ref()
+}
+
+fn hash_cell(cell: &DataCell) -> u64 {
+    crate::profile_function!();
+
+    let mut hasher = re_log_types::hash::HASH_RANDOM_STATE.build_hasher();
+    cell.component_name().hash(&mut hasher);
+    hash_array(cell.as_arrow_ref(), &mut hasher);
+    hasher.finish()
+}
+
+// --- Arrow hashing ---
+
+/// Hashes the contents of the given array: arrays holding the same data always have the same
+/// hash.
+///
+/// The reverse isn't necessarily true, e.g. when the null slots of two arrays hold different
+/// values, or for the physical types whose values aren't hashed at all (dictionaries and maps).
+/// This only makes deduplication less effective, since the data is always compared in the end.
+fn hash_array(array: &dyn Array, hasher: &mut impl Hasher) {
+    array.data_type().hash(hasher);
+    array.len().hash(hasher);
+    if let Some(validity) = array.validity() {
+        for is_valid in validity.iter() {
+            is_valid.hash(hasher);
+        }
+    }
+
+    #[allow(clippy::enum_glob_use)]
+    use PhysicalType::*;
+    match array.data_type().to_physical_type() {
+        Null | Dictionary(_) | Map => {}
+        Boolean => {
+            let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+            for value in array.values().iter() {
+                value.hash(hasher);
+            }
+        }
+        Primitive(primitive) => hash_primitive_array(primitive, array, hasher),
+        Binary => {
+            let array = array.as_any().downcast_ref::<BinaryArray<i32>>().unwrap();
+            for value in array.iter() {
+                value.hash(hasher);
+            }
+        }
+        LargeBinary => {
+            let array = array.as_any().downcast_ref::<BinaryArray<i64>>().unwrap();
+            for value in array.iter() {
+                value.hash(hasher);
+            }
+        }
+        FixedSizeBinary => {
+            let array = array
+                .as_any()
+                .downcast_ref::<FixedSizeBinaryArray>()
+                .unwrap();
+            for value in array.iter() {
+                value.hash(hasher);
+            }
+        }
+        Utf8 => {
+            let array = array.as_any().downcast_ref::<Utf8Array<i32>>().unwrap();
+            for value in array.iter() {
+                value.hash(hasher);
+            }
+        }
+        LargeUtf8 => {
+            let array = array.as_any().downcast_ref::<Utf8Array<i64>>().unwrap();
+            for value in array.iter() {
+                value.hash(hasher);
+            }
+        }
+        List => {
+            let array = array.as_any().downcast_ref::<ListArray<i32>>().unwrap();
+            for value in array.iter().flatten() {
+                hash_array(value.as_ref(), hasher);
+            }
+        }
+        LargeList => {
+            let array = array.as_any().downcast_ref::<ListArray<i64>>().unwrap();
+            for value in array.iter().flatten() {
+                hash_array(value.as_ref(), hasher);
+            }
+        }
+        // NOTE: Slicing these arrays slices their children too.
+        FixedSizeList => {
+            let array = array.as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+            hash_array(array.values().as_ref(), hasher);
+        }
+        Struct => {
+            let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+            for field in array.values() {
+                hash_array(field.as_ref(), hasher);
+            }
+        }
+        Union => {
+            let array = array.as_any().downcast_ref::<UnionArray>().unwrap();
+            array.types().as_slice().hash(hasher);
+            if let Some(offsets) = array.offsets() {
+                offsets.as_slice().hash(hasher);
+            }
+            for field in array.fields() {
+                hash_array(field.as_ref(), hasher);
+            }
+        }
+    }
+}
+
+fn hash_primitive_array(primitive: PrimitiveType, array: &dyn Array, hasher: &mut impl Hasher) {
+    fn hash_values<T: NativeType>(array: &dyn Array, hasher: &mut impl Hasher) {
+        let array = array.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+        for value in array.values().iter() {
+            hasher.write(value.to_le_bytes().as_ref());
+        }
+    }
+
+    use arrow2::types::{days_ms, f16, i256, months_days_ns};
+    #[allow(clippy::enum_glob_use)]
+    use PrimitiveType::*;
+    match primitive {
+        Int8 => hash_values::<i8>(array, hasher),
+        Int16 => hash_values::<i16>(array, hasher),
+        Int32 => hash_values::<i32>(array, hasher),
+        Int64 => hash_values::<i64>(array, hasher),
+        Int128 => hash_values::<i128>(array, hasher),
+        Int256 => hash_values::<i256>(array, hasher),
+        DaysMs => hash_values::<days_ms>(array, hasher),
+        MonthDayNano => hash_values::<months_days_ns>(array, hasher),
+        UInt8 => hash_values::<u8>(array, hasher),
+        UInt16 => hash_values::<u16>(array, hasher),
+        UInt32 => hash_values::<u32>(array, hasher),
+        UInt64 => hash_values::<u64>(array, hasher),
+        Float16 => hash_values::<f16>(array, hasher),
+        Float32 => hash_values::<f32>(array, hasher),
+        Float64 => hash_values::<f64>(array, hasher),
+    }
+}
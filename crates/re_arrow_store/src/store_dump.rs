@@ -2,7 +2,8 @@ use std::collections::BTreeMap;
 
 use arrow2::Either;
 use re_log_types::{
-    DataCellColumn, DataTable, ErasedTimeVec, RowIdVec, TableId, TimeRange, Timeline,
+    ComponentName, DataCellColumn, DataRow, DataTable, EntityPath, ErasedTimeVec, RowId, RowIdVec,
+    TableId, TimeRange, Timeline,
 };
 
 use crate::{
@@ -30,6 +31,42 @@ impl DataStore {
         timeless.chain(temporal)
     }
 
+    /// All the rows in the datastore, each with its complete timepoint.
+    ///
+    /// The store keeps a copy of each row per timeline: unlike [`Self::to_data_tables`], these
+    /// are merged back together. Rows are sorted by [`RowId`].
+    pub fn to_rows(&self) -> Vec<DataRow> {
+        crate::profile_function!();
+
+        // Clears share the row id of the operation that caused them, across entities and
+        // components, so the row id alone does not identify a row.
+        let mut rows: BTreeMap<(RowId, EntityPath, Vec<ComponentName>), DataRow> =
+            Default::default();
+
+        for table in self.to_data_tables(None) {
+            for row in table.to_rows() {
+                let key = (
+                    row.row_id(),
+                    row.entity_path().clone(),
+                    row.component_names().collect(),
+                );
+                match rows.entry(key) {
+                    std::collections::btree_map::Entry::Vacant(entry) => {
+                        entry.insert(row);
+                    }
+                    std::collections::btree_map::Entry::Occupied(mut entry) => {
+                        let timepoint = &mut entry.get_mut().timepoint;
+                        for (timeline, time) in row.timepoint {
+                            timepoint.insert(timeline, time);
+                        }
+                    }
+                }
+            }
+        }
+
+        rows.into_values().collect()
+    }
+
     fn dump_timeless_tables(&self) -> impl Iterator<Item = DataTable> + '_ {
         self.timeless_tables.values().map(|table| {
             crate::profile_scope!("timeless_table");
@@ -41,6 +78,8 @@ impl DataStore {
                 col_row_id,
                 col_num_instances,
                 columns,
+                size_bytes: _,
+                components_stats: _,
             } = table;
 
             DataTable {
@@ -60,9 +99,13 @@ impl DataStore {
         self.tables.values().flat_map(|table| {
             crate::profile_scope!("temporal_table");
 
-            table.buckets.values().map(move |bucket| {
+            table.buckets.values().filter_map(move |bucket| {
                 crate::profile_scope!("temporal_bucket");
 
+                // NOTE: Unreadable spill files are reported by `page_in_if_needed` itself.
+                if !bucket.page_in_if_needed() {
+                    return None;
+                }
                 bucket.sort_indices_if_needed();
 
                 let IndexedBucket {
@@ -80,10 +123,13 @@ impl DataStore {
                     col_num_instances,
                     columns,
                     size_bytes: _,
+                    spilled: _,
+                    paged_in: _,
+                    scalar_summaries: _,
                 } = &*inner.read();
                 debug_assert!(is_sorted);
 
-                DataTable {
+                Some(DataTable {
                     table_id: TableId::random(),
                     col_row_id: col_row_id.clone(),
                     col_timelines: [(*timeline, col_time.iter().copied().map(Some).collect())]
@@ -93,7 +139,7 @@ impl DataStore {
                         .collect(),
                     col_num_instances: col_num_instances.clone(),
                     columns: columns.clone().into_iter().collect(), // shallow
-                }
+                })
             })
         })
     }
@@ -114,6 +160,10 @@ impl DataStore {
                 Some(table.buckets.values().filter_map(move |bucket| {
                     crate::profile_scope!("temporal_bucket_filtered");
 
+                    // NOTE: Unreadable spill files are reported by `page_in_if_needed` itself.
+                    if !bucket.page_in_if_needed() {
+                        return None;
+                    }
                     bucket.sort_indices_if_needed();
 
                     let IndexedBucket {
@@ -131,6 +181,9 @@ impl DataStore {
                         col_num_instances,
                         columns,
                         size_bytes: _,
+                        spilled: _,
+                        paged_in: _,
+                        scalar_summaries: _,
                     } = &*inner.read();
                     debug_assert!(is_sorted);
 
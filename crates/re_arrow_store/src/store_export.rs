@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use arrow2::{
+    array::{Array, Utf8Array},
+    chunk::Chunk,
+    datatypes::{DataType, Field, Schema},
+};
+use re_log_types::{
+    ComponentName, DataRow, DataTable, DataTableError, EntityPath, TableId, COLUMN_ENTITY_PATH,
+    COLUMN_NUM_INSTANCES, COLUMN_ROW_ID, METADATA_KIND, METADATA_KIND_DATA, METADATA_KIND_TIME,
+};
+
+use crate::DataStore;
+
+// ---
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("Error with one or more the underlying data tables: {0}")]
+    DataTable(#[from] DataTableError),
+
+    #[error("Could not write arrow data: {0}")]
+    Arrow(#[from] arrow2::error::Error),
+}
+
+pub type ExportResult<T> = ::std::result::Result<T, ExportError>;
+
+/// How to split the rows of a [`DataStore`] into [`ExportTable`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportGrouping {
+    /// One table per entity path.
+    EntityPath,
+
+    /// One table per distinct set of components, across all entity paths.
+    ComponentSet,
+}
+
+/// A flat table of rows, ready to be written to a standard file format, e.g. for use with
+/// `pandas` or `DuckDB`.
+///
+/// Column order:
+/// - `rerun.row_id`, as a string
+/// - `rerun.entity_path`, as a string
+/// - one column per timeline, null where a row has no time on that timeline (e.g. timeless rows)
+/// - `rerun.num_instances`
+/// - one list column per component, in ascending lexical order
+#[derive(Debug, Clone)]
+pub struct ExportTable {
+    /// The entity path, or the components, that this table groups rows by.
+    pub name: String,
+
+    pub schema: Schema,
+    pub chunk: Chunk<Box<dyn Array>>,
+}
+
+impl DataStore {
+    /// Flattens the entire datastore into one [`ExportTable`] per group of rows.
+    pub fn to_export_tables(&self, grouping: ExportGrouping) -> ExportResult<Vec<ExportTable>> {
+        crate::profile_function!();
+
+        let mut groups: BTreeMap<String, Vec<DataRow>> = Default::default();
+        for row in self.to_rows() {
+            let name = match grouping {
+                ExportGrouping::EntityPath => row.entity_path().to_string(),
+                ExportGrouping::ComponentSet => {
+                    let mut components: Vec<ComponentName> = row.component_names().collect();
+                    components.sort();
+                    components
+                        .iter()
+                        .map(|c| c.full_name())
+                        .collect::<Vec<_>>()
+                        .join("+")
+                }
+            };
+            groups.entry(name).or_default().push(row);
+        }
+
+        groups
+            .into_iter()
+            .map(|(name, rows)| ExportTable::from_rows(name, rows))
+            .collect()
+    }
+}
+
+impl ExportTable {
+    fn from_rows(name: String, rows: Vec<DataRow>) -> ExportResult<Self> {
+        crate::profile_function!();
+
+        let table = DataTable::from_rows(TableId::ZERO, rows);
+        let (schema, chunk) = table.serialize()?;
+
+        let mut time_columns = Vec::new();
+        let mut num_instances_column = None;
+        let mut data_columns = Vec::new();
+        for (field, column) in schema.fields.into_iter().zip(chunk.into_arrays()) {
+            let kind = field.metadata.get(METADATA_KIND).map(String::as_str);
+            if kind == Some(METADATA_KIND_TIME) {
+                // Rows that aren't on this timeline have nulls.
+                time_columns.push((
+                    Field {
+                        is_nullable: true,
+                        ..field
+                    },
+                    column,
+                ));
+            } else if kind == Some(METADATA_KIND_DATA) {
+                data_columns.push((field, column));
+            } else if field.name == COLUMN_NUM_INSTANCES {
+                num_instances_column = Some((field, column));
+            }
+            // The row ids and entity paths are exported as plain strings, see below.
+        }
+
+        // Extension types are not understood outside of Rerun, so use strings instead:
+        let strings = |name: &str, values: Vec<String>| {
+            (
+                Field::new(name, DataType::Utf8, false),
+                Utf8Array::<i32>::from_iter_values(values.iter()).boxed(),
+            )
+        };
+        let row_id_column = strings(
+            COLUMN_ROW_ID,
+            table.col_row_id.iter().map(|id| id.to_string()).collect(),
+        );
+        let entity_path_column = strings(
+            COLUMN_ENTITY_PATH,
+            table
+                .col_entity_path
+                .iter()
+                .map(EntityPath::to_string)
+                .collect(),
+        );
+
+        data_columns.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+
+        let (fields, columns): (Vec<_>, Vec<_>) = [row_id_column, entity_path_column]
+            .into_iter()
+            .chain(time_columns)
+            .chain(num_instances_column)
+            .chain(data_columns)
+            .unzip();
+
+        Ok(Self {
+            name,
+            schema: Schema::from(fields),
+            chunk: Chunk::try_new(columns)?,
+        })
+    }
+
+    #[inline]
+    pub fn num_rows(&self) -> usize {
+        self.chunk.len()
+    }
+
+    /// A name for the file this table is written to, without extension.
+    ///
+    /// E.g. `world.points` for the `world/points` entity.
+    pub fn file_stem(&self) -> String {
+        let stem: String = self
+            .name
+            .trim_start_matches('/')
+            .chars()
+            .map(|c| match c {
+                '/' => '.',
+                c if c.is_ascii_alphanumeric() || "._-+".contains(c) => c,
+                _ => '_',
+            })
+            .collect();
+
+        if stem.is_empty() {
+            "root".to_owned()
+        } else {
+            stem
+        }
+    }
+
+    /// Writes the table as an [Arrow IPC file](https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format).
+    pub fn write_arrow_ipc(&self, write: impl std::io::Write) -> ExportResult<()> {
+        crate::profile_function!();
+
+        use arrow2::io::ipc::write::{FileWriter, WriteOptions};
+
+        let mut writer =
+            FileWriter::try_new(write, self.schema.clone(), None, WriteOptions::default())?;
+        writer.write(&self.chunk, None)?;
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Writes the table as a [Parquet file](https://parquet.apache.org/).
+    ///
+    /// Parquet has no union types, so columns of components that use them are left out.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, write: impl std::io::Write) -> ExportResult<()> {
+        crate::profile_function!();
+
+        use arrow2::io::parquet::write::{
+            transverse, CompressionOptions, Encoding, FileWriter, RowGroupIterator, Version,
+            WriteOptions,
+        };
+
+        fn has_union(datatype: &DataType) -> bool {
+            match datatype.to_logical_type() {
+                DataType::Union(_, _, _) => true,
+                DataType::List(field)
+                | DataType::LargeList(field)
+                | DataType::FixedSizeList(field, _)
+                | DataType::Map(field, _) => has_union(&field.data_type),
+                DataType::Struct(fields) => fields.iter().any(|field| has_union(&field.data_type)),
+                _ => false,
+            }
+        }
+
+        let (fields, columns): (Vec<_>, Vec<_>) = self
+            .schema
+            .fields
+            .iter()
+            .zip(self.chunk.arrays())
+            .filter(|(field, _)| {
+                let has_union = has_union(&field.data_type);
+                if has_union {
+                    re_log::warn_once!(
+                        "Can't export {:?} to Parquet, which doesn't support union types",
+                        field.name
+                    );
+                }
+                !has_union
+            })
+            .map(|(field, column)| (field.clone(), column.clone()))
+            .unzip();
+        let schema = Schema::from(fields);
+
+        let options = WriteOptions {
+            write_statistics: true,
+            compression: CompressionOptions::Zstd(None),
+            version: Version::V2,
+            data_pagesize_limit: None,
+        };
+        let encodings = schema
+            .fields
+            .iter()
+            .map(|field| transverse(&field.data_type, |_| Encoding::Plain))
+            .collect();
+        let row_groups = RowGroupIterator::try_new(
+            std::iter::once(Chunk::try_new(columns)),
+            &schema,
+            options,
+            encodings,
+        )?;
+
+        let mut writer = FileWriter::try_new(write, schema, options)?;
+        for row_group in row_groups {
+            writer.write(row_group?)?;
+        }
+        writer.end(None)?;
+
+        Ok(())
+    }
+}
@@ -12,6 +12,7 @@ impl std::fmt::Display for DataStore {
             cluster_key,
             config,
             cluster_cell_cache: _,
+            dedup_cell_cache: _,
             metadata_registry: _,
             type_registry: _,
             tables,
@@ -19,6 +20,11 @@ impl std::fmt::Display for DataStore {
             insert_id: _,
             query_id: _,
             gc_id: _,
+            generation: _,
+            entity_generations: _,
+            subscribers: _,
+            next_subscriber_handle: _,
+            spill_dir: _,
         } = self;
 
         f.write_str("DataStore {\n")?;
@@ -86,6 +92,7 @@ impl std::fmt::Display for IndexedTable {
             all_components: _,
             buckets_num_rows: _,
             buckets_size_bytes: _,
+            components_stats: _,
         } = self;
 
         f.write_fmt(format_args!("timeline: {}\n", timeline.name()))?;
@@ -157,6 +164,8 @@ impl std::fmt::Display for PersistentIndexedTable {
             col_row_id: _,
             col_num_instances: _,
             columns: _,
+            size_bytes: _,
+            components_stats: _,
         } = self;
 
         f.write_fmt(format_args!("entity: {ent_path}\n"))?;
@@ -1,18 +1,38 @@
-use re_log_types::{RowId, SizeBytes as _, TimeInt, TimeRange};
+use std::collections::{BTreeMap, BTreeSet};
+
+use re_log_types::{
+    ComponentName, DataCell, Duration, EntityPath, EntityPathHash, RowId, RowSelector,
+    SizeBytes as _, TimeInt, TimeRange, TimeType, Timeline,
+};
 
 use crate::{
-    store::{IndexedBucketInner, IndexedTable},
-    DataStore, DataStoreStats,
+    store::{IndexedBucketInner, IndexedTable, PersistentIndexedTable},
+    store_stats::{remove_cell_stats, remove_component_stats},
+    DataStore, DataStoreStats, StoreEvent, StoreEventKind,
 };
 
 // ---
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum GarbageCollectionTarget {
     /// Try to drop _at least_ the given fraction.
     ///
     /// The fraction must be a float in the range [0.0 : 1.0].
     DropAtLeastFraction(f64),
+
+    /// Drop all the rows that are older than `window` on the given timeline, relative to the
+    /// most recent time on that timeline, e.g. to only keep the last 10 seconds of a live stream.
+    ///
+    /// The `window` is in the units of the timeline, i.e. nanoseconds for temporal timelines.
+    /// Rows that aren't on that timeline are left untouched.
+    KeepTimeWindow { timeline: Timeline, window: i64 },
+
+    /// Drop the oldest rows of the given entity and all of its descendants, until they take up
+    /// no more than `max_bytes`.
+    EntityQuota {
+        entity_path: EntityPath,
+        max_bytes: u64,
+    },
 }
 
 impl std::fmt::Display for GarbageCollectionTarget {
@@ -21,12 +41,72 @@ impl std::fmt::Display for GarbageCollectionTarget {
             GarbageCollectionTarget::DropAtLeastFraction(p) => {
                 write!(f, "DropAtLeast({:.3}%)", re_format::format_f64(*p * 100.0))
             }
+            GarbageCollectionTarget::KeepTimeWindow { timeline, window } => match timeline.typ() {
+                TimeType::Time => {
+                    write!(
+                        f,
+                        "KeepTimeWindow({}, {})",
+                        timeline.name(),
+                        Duration::from_nanos(*window)
+                    )
+                }
+                TimeType::Sequence => {
+                    write!(f, "KeepTimeWindow({}, {window})", timeline.name())
+                }
+            },
+            GarbageCollectionTarget::EntityQuota {
+                entity_path,
+                max_bytes,
+            } => {
+                write!(
+                    f,
+                    "EntityQuota({entity_path}/**, {})",
+                    re_format::format_bytes(*max_bytes as _)
+                )
+            }
+        }
+    }
+}
+
+/// Controls how [`DataStore::gc`] purges data.
+#[derive(Debug, Clone)]
+pub struct GarbageCollectionOptions {
+    /// How much data to purge.
+    pub target: GarbageCollectionTarget,
+
+    /// Keep the rows that `latest_at` queries at the start of what's left of each timeline
+    /// depend on.
+    ///
+    /// For each timeline, let the _boundary_ be the most recent time of all the rows that the
+    /// `target` picks for purging on it.
+    /// Then for each entity and component, the most recent row at or before the boundary, i.e.
+    /// the row that a `latest_at` query at the boundary would return, is kept.
+    /// As such, the results of all `latest_at` queries at or after the boundary are the same
+    /// as before the garbage collection, e.g. a pose that was logged once at the start of a
+    /// recording doesn't disappear.
+    ///
+    /// The kept rows still count towards the `target`, which might therefore not be reached.
+    pub protect_latest_at: bool,
+
+    /// The data of these entities, and of all their descendants, is never purged.
+    ///
+    /// It still counts towards the `target`, which might therefore not be reached.
+    pub protected_paths: Vec<EntityPath>,
+}
+
+impl GarbageCollectionOptions {
+    /// Purges all temporal data, regardless of `latest_at` semantics.
+    pub fn gc_everything() -> Self {
+        Self {
+            target: GarbageCollectionTarget::DropAtLeastFraction(1.0),
+            protect_latest_at: false,
+            protected_paths: Vec::new(),
         }
     }
 }
 
 impl DataStore {
-    /// Triggers a garbage collection according to the desired `target`.
+    /// Triggers a garbage collection according to the desired `options`.
     ///
     /// Garbage collection's performance is bounded by the number of buckets in each table (for
     /// each `RowId`, we have to find the corresponding bucket, which is roughly `O(log(n))`) as
@@ -45,27 +125,31 @@ impl DataStore {
     /// in a fair, deterministic manner.
     /// Similarly, out-of-order data is supported out of the box.
     ///
+    /// A row is always dropped from all of its timelines at once, even if the target only
+    /// concerns one of them.
+    ///
     /// The garbage collector doesn't deallocate data in and of itself: all it does is drop the
     /// store's internal references to that data (the `DataCell`s), which will be deallocated once
     /// their reference count reaches 0.
     ///
-    /// ## Limitations
-    ///
-    /// The garbage collector is currently unaware of our latest-at semantics, i.e. it will drop
-    /// old data even if doing so would impact the results of recent queries.
-    /// See <https://github.com/rerun-io/rerun/issues/1803>.
+    /// Unless [`GarbageCollectionOptions::protect_latest_at`] is set, the garbage collector is
+    /// unaware of our latest-at semantics, i.e. it will drop old data even if doing so would
+    /// impact the results of recent queries.
     //
     // TODO(#1804): There shouldn't be any need to return the purged `RowId`s, all secondary
-    // datastructures should be able to purge themselves based solely off of
-    // [`DataStore::oldest_time_per_timeline`].
-    //
-    // TODO(#1803): The GC should be aware of latest-at semantics and make sure they are upheld
-    // when purging data.
+    // datastructures should be able to purge themselves based solely off of the
+    // [`StoreEventKind::Deletion`] events notified to [`crate::StoreSubscriber`]s.
     //
     // TODO(#1823): Workload specific optimizations.
-    pub fn gc(&mut self, target: GarbageCollectionTarget) -> (Vec<RowId>, DataStoreStats) {
+    pub fn gc(&mut self, options: GarbageCollectionOptions) -> (Vec<RowId>, DataStoreStats) {
         crate::profile_function!();
 
+        let GarbageCollectionOptions {
+            target,
+            protect_latest_at,
+            protected_paths,
+        } = options;
+
         self.gc_id += 1;
 
         // NOTE: only temporal data and row metadata get purged!
@@ -75,7 +159,29 @@ impl DataStore {
         let initial_num_bytes =
             (stats_before.temporal.num_bytes + stats_before.metadata_registry.num_bytes) as f64;
 
-        let row_ids = match target {
+        re_log::debug!(
+            kind = "gc",
+            id = self.gc_id,
+            %target,
+            initial_num_rows = re_format::format_large_number(initial_num_rows as _),
+            initial_num_bytes = re_format::format_bytes(initial_num_bytes),
+            protect_latest_at,
+            num_protected_paths = protected_paths.len(),
+            "starting GC"
+        );
+
+        let mut gc = GcState {
+            protect_latest_at,
+            protected_paths,
+            protected_row_ids: None,
+            drop_timeless: false,
+            collect_events: !self.subscribers.is_empty(),
+            num_protected_rows: 0,
+            row_ids: Vec::new(),
+            events: Vec::new(),
+        };
+
+        match target {
             GarbageCollectionTarget::DropAtLeastFraction(p) => {
                 assert!((0.0..=1.0).contains(&p));
 
@@ -85,21 +191,39 @@ impl DataStore {
                 re_log::debug!(
                     kind = "gc",
                     id = self.gc_id,
-                    %target,
-                    initial_num_rows = re_format::format_large_number(initial_num_rows as _),
-                    initial_num_bytes = re_format::format_bytes(initial_num_bytes),
                     target_num_bytes = re_format::format_bytes(target_num_bytes),
                     drop_at_least_num_bytes = re_format::format_bytes(num_bytes_to_drop),
-                    "starting GC"
                 );
 
-                self.gc_drop_at_least_num_bytes(num_bytes_to_drop)
+                self.gc_drop_at_least_num_bytes(&mut gc, num_bytes_to_drop);
             }
-        };
+
+            GarbageCollectionTarget::KeepTimeWindow { timeline, window } => {
+                self.gc_keep_time_window(&mut gc, timeline, window);
+            }
+
+            GarbageCollectionTarget::EntityQuota {
+                entity_path,
+                max_bytes,
+            } => {
+                self.gc_entity_quota(&mut gc, &entity_path, max_bytes);
+            }
+        }
+
+        if gc.num_protected_rows > 0 {
+            re_log::debug!(
+                kind = "gc",
+                id = self.gc_id,
+                num_protected_rows = gc.num_protected_rows,
+                "kept protected rows"
+            );
+        }
 
         #[cfg(debug_assertions)]
         self.sanity_check().unwrap();
 
+        self.notify_subscribers(&gc.events);
+
         // NOTE: only temporal data and row metadata get purged!
         let stats_after = DataStoreStats::from_store(self);
         let new_num_rows = stats_after.temporal.num_rows + stats_after.metadata_registry.num_rows;
@@ -109,7 +233,6 @@ impl DataStore {
         re_log::debug!(
             kind = "gc",
             id = self.gc_id,
-            %target,
             initial_num_rows = re_format::format_large_number(initial_num_rows as _),
             initial_num_bytes = re_format::format_bytes(initial_num_bytes),
             new_num_rows = re_format::format_large_number(new_num_rows as _),
@@ -119,52 +242,459 @@ impl DataStore {
 
         let stats_diff = stats_before - stats_after;
 
-        (row_ids, stats_diff)
+        (gc.row_ids, stats_diff)
     }
 
     /// Tries to drop _at least_ `num_bytes_to_drop` bytes of data from the store.
-    ///
-    /// Returns the list of `RowId`s that were purged from the store.
-    fn gc_drop_at_least_num_bytes(&mut self, mut num_bytes_to_drop: f64) -> Vec<RowId> {
+    fn gc_drop_at_least_num_bytes(&mut self, gc: &mut GcState, num_bytes_to_drop: f64) {
         crate::profile_function!();
 
-        let mut row_ids = Vec::new();
-
         // The algorithm is straightforward:
-        // 1. Pop the oldest `RowId` available
+        // 1. Pick the oldest `RowId` available, skipping the ones we have to keep
         // 2. Find all tables that potentially hold data associated with that `RowId`
         // 3. Drop the associated row and account for the space we got back
-        while num_bytes_to_drop > 0.0 {
-            // pop next row id
-            let Some((row_id, timepoint)) = self.metadata_registry.pop_first() else {
+        let row_ids = if gc.protect_latest_at {
+            // Timeless rows aren't in any table, and are never protected.
+            let entities = self.temporal_row_entities(|_| true);
+            self.metadata_registry
+                .keys()
+                .map(|row_id| (*row_id, entities.get(row_id).copied()))
+                .collect()
+        } else {
+            self.metadata_registry
+                .keys()
+                .map(|row_id| (*row_id, None))
+                .collect()
+        };
+
+        self.gc_drop_rows(gc, row_ids, num_bytes_to_drop, true);
+    }
+
+    /// Drops all rows older than `window` on the given `timeline`, relative to its most recent
+    /// time.
+    fn gc_keep_time_window(&mut self, gc: &mut GcState, timeline: Timeline, window: i64) {
+        crate::profile_function!();
+
+        let latest_time = self
+            .tables
+            .values()
+            .filter(|table| table.timeline == timeline)
+            .filter_map(|table| table.buckets.values().next_back())
+            .map(|bucket| bucket.inner.read().time_range.max)
+            .max();
+        let Some(latest_time) = latest_time else {
+            return;
+        };
+        let cutoff = latest_time.as_i64().saturating_sub(window);
+
+        let mut row_ids = BTreeMap::new();
+        for table in self.tables.values() {
+            if table.timeline != timeline {
+                continue;
+            }
+            let ent_path_hash = table.ent_path.hash();
+            for bucket in table.range_buckets(..TimeInt::from(cutoff)).map(|(_, b)| b) {
+                let inner = bucket.inner.read();
+                if inner.time_range.min.as_i64() >= cutoff {
+                    continue;
+                }
+                row_ids.extend(
+                    inner
+                        .col_time
+                        .iter()
+                        .zip(&inner.col_row_id)
+                        .filter(|(time, _)| **time < cutoff)
+                        .map(|(_, row_id)| (*row_id, Some(ent_path_hash))),
+                );
+            }
+        }
+
+        self.gc_drop_rows(gc, row_ids.into_iter().collect(), f64::INFINITY, false);
+    }
+
+    /// Drops the oldest rows of the subtree at `entity_path`, until it takes up no more than
+    /// `max_bytes`.
+    fn gc_entity_quota(&mut self, gc: &mut GcState, entity_path: &EntityPath, max_bytes: u64) {
+        crate::profile_function!();
+
+        let subtree_num_bytes: u64 = self
+            .tables
+            .values()
+            .filter(|table| is_in_subtree(&table.ent_path, entity_path))
+            .map(|table| table.buckets_size_bytes)
+            .sum();
+        if subtree_num_bytes <= max_bytes {
+            return;
+        }
+
+        let row_ids = self
+            .temporal_row_entities(|table| is_in_subtree(&table.ent_path, entity_path))
+            .into_iter()
+            .map(|(row_id, ent_path_hash)| (row_id, Some(ent_path_hash)))
+            .collect();
+
+        self.gc_drop_rows(gc, row_ids, (subtree_num_bytes - max_bytes) as f64, false);
+    }
+
+    /// All the temporal rows of the given entities and their descendants, oldest first.
+    fn row_ids_in_subtrees(&self, entity_paths: &[EntityPath]) -> BTreeSet<RowId> {
+        if entity_paths.is_empty() {
+            return Default::default();
+        }
+
+        self.temporal_row_entities(|table| {
+            entity_paths
+                .iter()
+                .any(|entity_path| is_in_subtree(&table.ent_path, entity_path))
+        })
+        .into_keys()
+        .collect()
+    }
+
+    /// The entity of each temporal row of the tables that match `filter`, oldest row first.
+    fn temporal_row_entities(
+        &self,
+        filter: impl Fn(&IndexedTable) -> bool,
+    ) -> BTreeMap<RowId, EntityPathHash> {
+        crate::profile_function!();
+
+        let mut row_ids = BTreeMap::new();
+        for table in self.tables.values().filter(|table| filter(table)) {
+            let ent_path_hash = table.ent_path.hash();
+            for bucket in table.buckets.values() {
+                let inner = bucket.inner.read();
+                row_ids.extend(
+                    inner
+                        .col_row_id
+                        .iter()
+                        .map(|row_id| (*row_id, ent_path_hash)),
+                );
+            }
+        }
+        row_ids
+    }
+
+    /// Drops the given rows, oldest first, until `num_bytes_to_drop` bytes are gone or there
+    /// are no rows left.
+    ///
+    /// Only the bytes dropped from the tables count, unless `count_metadata` is set.
+    /// The entity of each row is only needed to protect `latest_at` results.
+    fn gc_drop_rows(
+        &mut self,
+        gc: &mut GcState,
+        row_ids: Vec<(RowId, Option<EntityPathHash>)>,
+        mut num_bytes_to_drop: f64,
+        count_metadata: bool,
+    ) {
+        crate::profile_function!();
+
+        let mut row_ids = row_ids.into_iter().peekable();
+        while num_bytes_to_drop > 0.0 && row_ids.peek().is_some() {
+            let batch = if gc.protect_latest_at {
+                self.gc_unprotected_batch(gc, &mut row_ids, num_bytes_to_drop, count_metadata)
+            } else {
+                row_ids
+                    .next()
+                    .map(|(row_id, _)| vec![row_id])
+                    .unwrap_or_default()
+            };
+
+            for row_id in batch {
+                if let Some((metadata_num_bytes, tables_num_bytes)) =
+                    self.gc_try_drop_row(gc, row_id)
+                {
+                    num_bytes_to_drop -= tables_num_bytes as f64;
+                    if count_metadata {
+                        num_bytes_to_drop -= metadata_num_bytes as f64;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Takes as many of the next `row_ids` as should be enough to drop `num_bytes_to_drop`
+    /// bytes, and returns those that aren't protected by `latest_at` semantics.
+    ///
+    /// The boundary of each timeline is the most recent time of the rows taken: for each entity
+    /// that these rows belong to, and each of its components, the most recent row at or before
+    /// the boundary is protected.
+    fn gc_unprotected_batch(
+        &self,
+        gc: &mut GcState,
+        row_ids: &mut impl Iterator<Item = (RowId, Option<EntityPathHash>)>,
+        num_bytes_to_drop: f64,
+        count_metadata: bool,
+    ) -> Vec<RowId> {
+        crate::profile_function!();
+
+        let mut batch = Vec::new();
+        let mut boundaries: BTreeMap<Timeline, TimeInt> = BTreeMap::new();
+        let mut tables = ahash::HashSet::default();
+        let mut expected_num_bytes = 0.0;
+        while expected_num_bytes < num_bytes_to_drop {
+            let Some((row_id, ent_path_hash)) = row_ids.next() else {
                 break;
             };
-            let metadata_dropped_size_bytes =
-                row_id.total_size_bytes() + timepoint.total_size_bytes();
-            self.metadata_registry.heap_size_bytes -= metadata_dropped_size_bytes;
-            num_bytes_to_drop -= metadata_dropped_size_bytes as f64;
-            row_ids.push(row_id);
-
-            // find all tables that could possibly contain this `RowId`
-            let tables = self.tables.iter_mut().filter_map(|((timeline, _), table)| {
-                timepoint.get(timeline).map(|time| (*time, table))
-            });
+            let Some(timepoint) = self.metadata_registry.get(&row_id) else {
+                continue;
+            };
+            if count_metadata {
+                expected_num_bytes +=
+                    (row_id.total_size_bytes() + timepoint.total_size_bytes()) as f64;
+            }
 
-            for (time, table) in tables {
-                num_bytes_to_drop -= table.try_drop_row(row_id, time.as_i64()) as f64;
+            for (&timeline, &time) in timepoint.iter() {
+                let boundary = boundaries.entry(timeline).or_insert(time);
+                *boundary = TimeInt::max(*boundary, time);
+
+                let Some(ent_path_hash) = ent_path_hash else {
+                    continue;
+                };
+                if let Some(table) = self.tables.get(&(timeline, ent_path_hash)) {
+                    // The exact size of a row is only known once dropped: the average size of
+                    // the rows of its bucket will do.
+                    let (_, bucket) = table.find_bucket(time);
+                    expected_num_bytes +=
+                        (bucket.total_size_bytes() / bucket.num_rows().max(1)) as f64;
+                    tables.insert((timeline, ent_path_hash));
+                }
             }
+
+            batch.push(row_id);
         }
 
-        row_ids
+        // Rows only ever affect the results of queries on their own entity.
+        let mut protected_row_ids = ahash::HashSet::default();
+        for (timeline, ent_path_hash) in tables {
+            let table = &self.tables[&(timeline, ent_path_hash)];
+            let boundary = boundaries[&timeline];
+            protected_row_ids.extend(table.all_components.iter().filter_map(|component| {
+                table
+                    .latest_at(boundary, *component, &[])
+                    .map(|(row_id, _)| row_id)
+            }));
+        }
+
+        let num_rows = batch.len();
+        batch.retain(|row_id| !protected_row_ids.contains(row_id));
+        gc.num_protected_rows += num_rows - batch.len();
+
+        batch
+    }
+
+    /// Drops the given row from the metadata registry and from all the tables of its timelines,
+    /// unless it has to be kept.
+    ///
+    /// Returns how many bytes were dropped from the metadata registry and from the tables,
+    /// or `None` if the row was kept.
+    fn gc_try_drop_row(&mut self, gc: &mut GcState, row_id: RowId) -> Option<(u64, u64)> {
+        if !self.metadata_registry.contains_key(&row_id) {
+            return None;
+        }
+
+        // Only gather the protected rows once we actually have to drop something.
+        let protected_row_ids = gc
+            .protected_row_ids
+            .get_or_insert_with(|| self.row_ids_in_subtrees(&gc.protected_paths));
+        if protected_row_ids.contains(&row_id) {
+            gc.num_protected_rows += 1;
+            return None;
+        }
+
+        let timepoint = self.metadata_registry.remove(&row_id)?;
+        let metadata_num_bytes = row_id.total_size_bytes() + timepoint.total_size_bytes();
+        self.metadata_registry.heap_size_bytes -= metadata_num_bytes;
+        gc.row_ids.push(row_id);
+
+        // find all tables that could possibly contain this `RowId`
+        let tables = self.tables.iter_mut().filter_map(|((timeline, _), table)| {
+            timepoint.get(timeline).map(|time| (*time, table))
+        });
+
+        let mut tables_num_bytes = 0;
+        let mut ent_paths = Vec::new();
+        let mut cells = Vec::new();
+        let mut spilled_components = Vec::new();
+        for (time, table) in tables {
+            let num_bytes =
+                table.try_drop_row(row_id, time.as_i64(), &mut cells, &mut spilled_components);
+            if num_bytes > 0 {
+                ent_paths.push(table.ent_path.clone());
+            }
+            tables_num_bytes += num_bytes;
+        }
+        if gc.drop_timeless && timepoint.is_timeless() {
+            for table in self.timeless_tables.values_mut() {
+                let num_bytes = table.try_drop_row(row_id, &mut cells);
+                if num_bytes > 0 {
+                    ent_paths.push(table.ent_path.clone());
+                    tables_num_bytes += num_bytes;
+                    break;
+                }
+            }
+        }
+        for ent_path in &ent_paths {
+            self.bump_entity_generation(ent_path.hash());
+        }
+
+        // NOTE: Tables account for deduplicated data on their own, see `DedupCellCache`.
+        self.release_cells(&cells);
+
+        // A row belongs to a single entity, no matter how many timelines it's indexed on.
+        let entity_path = ent_paths.pop().filter(|_| gc.collect_events);
+        if let Some(entity_path) = entity_path {
+            // Auto-generated cluster keys were never part of the row as it was logged.
+            let mut components: Vec<_> = cells
+                .iter()
+                .filter(|cell| !self.cluster_cell_cache.is_generated(cell))
+                .map(|cell| cell.component_name())
+                .chain(spilled_components)
+                .collect();
+            components.sort();
+            components.dedup();
+            gc.events.push(StoreEvent {
+                kind: StoreEventKind::Deletion,
+                row_id,
+                timepoint: timepoint.clone(),
+                entity_path,
+                components,
+            });
+        }
+
+        Some((metadata_num_bytes, tables_num_bytes))
+    }
+}
+
+// --- Deletion ---
+
+impl DataStore {
+    /// Deletes the rows that match the given `selector`, from all of their timelines, as well as
+    /// from the metadata registry.
+    ///
+    /// Unlike [`DataStore::gc`], this is not about freeing memory: the rows are gone for good,
+    /// timeless ones included, as if they had never been inserted, and without any regard for
+    /// `latest_at` semantics.
+    ///
+    /// Returns one [`StoreEventKind::Deletion`] event per deleted row, which the store's
+    /// subscribers are notified of too.
+    pub fn delete_rows(&mut self, selector: &RowSelector) -> Vec<StoreEvent> {
+        crate::profile_function!();
+
+        let row_ids: BTreeSet<RowId> = match selector {
+            RowSelector::RowIds(row_ids) => row_ids.iter().copied().collect(),
+            RowSelector::TimeRange {
+                entity_path,
+                timeline,
+                time_range,
+            } => self.row_ids_in_time_range(entity_path, *timeline, *time_range),
+        };
+
+        let mut gc = GcState {
+            protect_latest_at: false,
+            protected_paths: Vec::new(),
+            protected_row_ids: None,
+            drop_timeless: true,
+            collect_events: true,
+            num_protected_rows: 0,
+            row_ids: Vec::new(),
+            events: Vec::new(),
+        };
+        for row_id in row_ids {
+            self.gc_try_drop_row(&mut gc, row_id);
+        }
+
+        re_log::debug!(
+            kind = "delete",
+            num_row_ids = gc.row_ids.len(),
+            num_events = gc.events.len(),
+            "deleted rows"
+        );
+
+        #[cfg(debug_assertions)]
+        self.sanity_check().unwrap();
+
+        self.notify_subscribers(&gc.events);
+
+        gc.events
+    }
+
+    /// All the rows of the given entity, but not of its descendants, that are indexed on
+    /// `timeline` within `time_range`.
+    fn row_ids_in_time_range(
+        &self,
+        entity_path: &EntityPath,
+        timeline: Timeline,
+        time_range: TimeRange,
+    ) -> BTreeSet<RowId> {
+        crate::profile_function!();
+
+        let Some(table) = self.tables.get(&(timeline, entity_path.hash())) else {
+            return Default::default();
+        };
+
+        let (min, max) = (time_range.min.as_i64(), time_range.max.as_i64());
+        table
+            .range_buckets(..=time_range.max)
+            .map(|(_, bucket)| bucket)
+            .flat_map(|bucket| {
+                let inner = bucket.inner.read();
+                inner
+                    .col_time
+                    .iter()
+                    .zip(&inner.col_row_id)
+                    .filter(|(time, _)| (min..=max).contains(*time))
+                    .map(|(_, row_id)| *row_id)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 }
 
+/// The bookkeeping of a single garbage collection.
+struct GcState {
+    protect_latest_at: bool,
+
+    protected_paths: Vec<EntityPath>,
+
+    /// The rows of the `protected_paths`, gathered lazily.
+    protected_row_ids: Option<BTreeSet<RowId>>,
+
+    /// Whether timeless rows get dropped too, rather than just forgotten about by the metadata
+    /// registry.
+    drop_timeless: bool,
+
+    /// Whether to fill in `events`, even without subscribers.
+    collect_events: bool,
+
+    num_protected_rows: usize,
+
+    /// The rows dropped so far.
+    row_ids: Vec<RowId>,
+
+    /// The deletion events to notify the store's subscribers of, once done.
+    ///
+    /// Only filled in if `collect_events` is set.
+    events: Vec<StoreEvent>,
+}
+
+fn is_in_subtree(entity_path: &EntityPath, root: &EntityPath) -> bool {
+    entity_path == root || entity_path.is_descendant_of(root)
+}
+
 impl IndexedTable {
     /// Tries to drop the given `row_id` from the table, which is expected to be found at the
     /// specified `time`.
     ///
     /// Returns how many bytes were actually dropped, or zero if the row wasn't found.
-    fn try_drop_row(&mut self, row_id: RowId, time: i64) -> u64 {
+    /// The cells of the dropped row are appended to `dropped_cells`, except for those that were
+    /// spilled to disk, whose components are appended to `spilled_components` instead.
+    fn try_drop_row(
+        &mut self,
+        row_id: RowId,
+        time: i64,
+        dropped_cells: &mut Vec<DataCell>,
+        spilled_components: &mut Vec<ComponentName>,
+    ) -> u64 {
         crate::profile_function!();
 
         let table_has_more_than_one_bucket = self.buckets.len() > 1;
@@ -172,9 +702,11 @@ impl IndexedTable {
         let (bucket_key, bucket) = self.find_bucket_mut(time.into());
         let bucket_num_bytes = bucket.total_size_bytes();
 
+        let num_dropped_cells = dropped_cells.len();
+        let mut spilled_cells = Vec::new();
         let mut dropped_num_bytes = {
             let inner = &mut *bucket.inner.write();
-            inner.try_drop_row(row_id, time)
+            inner.try_drop_row(row_id, time, dropped_cells, &mut spilled_cells)
         };
 
         // NOTE: We always need to keep at least one bucket alive, otherwise we have
@@ -200,6 +732,13 @@ impl IndexedTable {
 
         self.buckets_size_bytes -= dropped_num_bytes;
         self.buckets_num_rows -= (dropped_num_bytes > 0) as u64;
+        for cell in &dropped_cells[num_dropped_cells..] {
+            remove_cell_stats(&mut self.components_stats, cell);
+        }
+        for (component, num_bytes) in spilled_cells {
+            remove_component_stats(&mut self.components_stats, component, num_bytes);
+            spilled_components.push(component);
+        }
 
         dropped_num_bytes
     }
@@ -210,7 +749,17 @@ impl IndexedBucketInner {
     /// specified `time`.
     ///
     /// Returns how many bytes were actually dropped, or zero if the row wasn't found.
-    fn try_drop_row(&mut self, row_id: RowId, time: i64) -> u64 {
+    /// The cells of the dropped row are appended to `dropped_cells`.
+    ///
+    /// Spilled buckets are not paged back in: the component and size of the cells of the dropped
+    /// row that were spilled to disk are appended to `spilled_cells` instead.
+    fn try_drop_row(
+        &mut self,
+        row_id: RowId,
+        time: i64,
+        dropped_cells: &mut Vec<DataCell>,
+        spilled_cells: &mut Vec<(ComponentName, u64)>,
+    ) -> u64 {
         crate::profile_function!();
 
         self.sort();
@@ -224,8 +773,13 @@ impl IndexedBucketInner {
             col_num_instances,
             columns,
             size_bytes,
+            spilled,
+            paged_in,
+            scalar_summaries,
         } = self;
 
+        scalar_summaries.clear();
+
         let mut dropped_num_bytes = 0u64;
 
         let mut row_index = col_time.partition_point(|&time2| time2 < time);
@@ -272,9 +826,19 @@ impl IndexedBucketInner {
 
             // each data column
             for column in columns.values_mut() {
-                dropped_num_bytes += column.0.swap_remove(row_index).total_size_bytes();
+                let cell = column.0.swap_remove(row_index);
+                dropped_num_bytes += cell.total_size_bytes();
+                dropped_cells.extend(cell);
             }
 
+            // the data columns that were spilled to disk
+            if let Some(spilled) = spilled {
+                dropped_num_bytes += spilled.forget_row(row_id, dropped_cells, spilled_cells);
+            }
+
+            // The data on disk doesn't match anymore.
+            *paged_in = None;
+
             // NOTE: A single `RowId` cannot possibly have more than one datapoint for
             // a single timeline.
             break;
@@ -285,3 +849,54 @@ impl IndexedBucketInner {
         dropped_num_bytes
     }
 }
+
+impl PersistentIndexedTable {
+    /// Tries to drop the given `row_id` from the table.
+    ///
+    /// Returns how many bytes were actually dropped, or zero if the row wasn't found.
+    /// The cells of the dropped row are appended to `dropped_cells`.
+    fn try_drop_row(&mut self, row_id: RowId, dropped_cells: &mut Vec<DataCell>) -> u64 {
+        crate::profile_function!();
+
+        let PersistentIndexedTable {
+            ent_path: _,
+            cluster_key: _,
+            col_insert_id,
+            col_row_id,
+            col_num_instances,
+            columns,
+            size_bytes,
+            components_stats,
+        } = self;
+
+        let Some(row_index) = col_row_id.iter().position(|row_id2| *row_id2 == row_id) else {
+            return 0;
+        };
+
+        // NOTE: Timeless data is ordered by insertion, which we must preserve.
+
+        let mut dropped_num_bytes = col_row_id.remove(row_index).total_size_bytes();
+
+        // col_insert_id (if present)
+        if !col_insert_id.is_empty() {
+            dropped_num_bytes += col_insert_id.remove(row_index).total_size_bytes();
+        }
+
+        // col_num_instances
+        dropped_num_bytes += col_num_instances.remove(row_index).total_size_bytes();
+
+        // each data column
+        for column in columns.values_mut() {
+            let cell = column.0.remove(row_index);
+            dropped_num_bytes += cell.total_size_bytes();
+            if let Some(cell) = cell {
+                remove_cell_stats(components_stats, &cell);
+                dropped_cells.push(cell);
+            }
+        }
+
+        *size_bytes -= dropped_num_bytes;
+
+        dropped_num_bytes
+    }
+}
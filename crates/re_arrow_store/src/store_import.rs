@@ -0,0 +1,698 @@
+use std::io::{Read, Seek};
+
+use arrow2::{
+    array::{Array, PrimitiveArray, Utf8Array},
+    chunk::Chunk,
+    compute::cast::{cast, CastOptions},
+    datatypes::{DataType, Field, Schema, TimeUnit},
+};
+use re_log_types::{
+    component_types::{Label, Point3D, Scalar},
+    ApplicationId, BeginRecordingMsg, DataCell, DataRow, DataRowError, DataTable, DataTableError,
+    EntityPath, EntityPathPart, LogMsg, PathParseError, RecordingId, RecordingInfo,
+    RecordingSource, RowId, TableId, Time, TimeInt, TimePoint, TimeType, Timeline,
+};
+
+// ---
+
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("No column named {0:?}")]
+    MissingColumn(String),
+
+    #[error("Column {column:?} is of type {datatype:?}, which can't be used as {usage}")]
+    UnsupportedColumn {
+        column: String,
+        datatype: DataType,
+        usage: &'static str,
+    },
+
+    #[error("Bad entity path {path:?}: {err}")]
+    BadEntityPath { path: String, err: PathParseError },
+
+    #[error("Support for {0} files was not compiled in")]
+    FormatNotEnabled(&'static str),
+
+    #[error("Error with one or more the resulting data rows: {0}")]
+    DataRow(#[from] DataRowError),
+
+    #[error("Error with one or more the resulting data tables: {0}")]
+    DataTable(#[from] DataTableError),
+
+    #[error("Could not read arrow data: {0}")]
+    Arrow(#[from] arrow2::error::Error),
+}
+
+pub type ImportResult<T> = ::std::result::Result<T, ImportError>;
+
+/// The file formats that tables can be imported from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// [Arrow IPC files](https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format).
+    ArrowIpc,
+
+    /// [Parquet files](https://parquet.apache.org/), with the `parquet` feature.
+    Parquet,
+
+    /// CSV files with a header row, with the `csv` feature.
+    Csv,
+}
+
+impl ImportFormat {
+    /// Guesses the format from a file extension, e.g. `csv`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "arrow" | "feather" | "ipc" => Some(Self::ArrowIpc),
+            "parquet" => Some(Self::Parquet),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the extension of a file name or path.
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        Self::from_extension(extension)
+    }
+}
+
+/// Where the rows of an imported table are logged to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntityPathSource {
+    /// E.g. `sensors/{sensor_id}/imu`.
+    ///
+    /// Every `{column}` is replaced by the value of that column in each row.
+    /// A template without any `{column}` logs all rows to the same entity.
+    Template(String),
+
+    /// The full entity path of each row is the value of this column, e.g. `world/points`.
+    Column(String),
+}
+
+impl EntityPathSource {
+    /// The columns this entity path is read from.
+    fn columns(&self) -> Vec<String> {
+        match self {
+            Self::Template(template) => template
+                .split('{')
+                .skip(1)
+                .filter_map(|s| s.split_once('}').map(|(column, _)| column.to_owned()))
+                .collect(),
+            Self::Column(column) => vec![column.clone()],
+        }
+    }
+}
+
+/// A column whose values become the times of the rows on a [`Timeline`] of the same name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimelineColumn {
+    pub column: String,
+
+    /// [`TimeType::Time`] for timestamps and dates, and integers counting nanoseconds since the
+    /// Unix epoch.
+    /// [`TimeType::Sequence`] for integers, e.g. frame numbers.
+    pub time_type: TimeType,
+}
+
+/// How some columns of a table are turned into a component.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ComponentColumns {
+    /// A [`Scalar`] from a numeric column.
+    ///
+    /// It is logged to `<entity>/<column>`, so that each column can be plotted on its own.
+    Scalar(String),
+
+    /// A [`Point3D`] from three numeric columns, logged to the entity of the row.
+    Point3D { x: String, y: String, z: String },
+
+    /// A [`Label`] from a string column, logged to the entity of the row.
+    Label(String),
+}
+
+impl ComponentColumns {
+    fn columns(&self) -> Vec<&str> {
+        match self {
+            Self::Scalar(column) | Self::Label(column) => vec![column],
+            Self::Point3D { x, y, z } => vec![x, y, z],
+        }
+    }
+}
+
+/// How to turn the columns of a table into Rerun data.
+///
+/// Each row of the table becomes one [`DataRow`] per entity it is logged to.
+#[derive(Clone, Debug)]
+pub struct ImportConfig {
+    pub entity_path: EntityPathSource,
+
+    /// If empty, all timestamp and date columns are used as time timelines.
+    /// If there are none, rows are put on a `row_nr` sequence timeline instead.
+    pub timelines: Vec<TimelineColumn>,
+
+    /// If empty, every other column is imported: `x`, `y` and `z` columns become a [`Point3D`],
+    /// numeric columns become [`Scalar`]s and string columns become [`Label`]s.
+    pub components: Vec<ComponentColumns>,
+
+    /// The maximum number of rows in each of the resulting [`DataTable`]s.
+    pub max_rows_per_table: usize,
+}
+
+impl ImportConfig {
+    pub fn new(entity_path: EntityPathSource) -> Self {
+        Self {
+            entity_path,
+            timelines: Vec::new(),
+            components: Vec::new(),
+            max_rows_per_table: 4096,
+        }
+    }
+}
+
+// ---
+
+/// Imports a file of the given format, see [`ImportConfig`].
+pub fn import_file(
+    format: ImportFormat,
+    read: impl Read + Seek,
+    config: &ImportConfig,
+) -> ImportResult<Vec<DataTable>> {
+    match format {
+        ImportFormat::ArrowIpc => import_arrow_ipc(read, config),
+
+        #[cfg(feature = "parquet")]
+        ImportFormat::Parquet => import_parquet(read, config),
+        #[cfg(not(feature = "parquet"))]
+        ImportFormat::Parquet => Err(ImportError::FormatNotEnabled("Parquet")),
+
+        #[cfg(feature = "csv")]
+        ImportFormat::Csv => import_csv(read, config),
+        #[cfg(not(feature = "csv"))]
+        ImportFormat::Csv => Err(ImportError::FormatNotEnabled("CSV")),
+    }
+}
+
+/// Imports an [Arrow IPC file](https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format).
+pub fn import_arrow_ipc(
+    mut read: impl Read + Seek,
+    config: &ImportConfig,
+) -> ImportResult<Vec<DataTable>> {
+    crate::profile_function!();
+
+    use arrow2::io::ipc::read::{read_file_metadata, FileReader};
+
+    let metadata = read_file_metadata(&mut read)?;
+    let schema = metadata.schema.clone();
+    import_chunks(&schema, FileReader::new(read, metadata, None, None), config)
+}
+
+/// Imports a [Parquet file](https://parquet.apache.org/).
+#[cfg(feature = "parquet")]
+pub fn import_parquet(
+    mut read: impl Read + Seek,
+    config: &ImportConfig,
+) -> ImportResult<Vec<DataTable>> {
+    crate::profile_function!();
+
+    use arrow2::io::parquet::read::{infer_schema, read_metadata, FileReader};
+
+    let metadata = read_metadata(&mut read)?;
+    let schema = infer_schema(&metadata)?;
+    let reader = FileReader::new(read, metadata.row_groups, schema.clone(), None, None, None);
+    import_chunks(&schema, reader, config)
+}
+
+/// Imports a CSV file with a header row.
+///
+/// The type of each column is inferred from its values, e.g. RFC 3339 strings are timestamps.
+#[cfg(feature = "csv")]
+pub fn import_csv(read: impl Read + Seek, config: &ImportConfig) -> ImportResult<Vec<DataTable>> {
+    crate::profile_function!();
+
+    use arrow2::io::csv::read::{
+        deserialize_batch, deserialize_column, infer, infer_schema, read_rows, ByteRecord,
+        ReaderBuilder,
+    };
+
+    let mut reader = ReaderBuilder::new().from_reader(read);
+    let (fields, _) = infer_schema(&mut reader, None, true, &infer)?;
+    let schema = Schema::from(fields);
+
+    let mut rows = vec![ByteRecord::default(); 4096];
+    let mut num_rows_read = 0;
+    let chunks = std::iter::from_fn(|| {
+        let num_rows = match read_rows(&mut reader, 0, &mut rows) {
+            Ok(0) => return None,
+            Ok(num_rows) => num_rows,
+            Err(err) => return Some(Err(err)),
+        };
+        let chunk = deserialize_batch(
+            &rows[..num_rows],
+            &schema.fields,
+            None,
+            num_rows_read,
+            deserialize_column,
+        );
+        num_rows_read += num_rows;
+        Some(chunk)
+    });
+
+    import_chunks(&schema, chunks, config)
+}
+
+/// Imports the chunks of a table, e.g. as read by any of the [`arrow2::io`] readers.
+pub fn import_chunks(
+    schema: &Schema,
+    chunks: impl IntoIterator<Item = arrow2::error::Result<Chunk<Box<dyn Array>>>>,
+    config: &ImportConfig,
+) -> ImportResult<Vec<DataTable>> {
+    crate::profile_function!();
+
+    let max_rows_per_table = config.max_rows_per_table.max(1);
+
+    let mut tables = Vec::new();
+    let mut rows = Vec::new();
+    let mut row_offset = 0;
+    for chunk in chunks {
+        let chunk = chunk?;
+        rows.extend(import_chunk(schema, &chunk, row_offset, config)?);
+        row_offset += chunk.len();
+
+        while rows.len() >= max_rows_per_table {
+            let rest = rows.split_off(max_rows_per_table);
+            tables.push(DataTable::from_rows(TableId::random(), rows));
+            rows = rest;
+        }
+    }
+    if !rows.is_empty() {
+        tables.push(DataTable::from_rows(TableId::random(), rows));
+    }
+
+    Ok(tables)
+}
+
+/// Wraps imported tables in the messages of a new recording, e.g. to write them to an `.rrd`
+/// file or to load them in the viewer.
+pub fn import_to_log_msgs(
+    application_id: ApplicationId,
+    tables: &[DataTable],
+) -> ImportResult<Vec<LogMsg>> {
+    crate::profile_function!();
+
+    let recording_id = RecordingId::random();
+    let begin = BeginRecordingMsg {
+        row_id: RowId::random(),
+        info: RecordingInfo {
+            application_id,
+            recording_id,
+            is_official_example: false,
+            started: Time::now(),
+            recording_source: RecordingSource::Other("imported table".to_owned()),
+        },
+    };
+
+    std::iter::once(Ok(LogMsg::BeginRecordingMsg(begin)))
+        .chain(tables.iter().map(|table| -> ImportResult<LogMsg> {
+            Ok(LogMsg::ArrowMsg(recording_id, table.to_arrow_msg()?))
+        }))
+        .collect()
+}
+
+// ---
+
+fn import_chunk(
+    schema: &Schema,
+    chunk: &Chunk<Box<dyn Array>>,
+    row_offset: usize,
+    config: &ImportConfig,
+) -> ImportResult<Vec<DataRow>> {
+    crate::profile_function!();
+
+    let columns = Columns { schema, chunk };
+    let num_rows = chunk.len();
+
+    // Times:
+    let timeline_columns = if config.timelines.is_empty() {
+        columns.auto_timelines()
+    } else {
+        config.timelines.clone()
+    };
+    let mut timelines = Vec::with_capacity(timeline_columns.len());
+    for TimelineColumn { column, time_type } in &timeline_columns {
+        let (field, array) = columns.get(column)?;
+        timelines.push((
+            Timeline::new(column.as_str(), *time_type),
+            time_values(field, array, *time_type)?,
+        ));
+    }
+    if timelines.is_empty() {
+        let row_nrs = (row_offset..row_offset + num_rows)
+            .map(|row_nr| Some(row_nr as i64))
+            .collect();
+        timelines.push((Timeline::new_sequence("row_nr"), row_nrs));
+    }
+
+    // Entity paths:
+    let entity_paths = entity_paths(&columns, &config.entity_path)?;
+
+    // Components:
+    let component_columns = if config.components.is_empty() {
+        let used: Vec<String> = config
+            .entity_path
+            .columns()
+            .into_iter()
+            .chain(timeline_columns.into_iter().map(|timeline| timeline.column))
+            .collect();
+        columns.auto_components(&used)
+    } else {
+        config.components.clone()
+    };
+    let mut cells_per_mapping = Vec::with_capacity(component_columns.len());
+    for mapping in &component_columns {
+        let sub_path = match mapping {
+            ComponentColumns::Scalar(column) => Some(EntityPathPart::from(column.as_str())),
+            ComponentColumns::Point3D { .. } | ComponentColumns::Label(_) => None,
+        };
+        cells_per_mapping.push((sub_path, cells(&columns, mapping)?));
+    }
+
+    let mut rows = Vec::with_capacity(num_rows);
+    let mut num_skipped_rows = 0;
+    for row in 0..num_rows {
+        let Some(entity_path) = &entity_paths[row] else {
+            num_skipped_rows += 1;
+            continue;
+        };
+
+        let timepoint: TimePoint = timelines
+            .iter()
+            .filter_map(|(timeline, times)| Some((*timeline, TimeInt::from(times[row]?))))
+            .collect();
+
+        // Group the cells of this row by the entity they're logged to:
+        let mut cells_per_entity: Vec<(Option<&EntityPathPart>, Vec<DataCell>)> = Vec::new();
+        for (sub_path, cells) in &cells_per_mapping {
+            let Some(cell) = &cells[row] else {
+                continue;
+            };
+            let sub_path = sub_path.as_ref();
+            match cells_per_entity
+                .iter_mut()
+                .find(|(path, _)| *path == sub_path)
+            {
+                Some((_, cells)) => cells.push(cell.clone()),
+                None => cells_per_entity.push((sub_path, vec![cell.clone()])),
+            }
+        }
+
+        for (sub_path, cells) in cells_per_entity {
+            let entity_path = match sub_path {
+                Some(part) => entity_path.iter().chain([part]).cloned().collect(),
+                None => entity_path.clone(),
+            };
+            rows.push(DataRow::try_from_cells(
+                RowId::random(),
+                timepoint.clone(),
+                entity_path,
+                1,
+                cells,
+            )?);
+        }
+    }
+
+    if num_skipped_rows > 0 {
+        re_log::warn!("Skipped {num_skipped_rows} rows without an entity path");
+    }
+
+    Ok(rows)
+}
+
+/// The columns of a chunk, by name.
+struct Columns<'a> {
+    schema: &'a Schema,
+    chunk: &'a Chunk<Box<dyn Array>>,
+}
+
+impl<'a> Columns<'a> {
+    fn get(&self, name: &str) -> ImportResult<(&'a Field, &'a dyn Array)> {
+        self.iter()
+            .find(|(field, _)| field.name == name)
+            .ok_or_else(|| ImportError::MissingColumn(name.to_owned()))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&'a Field, &'a dyn Array)> {
+        self.schema
+            .fields
+            .iter()
+            .zip(self.chunk.arrays().iter().map(|array| &**array))
+    }
+
+    fn auto_timelines(&self) -> Vec<TimelineColumn> {
+        self.iter()
+            .filter(|(field, _)| {
+                matches!(
+                    field.data_type.to_logical_type(),
+                    DataType::Timestamp(_, _) | DataType::Date32 | DataType::Date64
+                )
+            })
+            .map(|(field, _)| TimelineColumn {
+                column: field.name.clone(),
+                time_type: TimeType::Time,
+            })
+            .collect()
+    }
+
+    fn auto_components(&self, used: &[String]) -> Vec<ComponentColumns> {
+        let mut remaining: Vec<&Field> = self
+            .iter()
+            .map(|(field, _)| field)
+            .filter(|field| !used.contains(&field.name))
+            .collect();
+
+        let mut components = Vec::new();
+
+        let find_numeric = |remaining: &[&Field], name: &str| {
+            remaining
+                .iter()
+                .find(|field| field.name.eq_ignore_ascii_case(name) && is_numeric(&field.data_type))
+                .map(|field| field.name.clone())
+        };
+        if let (Some(x), Some(y), Some(z)) = (
+            find_numeric(&remaining, "x"),
+            find_numeric(&remaining, "y"),
+            find_numeric(&remaining, "z"),
+        ) {
+            remaining.retain(|field| field.name != x && field.name != y && field.name != z);
+            components.push(ComponentColumns::Point3D { x, y, z });
+        }
+
+        for field in remaining {
+            if is_numeric(&field.data_type) {
+                components.push(ComponentColumns::Scalar(field.name.clone()));
+            } else if is_string(&field.data_type) {
+                components.push(ComponentColumns::Label(field.name.clone()));
+            } else {
+                re_log::debug!(
+                    "Not importing column {:?} of type {:?}",
+                    field.name,
+                    field.data_type
+                );
+            }
+        }
+
+        components
+    }
+}
+
+fn is_numeric(datatype: &DataType) -> bool {
+    matches!(
+        datatype.to_logical_type(),
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
+fn is_string(datatype: &DataType) -> bool {
+    matches!(
+        datatype.to_logical_type(),
+        DataType::Utf8 | DataType::LargeUtf8
+    )
+}
+
+fn is_integer(datatype: &DataType) -> bool {
+    is_numeric(datatype)
+        && !matches!(
+            datatype.to_logical_type(),
+            DataType::Float16 | DataType::Float32 | DataType::Float64
+        )
+}
+
+fn unsupported(field: &Field, usage: &'static str) -> ImportError {
+    ImportError::UnsupportedColumn {
+        column: field.name.clone(),
+        datatype: field.data_type.clone(),
+        usage,
+    }
+}
+
+/// The values of a column as nanoseconds since the Unix epoch, or as sequence numbers.
+fn time_values(
+    field: &Field,
+    array: &dyn Array,
+    time_type: TimeType,
+) -> ImportResult<Vec<Option<i64>>> {
+    let nanos_per_unit: i64 = match (time_type, array.data_type().to_logical_type()) {
+        (TimeType::Time, DataType::Timestamp(unit, _)) => match unit {
+            TimeUnit::Second => 1_000_000_000,
+            TimeUnit::Millisecond => 1_000_000,
+            TimeUnit::Microsecond => 1_000,
+            TimeUnit::Nanosecond => 1,
+        },
+        (TimeType::Time, DataType::Date32) => {
+            let days = array
+                .as_any()
+                .downcast_ref::<PrimitiveArray<i32>>()
+                .ok_or_else(|| unsupported(field, "a timeline"))?;
+            const NANOS_PER_DAY: i64 = 24 * 60 * 60 * 1_000_000_000;
+            return Ok(days
+                .iter()
+                .map(|days| days.map(|days| *days as i64 * NANOS_PER_DAY))
+                .collect());
+        }
+        (TimeType::Time, DataType::Date64) => 1_000_000, // milliseconds
+        (_, datatype) if is_integer(datatype) => 1,
+        _ => return Err(unsupported(field, "a timeline")),
+    };
+
+    let values = cast(array, &DataType::Int64, CastOptions::default())?;
+    let values = values
+        .as_any()
+        .downcast_ref::<PrimitiveArray<i64>>()
+        .ok_or_else(|| unsupported(field, "a timeline"))?;
+    Ok(values
+        .iter()
+        .map(|value| value.map(|value| value.saturating_mul(nanos_per_unit)))
+        .collect())
+}
+
+fn f64_values(field: &Field, array: &dyn Array) -> ImportResult<Vec<Option<f64>>> {
+    if !is_numeric(array.data_type()) {
+        return Err(unsupported(field, "a number"));
+    }
+    let values = cast(array, &DataType::Float64, CastOptions::default())?;
+    let values = values
+        .as_any()
+        .downcast_ref::<PrimitiveArray<f64>>()
+        .ok_or_else(|| unsupported(field, "a number"))?;
+    Ok(values.iter().map(|value| value.copied()).collect())
+}
+
+fn string_values(field: &Field, array: &dyn Array) -> ImportResult<Vec<Option<String>>> {
+    let values = cast(array, &DataType::Utf8, CastOptions::default())
+        .map_err(|_err| unsupported(field, "a string"))?;
+    let values = values
+        .as_any()
+        .downcast_ref::<Utf8Array<i32>>()
+        .ok_or_else(|| unsupported(field, "a string"))?;
+    Ok(values
+        .iter()
+        .map(|value| value.map(ToOwned::to_owned))
+        .collect())
+}
+
+/// The entity path of each row, or `None` where it is missing.
+fn entity_paths(
+    columns: &Columns<'_>,
+    source: &EntityPathSource,
+) -> ImportResult<Vec<Option<EntityPath>>> {
+    let num_rows = columns.chunk.len();
+
+    match source {
+        EntityPathSource::Template(template) => {
+            let mut values = Vec::new();
+            for column in source.columns() {
+                let (field, array) = columns.get(&column)?;
+                values.push((format!("{{{column}}}"), string_values(field, array)?));
+            }
+
+            let template = template.trim_start_matches('/');
+            (0..num_rows)
+                .map(|row| {
+                    let mut parts = Vec::new();
+                    for part in template.split('/').filter(|part| !part.is_empty()) {
+                        let mut part = part.to_owned();
+                        for (placeholder, values) in &values {
+                            if part.contains(placeholder.as_str()) {
+                                let Some(value) = &values[row] else {
+                                    return Ok(None);
+                                };
+                                part = part.replace(placeholder.as_str(), value);
+                            }
+                        }
+                        parts.push(EntityPathPart::from(part));
+                    }
+                    Ok(Some(EntityPath::from(parts)))
+                })
+                .collect()
+        }
+
+        EntityPathSource::Column(column) => {
+            let (field, array) = columns.get(column)?;
+            string_values(field, array)?
+                .into_iter()
+                .map(|path| {
+                    path.map(|path| {
+                        let parts = re_log_types::parse_entity_path(&path)
+                            .map_err(|err| ImportError::BadEntityPath { path, err })?;
+                        Ok(EntityPath::from(parts))
+                    })
+                    .transpose()
+                })
+                .collect()
+        }
+    }
+}
+
+/// The cell of each row, or `None` where a value is missing.
+fn cells(columns: &Columns<'_>, mapping: &ComponentColumns) -> ImportResult<Vec<Option<DataCell>>> {
+    let mut values = Vec::new();
+    for column in mapping.columns() {
+        values.push(columns.get(column)?);
+    }
+
+    Ok(match mapping {
+        ComponentColumns::Scalar(_) => {
+            let (field, array) = values[0];
+            f64_values(field, array)?
+                .into_iter()
+                .map(|value| value.map(|value| DataCell::from_native(&[Scalar(value)])))
+                .collect()
+        }
+        ComponentColumns::Point3D { .. } => {
+            let xs = f64_values(values[0].0, values[0].1)?;
+            let ys = f64_values(values[1].0, values[1].1)?;
+            let zs = f64_values(values[2].0, values[2].1)?;
+            itertools::izip!(xs, ys, zs)
+                .map(|(x, y, z)| {
+                    let point = Point3D::new(x? as f32, y? as f32, z? as f32);
+                    Some(DataCell::from_native(&[point]))
+                })
+                .collect()
+        }
+        ComponentColumns::Label(_) => {
+            let (field, array) = values[0];
+            string_values(field, array)?
+                .into_iter()
+                .map(|value| value.map(|value| DataCell::from_native(&[Label(value)])))
+                .collect()
+        }
+    })
+}
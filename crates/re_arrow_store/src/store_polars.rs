@@ -176,6 +176,8 @@ impl PersistentIndexedTable {
             col_row_id,
             col_num_instances,
             columns,
+            size_bytes: _,
+            components_stats: _,
         } = self;
 
         let num_rows = self.num_rows() as usize;
@@ -208,6 +210,8 @@ impl IndexedBucket {
     pub fn to_dataframe(&self, store: &DataStore, config: &DataStoreConfig) -> DataFrame {
         crate::profile_function!();
 
+        self.page_in_if_needed();
+
         let IndexedBucketInner {
             is_sorted: _,
             time_range: _,
@@ -217,6 +221,9 @@ impl IndexedBucket {
             col_num_instances,
             columns,
             size_bytes: _,
+            spilled: _,
+            paged_in: _,
+            scalar_summaries: _,
         } = &*self.inner.read();
 
         let (_, times) = DataTable::serialize_primitive_column(
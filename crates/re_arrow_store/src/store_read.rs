@@ -1,10 +1,11 @@
-use std::{ops::RangeBounds, sync::atomic::Ordering};
+use std::{collections::BTreeSet, ops::RangeBounds, sync::atomic::Ordering};
 
 use itertools::Itertools;
 use nohash_hasher::IntSet;
 use re_log::trace;
 use re_log_types::{
-    ComponentName, DataCell, EntityPath, RowId, TimeInt, TimePoint, TimeRange, Timeline,
+    ComponentName, DataCell, EntityPath, EntityPathPattern, RowId, TimeInt, TimePoint, TimeRange,
+    Timeline,
 };
 use smallvec::SmallVec;
 
@@ -71,6 +72,137 @@ impl RangeQuery {
     }
 }
 
+/// A latest-at query on a given timeline, that only considers the rows whose times on other
+/// timelines fall within the given ranges.
+///
+/// E.g. "the latest data as of frame 120 on `frame_nr`, that was logged before `T` on
+/// `log_time`".
+///
+/// See [`DataStore::latest_at_multi`].
+#[derive(Clone)]
+pub struct MultiLatestAtQuery {
+    pub timeline: Timeline,
+    pub at: TimeInt,
+
+    /// Rows must have a time on each of these timelines, within the associated range (all
+    /// inclusive).
+    pub filters: Vec<(Timeline, TimeRange)>,
+}
+
+impl std::fmt::Debug for MultiLatestAtQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "<latest at {} on {:?} (including timeless), filtered on {}>",
+            self.timeline.typ().format(self.at),
+            self.timeline.name(),
+            format_filters(&self.filters),
+        ))
+    }
+}
+
+impl MultiLatestAtQuery {
+    pub const fn new(timeline: Timeline, at: TimeInt) -> Self {
+        Self {
+            timeline,
+            at,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Only consider rows whose time on `timeline` is within `range` (all inclusive).
+    pub fn with_filter(mut self, timeline: Timeline, range: TimeRange) -> Self {
+        self.filters.push((timeline, range));
+        self
+    }
+
+    /// The latest data as of all the times of the given `timepoint` at once, as seen from
+    /// `timeline`.
+    ///
+    /// Returns `None` if `timeline` isn't part of `timepoint`.
+    pub fn at_timepoint(timeline: Timeline, timepoint: &TimePoint) -> Option<Self> {
+        let at = *timepoint.get(&timeline)?;
+        let filters = timepoint
+            .iter()
+            .filter(|(other, _)| **other != timeline)
+            .map(|(other, time)| (*other, TimeRange::new(TimeInt::MIN, *time)))
+            .collect();
+        Some(Self {
+            timeline,
+            at,
+            filters,
+        })
+    }
+}
+
+/// A range query on a given timeline, that only considers the rows whose times on other
+/// timelines fall within the given ranges.
+///
+/// See [`DataStore::range_multi`].
+#[derive(Clone)]
+pub struct MultiRangeQuery {
+    pub timeline: Timeline,
+    pub range: TimeRange,
+
+    /// Rows must have a time on each of these timelines, within the associated range (all
+    /// inclusive).
+    pub filters: Vec<(Timeline, TimeRange)>,
+}
+
+impl std::fmt::Debug for MultiRangeQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "<ranging from {} to {} (all inclusive) on {:?} ({} timeless), filtered on {}>",
+            self.timeline.typ().format(self.range.min),
+            self.timeline.typ().format(self.range.max),
+            self.timeline.name(),
+            if self.range.min == TimeInt::MIN {
+                "including"
+            } else {
+                "excluding"
+            },
+            format_filters(&self.filters),
+        ))
+    }
+}
+
+impl MultiRangeQuery {
+    pub const fn new(timeline: Timeline, range: TimeRange) -> Self {
+        Self {
+            timeline,
+            range,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Only consider rows whose time on `timeline` is within `range` (all inclusive).
+    pub fn with_filter(mut self, timeline: Timeline, range: TimeRange) -> Self {
+        self.filters.push((timeline, range));
+        self
+    }
+}
+
+fn format_filters(filters: &[(Timeline, TimeRange)]) -> String {
+    filters
+        .iter()
+        .map(|(timeline, range)| {
+            format!(
+                "{:?} in {}",
+                timeline.name(),
+                timeline.typ().format_range(*range)
+            )
+        })
+        .join(", ")
+}
+
+/// Does `timepoint` have a time within the associated range on each of the `filters`' timelines?
+fn matches_filters(filters: &[(Timeline, TimeRange)], timepoint: &TimePoint) -> bool {
+    filters.iter().all(|(timeline, range)| {
+        timepoint
+            .get(timeline)
+            .map_or(false, |time| range.contains(*time))
+    })
+}
+
 // --- Data store ---
 
 impl DataStore {
@@ -425,12 +557,267 @@ impl DataStore {
         }
     }
 
+    /// Same as [`Self::latest_at`], but only considers the rows that also match the query's
+    /// filters on other timelines.
+    ///
+    /// On success, also returns the full [`TimePoint`] of the row that was found.
+    /// The timepoint is empty if the data comes solely from the timeless tables.
+    ///
+    /// # Temporal semantics
+    ///
+    /// Same as [`Self::latest_at`]: timeless data isn't subject to the filters, and fills the
+    /// holes left by missing temporal data.
+    pub fn latest_at_multi<const N: usize>(
+        &self,
+        query: &MultiLatestAtQuery,
+        ent_path: &EntityPath,
+        primary: ComponentName,
+        components: &[ComponentName; N],
+    ) -> Option<(RowId, TimePoint, [Option<DataCell>; N])> {
+        crate::profile_function!();
+
+        // TODO(cmc): kind & query_id need to somehow propagate through the span system.
+        self.query_id.fetch_add(1, Ordering::Relaxed);
+
+        let ent_path_hash = ent_path.hash();
+
+        trace!(
+            kind = "latest_at_multi",
+            id = self.query_id.load(Ordering::Relaxed),
+            query = ?query,
+            entity = %ent_path,
+            %primary,
+            ?components,
+            "query started..."
+        );
+
+        let is_match = |row_id: RowId| {
+            self.metadata_registry
+                .get(&row_id)
+                .map_or(false, |timepoint| {
+                    matches_filters(&query.filters, timepoint)
+                })
+        };
+
+        let cells = self
+            .tables
+            .get(&(query.timeline, ent_path_hash))
+            .and_then(|table| table.latest_at_filtered(query.at, primary, components, &is_match));
+
+        // If we've found everything we were looking for in the temporal table, then we can
+        // return the results immediately.
+        if cells
+            .as_ref()
+            .map_or(false, |(_, cells)| cells.iter().all(Option::is_some))
+        {
+            let (row_id, cells) = cells?;
+            let timepoint = self.metadata_registry.get(&row_id).cloned()?;
+            return Some((row_id, timepoint, cells));
+        }
+
+        let cells_timeless = self
+            .timeless_tables
+            .get(&ent_path_hash)
+            .and_then(|table| table.latest_at(primary, components));
+
+        match (cells, cells_timeless) {
+            (Some((row_id, cells)), None) => {
+                let timepoint = self.metadata_registry.get(&row_id).cloned()?;
+                Some((row_id, timepoint, cells))
+            }
+            (None, Some((row_id, cells_timeless))) => {
+                Some((row_id, TimePoint::timeless(), cells_timeless))
+            }
+            (Some((row_id, mut cells)), Some((_, cells_timeless))) => {
+                for (i, cell) in cells_timeless.into_iter().enumerate() {
+                    if cells[i].is_none() {
+                        cells[i] = cell;
+                    }
+                }
+                let timepoint = self.metadata_registry.get(&row_id).cloned()?;
+                Some((row_id, timepoint, cells))
+            }
+            (None, None) => {
+                trace!(
+                    kind = "latest_at_multi",
+                    query = ?query,
+                    entity = %ent_path,
+                    %primary,
+                    ?components,
+                    "primary component not found"
+                );
+                None
+            }
+        }
+    }
+
+    /// Same as [`Self::range`], but only yields the rows that also match the query's filters on
+    /// other timelines, alongside their full [`TimePoint`]s.
+    ///
+    /// # Temporal semantics
+    ///
+    /// Same as [`Self::range`]: iff the query's time range starts at `TimeInt::MIN`, this will
+    /// yield the contents of the timeless tables before anything else, regardless of the
+    /// filters.
+    /// Timeless entries come with an empty timepoint.
+    pub fn range_multi<'a, const N: usize>(
+        &'a self,
+        query: &MultiRangeQuery,
+        ent_path: &EntityPath,
+        components: [ComponentName; N],
+    ) -> impl Iterator<Item = (RowId, TimePoint, [Option<DataCell>; N])> + 'a {
+        // Beware! This merely measures the time it takes to gather all the necessary metadata
+        // for building the returned iterator.
+        crate::profile_function!();
+
+        trace!(
+            kind = "range_multi",
+            query = ?query,
+            entity = %ent_path,
+            ?components,
+            "query started..."
+        );
+
+        let filters = query.filters.clone();
+        let query = RangeQuery::new(query.timeline, query.range);
+        self.range(&query, ent_path, components)
+            .filter_map(move |(time, row_id, cells)| {
+                if time.is_none() {
+                    return Some((row_id, TimePoint::timeless(), cells));
+                }
+                let timepoint = self.metadata_registry.get(&row_id)?;
+                matches_filters(&filters, timepoint).then(|| (row_id, timepoint.clone(), cells))
+            })
+    }
+
+    /// All the entities that hold data in this store, timeless or not, and match `pattern`.
+    ///
+    /// The results are sorted.
+    pub fn entities_matching(&self, pattern: &EntityPathPattern) -> Vec<EntityPath> {
+        crate::profile_function!();
+
+        let ent_paths = self
+            .tables
+            .values()
+            .map(|table| &table.ent_path)
+            .chain(self.timeless_tables.values().map(|table| &table.ent_path));
+
+        let ent_paths: BTreeSet<_> = if let Some(ent_path) = pattern.as_exact() {
+            ent_paths
+                .filter(|ent_path2| **ent_path2 == ent_path)
+                .collect()
+        } else {
+            ent_paths
+                .filter(|ent_path| pattern.matches(ent_path))
+                .collect()
+        };
+
+        ent_paths.into_iter().cloned().collect()
+    }
+
+    /// Runs [`Self::latest_at`] for every entity that matches `pattern`.
+    ///
+    /// Only the entities for which the `primary` component could be found are returned, sorted
+    /// by entity path.
+    pub fn latest_at_pattern<const N: usize>(
+        &self,
+        query: &LatestAtQuery,
+        pattern: &EntityPathPattern,
+        primary: ComponentName,
+        components: &[ComponentName; N],
+    ) -> Vec<(EntityPath, RowId, [Option<DataCell>; N])> {
+        crate::profile_function!();
+
+        trace!(
+            kind = "latest_at_pattern",
+            query = ?query,
+            %pattern,
+            %primary,
+            ?components,
+            "query started..."
+        );
+
+        self.entities_matching(pattern)
+            .into_iter()
+            .filter_map(|ent_path| {
+                let (row_id, cells) = self.latest_at(query, &ent_path, primary, components)?;
+                Some((ent_path, row_id, cells))
+            })
+            .collect()
+    }
+
+    /// Runs [`Self::range`] for every entity that matches `pattern`.
+    ///
+    /// The results are yielded one entity after the other, sorted by entity path.
+    pub fn range_pattern<'a, const N: usize>(
+        &'a self,
+        query: &RangeQuery,
+        pattern: &EntityPathPattern,
+        components: [ComponentName; N],
+    ) -> impl Iterator<Item = (EntityPath, Option<TimeInt>, RowId, [Option<DataCell>; N])> + 'a
+    {
+        // Beware! This merely measures the time it takes to gather all the necessary metadata
+        // for building the returned iterator.
+        crate::profile_function!();
+
+        trace!(
+            kind = "range_pattern",
+            query = ?query,
+            %pattern,
+            ?components,
+            "query started..."
+        );
+
+        let query = query.clone();
+        self.entities_matching(pattern)
+            .into_iter()
+            .flat_map(move |ent_path| {
+                self.range(&query, &ent_path, components)
+                    .map(move |(time, row_id, cells)| (ent_path.clone(), time, row_id, cells))
+            })
+    }
+
     pub fn get_msg_metadata(&self, row_id: &RowId) -> Option<&TimePoint> {
         crate::profile_function!();
 
         self.metadata_registry.get(row_id)
     }
 
+    /// Does the given entity have any data within `time_range` on `timeline`?
+    ///
+    /// Ignores timeless data.
+    ///
+    /// This is much cheaper than running an actual query, which makes it useful to know whether
+    /// the results of a past query still hold at a different time.
+    pub fn has_data_in_range(
+        &self,
+        timeline: Timeline,
+        ent_path: &EntityPath,
+        time_range: TimeRange,
+    ) -> bool {
+        crate::profile_function!();
+
+        let Some(table) = self.tables.get(&(timeline, ent_path.hash())) else {
+            return false;
+        };
+
+        for (_, bucket) in table.range_buckets_rev(..=time_range.max) {
+            let inner = bucket.inner.read();
+            if inner.time_range.max < time_range.min {
+                break;
+            }
+            if time_range.min <= inner.time_range.min && inner.time_range.max <= time_range.max {
+                return true;
+            }
+            let (min, max) = (time_range.min.as_i64(), time_range.max.as_i64());
+            if inner.col_time.iter().any(|time| (min..=max).contains(time)) {
+                return true;
+            }
+        }
+
+        false
+    }
+
     /// Sort all unsorted indices in the store.
     pub fn sort_indices_if_needed(&mut self) {
         for index in self.tables.values_mut() {
@@ -492,6 +879,26 @@ impl IndexedTable {
         None // primary component not found
     }
 
+    /// Same as [`Self::latest_at`], but only considers the rows for which `is_match` holds.
+    pub fn latest_at_filtered<const N: usize>(
+        &self,
+        time: TimeInt,
+        primary: ComponentName,
+        components: &[ComponentName; N],
+        is_match: &impl Fn(RowId) -> bool,
+    ) -> Option<(RowId, [Option<DataCell>; N])> {
+        crate::profile_function!();
+
+        // Early-exit if this entire table is unaware of this component.
+        if !self.all_components.contains(&primary) {
+            return None;
+        }
+
+        // Just like unfiltered queries, we might have to walk backwards across multiple buckets.
+        self.range_buckets_rev(..=time)
+            .find_map(|(_, bucket)| bucket.latest_at_filtered(time, primary, components, is_match))
+    }
+
     /// Iterates the table in order to return the cells of the the specified `components`,
     /// as seen from the point of view of the so-called `primary` component, for the given time
     /// range.
@@ -663,6 +1070,9 @@ impl IndexedBucket {
     ) -> Option<(RowId, [Option<DataCell>; N])> {
         crate::profile_function!();
 
+        // NOTE: Should its spilled data be unreadable, the bucket has no component data to speak
+        // of and is skipped altogether.
+        self.page_in_if_needed();
         self.sort_indices_if_needed();
 
         let IndexedBucketInner {
@@ -674,6 +1084,9 @@ impl IndexedBucket {
             col_num_instances: _,
             columns,
             size_bytes: _,
+            spilled: _,
+            paged_in: _,
+            scalar_summaries: _,
         } = &*self.inner.read();
         debug_assert!(is_sorted);
 
@@ -761,6 +1174,64 @@ impl IndexedBucket {
         Some((col_row_id[secondary_row_nr as usize], cells))
     }
 
+    /// Same as [`Self::latest_at`], but only considers the rows for which `is_match` holds.
+    pub fn latest_at_filtered<const N: usize>(
+        &self,
+        time: TimeInt,
+        primary: ComponentName,
+        components: &[ComponentName; N],
+        is_match: &impl Fn(RowId) -> bool,
+    ) -> Option<(RowId, [Option<DataCell>; N])> {
+        crate::profile_function!();
+
+        self.page_in_if_needed();
+        self.sort_indices_if_needed();
+
+        let IndexedBucketInner {
+            is_sorted,
+            time_range: _,
+            col_time,
+            col_insert_id: _,
+            col_row_id,
+            col_num_instances: _,
+            columns,
+            size_bytes: _,
+            spilled: _,
+            paged_in: _,
+            scalar_summaries: _,
+        } = &*self.inner.read();
+        debug_assert!(is_sorted);
+
+        // Early-exit if this bucket is unaware of this component.
+        let column = columns.get(&primary)?;
+
+        // The partition point is always _beyond_ the index that we're looking for, walk
+        // backwards from there until we find a matching row that holds the primary component.
+        let time_row_nr = col_time.partition_point(|t| *t <= time.as_i64());
+        let row_nr = (0..time_row_nr)
+            .rev()
+            .find(|&row_nr| column[row_nr].is_some() && is_match(col_row_id[row_nr]))?;
+
+        trace!(
+            kind = "latest_at_filtered",
+            %primary,
+            ?components,
+            timeline = %self.timeline.name(),
+            time = self.timeline.typ().format(time),
+            %row_nr,
+            "found row number",
+        );
+
+        let mut cells = [(); N].map(|_| None);
+        for (i, component) in components.iter().enumerate() {
+            if let Some(column) = columns.get(component) {
+                cells[i] = column[row_nr].clone() /* shallow */;
+            }
+        }
+
+        Some((col_row_id[row_nr], cells))
+    }
+
     /// Iterates the bucket in order to return the cells of the the specified `components`,
     /// as seen from the point of view of the so-called `primary` component, for the given time
     /// range.
@@ -776,6 +1247,7 @@ impl IndexedBucket {
         time_range: TimeRange,
         components: [ComponentName; N],
     ) -> impl Iterator<Item = (TimeInt, RowId, [Option<DataCell>; N])> + '_ {
+        self.page_in_if_needed();
         self.sort_indices_if_needed();
 
         let IndexedBucketInner {
@@ -787,6 +1259,9 @@ impl IndexedBucket {
             col_num_instances: _,
             columns,
             size_bytes: _,
+            spilled: _,
+            paged_in: _,
+            scalar_summaries: _,
         } = &*self.inner.read();
         debug_assert!(is_sorted);
 
@@ -899,6 +1374,9 @@ impl IndexedBucketInner {
             col_num_instances,
             columns,
             size_bytes: _,
+            spilled: _,
+            paged_in: _,
+            scalar_summaries: _,
         } = self;
 
         if *is_sorted {
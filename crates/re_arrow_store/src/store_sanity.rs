@@ -1,9 +1,15 @@
 use re_log_types::{
-    ComponentName, DataCellColumn, SizeBytes as _, TimeRange, COLUMN_NUM_INSTANCES, COLUMN_ROW_ID,
-    COLUMN_TIMEPOINT,
+    ComponentName, DataCell, DataCellColumn, SizeBytes as _, TimeRange, COLUMN_NUM_INSTANCES,
+    COLUMN_ROW_ID, COLUMN_TIMEPOINT,
 };
 
-use crate::{DataStore, IndexedBucket, IndexedBucketInner, IndexedTable, PersistentIndexedTable};
+use nohash_hasher::IntMap;
+
+use crate::{
+    store_dedup::cell_address, store_stats::components_stats_uncached, DataStore,
+    DataStoreRowStats, DedupCellCache, IndexedBucket, IndexedBucketInner, IndexedTable,
+    PersistentIndexedTable,
+};
 
 // ---
 
@@ -31,6 +37,25 @@ pub enum SanityError {
         got: String,
     },
 
+    #[error(
+        "Reported stats for component '{component}' are out of sync: got {got:?}, expected {expected:?}"
+    )]
+    ComponentStatsOutOfSync {
+        component: ComponentName,
+        expected: DataStoreRowStats,
+        got: DataStoreRowStats,
+    },
+
+    #[error(
+        "Reported references to deduplicated cells are out of sync: got {got} references to {got_cells} cells, expected {expected} references to {expected_cells} cells"
+    )]
+    DedupRefsOutOfSync {
+        expected: u64,
+        expected_cells: u64,
+        got: u64,
+        got_cells: u64,
+    },
+
     #[error("Column '{component}' has too few/many rows: got {got} instead of {expected}")]
     ColumnLengthMismatch {
         component: ComponentName,
@@ -72,6 +97,84 @@ impl DataStore {
             table.sanity_check()?;
         }
 
+        self.sanity_check_dedup_cell_cache()?;
+
+        Ok(())
+    }
+
+    /// Makes sure that the deduplicated cells are referenced exactly as many times as the cache
+    /// thinks they are, and that their sizes are properly accounted for.
+    fn sanity_check_dedup_cell_cache(&self) -> SanityResult<()> {
+        crate::profile_function!();
+
+        let cache = &self.dedup_cell_cache;
+
+        {
+            let mut num_refs_uncached: IntMap<usize, u64> = IntMap::default();
+            let mut count_ref = |cell: &DataCell| {
+                if cache.is_deduplicated(cell) {
+                    *num_refs_uncached.entry(cell_address(cell)).or_default() += 1;
+                }
+            };
+            for table in self.timeless_tables.values() {
+                table
+                    .columns
+                    .values()
+                    .flat_map(|column| column.iter().flatten())
+                    .for_each(&mut count_ref);
+            }
+            for table in self.tables.values() {
+                for bucket in table.buckets.values() {
+                    let inner = bucket.inner.read();
+                    inner
+                        .columns
+                        .values()
+                        .flat_map(|column| column.iter().flatten())
+                        .for_each(&mut count_ref);
+                    if let Some(spilled) = &inner.spilled {
+                        spilled.shared_cells().for_each(&mut count_ref);
+                    }
+                }
+            }
+
+            let num_refs: IntMap<usize, u64> = cache
+                .num_refs
+                .iter()
+                .map(|(address, (_, num_refs))| (*address, *num_refs))
+                .collect();
+            if num_refs != num_refs_uncached {
+                return Err(SanityError::DedupRefsOutOfSync {
+                    expected: num_refs_uncached.values().sum(),
+                    expected_cells: num_refs_uncached.len() as _,
+                    got: num_refs.values().sum(),
+                    got_cells: num_refs.len() as _,
+                });
+            }
+        }
+
+        {
+            let size_bytes = cache.saved_size_bytes;
+            let size_bytes_uncached: u64 = cache
+                .cells
+                .values()
+                .flatten()
+                .map(|cell| {
+                    let num_refs = cache
+                        .num_refs
+                        .get(&cell_address(cell))
+                        .map_or(0, |(_, num_refs)| *num_refs);
+                    num_refs.saturating_sub(1) * cell.total_size_bytes()
+                })
+                .sum();
+            if size_bytes != size_bytes_uncached {
+                return Err(SanityError::SizeOutOfSync {
+                    origin: std::any::type_name::<DedupCellCache>(),
+                    expected: re_format::format_bytes(size_bytes_uncached as _),
+                    got: re_format::format_bytes(size_bytes as _),
+                });
+            }
+        }
+
         Ok(())
     }
 }
@@ -136,6 +239,24 @@ impl IndexedTable {
             }
         }
 
+        // Make sure component stats aren't out of sync
+        //
+        // NOTE: Spilled buckets don't have any component data in memory to check.
+        let is_spilled = self
+            .buckets
+            .values()
+            .any(|bucket| bucket.inner.read().spilled.is_some());
+        if !is_spilled {
+            let buckets: Vec<_> = self
+                .buckets
+                .values()
+                .map(|bucket| bucket.inner.read())
+                .collect();
+            let components_stats_uncached =
+                components_stats_uncached(buckets.iter().flat_map(|inner| inner.columns.values()));
+            check_components_stats(&self.components_stats, &components_stats_uncached)?;
+        }
+
         Ok(())
     }
 }
@@ -163,6 +284,9 @@ impl IndexedBucket {
                 col_num_instances,
                 columns,
                 size_bytes: _,
+                spilled,
+                paged_in: _,
+                scalar_summaries: _,
             } = &*inner.read();
 
             // Time ranges are eagerly maintained.
@@ -214,7 +338,9 @@ impl IndexedBucket {
             }
 
             // The cluster column must be fully dense.
-            if self.num_rows() > 0 {
+            //
+            // NOTE: Spilled buckets don't have any component data in memory to check.
+            if self.num_rows() > 0 && spilled.is_none() {
                 let cluster_column =
                     columns
                         .get(cluster_key)
@@ -262,8 +388,28 @@ impl PersistentIndexedTable {
             col_row_id,
             col_num_instances,
             columns,
+            size_bytes,
+            components_stats,
         } = self;
 
+        // Make sure size values aren't out of sync
+        {
+            let size_bytes_uncached = self.size_bytes_uncached();
+            if *size_bytes != size_bytes_uncached {
+                return Err(SanityError::SizeOutOfSync {
+                    origin: std::any::type_name::<Self>(),
+                    expected: re_format::format_bytes(size_bytes_uncached as _),
+                    got: re_format::format_bytes(*size_bytes as _),
+                });
+            }
+        }
+
+        // Make sure component stats aren't out of sync
+        check_components_stats(
+            components_stats,
+            &components_stats_uncached(columns.values()),
+        )?;
+
         // All columns should be `Self::num_rows` long.
         {
             let num_rows = self.num_rows();
@@ -312,3 +458,29 @@ impl PersistentIndexedTable {
         Ok(())
     }
 }
+
+// --- Components ---
+
+fn check_components_stats(
+    components_stats: &IntMap<ComponentName, DataStoreRowStats>,
+    components_stats_uncached: &IntMap<ComponentName, DataStoreRowStats>,
+) -> SanityResult<()> {
+    let components = components_stats
+        .keys()
+        .chain(components_stats_uncached.keys());
+    for component in components {
+        let got = components_stats.get(component).copied().unwrap_or_default();
+        let expected = components_stats_uncached
+            .get(component)
+            .copied()
+            .unwrap_or_default();
+        if got != expected {
+            return Err(SanityError::ComponentStatsOutOfSync {
+                component: *component,
+                expected,
+                got,
+            });
+        }
+    }
+    Ok(())
+}
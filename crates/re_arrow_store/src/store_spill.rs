@@ -0,0 +1,705 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use arrow2::{chunk::Chunk, datatypes::Schema};
+use nohash_hasher::IntMap;
+use re_log_types::{
+    ComponentName, DataCell, DataCellColumn, DataTable, DataTableError, RowId, SizeBytes as _,
+};
+use smallvec::SmallVec;
+
+use crate::{
+    store::{ClusterCellCache, DedupCellCache, IndexedBucketInner},
+    DataStore, IndexedBucket,
+};
+
+// ---
+
+#[derive(thiserror::Error, Debug)]
+pub enum SpillError {
+    #[error("Could not access the spill file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error with one or more the underlying data cells: {0}")]
+    DataTable(#[from] DataTableError),
+
+    #[error("Could not read or write arrow data: {0}")]
+    Arrow(#[from] arrow2::error::Error),
+}
+
+pub type SpillResult<T> = ::std::result::Result<T, SpillError>;
+
+#[derive(Debug, Clone)]
+pub enum SpillTarget {
+    /// Try to spill _at least_ the given fraction of the temporal data that is still resident.
+    ///
+    /// The fraction must be a float in the range [0.0 : 1.0].
+    SpillAtLeastFraction(f64),
+
+    /// Spill until at most `max_bytes` of temporal data are left resident.
+    MaxResidentBytes(u64),
+}
+
+impl std::fmt::Display for SpillTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpillTarget::SpillAtLeastFraction(p) => {
+                write!(f, "SpillAtLeast({:.3}%)", re_format::format_f64(*p * 100.0))
+            }
+            SpillTarget::MaxResidentBytes(max_bytes) => {
+                write!(
+                    f,
+                    "MaxResident({})",
+                    re_format::format_bytes(*max_bytes as _)
+                )
+            }
+        }
+    }
+}
+
+/// Controls how [`DataStore::spill`] moves data to disk.
+#[derive(Debug, Clone)]
+pub struct SpillOptions {
+    /// The directory the spilled buckets are written to, e.g. a subdirectory of
+    /// [`std::env::temp_dir`].
+    ///
+    /// It is created if needed. Each store writes to its own subdirectory within, which is
+    /// removed once the store (and all of its clones) are dropped.
+    /// The subdirectories left behind by processes that are not running anymore, e.g. because
+    /// they crashed, are removed the first time a store spills to `dir`.
+    pub dir: PathBuf,
+
+    /// How much data to spill.
+    pub target: SpillTarget,
+}
+
+impl DataStore {
+    /// Spills the component data of cold buckets to disk according to the desired `options`,
+    /// as an alternative to purging it with [`DataStore::gc`].
+    ///
+    /// Returns how many bytes of component data were spilled.
+    ///
+    /// ## Semantics
+    ///
+    /// Buckets that were paged back in and haven't been modified since are spilled first, least
+    /// recently accessed first: their spill file is still around, so this doesn't write anything.
+    /// Other buckets are then spilled in the order they were last written to, driven by [`RowId`]
+    /// order, just like garbage collection.
+    /// The most recent bucket of each table, which is where new rows land, always stays resident.
+    ///
+    /// Only the component data of a bucket is spilled, as an arrow IPC file: its control columns
+    /// (times, `RowId`s, ...) stay in memory, so that spilled buckets can still be indexed and
+    /// garbage collected without being paged back in.
+    ///
+    /// Spilling is transparent: any query or write that touches a spilled bucket pages its data
+    /// back in first, where it stays until it gets spilled again.
+    /// Should a spill file turn out to be unreadable, its bucket stays spilled: queries skip it
+    /// and writes into it fail, see [`crate::WriteError::Spill`].
+    /// Spilled data still counts towards the size of the store, see
+    /// [`crate::DataStoreStats::spilled`] for how much of it is resident.
+    pub fn spill(&mut self, options: &SpillOptions) -> SpillResult<u64> {
+        crate::profile_function!();
+
+        let SpillOptions { dir, target } = options;
+
+        let resident_num_bytes = self
+            .temporal_size_bytes()
+            .saturating_sub(self.spilled_stats().num_bytes);
+        let num_bytes_to_spill = match target {
+            SpillTarget::SpillAtLeastFraction(p) => {
+                assert!((0.0..=1.0).contains(p));
+                (resident_num_bytes as f64 * p) as u64
+            }
+            SpillTarget::MaxResidentBytes(max_bytes) => {
+                resident_num_bytes.saturating_sub(*max_bytes)
+            }
+        };
+
+        re_log::debug!(
+            kind = "spill",
+            %target,
+            resident = re_format::format_bytes(resident_num_bytes as _),
+            to_spill = re_format::format_bytes(num_bytes_to_spill as _),
+            "starting spill"
+        );
+
+        if num_bytes_to_spill == 0 {
+            return Ok(0);
+        }
+
+        let spill_dir = match &self.spill_dir {
+            Some(spill_dir) if spill_dir.parent == *dir => spill_dir.clone(),
+            _ => {
+                let spill_dir = Arc::new(SpillDir::new(dir)?);
+                self.spill_dir = Some(spill_dir.clone());
+                spill_dir
+            }
+        };
+
+        // All resident, non-empty buckets but the most recent one of each table.
+        let mut paged_in = Vec::new();
+        let mut never_spilled = Vec::new();
+        for table in self.tables.values_mut() {
+            let num_buckets = table.buckets.len();
+            for bucket in table
+                .buckets
+                .values_mut()
+                .take(num_buckets.saturating_sub(1))
+            {
+                let inner = bucket.inner.get_mut();
+                if inner.spilled.is_some() {
+                    continue;
+                }
+                if let Some(last_access) = inner.paged_in.as_ref().map(PagedInColumns::last_access)
+                {
+                    paged_in.push((last_access, inner));
+                } else if let Some(last_row_id) = inner.col_row_id.iter().max().copied() {
+                    never_spilled.push((last_row_id, inner));
+                }
+            }
+        }
+        paged_in.sort_by_key(|(last_access, _)| *last_access);
+        never_spilled.sort_by_key(|(last_row_id, _)| *last_row_id);
+
+        let buckets = paged_in
+            .into_iter()
+            .map(|(_, inner)| inner)
+            .chain(never_spilled.into_iter().map(|(_, inner)| inner));
+
+        let mut num_bytes_spilled = 0;
+        for inner in buckets {
+            if num_bytes_spilled >= num_bytes_to_spill {
+                break;
+            }
+            num_bytes_spilled +=
+                inner.spill(&spill_dir, &self.cluster_cell_cache, &self.dedup_cell_cache)?;
+        }
+
+        re_log::debug!(
+            kind = "spill",
+            %target,
+            spilled = re_format::format_bytes(num_bytes_spilled as _),
+            "spill done"
+        );
+
+        #[cfg(debug_assertions)]
+        self.sanity_check().unwrap();
+
+        Ok(num_bytes_spilled)
+    }
+}
+
+// ---
+
+/// The component data of an [`IndexedBucket`] that has been spilled to disk.
+///
+/// Clones of a store share their spill files.
+#[derive(Debug, Clone)]
+pub struct SpilledColumns {
+    file: Arc<SpillFile>,
+
+    /// All the components the bucket had, including those that only held nulls and weren't
+    /// written to disk.
+    components: Vec<ComponentName>,
+
+    /// The cells that are deduplicated or auto-generated by the store, along with their component
+    /// and row index in the spill file.
+    ///
+    /// These stay resident: their data is owned by the store as a whole rather than by the
+    /// bucket, see [`crate::DataStoreConfig::dedup_min_size_bytes`] and [`ClusterCellCache`].
+    shared: Vec<(ComponentName, usize, DataCell)>,
+
+    /// The [`RowId`] of each row of the spill file.
+    ///
+    /// Rows can be dropped from a spilled bucket without paging it in, so this is what matches
+    /// the spilled data back to the control columns.
+    row_ids: Vec<RowId>,
+
+    /// The component and size of each cell of each row of the spill file, [`Self::shared`] ones
+    /// excluded.
+    ///
+    /// This is what keeps the stats of the store in sync when rows get dropped without being
+    /// paged in.
+    row_cells: Vec<SmallVec<[(ComponentName, u64); 4]>>,
+
+    /// The size of the spilled component data of each row of the spill file, in bytes.
+    ///
+    /// Unlike [`Self::row_cells`], this includes the empty slots of the row, as well as the slots
+    /// of its [`Self::shared`] cells (but not their data), just like the columns it was spilled
+    /// from.
+    row_num_bytes: Vec<u64>,
+
+    /// The size of the spilled component data of the rows that are still around, in bytes.
+    num_bytes: u64,
+}
+
+impl SpilledColumns {
+    /// The path of the file holding the spilled data.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    /// The size of the spilled component data, in bytes.
+    #[inline]
+    pub fn num_bytes(&self) -> u64 {
+        self.num_bytes
+    }
+
+    /// The deduplicated and auto-generated cells that stayed resident, see [`Self::shared`].
+    #[inline]
+    pub(crate) fn shared_cells(&self) -> impl Iterator<Item = &DataCell> {
+        self.shared.iter().map(|(_, _, cell)| cell)
+    }
+
+    /// Forgets about the spilled data of a row that is being dropped from its bucket, without
+    /// reading anything back from disk.
+    ///
+    /// Returns the size of the forgotten data, resident cells included, in bytes.
+    /// The cells of the row that stayed resident are appended to `dropped_cells`, while the
+    /// component and size of those that were spilled are appended to `spilled_cells`.
+    pub(crate) fn forget_row(
+        &mut self,
+        row_id: RowId,
+        dropped_cells: &mut Vec<DataCell>,
+        spilled_cells: &mut Vec<(ComponentName, u64)>,
+    ) -> u64 {
+        let Some(row_index) = self.row_ids.iter().position(|row_id2| *row_id2 == row_id) else {
+            return 0;
+        };
+
+        let mut num_bytes = std::mem::take(&mut self.row_num_bytes[row_index]);
+        self.num_bytes = self.num_bytes.saturating_sub(num_bytes);
+        spilled_cells.extend(std::mem::take(&mut self.row_cells[row_index]));
+
+        let (dropped, kept) = std::mem::take(&mut self.shared)
+            .into_iter()
+            .partition(|(_, row_index2, _)| *row_index2 == row_index);
+        self.shared = kept;
+        for (_, _, cell) in dropped {
+            num_bytes += cell.heap_size_bytes(); // its slot is part of `row_num_bytes`
+            dropped_cells.push(cell);
+        }
+
+        num_bytes
+    }
+}
+
+/// The spill file of an [`IndexedBucket`] whose component data has been paged back in and hasn't
+/// been modified since.
+///
+/// The bucket can thus be spilled again without writing anything, see [`DataStore::spill`].
+#[derive(Debug)]
+pub(crate) struct PagedInColumns {
+    /// Its [`SpilledColumns::shared`] cells are back in the bucket, and thus left empty.
+    spilled: SpilledColumns,
+
+    /// When the bucket was last accessed, for least-recently-used eviction.
+    last_access: AtomicU64,
+}
+
+impl Clone for PagedInColumns {
+    fn clone(&self) -> Self {
+        Self {
+            spilled: self.spilled.clone(),
+            last_access: self.last_access().into(),
+        }
+    }
+}
+
+impl PagedInColumns {
+    fn new(spilled: SpilledColumns) -> Self {
+        let this = Self {
+            spilled,
+            last_access: AtomicU64::new(0),
+        };
+        this.touch();
+        this
+    }
+
+    #[inline]
+    fn last_access(&self) -> u64 {
+        self.last_access.load(Ordering::Relaxed)
+    }
+
+    /// Marks the bucket as just accessed.
+    #[inline]
+    fn touch(&self) {
+        static CLOCK: AtomicU64 = AtomicU64::new(0);
+        let now = CLOCK.fetch_add(1, Ordering::Relaxed);
+        self.last_access.store(now, Ordering::Relaxed);
+    }
+}
+
+/// The spill files of a single [`DataStore`] and its clones live in here.
+///
+/// It is created within [`SpillOptions::dir`], and removed once dropped.
+#[derive(Debug)]
+pub(crate) struct SpillDir {
+    /// The [`SpillOptions::dir`] this lives in.
+    parent: PathBuf,
+
+    path: PathBuf,
+}
+
+/// Spill directories are named `{SPILL_DIR_PREFIX}{pid}-{id}`, so that the ones that were left
+/// behind by a process that is not running anymore can be told apart.
+const SPILL_DIR_PREFIX: &str = "rerun-spill-";
+
+impl SpillDir {
+    fn new(parent: &Path) -> SpillResult<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+        std::fs::create_dir_all(parent)?;
+        remove_stale_spill_dirs(parent);
+
+        let path = parent.join(format!("{SPILL_DIR_PREFIX}{}-{id}", std::process::id()));
+        std::fs::create_dir_all(&path)?;
+
+        Ok(Self {
+            parent: parent.to_owned(),
+            path,
+        })
+    }
+}
+
+impl Drop for SpillDir {
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                re_log::warn_once!("Failed to remove spill directory {:?}: {err}", self.path);
+            }
+        }
+    }
+}
+
+/// Removes the spill directories that were left behind in `parent` by processes that are not
+/// running anymore, e.g. because they crashed.
+fn remove_stale_spill_dirs(parent: &Path) {
+    crate::profile_function!();
+
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let pid = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(SPILL_DIR_PREFIX))
+            .and_then(|name| name.split('-').next())
+            .and_then(|pid| pid.parse::<u32>().ok());
+        let Some(pid) = pid else {
+            continue;
+        };
+        if pid == std::process::id() || is_process_running(pid) {
+            continue;
+        }
+
+        let path = entry.path();
+        re_log::debug!("Removing stale spill directory {path:?}");
+        if let Err(err) = std::fs::remove_dir_all(&path) {
+            re_log::warn_once!("Failed to remove stale spill directory {path:?}: {err}");
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_process_running(pid: u32) -> bool {
+    use sysinfo::{PidExt as _, SystemExt as _};
+    sysinfo::System::new().refresh_process(sysinfo::Pid::from_u32(pid))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_process_running(_pid: u32) -> bool {
+    true // can't tell, better safe than sorry
+}
+
+/// Removes the underlying file once dropped.
+#[derive(Debug)]
+struct SpillFile {
+    path: PathBuf,
+
+    /// Keeps the directory around for as long as any of its files are.
+    _dir: Arc<SpillDir>,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => {}
+            // NOTE: The file might never have been created in the first place.
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                re_log::warn_once!("Failed to remove spill file {:?}: {err}", self.path);
+            }
+        }
+    }
+}
+
+impl SpillFile {
+    fn new(dir: &Arc<SpillDir>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self {
+            path: dir.path.join(format!("{id}.arrow")),
+            _dir: dir.clone(),
+        }
+    }
+}
+
+impl IndexedBucket {
+    /// Pages the component data of this bucket back in from disk, provided it had been spilled.
+    ///
+    /// Returns whether the component data is resident. Should the spill file be unreadable, the
+    /// error is logged and the bucket stays spilled.
+    pub fn page_in_if_needed(&self) -> bool {
+        {
+            let inner = self.inner.read();
+            if inner.spilled.is_none() {
+                if let Some(paged_in) = &inner.paged_in {
+                    paged_in.touch();
+                }
+                return true; // early read-only exit
+            }
+        }
+
+        crate::profile_scope!("page_in");
+        let mut inner = self.inner.write();
+        match inner.page_in() {
+            Ok(()) => true,
+            Err(err) => {
+                let path = inner.spilled.as_ref().map(SpilledColumns::path);
+                re_log::error_once!("Failed to page in spilled data from {path:?}: {err}");
+                false
+            }
+        }
+    }
+}
+
+impl IndexedBucketInner {
+    /// Drops the component data of this bucket from memory, writing it to a new file in `dir`
+    /// first unless it still has the one it was paged in from.
+    ///
+    /// Returns the size of the spilled component data, in bytes.
+    ///
+    /// The cells that were deduplicated or auto-generated by the store stay resident, so they
+    /// can still be told apart once paged back in, see [`ClusterCellCache::is_generated`] and
+    /// [`DedupCellCache::is_deduplicated`].
+    fn spill(
+        &mut self,
+        dir: &Arc<SpillDir>,
+        cluster_cell_cache: &ClusterCellCache,
+        dedup_cell_cache: &DedupCellCache,
+    ) -> SpillResult<u64> {
+        crate::profile_function!();
+
+        if let Some(paged_in) = self.paged_in.take() {
+            let PagedInColumns {
+                mut spilled,
+                last_access: _,
+            } = paged_in;
+
+            let row_indices: ahash::HashMap<RowId, usize> = spilled
+                .row_ids
+                .iter()
+                .enumerate()
+                .map(|(row_index, row_id)| (*row_id, row_index))
+                .collect();
+            for (component, column) in std::mem::take(&mut self.columns) {
+                for (row_id, cell) in self.col_row_id.iter().zip(column.0) {
+                    let Some(cell) = cell else {
+                        continue;
+                    };
+                    if is_resident(&cell, cluster_cell_cache, dedup_cell_cache) {
+                        if let Some(row_index) = row_indices.get(row_id) {
+                            spilled.shared.push((component, *row_index, cell));
+                        }
+                    }
+                }
+            }
+
+            let num_bytes = spilled.num_bytes;
+            self.spilled = Some(spilled);
+            return Ok(num_bytes);
+        }
+
+        // NOTE: Keeps the rows in the same order as the control columns, which is only for
+        // convenience: spilled data is matched back to them by `RowId`.
+        self.sort();
+
+        let mut components: Vec<_> = self.columns.keys().copied().collect();
+        components.sort();
+
+        let num_rows = self.col_row_id.len();
+        let mut schema = Schema::default();
+        let mut arrays = Vec::new();
+        let mut shared = Vec::new();
+        let mut row_cells = vec![SmallVec::new(); num_rows];
+        let mut row_num_bytes = vec![0; num_rows];
+        let mut resident_num_bytes = 0;
+        for component in &components {
+            let mut column = self.columns[component].clone(); // shallow
+            for (row_index, cell) in column.0.iter_mut().enumerate() {
+                row_num_bytes[row_index] += cell.total_size_bytes();
+                let Some(cell_ref) = cell.as_ref() else {
+                    continue;
+                };
+                if is_resident(cell_ref, cluster_cell_cache, dedup_cell_cache) {
+                    if let Some(cell) = cell.take() {
+                        let cell_num_bytes = cell.heap_size_bytes();
+                        row_num_bytes[row_index] -= cell_num_bytes;
+                        resident_num_bytes += cell_num_bytes;
+                        shared.push((*component, row_index, cell));
+                    }
+                } else {
+                    row_cells[row_index].push((*component, cell_ref.total_size_bytes()));
+                }
+            }
+
+            // NOTE: Columns with only null values cannot be serialized, they are rebuilt from
+            // `components` instead.
+            if column.iter().any(Option::is_some) {
+                let (field, array) = DataTable::serialize_data_column(component.as_str(), &column)?;
+                schema.fields.push(field);
+                arrays.push(array);
+            }
+        }
+
+        // NOTE: Created right away so that the file gets cleaned up if anything goes wrong.
+        let file = Arc::new(SpillFile::new(dir));
+        {
+            use arrow2::io::ipc::write::{FileWriter, WriteOptions};
+
+            let write = std::io::BufWriter::new(std::fs::File::create(&file.path)?);
+            let mut writer = FileWriter::try_new(write, schema, None, WriteOptions::default())?;
+            writer.write(&Chunk::new(arrays), None)?;
+            writer.finish()?;
+        }
+
+        let num_bytes = self.columns.heap_size_bytes() - resident_num_bytes;
+        self.columns = Default::default();
+        self.spilled = Some(SpilledColumns {
+            file,
+            components,
+            shared,
+            row_ids: self.col_row_id.to_vec(),
+            row_cells,
+            row_num_bytes,
+            num_bytes,
+        });
+
+        Ok(num_bytes)
+    }
+
+    /// Reads the component data of this bucket back from disk, provided it had been spilled.
+    ///
+    /// Should that fail, the bucket stays spilled.
+    ///
+    /// The spill file is kept around until the bucket gets modified, so that it can be spilled
+    /// again for free in the meantime, see [`PagedInColumns`].
+    pub(crate) fn page_in(&mut self) -> SpillResult<()> {
+        let Some(spilled) = &self.spilled else {
+            return Ok(());
+        };
+
+        crate::profile_function!();
+
+        let file_columns = read_spill_file(spilled.path())?;
+        let Some(mut spilled) = self.spilled.take() else {
+            return Ok(());
+        };
+
+        // Rows might have been dropped since spilling: match what's left by `RowId`.
+        let row_indices: ahash::HashMap<RowId, usize> = spilled
+            .row_ids
+            .iter()
+            .enumerate()
+            .map(|(row_index, row_id)| (*row_id, row_index))
+            .collect();
+        let file_row_indices: Vec<_> = self
+            .col_row_id
+            .iter()
+            .map(|row_id| row_indices.get(row_id).copied())
+            .collect();
+
+        let mut columns = IntMap::default();
+        for component in &spilled.components {
+            let file_column = file_columns.get(component);
+            let column = file_row_indices
+                .iter()
+                .map(|row_index| {
+                    let row_index = (*row_index)?;
+                    file_column.and_then(|column| column.0.get(row_index).cloned().flatten())
+                })
+                .collect();
+            columns.insert(*component, DataCellColumn(column));
+        }
+
+        let mut resident_row_indices = vec![None; spilled.row_ids.len()];
+        for (row_index, file_row_index) in file_row_indices.iter().enumerate() {
+            if let Some(file_row_index) = file_row_index {
+                resident_row_indices[*file_row_index] = Some(row_index);
+            }
+        }
+        for (component, file_row_index, cell) in std::mem::take(&mut spilled.shared) {
+            let row_index = resident_row_indices.get(file_row_index).copied().flatten();
+            if let Some(slot) = row_index.and_then(|row_index| {
+                columns
+                    .get_mut(&component)
+                    .and_then(|column| column.0.get_mut(row_index))
+            }) {
+                *slot = Some(cell);
+            }
+        }
+
+        // NOTE: We do not recompute `size_bytes`: the cells come out of the exact same
+        // deserialization path as freshly inserted ones, so their sizes match those that were
+        // accounted for on insertion.
+        self.columns = columns;
+        self.paged_in = Some(PagedInColumns::new(spilled));
+
+        Ok(())
+    }
+}
+
+/// Whether the given cell stays resident when its bucket gets spilled, see
+/// [`SpilledColumns::shared`].
+fn is_resident(
+    cell: &DataCell,
+    cluster_cell_cache: &ClusterCellCache,
+    dedup_cell_cache: &DedupCellCache,
+) -> bool {
+    dedup_cell_cache.is_deduplicated(cell) || cluster_cell_cache.is_generated(cell)
+}
+
+fn read_spill_file(path: &Path) -> SpillResult<IntMap<ComponentName, DataCellColumn>> {
+    use arrow2::io::ipc::read::{read_file_metadata, FileReader};
+
+    let mut read = std::io::BufReader::new(std::fs::File::open(path)?);
+    let metadata = read_file_metadata(&mut read)?;
+    let schema = metadata.schema.clone();
+
+    let mut columns = IntMap::default();
+    for chunk in FileReader::new(read, metadata, None, None) {
+        let chunk = chunk?;
+        for (field, array) in schema.fields.iter().zip(chunk.arrays()) {
+            let component = ComponentName::from(field.name.as_str());
+            let mut column = DataTable::deserialize_data_column(component, &**array)?;
+            for cell in column.0.iter_mut().flatten() {
+                cell.compute_size_bytes();
+            }
+            columns.insert(component, column);
+        }
+    }
+
+    Ok(columns)
+}
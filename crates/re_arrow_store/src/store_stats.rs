@@ -1,5 +1,7 @@
+use std::collections::BTreeMap;
+
 use nohash_hasher::IntMap;
-use re_log_types::{ComponentName, SizeBytes, TimePoint};
+use re_log_types::{ComponentName, DataCell, DataCellColumn, EntityPath, SizeBytes, TimePoint};
 
 use crate::{
     store::IndexedBucketInner, ClusterCellCache, DataStore, DataTypeRegistry, IndexedBucket,
@@ -41,10 +43,36 @@ pub struct DataStoreStats {
     pub type_registry: DataStoreRowStats,
     pub metadata_registry: DataStoreRowStats,
     pub autogenerated: DataStoreRowStats,
+
+    /// The cells that are deduplicated on insertion, see
+    /// [`crate::DataStoreConfig::dedup_min_size_bytes`].
+    ///
+    /// `num_rows` is the number of distinct cells, while `num_bytes` is how many bytes that saves:
+    /// [`Self::timeless`] and [`Self::temporal`] account for every reference to a deduplicated
+    /// cell as if it held its own copy of the data, and [`Self::total`] corrects for that.
+    pub deduplicated: DataStoreRowStats,
+
     pub timeless: DataStoreRowStats,
     pub temporal: DataStoreRowStats,
     pub temporal_buckets: u64,
+
+    /// The part of [`Self::temporal`] whose component data is currently spilled to disk, see
+    /// [`DataStore::spill`].
+    ///
+    /// Everything else is resident in memory.
+    pub spilled: DataStoreRowStats,
+
     pub total: DataStoreRowStats,
+
+    /// The part of [`Self::timeless`] and [`Self::temporal`] that belongs to each entity,
+    /// control columns included.
+    pub entities: BTreeMap<EntityPath, DataStoreRowStats>,
+
+    /// The component data of [`Self::timeless`] and [`Self::temporal`], per component.
+    ///
+    /// `num_rows` is the number of cells rather than rows, and control columns are not
+    /// accounted for.
+    pub components: BTreeMap<ComponentName, DataStoreRowStats>,
 }
 
 impl std::ops::Sub for DataStoreStats {
@@ -55,10 +83,14 @@ impl std::ops::Sub for DataStoreStats {
             type_registry: self.type_registry - rhs.type_registry,
             metadata_registry: self.metadata_registry - rhs.metadata_registry,
             autogenerated: self.autogenerated - rhs.autogenerated,
+            deduplicated: self.deduplicated - rhs.deduplicated,
             timeless: self.timeless - rhs.timeless,
             temporal: self.temporal - rhs.temporal,
             temporal_buckets: self.temporal_buckets - rhs.temporal_buckets,
+            spilled: self.spilled - rhs.spilled,
             total: self.total - rhs.total,
+            entities: merge_breakdowns(self.entities, rhs.entities, |lhs, rhs| lhs - rhs),
+            components: merge_breakdowns(self.components, rhs.components, |lhs, rhs| lhs - rhs),
         }
     }
 }
@@ -71,15 +103,40 @@ impl std::ops::Add for DataStoreStats {
             type_registry: self.type_registry + rhs.type_registry,
             metadata_registry: self.metadata_registry + rhs.metadata_registry,
             autogenerated: self.autogenerated + rhs.autogenerated,
+            deduplicated: self.deduplicated + rhs.deduplicated,
             timeless: self.timeless + rhs.timeless,
             temporal: self.temporal + rhs.temporal,
             temporal_buckets: self.temporal_buckets + rhs.temporal_buckets,
+            spilled: self.spilled + rhs.spilled,
             total: self.total + rhs.total,
+            entities: merge_breakdowns(self.entities, rhs.entities, |lhs, rhs| lhs + rhs),
+            components: merge_breakdowns(self.components, rhs.components, |lhs, rhs| lhs + rhs),
         }
     }
 }
 
+/// Applies `op` key by key; entries that end up empty are dropped, so that e.g. the difference
+/// of two identical breakdowns is empty.
+fn merge_breakdowns<K: Ord>(
+    mut lhs: BTreeMap<K, DataStoreRowStats>,
+    rhs: BTreeMap<K, DataStoreRowStats>,
+    op: impl Fn(DataStoreRowStats, DataStoreRowStats) -> DataStoreRowStats,
+) -> BTreeMap<K, DataStoreRowStats> {
+    for (key, rhs) in rhs {
+        let lhs = lhs.entry(key).or_default();
+        *lhs = op(*lhs, rhs);
+    }
+    lhs.retain(|_, stats| *stats != DataStoreRowStats::default());
+    lhs
+}
+
 impl DataStoreStats {
+    /// Gathers the stats of the given store.
+    ///
+    /// This only reads the running totals that the store keeps up to date as it goes, and thus
+    /// doesn't depend on the amount of data in the store, but only on its number of tables
+    /// (i.e. entities and timelines) and components.
+    /// The only exception is [`Self::spilled`], which goes through every temporal bucket.
     pub fn from_store(store: &DataStore) -> Self {
         crate::profile_function!();
 
@@ -107,6 +164,14 @@ impl DataStoreStats {
             }
         };
 
+        let deduplicated = {
+            crate::profile_scope!("deduplicated");
+            DataStoreRowStats {
+                num_rows: store.dedup_cell_cache.num_cells(),
+                num_bytes: store.dedup_cell_cache.saved_size_bytes,
+            }
+        };
+
         let timeless = {
             crate::profile_scope!("timeless");
             DataStoreRowStats {
@@ -126,23 +191,36 @@ impl DataStoreStats {
             )
         };
 
+        let spilled = {
+            crate::profile_scope!("spilled");
+            store.spilled_stats()
+        };
+
+        let entities = store.entity_stats();
+        let components = store.component_stats();
+
         let total = DataStoreRowStats {
             num_rows: timeless.num_rows + temporal.num_rows,
-            num_bytes: type_registry.num_bytes
+            num_bytes: (type_registry.num_bytes
                 + metadata_registry.num_bytes
                 + autogenerated.num_bytes
                 + timeless.num_bytes
-                + temporal.num_bytes,
+                + temporal.num_bytes)
+                .saturating_sub(deduplicated.num_bytes),
         };
 
         Self {
             type_registry,
             metadata_registry,
             autogenerated,
+            deduplicated,
             timeless,
             temporal,
             temporal_buckets,
+            spilled,
             total,
+            entities,
+            components,
         }
     }
 }
@@ -228,6 +306,75 @@ impl DataStore {
         crate::profile_function!();
         self.tables.values().map(|table| table.num_buckets()).sum()
     }
+
+    /// Returns the number of index rows and the size of the data, control columns included,
+    /// stored for each entity, timeless and temporal tables alike.
+    pub fn entity_stats(&self) -> BTreeMap<EntityPath, DataStoreRowStats> {
+        crate::profile_function!();
+
+        let timeless = self.timeless_tables.values().map(|table| {
+            let stats = DataStoreRowStats {
+                num_rows: table.num_rows(),
+                num_bytes: table.total_size_bytes(),
+            };
+            (&table.ent_path, stats)
+        });
+        let temporal = self.tables.values().map(|table| {
+            let stats = DataStoreRowStats {
+                num_rows: table.num_rows(),
+                num_bytes: table.total_size_bytes(),
+            };
+            (&table.ent_path, stats)
+        });
+
+        let mut entities: BTreeMap<EntityPath, DataStoreRowStats> = BTreeMap::new();
+        for (ent_path, stats) in timeless.chain(temporal) {
+            let entity = entities.entry(ent_path.clone()).or_default();
+            *entity = *entity + stats;
+        }
+        entities
+    }
+
+    /// Returns the number of cells and the size of the component data stored for each
+    /// component, timeless and temporal tables alike.
+    pub fn component_stats(&self) -> BTreeMap<ComponentName, DataStoreRowStats> {
+        crate::profile_function!();
+
+        let timeless = self
+            .timeless_tables
+            .values()
+            .map(|table| &table.components_stats);
+        let temporal = self.tables.values().map(|table| &table.components_stats);
+
+        let mut components: BTreeMap<ComponentName, DataStoreRowStats> = BTreeMap::new();
+        for (component, stats) in timeless.chain(temporal).flatten() {
+            let component = components.entry(*component).or_default();
+            *component = *component + *stats;
+        }
+        components
+    }
+
+    /// Returns the number of temporal index rows whose component data is currently spilled to
+    /// disk, and the size of that component data, in bytes.
+    ///
+    /// See [`DataStore::spill`].
+    pub fn spilled_stats(&self) -> DataStoreRowStats {
+        crate::profile_function!();
+        self.tables
+            .values()
+            .flat_map(|table| table.buckets.values())
+            .map(|bucket| {
+                let inner = bucket.inner.read();
+                inner
+                    .spilled
+                    .as_ref()
+                    .map_or_else(DataStoreRowStats::default, |spilled| DataStoreRowStats {
+                        num_rows: inner.col_time.len() as _,
+                        num_bytes: spilled.num_bytes(),
+                    })
+            })
+            .fold(DataStoreRowStats::default(), |acc, stats| acc + stats)
+    }
 }
 
 // --- Temporal ---
@@ -310,8 +457,21 @@ impl IndexedBucketInner {
             col_num_instances,
             columns,
             size_bytes,
+            spilled,
+            paged_in: _,         // shares its spill file with whoever spilled it
+            scalar_summaries: _, // lazily computed, and tiny
         } = self;
 
+        // NOTE: Spilled component data still counts, see `DataStore::spill`, and so do the cells
+        // that stayed resident.
+        let spilled_size_bytes = spilled.as_ref().map_or(0, |spilled| {
+            spilled.num_bytes()
+                + spilled
+                    .shared_cells()
+                    .map(SizeBytes::heap_size_bytes)
+                    .sum::<u64>()
+        });
+
         *size_bytes = is_sorted.total_size_bytes()
             + time_range.total_size_bytes()
             + col_time.total_size_bytes()
@@ -319,6 +479,7 @@ impl IndexedBucketInner {
             + col_row_id.total_size_bytes()
             + col_num_instances.total_size_bytes()
             + columns.total_size_bytes()
+            + spilled_size_bytes
             + size_bytes.total_size_bytes();
 
         *size_bytes
@@ -338,6 +499,16 @@ impl PersistentIndexedTable {
 impl SizeBytes for PersistentIndexedTable {
     #[inline]
     fn heap_size_bytes(&self) -> u64 {
+        self.size_bytes
+    }
+}
+
+impl PersistentIndexedTable {
+    /// Computes the size of both the control & component data stored in this table, heap only,
+    /// in bytes.
+    ///
+    /// Recomputed from scratch, see [`PersistentIndexedTable::size_bytes`] for the cached value.
+    pub(crate) fn size_bytes_uncached(&self) -> u64 {
         crate::profile_function!();
 
         let Self {
@@ -347,6 +518,8 @@ impl SizeBytes for PersistentIndexedTable {
             col_row_id,
             col_num_instances,
             columns,
+            size_bytes: _,
+            components_stats: _,
         } = self;
 
         ent_path.total_size_bytes()
@@ -357,3 +530,56 @@ impl SizeBytes for PersistentIndexedTable {
             + columns.total_size_bytes()
     }
 }
+
+// --- Components ---
+
+/// Accounts for a cell that was just added to a table, see e.g.
+/// [`IndexedTable::components_stats`].
+pub(crate) fn add_cell_stats(
+    components_stats: &mut IntMap<ComponentName, DataStoreRowStats>,
+    cell: &DataCell,
+) {
+    let stats = components_stats.entry(cell.component_name()).or_default();
+    stats.num_rows += 1;
+    stats.num_bytes += cell.total_size_bytes();
+}
+
+/// Accounts for a cell that was just removed from a table, see e.g.
+/// [`IndexedTable::components_stats`].
+pub(crate) fn remove_cell_stats(
+    components_stats: &mut IntMap<ComponentName, DataStoreRowStats>,
+    cell: &DataCell,
+) {
+    remove_component_stats(
+        components_stats,
+        cell.component_name(),
+        cell.total_size_bytes(),
+    );
+}
+
+/// Same as [`remove_cell_stats`], for a cell that isn't around anymore, e.g. because it was
+/// spilled to disk: only its component and size are known.
+pub(crate) fn remove_component_stats(
+    components_stats: &mut IntMap<ComponentName, DataStoreRowStats>,
+    component: ComponentName,
+    num_bytes: u64,
+) {
+    if let Some(stats) = components_stats.get_mut(&component) {
+        stats.num_rows -= 1;
+        stats.num_bytes -= num_bytes;
+        if stats.num_rows == 0 {
+            components_stats.remove(&component);
+        }
+    }
+}
+
+/// Recomputes the stats of every component from scratch, for sanity checking.
+pub(crate) fn components_stats_uncached<'a>(
+    columns: impl Iterator<Item = &'a DataCellColumn>,
+) -> IntMap<ComponentName, DataStoreRowStats> {
+    let mut components_stats = IntMap::default();
+    for cell in columns.flat_map(|column| column.iter().flatten()) {
+        add_cell_stats(&mut components_stats, cell);
+    }
+    components_stats
+}
@@ -0,0 +1,154 @@
+use re_log_types::{ComponentName, DataRow, EntityPath, RowId, TimePoint};
+
+use crate::DataStore;
+
+// ---
+
+/// What happened to a row of the [`DataStore`], see [`StoreEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreEventKind {
+    /// The row was inserted.
+    Insertion,
+
+    /// The row was inserted as a clear, see [`DataStore::insert_clear_row`]: it only holds empty
+    /// cells, which clear its components from its timepoint onwards.
+    ///
+    /// Rows that merely happen to be empty, e.g. an empty point cloud, are
+    /// [`StoreEventKind::Insertion`]s.
+    Clear,
+
+    /// The row was removed, e.g. by the garbage collector.
+    Deletion,
+}
+
+/// A change to the contents of a [`DataStore`], as seen by [`StoreSubscriber`]s.
+///
+/// There's exactly one event per row and per change.
+/// A row that gets garbage collected yields a single [`StoreEventKind::Deletion`] event, no matter
+/// how many timelines it was indexed on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreEvent {
+    pub kind: StoreEventKind,
+    pub row_id: RowId,
+
+    /// Empty for timeless rows.
+    pub timepoint: TimePoint,
+    pub entity_path: EntityPath,
+
+    /// The components of the row as it was logged, sorted.
+    ///
+    /// This doesn't include the cluster key if it was auto-generated by the store.
+    pub components: Vec<ComponentName>,
+}
+
+impl StoreEvent {
+    pub(crate) fn from_row(kind: StoreEventKind, row: &DataRow) -> Self {
+        let mut components: Vec<_> = row.component_names().collect();
+        components.sort();
+
+        Self {
+            kind,
+            row_id: row.row_id(),
+            timepoint: row.timepoint().clone(),
+            entity_path: row.entity_path().clone(),
+            components,
+        }
+    }
+}
+
+/// Gets notified of every change to the contents of the [`DataStore`] it is registered with.
+///
+/// This is how secondary indices (e.g. an entity tree, or a histogram of all the times on each
+/// timeline) can be kept in sync with a store, without having to wire them up by hand.
+///
+/// See [`DataStore::register_subscriber`].
+pub trait StoreSubscriber: std::any::Any + Send + Sync {
+    /// Arbitrary name for the subscriber, for debugging purposes.
+    fn name(&self) -> String {
+        "rerun.store_subscriber".to_owned()
+    }
+
+    /// Workaround for downcasting support, simply return `self`.
+    fn as_any(&self) -> &dyn std::any::Any;
+
+    /// Workaround for downcasting support, simply return `self`.
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+
+    /// Called with all the changes that resulted from a single write or garbage collection, in
+    /// the order they happened.
+    ///
+    /// Subscribers cannot modify the store they are notified by: this is called while the store
+    /// is being modified.
+    fn on_events(&mut self, events: &[StoreEvent]);
+}
+
+/// Identifies a [`StoreSubscriber`] registered with a [`DataStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StoreSubscriberHandle(u32);
+
+impl DataStore {
+    /// Registers a [`StoreSubscriber`], which will be notified of all future changes to the
+    /// contents of this store.
+    ///
+    /// Subscribers are not carried over when cloning the store.
+    pub fn register_subscriber(
+        &mut self,
+        subscriber: Box<dyn StoreSubscriber>,
+    ) -> StoreSubscriberHandle {
+        let handle = StoreSubscriberHandle(self.next_subscriber_handle);
+        self.next_subscriber_handle += 1;
+        self.subscribers.push((handle, subscriber));
+        handle
+    }
+
+    /// Unregisters a [`StoreSubscriber`], returning it if it was registered.
+    pub fn unregister_subscriber(
+        &mut self,
+        handle: StoreSubscriberHandle,
+    ) -> Option<Box<dyn StoreSubscriber>> {
+        let index = self.subscribers.iter().position(|(h, _)| *h == handle)?;
+        Some(self.subscribers.remove(index).1)
+    }
+
+    /// Passes a reference to the downcasted subscriber to the given callback.
+    ///
+    /// Returns `None` if the subscriber doesn't exist or downcasting failed.
+    pub fn with_subscriber<V: StoreSubscriber, T, F: FnOnce(&V) -> T>(
+        &self,
+        handle: StoreSubscriberHandle,
+        f: F,
+    ) -> Option<T> {
+        self.subscribers
+            .iter()
+            .find(|(h, _)| *h == handle)
+            .and_then(|(_, subscriber)| subscriber.as_any().downcast_ref::<V>())
+            .map(f)
+    }
+
+    /// Passes a mutable reference to the downcasted subscriber to the given callback.
+    ///
+    /// Returns `None` if the subscriber doesn't exist or downcasting failed.
+    pub fn with_subscriber_mut<V: StoreSubscriber, T, F: FnOnce(&mut V) -> T>(
+        &mut self,
+        handle: StoreSubscriberHandle,
+        f: F,
+    ) -> Option<T> {
+        self.subscribers
+            .iter_mut()
+            .find(|(h, _)| *h == handle)
+            .and_then(|(_, subscriber)| subscriber.as_any_mut().downcast_mut::<V>())
+            .map(f)
+    }
+
+    pub(crate) fn notify_subscribers(&mut self, events: &[StoreEvent]) {
+        if events.is_empty() {
+            return;
+        }
+
+        crate::profile_function!();
+
+        for (_, subscriber) in &mut self.subscribers {
+            subscriber.on_events(events);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use arrow2::array::PrimitiveArray;
+use re_log_types::{ComponentName, DataCell, EntityPath, TimeInt, TimeRange};
+
+use crate::{DataStore, IndexedBucket, RangeQuery};
+
+// ---
+
+/// Summary statistics of a set of scalar values, e.g. all the values of a component within an
+/// [`IndexedBucket`].
+///
+/// Summaries of disjoint sets of values can be merged together, see [`Self::merge`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalarSummary {
+    pub min: f64,
+    pub max: f64,
+
+    /// The sum of all the values, from which the mean is derived.
+    pub sum: f64,
+
+    /// How many values were summarized.
+    pub count: u64,
+}
+
+impl Default for ScalarSummary {
+    #[inline]
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl ScalarSummary {
+    pub const EMPTY: Self = Self {
+        min: f64::INFINITY,
+        max: f64::NEG_INFINITY,
+        sum: 0.0,
+        count: 0,
+    };
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// `None` if the summary is empty.
+    #[inline]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    /// Accounts for a single value. `NaN`s are ignored.
+    #[inline]
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    #[inline]
+    pub fn merge(&mut self, rhs: &Self) {
+        let Self {
+            min,
+            max,
+            sum,
+            count,
+        } = rhs;
+        self.min = self.min.min(*min);
+        self.max = self.max.max(*max);
+        self.sum += sum;
+        self.count += count;
+    }
+
+    /// Summarizes all the non-null values of the given cell.
+    ///
+    /// Returns `None` if the cell doesn't hold floating point values, i.e. isn't a scalar
+    /// component.
+    pub fn from_cell(cell: &DataCell) -> Option<Self> {
+        let array = cell.as_arrow_ref().as_any();
+
+        let mut summary = Self::EMPTY;
+        if let Some(values) = array.downcast_ref::<PrimitiveArray<f64>>() {
+            for value in values.iter().flatten() {
+                summary.add(*value);
+            }
+        } else if let Some(values) = array.downcast_ref::<PrimitiveArray<f32>>() {
+            for value in values.iter().flatten() {
+                summary.add(*value as f64);
+            }
+        } else {
+            return None;
+        }
+
+        Some(summary)
+    }
+}
+
+impl DataStore {
+    /// Summarizes the scalar values of `component` for the given entity, over the time range of
+    /// `query`, in as few chunks as possible.
+    ///
+    /// Returns `(time_range, summary)` pairs that together account for every value in range
+    /// exactly once, in increasing time order.
+    /// Whenever `summarize_whole` accepts the time range of an [`IndexedBucket`] that lies entirely
+    /// within the query, that bucket is summarized as a whole, using its cached summary (see
+    /// [`IndexedBucket::scalar_summary`]).
+    /// Otherwise, every row of the bucket is summarized on its own, with a point time range.
+    ///
+    /// Rows that don't hold floating point values for `component` are skipped, as is timeless
+    /// data.
+    pub fn range_scalar_summaries(
+        &self,
+        query: &RangeQuery,
+        ent_path: &EntityPath,
+        component: ComponentName,
+        mut summarize_whole: impl FnMut(TimeRange) -> bool,
+    ) -> Vec<(TimeRange, ScalarSummary)> {
+        crate::profile_function!();
+
+        let Some(table) = self.tables.get(&(query.timeline, ent_path.hash())) else {
+            return Vec::new();
+        };
+
+        // We need to find the _indexing time_ that corresponds to this time range's minimum bound!
+        let (time_range_min, _) = table.find_bucket(query.range.min);
+
+        let mut summaries = Vec::new();
+        for (_, bucket) in table.range_buckets(time_range_min..=query.range.max) {
+            if bucket.num_rows() == 0 {
+                continue;
+            }
+
+            let time_range = bucket.inner.read().time_range;
+            if time_range.max < query.range.min || query.range.max < time_range.min {
+                continue;
+            }
+
+            let is_within_query =
+                query.range.min <= time_range.min && time_range.max <= query.range.max;
+            if is_within_query && summarize_whole(time_range) {
+                let summary = bucket.scalar_summary(component);
+                if !summary.is_empty() {
+                    summaries.push((time_range, summary));
+                }
+            } else {
+                bucket.row_scalar_summaries(query.range, component, &mut summaries);
+            }
+        }
+
+        summaries
+    }
+}
+
+impl IndexedBucket {
+    /// Summarizes all the scalar values of `component` in this bucket.
+    ///
+    /// The summary is computed on first access and cached until the rows of the bucket change.
+    /// Cached summaries survive spilling, and thus don't require paging the bucket back in.
+    pub fn scalar_summary(&self, component: ComponentName) -> ScalarSummary {
+        if let Some(summary) = self.inner.read().scalar_summaries.get(&component) {
+            return *summary; // early read-only exit
+        }
+
+        crate::profile_scope!("scalar_summary");
+
+        if !self.page_in_if_needed() {
+            // Don't cache anything: the data might still be readable next time around.
+            return ScalarSummary::EMPTY;
+        }
+
+        let mut inner = self.inner.write();
+        let mut summary = ScalarSummary::EMPTY;
+        if let Some(column) = inner.columns.get(&component) {
+            for cell_summary in column.iter().flatten().filter_map(ScalarSummary::from_cell) {
+                summary.merge(&cell_summary);
+            }
+        }
+        inner.scalar_summaries.insert(component, summary);
+
+        summary
+    }
+
+    /// Summarizes the scalar values of `component` for every single row of this bucket that lies
+    /// within `time_range`, in increasing time order.
+    fn row_scalar_summaries(
+        &self,
+        time_range: TimeRange,
+        component: ComponentName,
+        summaries: &mut Vec<(TimeRange, ScalarSummary)>,
+    ) {
+        self.page_in_if_needed();
+        self.sort_indices_if_needed();
+
+        let inner = self.inner.read();
+        let Some(column) = inner.columns.get(&component) else {
+            return;
+        };
+
+        for (time, cell) in inner.col_time.iter().zip(column.iter()) {
+            let time = TimeInt::from(*time);
+            if !time_range.contains(time) {
+                continue;
+            }
+            let Some(summary) = cell.as_ref().and_then(ScalarSummary::from_cell) else {
+                continue;
+            };
+            if !summary.is_empty() {
+                summaries.push((TimeRange::point(time), summary));
+            }
+        }
+    }
+}
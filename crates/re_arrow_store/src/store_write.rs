@@ -11,8 +11,9 @@ use re_log_types::{
 };
 
 use crate::{
-    store::MetadataRegistry, DataStore, DataStoreConfig, IndexedBucket, IndexedBucketInner,
-    IndexedTable, PersistentIndexedTable,
+    store::MetadataRegistry, store_stats::add_cell_stats, DataStore, DataStoreConfig,
+    IndexedBucket, IndexedBucketInner, IndexedTable, PersistentIndexedTable, SpillError,
+    StoreEvent, StoreEventKind,
 };
 
 // TODO(cmc): the store should insert column-per-column rather than row-per-row (purely a
@@ -42,6 +43,9 @@ pub enum WriteError {
         expected: DataType,
         got: DataType,
     },
+
+    #[error("Could not page in the spilled bucket the row belongs to: {0}")]
+    Spill(#[from] SpillError),
 }
 
 pub type WriteResult<T> = ::std::result::Result<T, WriteError>;
@@ -69,6 +73,23 @@ impl DataStore {
     /// based on the length of the components in the payload, in the form of an array of
     /// monotonically increasing `u64`s going from `0` to `N-1`.
     pub fn insert_row(&mut self, row: &DataRow) -> WriteResult<()> {
+        self.insert_row_impl(row, StoreEventKind::Insertion)
+    }
+
+    /// Inserts a [`DataRow`] of empty cells that clears its components from its timepoint
+    /// onwards, e.g. as the result of a `PathOp`.
+    ///
+    /// This is stored exactly like [`Self::insert_row`] would, the only difference is that
+    /// subscribers get notified with a [`StoreEventKind::Clear`] event rather than an insertion.
+    pub fn insert_clear_row(&mut self, row: &DataRow) -> WriteResult<()> {
+        debug_assert!(
+            row.cells().iter().all(|cell| cell.num_instances() == 0),
+            "clear rows must only hold empty cells"
+        );
+        self.insert_row_impl(row, StoreEventKind::Clear)
+    }
+
+    fn insert_row_impl(&mut self, row: &DataRow, kind: StoreEventKind) -> WriteResult<()> {
         // TODO(cmc): kind & insert_id need to somehow propagate through the span system.
         self.insert_id += 1;
 
@@ -156,6 +177,25 @@ impl DataStore {
             Some(self.generate_cluster_cell(num_instances))
         };
 
+        // NOTE: Page in the buckets that the row is about to land in before modifying anything,
+        // so that an unreadable spill file cannot leave the row half-inserted.
+        for (timeline, time) in timepoint.iter() {
+            if let Some(table) = self.tables.get(&(*timeline, ent_path_hash)) {
+                let (_, bucket) = table.find_bucket(*time);
+                bucket.inner.write().page_in()?;
+            }
+        }
+
+        // NOTE: Only deduplicate once the row is known to be valid, so that the cache never
+        // accounts for references that the tables don't actually hold.
+        let num_tables = if timepoint.is_timeless() {
+            1
+        } else {
+            timepoint.iter().len() as u64
+        };
+        let deduped_row = self.dedup_row(row, num_tables);
+        let row = deduped_row.as_ref().unwrap_or(row);
+
         let insert_id = self.config.store_insert_ids.then_some(self.insert_id);
 
         if timepoint.is_timeless() {
@@ -184,6 +224,12 @@ impl DataStore {
         }
 
         self.metadata_registry.upsert(*row_id, timepoint.clone());
+        self.bump_entity_generation(ent_path_hash);
+
+        if !self.subscribers.is_empty() {
+            let event = StoreEvent::from_row(kind, row);
+            self.notify_subscribers(&[event]);
+        }
 
         Ok(())
     }
@@ -192,7 +238,44 @@ impl DataStore {
     ///
     /// Mostly useful for testing/debugging purposes.
     pub fn wipe_timeless_data(&mut self) {
-        self.timeless_tables = Default::default();
+        let timeless_tables = std::mem::take(&mut self.timeless_tables);
+
+        let mut events = Vec::new();
+        for (ent_path_hash, table) in timeless_tables {
+            self.bump_entity_generation(ent_path_hash);
+            self.release_cells(
+                table
+                    .columns
+                    .values()
+                    .flat_map(|column| column.iter().flatten()),
+            );
+
+            if self.subscribers.is_empty() {
+                continue;
+            }
+            for (row_index, row_id) in table.col_row_id.iter().enumerate() {
+                let mut components: Vec<_> = table
+                    .columns
+                    .iter()
+                    .filter(|(_, column)| {
+                        matches!(
+                            column.0.get(row_index),
+                            Some(Some(cell)) if !self.cluster_cell_cache.is_generated(cell)
+                        )
+                    })
+                    .map(|(component, _)| *component)
+                    .collect();
+                components.sort();
+                events.push(StoreEvent {
+                    kind: StoreEventKind::Deletion,
+                    row_id: *row_id,
+                    timepoint: TimePoint::timeless(),
+                    entity_path: table.ent_path.clone(),
+                    components,
+                });
+            }
+        }
+        self.notify_subscribers(&events);
     }
 
     /// Auto-generates an appropriate cluster cell for the specified number of instances and
@@ -396,9 +479,17 @@ impl IndexedTable {
             "inserted into indexed tables"
         );
 
-        self.buckets_size_bytes +=
-            bucket.insert_row(insert_id, time, generated_cluster_cell, row, &components);
+        self.buckets_size_bytes += bucket.insert_row(
+            insert_id,
+            time,
+            generated_cluster_cell.clone(), /* shallow */
+            row,
+            &components,
+        );
         self.buckets_num_rows += 1;
+        for cell in generated_cluster_cell.iter().chain(row.cells().iter()) {
+            add_cell_stats(&mut self.components_stats, cell);
+        }
 
         // Insert components last, only if bucket-insert succeeded.
         self.all_components.extend(components);
@@ -421,6 +512,11 @@ impl IndexedBucket {
         let num_rows = self.num_rows() as usize;
 
         let mut inner = self.inner.write();
+        // NOTE: `DataStore::insert_row` pages the bucket in before it ever gets here.
+        debug_assert!(
+            inner.spilled.is_none(),
+            "cannot insert into a spilled bucket"
+        );
         let IndexedBucketInner {
             is_sorted,
             time_range,
@@ -430,8 +526,14 @@ impl IndexedBucket {
             col_num_instances,
             columns,
             size_bytes,
+            spilled: _,
+            paged_in,
+            scalar_summaries,
         } = &mut *inner;
 
+        scalar_summaries.clear();
+        *paged_in = None;
+
         // append time to primary column and update time range appropriately
         col_time.push(time.as_i64());
         *time_range = TimeRange::new(time_range.min.min(time), time_range.max.max(time));
@@ -538,6 +640,8 @@ impl IndexedBucket {
         } = self;
 
         let mut inner1 = inner.write();
+        // NOTE: Only ever called on the bucket that `DataStore::insert_row` paged in.
+        debug_assert!(inner1.spilled.is_none(), "cannot split a spilled bucket");
         inner1.sort();
 
         let IndexedBucketInner {
@@ -548,7 +652,10 @@ impl IndexedBucket {
             col_row_id: col_row_id1,
             col_num_instances: col_num_instances1,
             columns: columns1,
-            size_bytes: _, // NOTE: recomputed below
+            size_bytes: size_bytes1, // NOTE: recomputed below
+            spilled: _,
+            paged_in: paged_in1,
+            scalar_summaries: scalar_summaries1,
         } = &mut *inner1;
 
         if col_time1.len() < 2 {
@@ -564,6 +671,11 @@ impl IndexedBucket {
         crate::profile_function!();
 
         let timeline = *timeline;
+        let size_bytes_before = *size_bytes1;
+
+        // Both halves will have to be summarized anew, and spilled anew.
+        scalar_summaries1.clear();
+        *paged_in1 = None;
 
         // Used in debug builds to assert that we've left everything in a sane state.
         let _num_rows = col_time1.len();
@@ -631,6 +743,9 @@ impl IndexedBucket {
                     col_num_instances: col_num_instances2,
                     columns: columns2,
                     size_bytes: 0, // NOTE: computed below
+                    spilled: None,
+                    paged_in: None,
+                    scalar_summaries: Default::default(),
                 };
                 inner2.compute_size_bytes();
                 inner2
@@ -644,7 +759,21 @@ impl IndexedBucket {
             (time_range2.min, bucket2)
         };
 
-        inner1.compute_size_bytes();
+        // NOTE: Sizes are additive: rather than going through all of its cells once again, the
+        // first half is made of whatever the second half didn't take, plus the fixed overhead of
+        // a bucket with the same columns.
+        {
+            let mut overhead = IndexedBucketInner {
+                columns: inner1
+                    .columns
+                    .keys()
+                    .map(|component| (*component, DataCellColumn(SmallVec::default())))
+                    .collect(),
+                ..Default::default()
+            };
+            inner1.size_bytes =
+                size_bytes_before + overhead.compute_size_bytes() - bucket2.inner.read().size_bytes;
+        }
 
         // sanity checks
         #[cfg(debug_assertions)]
@@ -804,34 +933,41 @@ impl PersistentIndexedTable {
             col_row_id,
             col_num_instances,
             columns,
+            size_bytes,
+            components_stats,
         } = self;
 
         let components: IntSet<_> = row.component_names().collect();
 
+        let mut size_bytes_added = 0u64;
+
         // --- update all control columns ---
 
         if let Some(insert_id) = insert_id {
             col_insert_id.push(insert_id);
+            size_bytes_added += insert_id.total_size_bytes();
         }
         col_row_id.push(row.row_id());
+        size_bytes_added += row.row_id().total_size_bytes();
         col_num_instances.push(row.num_instances());
+        size_bytes_added += row.num_instances().total_size_bytes();
 
         // --- append components to their respective columns (2-way merge) ---
 
-        // insert auto-generated cluster cell if present
-        if let Some(cluster_cell) = generated_cluster_cell {
-            let column = columns
-                .entry(cluster_cell.component_name())
-                .or_insert_with(|| DataCellColumn::empty(num_rows));
-            column.0.push(Some(cluster_cell));
-        }
-
         // 2-way merge, step 1: left-to-right
-        for cell in row.cells().iter() {
-            let column = columns
-                .entry(cell.component_name())
-                .or_insert_with(|| DataCellColumn::empty(num_rows));
+        //
+        // insert auto-generated cluster cell first, if present
+        for cell in generated_cluster_cell.iter().chain(row.cells().iter()) {
+            let component = cell.component_name();
+            let column = columns.entry(component).or_insert_with(|| {
+                let column = DataCellColumn::empty(num_rows);
+                size_bytes_added += component.total_size_bytes();
+                size_bytes_added += column.total_size_bytes();
+                column
+            });
+            size_bytes_added += cell.total_size_bytes();
             column.0.push(Some(cell.clone() /* shallow */));
+            add_cell_stats(components_stats, cell);
         }
 
         // 2-way merge, step 2: right-to-left
@@ -844,10 +980,14 @@ impl PersistentIndexedTable {
             }
 
             if !components.contains(component) {
-                column.0.push(None);
+                let none_cell: Option<DataCell> = None;
+                size_bytes_added += none_cell.total_size_bytes();
+                column.0.push(none_cell);
             }
         }
 
+        *size_bytes += size_bytes_added;
+
         #[cfg(debug_assertions)]
         self.sanity_check().unwrap();
     }
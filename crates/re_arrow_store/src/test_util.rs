@@ -44,11 +44,17 @@ pub fn all_configs() -> impl Iterator<Item = DataStoreConfig> {
             indexed_bucket_num_rows: 3,
             ..DataStoreConfig::DEFAULT
         },
+        DataStoreConfig {
+            indexed_bucket_num_rows: 2,
+            dedup_min_size_bytes: 0,
+            ..DataStoreConfig::DEFAULT
+        },
     ];
     INDEX_CONFIGS.iter().map(|idx| DataStoreConfig {
         indexed_bucket_num_rows: idx.indexed_bucket_num_rows,
         store_insert_ids: idx.store_insert_ids,
         enable_typecheck: idx.enable_typecheck,
+        dedup_min_size_bytes: idx.dedup_min_size_bytes,
     })
 }
 
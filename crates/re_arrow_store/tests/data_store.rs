@@ -12,7 +12,8 @@ use polars_ops::prelude::DataFrameJoinOps;
 use rand::Rng;
 use re_arrow_store::{
     polars_util, test_row, test_util::sanity_unwrap, DataStore, DataStoreConfig, DataStoreStats,
-    GarbageCollectionTarget, LatestAtQuery, RangeQuery, TimeInt, TimeRange,
+    GarbageCollectionOptions, GarbageCollectionTarget, LatestAtQuery, RangeQuery, TimeInt,
+    TimeRange,
 };
 use re_log_types::{
     component_types::{ColorRGBA, InstanceKey, Point2D, Rect2D},
@@ -20,8 +21,8 @@ use re_log_types::{
         build_frame_nr, build_some_colors, build_some_instances, build_some_instances_from,
         build_some_point2d, build_some_rects,
     },
-    Component as _, ComponentName, DataCell, DataRow, DataTable, EntityPath, TableId, TimeType,
-    Timeline,
+    Component as _, ComponentName, DataCell, DataRow, DataTable, EntityPath, RowId, TableId,
+    TimeType, Timeline,
 };
 
 // --- LatestComponentsAt ---
@@ -47,7 +48,7 @@ fn all_components() {
             }
 
             // Stress test GC
-            store2.gc(GarbageCollectionTarget::DropAtLeastFraction(1.0));
+            store2.gc(GarbageCollectionOptions::gc_everything());
             for table in store.to_data_tables(None) {
                 store2.insert_table(&table).unwrap();
             }
@@ -302,7 +303,7 @@ fn latest_at_impl(store: &mut DataStore) {
         store2.insert_table(&table).unwrap();
     }
     // Stress test GC
-    store2.gc(GarbageCollectionTarget::DropAtLeastFraction(1.0));
+    store2.gc(GarbageCollectionOptions::gc_everything());
     for table in store.to_data_tables(None) {
         store2.insert_table(&table).unwrap();
     }
@@ -444,7 +445,7 @@ fn range_impl(store: &mut DataStore) {
                 store2.insert_table(&table).unwrap();
             }
             store2.wipe_timeless_data();
-            store2.gc(GarbageCollectionTarget::DropAtLeastFraction(1.0));
+            store2.gc(GarbageCollectionOptions::gc_everything());
             for table in store.to_data_tables(None) {
                 store2.insert_table(&table).unwrap();
             }
@@ -873,8 +874,11 @@ fn gc_impl(store: &mut DataStore) {
 
         let stats = DataStoreStats::from_store(store);
 
-        let (row_ids, stats_diff) =
-            store.gc(GarbageCollectionTarget::DropAtLeastFraction(1.0 / 3.0));
+        let (row_ids, stats_diff) = store.gc(GarbageCollectionOptions {
+            target: GarbageCollectionTarget::DropAtLeastFraction(1.0 / 3.0),
+            protect_latest_at: false,
+            protected_paths: Vec::new(),
+        });
         for row_id in &row_ids {
             assert!(store.get_msg_metadata(row_id).is_none());
         }
@@ -897,6 +901,404 @@ fn gc_impl(store: &mut DataStore) {
     }
 }
 
+#[test]
+fn gc_protect_latest_at() {
+    init_logs();
+
+    for config in re_arrow_store::test_util::all_configs() {
+        let mut store = DataStore::new(InstanceKey::name(), config.clone());
+        gc_protect_latest_at_impl(&mut store);
+    }
+}
+
+fn gc_protect_latest_at_impl(store: &mut DataStore) {
+    let mut rng = rand::thread_rng();
+
+    let timeline_frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+    let components = [ColorRGBA::name(), Point2D::name(), Rect2D::name()];
+
+    // A pose-like entity that is logged once, at the very start:
+    let pose = EntityPath::from("world/pose");
+    let frame0 = [build_frame_nr(0.into())];
+    store
+        .insert_row(&test_row!(pose @ frame0 => 1; [build_some_rects(1)]))
+        .unwrap();
+
+    // Entities with sparse, randomly interleaved components:
+    let ent_paths: Vec<_> = (0..5)
+        .map(|i| EntityPath::from(format!("world/points/{i}")))
+        .collect();
+    for frame_nr in 0..100 {
+        for ent_path in &ent_paths {
+            let num_instances = rng.gen_range(1..=10);
+            let mut cells = Vec::new();
+            if rng.gen_bool(0.5) {
+                cells.push(build_some_colors(num_instances as _));
+            }
+            if rng.gen_bool(0.3) {
+                cells.push(build_some_point2d(num_instances as _));
+            }
+            if rng.gen_bool(0.1) {
+                cells.push(build_some_rects(num_instances as _));
+            }
+            let row = DataRow::from_cells_sized(
+                RowId::random(),
+                [build_frame_nr(frame_nr.into())],
+                ent_path.clone(),
+                num_instances,
+                cells,
+            );
+            store.insert_row(&row).unwrap();
+        }
+    }
+
+    let ent_paths: Vec<_> = std::iter::once(pose.clone()).chain(ent_paths).collect();
+    let latest_at_all = |store: &DataStore| {
+        let mut results = Vec::new();
+        for frame_nr in 0..110 {
+            let query = LatestAtQuery::new(timeline_frame_nr, frame_nr.into());
+            for ent_path in &ent_paths {
+                for component in components {
+                    let row_id = store
+                        .latest_at(&query, ent_path, component, &[component])
+                        .map(|(row_id, _)| row_id);
+                    results.push((frame_nr, ent_path.clone(), component, row_id));
+                }
+            }
+        }
+        results
+    };
+
+    let results_before = latest_at_all(store);
+
+    // Dropping the oldest rows: the boundary is somewhere in the middle of the recording, so
+    // only the results past the end of it are known to be the same as before.
+    let target = GarbageCollectionTarget::DropAtLeastFraction(rng.gen_range(0.1..=1.0));
+    let mut unprotected_store = store.clone();
+    unprotected_store.gc(GarbageCollectionOptions {
+        target: target.clone(),
+        protect_latest_at: false,
+        protected_paths: Vec::new(),
+    });
+    let mut fraction_store = store.clone();
+    let (row_ids, _) = fraction_store.gc(GarbageCollectionOptions {
+        target,
+        protect_latest_at: true,
+        protected_paths: Vec::new(),
+    });
+    assert!(!row_ids.is_empty());
+    sanity_unwrap(&mut fraction_store);
+
+    let results_after = latest_at_all(&fraction_store);
+    for (before, after) in results_before.iter().zip(&results_after) {
+        if before.0 >= 100 {
+            assert_eq!(before, after);
+        }
+    }
+
+    // The pose is still there, even though it was logged before everything else:
+    let query = LatestAtQuery::new(timeline_frame_nr, 1000.into());
+    assert!(fraction_store
+        .latest_at(&query, &pose, Rect2D::name(), &[Rect2D::name()])
+        .is_some());
+
+    // …which isn't the case without the protection:
+    assert!(unprotected_store
+        .latest_at(&query, &pose, Rect2D::name(), &[Rect2D::name()])
+        .is_none());
+
+    // Dropping everything before a cutoff: every row before it gets picked, so the boundary is
+    // the frame right before the cutoff.
+    let cutoff = rng.gen_range(10..90);
+    let (row_ids, _) = store.gc(GarbageCollectionOptions {
+        target: GarbageCollectionTarget::KeepTimeWindow {
+            timeline: timeline_frame_nr,
+            window: 99 - cutoff,
+        },
+        protect_latest_at: true,
+        protected_paths: Vec::new(),
+    });
+    assert!(!row_ids.is_empty());
+    sanity_unwrap(store);
+
+    // Every `latest_at` query at or after the boundary gives the same results as before…
+    let results_after = latest_at_all(store);
+    for (before, after) in results_before.iter().zip(&results_after) {
+        if before.0 >= cutoff - 1 {
+            assert_eq!(before, after, "cutoff: {cutoff}");
+        }
+    }
+
+    // …and only the rows these results depend on are left before the cutoff, at most one per
+    // entity and component.
+    let num_rows_kept = store
+        .to_rows()
+        .into_iter()
+        .filter(|row| {
+            row.timepoint()
+                .get(&timeline_frame_nr)
+                .map_or(false, |time| time.as_i64() < cutoff)
+        })
+        .count();
+    assert!(
+        num_rows_kept <= ent_paths.len() * components.len(),
+        "{num_rows_kept} rows kept"
+    );
+}
+
+#[test]
+fn gc_time_window() {
+    init_logs();
+
+    for config in re_arrow_store::test_util::all_configs() {
+        let mut store = DataStore::new(InstanceKey::name(), config.clone());
+        gc_time_window_impl(&mut store);
+    }
+}
+
+fn gc_time_window_impl(store: &mut DataStore) {
+    let timeline_frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+    let timeline_other = Timeline::new("other", TimeType::Sequence);
+
+    let ent_paths: Vec<_> = (0..3)
+        .map(|i| EntityPath::from(format!("this/that/{i}")))
+        .collect();
+    for frame_nr in 0..100 {
+        for ent_path in &ent_paths {
+            let row = test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => 2; [
+                build_some_point2d(2),
+            ]);
+            store.insert_row(&row).unwrap();
+        }
+    }
+    // Not on the frame_nr timeline:
+    let other = EntityPath::from("other");
+    for time in 0..10 {
+        let row = test_row!(other @ [(timeline_other, time.into())] => 2; [
+            build_some_colors(2),
+        ]);
+        store.insert_row(&row).unwrap();
+    }
+
+    let (row_ids, _) = store.gc(GarbageCollectionOptions {
+        target: GarbageCollectionTarget::KeepTimeWindow {
+            timeline: timeline_frame_nr,
+            window: 10,
+        },
+        protect_latest_at: false,
+        protected_paths: Vec::new(),
+    });
+    sanity_unwrap(store);
+    assert_eq!(row_ids.len(), ent_paths.len() * 89);
+
+    let rows = store.to_rows();
+    let mut frame_nrs: Vec<_> = rows
+        .iter()
+        .filter_map(|row| row.timepoint().get(&timeline_frame_nr).copied())
+        .collect();
+    frame_nrs.sort();
+    frame_nrs.dedup();
+    assert_eq!(
+        frame_nrs,
+        (89..100).map(TimeInt::from).collect::<Vec<_>>(),
+        "only the last 10 frames are kept"
+    );
+    assert_eq!(
+        rows.iter()
+            .filter(|row| row.entity_path() == &other)
+            .count(),
+        10,
+        "rows on other timelines are left untouched"
+    );
+
+    // Nothing left to purge:
+    let (row_ids, _) = store.gc(GarbageCollectionOptions {
+        target: GarbageCollectionTarget::KeepTimeWindow {
+            timeline: timeline_frame_nr,
+            window: 10,
+        },
+        protect_latest_at: false,
+        protected_paths: Vec::new(),
+    });
+    assert!(row_ids.is_empty());
+}
+
+#[test]
+fn gc_entity_quota() {
+    init_logs();
+
+    for config in re_arrow_store::test_util::all_configs() {
+        let mut store = DataStore::new(InstanceKey::name(), config.clone());
+        gc_entity_quota_impl(&mut store);
+    }
+}
+
+fn gc_entity_quota_impl(store: &mut DataStore) {
+    let mut rng = rand::thread_rng();
+
+    let world = EntityPath::from("world");
+    let pose = EntityPath::from("world/pose");
+    let stats = EntityPath::from("debug/stats");
+    let ent_paths = [
+        EntityPath::from("world/points"),
+        EntityPath::from("world/points/sub"),
+        pose.clone(),
+        stats.clone(),
+    ];
+    for frame_nr in 0..100 {
+        for ent_path in &ent_paths {
+            let num_instances = if ent_path == &pose {
+                1
+            } else {
+                rng.gen_range(1..=100)
+            };
+            let row = test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => num_instances; [
+                build_some_point2d(num_instances as _),
+            ]);
+            store.insert_row(&row).unwrap();
+        }
+    }
+
+    let rows_before = store.to_rows();
+    let options = GarbageCollectionOptions {
+        target: GarbageCollectionTarget::EntityQuota {
+            entity_path: world.clone(),
+            max_bytes: 50_000,
+        },
+        protect_latest_at: false,
+        protected_paths: vec![pose.clone()],
+    };
+    let (row_ids, _) = store.gc(options.clone());
+    sanity_unwrap(store);
+    assert!(!row_ids.is_empty());
+
+    // The oldest rows of the subtree were dropped first, leaving the protected entity and
+    // everything outside of the subtree alone:
+    let mut candidates: Vec<_> = rows_before
+        .iter()
+        .filter(|row| row.entity_path().is_descendant_of(&world))
+        .filter(|row| row.entity_path() != &pose)
+        .map(|row| row.row_id())
+        .collect();
+    candidates.sort();
+    let mut row_ids_sorted = row_ids.clone();
+    row_ids_sorted.sort();
+    assert_eq!(row_ids_sorted, candidates[..row_ids.len()]);
+
+    let count = |ent_path: &EntityPath| {
+        store
+            .to_rows()
+            .iter()
+            .filter(|row| row.entity_path() == ent_path)
+            .count()
+    };
+    assert_eq!(count(&pose), 100);
+    assert_eq!(count(&stats), 100);
+
+    // The quota is met:
+    let (row_ids, _) = store.gc(options);
+    assert!(row_ids.is_empty());
+}
+
+#[test]
+fn gc_retention_protect_latest_at() {
+    init_logs();
+
+    for config in re_arrow_store::test_util::all_configs() {
+        let mut store = DataStore::new(InstanceKey::name(), config.clone());
+        gc_retention_protect_latest_at_impl(&mut store);
+    }
+}
+
+/// The retention targets still purge data when the latest-at results are protected, as the
+/// viewer does it.
+fn gc_retention_protect_latest_at_impl(store: &mut DataStore) {
+    let mut rng = rand::thread_rng();
+
+    let timeline_frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+
+    let world = EntityPath::from("world");
+    let ent_paths = [
+        EntityPath::from("world/points"),
+        EntityPath::from("world/rects"),
+        EntityPath::from("debug/stats"),
+    ];
+    for frame_nr in 0..100 {
+        for ent_path in &ent_paths {
+            let num_instances = rng.gen_range(1..=100);
+            let row = test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => num_instances; [
+                build_some_point2d(num_instances as _),
+            ]);
+            store.insert_row(&row).unwrap();
+        }
+    }
+
+    let latest = |store: &DataStore| {
+        let query = LatestAtQuery::new(timeline_frame_nr, 99.into());
+        ent_paths
+            .iter()
+            .map(|ent_path| {
+                store
+                    .latest_at(&query, ent_path, Point2D::name(), &[Point2D::name()])
+                    .map(|(row_id, _)| row_id)
+            })
+            .collect::<Vec<_>>()
+    };
+    let latest_before = latest(store);
+
+    // Time window:
+    let stats_before = DataStoreStats::from_store(store);
+    let (row_ids, _) = store.gc(GarbageCollectionOptions {
+        target: GarbageCollectionTarget::KeepTimeWindow {
+            timeline: timeline_frame_nr,
+            window: 10,
+        },
+        protect_latest_at: true,
+        protected_paths: Vec::new(),
+    });
+    sanity_unwrap(store);
+    let stats_after = DataStoreStats::from_store(store);
+
+    // Every entity logs the same component on every frame, so only the last frame before the
+    // window is still needed.
+    assert_eq!(row_ids.len(), ent_paths.len() * 88);
+    assert!(stats_after.total.num_rows < stats_before.total.num_rows);
+    assert!(stats_after.total.num_bytes < stats_before.total.num_bytes);
+    assert_eq!(latest_before, latest(store));
+
+    // Entity quota:
+    let world_num_bytes = |stats: &DataStoreStats| {
+        stats
+            .entities
+            .iter()
+            .filter(|(ent_path, _)| ent_path.is_descendant_of(&world))
+            .map(|(_, stats)| stats.num_bytes)
+            .sum::<u64>()
+    };
+    let max_bytes = world_num_bytes(&stats_after) / 2;
+    let stats_before = stats_after;
+    let (row_ids, _) = store.gc(GarbageCollectionOptions {
+        target: GarbageCollectionTarget::EntityQuota {
+            entity_path: world.clone(),
+            max_bytes,
+        },
+        protect_latest_at: true,
+        protected_paths: Vec::new(),
+    });
+    sanity_unwrap(store);
+    let stats_after = DataStoreStats::from_store(store);
+
+    assert!(!row_ids.is_empty());
+    assert!(stats_after.total.num_rows < stats_before.total.num_rows);
+    assert!(stats_after.total.num_bytes < stats_before.total.num_bytes);
+    assert!(world_num_bytes(&stats_after) < world_num_bytes(&stats_before));
+    assert_eq!(
+        stats_after.entities[&ent_paths[2]], stats_before.entities[&ent_paths[2]],
+        "entities outside of the subtree are left untouched"
+    );
+    assert_eq!(latest_before, latest(store));
+}
+
 // ---
 
 pub fn init_logs() {
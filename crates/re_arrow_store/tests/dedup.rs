@@ -0,0 +1,167 @@
+//! Deduplicating identical cells on insertion.
+
+use std::sync::Arc;
+
+use re_arrow_store::{
+    test_row, test_util::sanity_unwrap, DataStore, DataStoreConfig, DataStoreStats,
+    GarbageCollectionOptions, LatestAtQuery, SpillOptions, SpillTarget,
+};
+use re_log_types::{
+    component_types::{InstanceKey, Label},
+    datagen::{build_frame_nr, build_log_time},
+    Component as _, DataCell, EntityPath, SizeBytes as _, Time, Timeline,
+};
+
+fn new_store(dedup_min_size_bytes: u64) -> DataStore {
+    DataStore::new(
+        InstanceKey::name(),
+        DataStoreConfig {
+            indexed_bucket_num_rows: 2,
+            dedup_min_size_bytes,
+            ..Default::default()
+        },
+    )
+}
+
+fn big_label(c: char) -> Vec<Label> {
+    vec![Label(std::iter::repeat(c).take(100_000).collect())]
+}
+
+fn label_cell(label: &[Label]) -> DataCell {
+    let mut cell = DataCell::from(label);
+    cell.compute_size_bytes();
+    cell
+}
+
+fn insert_labels(store: &mut DataStore, ent_path: &EntityPath, labels: &[Vec<Label>]) {
+    for (frame_nr, label) in labels.iter().enumerate() {
+        let row = test_row!(ent_path @ [
+            build_frame_nr((frame_nr as i64).into()),
+            build_log_time(Time::now()),
+        ] => 1; [label.clone()]);
+        store.insert_row(&row).unwrap();
+    }
+}
+
+#[test]
+fn dedup_identical_cells() {
+    let mut store = new_store(DataStoreConfig::DEFAULT.dedup_min_size_bytes);
+    let ent_path = EntityPath::from("this/that");
+
+    let label_a = big_label('a');
+    let label_b = big_label('b');
+    let size_bytes = label_cell(&label_a).total_size_bytes();
+
+    let labels: Vec<_> = (0..10)
+        .map(|i| {
+            if i == 5 {
+                label_b.clone()
+            } else {
+                label_a.clone()
+            }
+        })
+        .collect();
+    insert_labels(&mut store, &ent_path, &labels);
+    sanity_unwrap(&mut store);
+
+    // Two distinct cells, each referenced by all the rows (and timelines) that hold it: the
+    // tables still account for every single reference, but only one of them holds the data.
+    let stats = DataStoreStats::from_store(&store);
+    assert_eq!(2, stats.deduplicated.num_rows);
+    assert_eq!((17 + 1) * size_bytes, stats.deduplicated.num_bytes);
+    assert!(stats.temporal.num_bytes > 2 * 10 * size_bytes);
+    assert!(stats.total.num_bytes < 3 * size_bytes);
+
+    // Deduplication is invisible to readers...
+    let timeline = Timeline::new_sequence("frame_nr");
+    let cells: Vec<_> = labels
+        .iter()
+        .enumerate()
+        .map(|(frame_nr, label)| {
+            let query = LatestAtQuery::new(timeline, (frame_nr as i64).into());
+            let (_, cells) = store
+                .latest_at(&query, &ent_path, Label::name(), &[Label::name()])
+                .unwrap();
+            let cell = cells[0].clone().unwrap();
+            assert_eq!(label_cell(label), cell);
+            cell
+        })
+        .collect();
+
+    // ...but all identical cells do point to the same data.
+    for (i, cell) in cells.iter().enumerate() {
+        let is_same_data = Arc::ptr_eq(&cell.inner, &cells[0].inner);
+        assert_eq!(i != 5, is_same_data);
+    }
+
+    // Deduplicated data is only freed along with the last row that references it.
+    let clone = store.clone();
+    store.gc(GarbageCollectionOptions::gc_everything());
+    sanity_unwrap(&mut store);
+    let stats = DataStoreStats::from_store(&store);
+    assert_eq!(0, stats.deduplicated.num_rows);
+    assert_eq!(0, stats.deduplicated.num_bytes);
+
+    // Clones share the data, but keep track of it on their own.
+    let stats = DataStoreStats::from_store(&clone);
+    assert_eq!(2, stats.deduplicated.num_rows);
+}
+
+#[test]
+fn dedup_disabled() {
+    let mut store = new_store(u64::MAX);
+    let ent_path = EntityPath::from("this/that");
+
+    let label = big_label('a');
+    let size_bytes = label_cell(&label).total_size_bytes();
+    let labels: Vec<_> = std::iter::repeat(label).take(10).collect();
+    insert_labels(&mut store, &ent_path, &labels);
+    sanity_unwrap(&mut store);
+
+    let stats = DataStoreStats::from_store(&store);
+    assert_eq!(0, stats.deduplicated.num_rows);
+    assert!(stats.temporal.num_bytes > 2 * 10 * size_bytes);
+}
+
+#[test]
+fn dedup_and_spill() {
+    let mut store = new_store(DataStoreConfig::DEFAULT.dedup_min_size_bytes);
+    let ent_path = EntityPath::from("this/that");
+
+    let labels: Vec<_> = std::iter::repeat(big_label('a')).take(10).collect();
+    insert_labels(&mut store, &ent_path, &labels);
+    let stats_before = DataStoreStats::from_store(&store);
+
+    let dir = std::env::temp_dir().join(format!("re_arrow_store-dedup-{}", std::process::id()));
+    store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    sanity_unwrap(&mut store);
+
+    // Deduplicated cells stay resident, and still point to the same data once the rest is paged
+    // back in.
+    let timeline = Timeline::new_sequence("frame_nr");
+    let cells: Vec<_> = (0..10)
+        .map(|frame_nr| {
+            let query = LatestAtQuery::new(timeline, frame_nr.into());
+            let (_, cells) = store
+                .latest_at(&query, &ent_path, Label::name(), &[Label::name()])
+                .unwrap();
+            cells[0].clone().unwrap()
+        })
+        .collect();
+    assert!(cells
+        .iter()
+        .all(|cell| Arc::ptr_eq(&cell.inner, &cells[0].inner)));
+    sanity_unwrap(&mut store);
+
+    let stats_after = DataStoreStats::from_store(&store);
+    assert_eq!(stats_before.deduplicated, stats_after.deduplicated);
+    assert_eq!(stats_before.temporal, stats_after.temporal);
+
+    drop(store);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,107 @@
+//! Deleting rows from a datastore.
+
+use re_arrow_store::{test_row, DataStore, DataStoreConfig, LatestAtQuery, StoreEventKind};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::{build_frame_nr, build_some_colors, build_some_point2d},
+    Component as _, EntityPath, RowSelector, TimePoint, TimeRange, Timeline,
+};
+
+fn new_store() -> DataStore {
+    DataStore::new(
+        InstanceKey::name(),
+        DataStoreConfig {
+            indexed_bucket_num_rows: 2,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn delete_by_row_ids() {
+    let mut store = new_store();
+
+    let ent_path = EntityPath::from("this/that");
+    let row_timeless = test_row!(ent_path @ (TimePoint::timeless()) => 2; [build_some_colors(2)]);
+    let row1 = test_row!(ent_path @ [build_frame_nr(1.into())] => 2; [build_some_point2d(2)]);
+    let row2 = test_row!(ent_path @ [build_frame_nr(2.into())] => 2; [build_some_point2d(2)]);
+    for row in [&row_timeless, &row1, &row2] {
+        store.insert_row(row).unwrap();
+    }
+
+    let events = store.delete_rows(&RowSelector::RowIds(vec![
+        row_timeless.row_id(),
+        row2.row_id(),
+    ]));
+    store.sanity_check().unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| event.kind == StoreEventKind::Deletion));
+    assert_eq!(events[0].row_id, row_timeless.row_id());
+    assert!(events[0].timepoint.is_timeless());
+    assert_eq!(events[1].row_id, row2.row_id());
+    assert_eq!(&events[1].timepoint, row2.timepoint());
+
+    assert!(store.get_msg_metadata(&row_timeless.row_id()).is_none());
+    assert!(store.get_msg_metadata(&row1.row_id()).is_some());
+    assert!(store.get_msg_metadata(&row2.row_id()).is_none());
+
+    // Unlike with a clear, latest-at falls back to the data that came before.
+    let timeline = Timeline::new_sequence("frame_nr");
+    let query = LatestAtQuery::new(timeline, 2.into());
+    let components = [Point2D::name(), ColorRGBA::name()];
+    let (row_id, cells) = store
+        .latest_at(&query, &ent_path, Point2D::name(), &components)
+        .unwrap();
+    assert_eq!(row_id, row1.row_id());
+    assert!(cells[0].is_some());
+    assert!(cells[1].is_none());
+
+    // Deleting unknown rows is a no-op.
+    let events = store.delete_rows(&RowSelector::RowIds(vec![row2.row_id()]));
+    assert!(events.is_empty());
+}
+
+#[test]
+fn delete_by_time_range() {
+    let mut store = new_store();
+
+    let ent_path = EntityPath::from("this/that");
+    let other_path = EntityPath::from("this/that/other");
+    let rows: Vec<_> = (1..=10)
+        .map(|frame_nr| {
+            test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => 2; [build_some_point2d(2)])
+        })
+        .collect();
+    for row in &rows {
+        store.insert_row(row).unwrap();
+    }
+    let row_other =
+        test_row!(other_path @ [build_frame_nr(5.into())] => 2; [build_some_point2d(2)]);
+    store.insert_row(&row_other).unwrap();
+
+    let timeline = Timeline::new_sequence("frame_nr");
+    let events = store.delete_rows(&RowSelector::TimeRange {
+        entity_path: ent_path.clone(),
+        timeline,
+        time_range: TimeRange::new(3.into(), 6.into()),
+    });
+    store.sanity_check().unwrap();
+
+    let mut deleted: Vec<_> = events.iter().map(|event| event.row_id).collect();
+    deleted.sort();
+    let expected: Vec<_> = rows[2..6].iter().map(|row| row.row_id()).collect();
+    assert_eq!(deleted, expected);
+
+    // Descendants are left untouched.
+    assert!(store.get_msg_metadata(&row_other.row_id()).is_some());
+
+    // Querying within the deleted range yields the last row before it.
+    let query = LatestAtQuery::new(timeline, 6.into());
+    let (row_id, _) = store
+        .latest_at(&query, &ent_path, Point2D::name(), &[Point2D::name()])
+        .unwrap();
+    assert_eq!(row_id, rows[1].row_id());
+}
@@ -0,0 +1,117 @@
+//! Querying several entities at once, using entity path patterns.
+
+use re_arrow_store::{test_row, DataStore, LatestAtQuery, RangeQuery, TimeRange};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::{build_frame_nr, build_some_colors, build_some_point2d},
+    Component as _, EntityPath, EntityPathPattern, TimeInt, TimePoint, TimeType, Timeline,
+};
+
+fn build_store() -> DataStore {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    for robot in ["r2d2", "c3po"] {
+        let lidar = EntityPath::from(format!("robots/{robot}/lidar"));
+        for frame_nr in [1, 2] {
+            let row = test_row!(lidar @ [build_frame_nr(frame_nr.into())] => 2; [
+                build_some_point2d(2),
+            ]);
+            store.insert_row(&row).unwrap();
+        }
+
+        let arm_lidar = EntityPath::from(format!("robots/{robot}/arm/lidar"));
+        let row = test_row!(arm_lidar @ [build_frame_nr(3.into())] => 2; [build_some_point2d(2)]);
+        store.insert_row(&row).unwrap();
+    }
+
+    let timeless = TimePoint::timeless();
+    let ground = EntityPath::from("world/ground");
+    let row = test_row!(ground @ timeless => 2; [build_some_colors(2)]);
+    store.insert_row(&row).unwrap();
+
+    store
+}
+
+fn pattern(pattern: &str) -> EntityPathPattern {
+    pattern.parse().unwrap()
+}
+
+#[test]
+fn entities_matching() {
+    let store = build_store();
+
+    assert_eq!(
+        store.entities_matching(&pattern("/robots/*/lidar")),
+        vec![
+            EntityPath::from("robots/c3po/lidar"),
+            EntityPath::from("robots/r2d2/lidar"),
+        ]
+    );
+    assert_eq!(
+        store.entities_matching(&pattern("robots/**/lidar")).len(),
+        4
+    );
+    assert_eq!(
+        store.entities_matching(&pattern("world/**")),
+        vec![EntityPath::from("world/ground")],
+        "timeless entities match too"
+    );
+    assert_eq!(
+        store.entities_matching(&pattern("robots/r2d2/lidar")),
+        vec![EntityPath::from("robots/r2d2/lidar")]
+    );
+    assert!(store.entities_matching(&pattern("robots/*")).is_empty());
+}
+
+#[test]
+fn latest_at_pattern() {
+    let store = build_store();
+    let timeline_frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+
+    let query = LatestAtQuery::new(timeline_frame_nr, 10.into());
+    let results = store.latest_at_pattern(
+        &query,
+        &pattern("**"),
+        Point2D::name(),
+        &[Point2D::name(), ColorRGBA::name()],
+    );
+    let ent_paths: Vec<_> = results
+        .iter()
+        .map(|(ent_path, _, _)| ent_path.to_string())
+        .collect();
+    assert_eq!(
+        ent_paths,
+        vec![
+            "robots/c3po/arm/lidar",
+            "robots/c3po/lidar",
+            "robots/r2d2/arm/lidar",
+            "robots/r2d2/lidar",
+        ],
+        "world/ground has no points"
+    );
+    assert!(results
+        .iter()
+        .all(|(_, _, [points, colors])| points.is_some() && colors.is_none()));
+}
+
+#[test]
+fn range_pattern() {
+    let store = build_store();
+    let timeline_frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+
+    let query = RangeQuery::new(timeline_frame_nr, TimeRange::new(2.into(), 3.into()));
+    let results: Vec<_> = store
+        .range_pattern(&query, &pattern("robots/*/**"), [Point2D::name()])
+        .map(|(ent_path, time, _, _)| (ent_path.to_string(), time))
+        .collect();
+    let frame = |frame_nr: i64| Some(TimeInt::from(frame_nr));
+    assert_eq!(
+        results,
+        vec![
+            ("robots/c3po/arm/lidar".to_owned(), frame(3)),
+            ("robots/c3po/lidar".to_owned(), frame(2)),
+            ("robots/r2d2/arm/lidar".to_owned(), frame(3)),
+            ("robots/r2d2/lidar".to_owned(), frame(2)),
+        ]
+    );
+}
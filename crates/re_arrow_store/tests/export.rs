@@ -0,0 +1,162 @@
+//! Exporting a datastore to standard file formats.
+
+use arrow2::datatypes::DataType;
+use re_arrow_store::{test_row, DataStore, ExportGrouping};
+use re_log_types::{
+    component_types::{InstanceKey, Point2D},
+    datagen::{build_frame_nr, build_log_time, build_some_colors, build_some_point2d},
+    Component as _, DataTable, EntityPath, TableId, Time, TimePoint, COLUMN_ENTITY_PATH,
+    COLUMN_NUM_INSTANCES, COLUMN_ROW_ID,
+};
+
+fn build_store() -> DataStore {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    let ent_path1 = EntityPath::from("this/that");
+    let ent_path2 = EntityPath::from("other");
+    let log_time = Time::now();
+    let timeless = TimePoint::timeless();
+
+    let rows = [
+        test_row!(ent_path1 @ [build_frame_nr(1.into()), build_log_time(log_time)] => 2; [
+            build_some_point2d(2), build_some_colors(2),
+        ]),
+        test_row!(ent_path1 @ [build_frame_nr(2.into())] => 2; [build_some_point2d(2)]),
+        test_row!(ent_path2 @ [build_frame_nr(3.into())] => 3; [build_some_point2d(3)]),
+        // timeless
+        test_row!(ent_path2 @ timeless => 1; [build_some_colors(1)]),
+    ];
+    let table = DataTable::from_rows(TableId::random(), rows);
+    store.insert_table(&table).unwrap();
+
+    store
+}
+
+fn field_names(schema: &arrow2::datatypes::Schema) -> Vec<&str> {
+    schema
+        .fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect()
+}
+
+#[test]
+fn export_per_entity_path() {
+    let store = build_store();
+    let tables = store.to_export_tables(ExportGrouping::EntityPath).unwrap();
+
+    let names: Vec<_> = tables.iter().map(|table| table.name.as_str()).collect();
+    assert_eq!(names, vec!["other", "this/that"]);
+
+    let other = &tables[0];
+    assert_eq!(other.num_rows(), 2);
+    assert_eq!(other.file_stem(), "other");
+
+    let this_that = &tables[1];
+    assert_eq!(this_that.num_rows(), 2);
+    assert_eq!(this_that.file_stem(), "this.that");
+
+    // Each row shows up once, with all of its timelines:
+    assert_eq!(
+        field_names(&this_that.schema),
+        vec![
+            COLUMN_ROW_ID,
+            COLUMN_ENTITY_PATH,
+            "frame_nr",
+            "log_time",
+            COLUMN_NUM_INSTANCES,
+            "rerun.colorrgba",
+            InstanceKey::name().as_str(),
+            Point2D::name().as_str(),
+        ]
+    );
+
+    let log_time = &this_that.chunk.arrays()[3];
+    assert_eq!(
+        log_time.null_count(),
+        1,
+        "The second row isn't on the log_time timeline"
+    );
+
+    // The timeless row has no time at all:
+    let frame_nr = &other.chunk.arrays()[2];
+    assert_eq!(frame_nr.null_count(), 1);
+
+    for table in &tables {
+        for (field, column) in table.schema.fields.iter().zip(table.chunk.arrays()) {
+            if field.name == COLUMN_ROW_ID || field.name == COLUMN_ENTITY_PATH {
+                assert_eq!(column.data_type(), &DataType::Utf8);
+            }
+        }
+    }
+}
+
+#[test]
+fn export_per_component_set() {
+    let store = build_store();
+    let tables = store
+        .to_export_tables(ExportGrouping::ComponentSet)
+        .unwrap();
+
+    let mut num_rows: Vec<_> = tables
+        .iter()
+        .map(|table| (table.name.as_str(), table.num_rows()))
+        .collect();
+    num_rows.sort();
+    assert_eq!(
+        num_rows,
+        vec![
+            ("rerun.colorrgba+rerun.instance_key", 1),
+            ("rerun.colorrgba+rerun.instance_key+rerun.point2d", 1),
+            ("rerun.instance_key+rerun.point2d", 2),
+        ]
+    );
+
+    let entity_paths = tables
+        .iter()
+        .find(|table| table.name == "rerun.instance_key+rerun.point2d")
+        .unwrap()
+        .chunk
+        .arrays()[1]
+        .as_any()
+        .downcast_ref::<arrow2::array::Utf8Array<i32>>()
+        .unwrap()
+        .values_iter()
+        .collect::<Vec<_>>();
+    assert_eq!(entity_paths, vec!["this/that", "other"]);
+}
+
+#[test]
+fn export_arrow_ipc_roundtrip() {
+    use arrow2::io::ipc::read::{read_file_metadata, FileReader};
+
+    let store = build_store();
+    for table in store.to_export_tables(ExportGrouping::EntityPath).unwrap() {
+        let mut file = Vec::new();
+        table.write_arrow_ipc(&mut file).unwrap();
+
+        let mut cursor = std::io::Cursor::new(file);
+        let metadata = read_file_metadata(&mut cursor).unwrap();
+        assert_eq!(metadata.schema.fields, table.schema.fields);
+
+        let chunks = FileReader::new(cursor, metadata, None, None)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(chunks, vec![table.chunk.clone()]);
+    }
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn export_parquet() {
+    use arrow2::io::parquet::read::read_metadata;
+
+    let store = build_store();
+    for table in store.to_export_tables(ExportGrouping::EntityPath).unwrap() {
+        let mut file = Vec::new();
+        table.write_parquet(&mut file).unwrap();
+
+        let metadata = read_metadata(&mut std::io::Cursor::new(file)).unwrap();
+        assert_eq!(metadata.num_rows, table.num_rows());
+    }
+}
@@ -0,0 +1,237 @@
+//! Importing tables from standard file formats.
+
+use arrow2::{
+    array::{Array, Float32Array, Float64Array, Int64Array, PrimitiveArray, Utf8Array},
+    chunk::Chunk,
+    datatypes::{DataType, Field, Schema, TimeUnit},
+};
+use re_arrow_store::{
+    import_arrow_ipc, import_chunks, import_to_log_msgs, ComponentColumns, EntityPathSource,
+    ImportConfig, ImportError, ImportFormat, TimelineColumn,
+};
+use re_log_types::{
+    component_types::{Label, Point3D, Scalar},
+    DataRow, DataTable, DeserializableComponent, EntityPath, LogMsg, TimeInt, TimeType, Timeline,
+};
+
+/// Three readings of two sensors.
+fn build_table() -> (Schema, Chunk<Box<dyn Array>>) {
+    let fields = vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ),
+        Field::new("sensor", DataType::Utf8, false),
+        Field::new("temperature", DataType::Float64, true),
+        Field::new("x", DataType::Float32, false),
+        Field::new("y", DataType::Float32, false),
+        Field::new("z", DataType::Float32, false),
+        Field::new("status", DataType::Utf8, true),
+        Field::new("frame", DataType::Int64, false),
+    ];
+    let columns = vec![
+        PrimitiveArray::<i64>::from_vec(vec![1_000, 2_000, 3_000])
+            .to(DataType::Timestamp(TimeUnit::Millisecond, None))
+            .boxed(),
+        Utf8Array::<i32>::from_slice(["front", "back", "front"]).boxed(),
+        Float64Array::from([Some(20.5), None, Some(21.0)]).boxed(),
+        Float32Array::from_slice([1.0, 2.0, 3.0]).boxed(),
+        Float32Array::from_slice([4.0, 5.0, 6.0]).boxed(),
+        Float32Array::from_slice([7.0, 8.0, 9.0]).boxed(),
+        Utf8Array::<i32>::from([Some("ok"), Some("ok"), None]).boxed(),
+        Int64Array::from_slice([10, 11, 12]).boxed(),
+    ];
+    (Schema::from(fields), Chunk::new(columns))
+}
+
+fn import(config: &ImportConfig) -> Vec<DataRow> {
+    let (schema, chunk) = build_table();
+    import_chunks(&schema, [Ok(chunk)], config)
+        .unwrap()
+        .iter()
+        .flat_map(|table| table.to_rows().collect::<Vec<_>>())
+        .collect()
+}
+
+fn native<C: DeserializableComponent>(row: &DataRow) -> C
+where
+    for<'a> &'a C::ArrayType: IntoIterator,
+{
+    let cell = row.find_cell(&C::name()).unwrap();
+    row.cells()[cell].to_native::<C>().next().unwrap()
+}
+
+fn rows_at<'a>(rows: &'a [DataRow], entity_path: &str) -> Vec<&'a DataRow> {
+    let entity_path = EntityPath::from(entity_path);
+    rows.iter()
+        .filter(|row| row.entity_path() == &entity_path)
+        .collect()
+}
+
+#[test]
+fn import_with_inferred_columns() {
+    let config = ImportConfig::new(EntityPathSource::Template("sensors/{sensor}".to_owned()));
+    let rows = import(&config);
+
+    // The timestamp column becomes a timeline, in nanoseconds:
+    let timestamp = Timeline::new("timestamp", TimeType::Time);
+    let front = rows_at(&rows, "sensors/front");
+    assert_eq!(front.len(), 2);
+    assert_eq!(
+        front[1].timepoint().get(&timestamp),
+        Some(&TimeInt::from(3_000_000_000))
+    );
+
+    // x, y and z become a point, and the status its label:
+    let mut components: Vec<_> = front[0].component_names().collect();
+    components.sort();
+    assert_eq!(components, vec![Label::name(), Point3D::name()]);
+    let point = native::<Point3D>(front[0]);
+    assert_eq!(point, Point3D::new(1.0, 4.0, 7.0));
+    assert_eq!(front[1].component_names().count(), 1, "the status is null");
+
+    // Every numeric column gets its own scalar entity, without the nulls:
+    let temperature = rows_at(&rows, "sensors/front/temperature");
+    assert_eq!(temperature.len(), 2);
+    assert_eq!(native::<Scalar>(temperature[1]).0, 21.0);
+    assert!(rows_at(&rows, "sensors/back/temperature").is_empty());
+    assert_eq!(rows_at(&rows, "sensors/back/frame").len(), 1);
+
+    // The entity path column isn't imported as a label:
+    assert!(rows
+        .iter()
+        .filter(|row| row.find_cell(&Label::name()).is_some())
+        .all(|row| native::<Label>(row).0 == "ok"));
+}
+
+#[test]
+fn import_with_explicit_columns() {
+    let mut config = ImportConfig::new(EntityPathSource::Template("robot".to_owned()));
+    config.timelines = vec![TimelineColumn {
+        column: "frame".to_owned(),
+        time_type: TimeType::Sequence,
+    }];
+    config.components = vec![
+        ComponentColumns::Scalar("temperature".to_owned()),
+        ComponentColumns::Label("sensor".to_owned()),
+    ];
+    let rows = import(&config);
+
+    let frame = Timeline::new("frame", TimeType::Sequence);
+    let robot = rows_at(&rows, "robot");
+    assert_eq!(robot.len(), 3);
+    for (row, frame_nr) in robot.iter().zip(10..) {
+        assert_eq!(row.timepoint().get(&frame), Some(&TimeInt::from(frame_nr)));
+        assert_eq!(
+            row.timepoint().timelines().len(),
+            1,
+            "only the requested timelines"
+        );
+        assert_eq!(
+            row.component_names().collect::<Vec<_>>(),
+            vec![Label::name()]
+        );
+    }
+    assert_eq!(rows_at(&rows, "robot/temperature").len(), 2);
+    assert_eq!(rows.len(), 5);
+}
+
+#[test]
+fn import_entity_paths_from_column() {
+    // Leave out the timestamps:
+    let (schema, chunk) = build_table();
+    let schema = Schema::from(schema.fields[1..].to_vec());
+    let chunk = Chunk::new(chunk.into_arrays().split_off(1));
+
+    let mut config = ImportConfig::new(EntityPathSource::Column("status".to_owned()));
+    config.components = vec![ComponentColumns::Scalar("frame".to_owned())];
+    config.max_rows_per_table = 1;
+
+    let tables = import_chunks(&schema, [Ok(chunk)], &config).unwrap();
+    assert_eq!(tables.len(), 2, "the row without a status is skipped");
+    assert!(tables
+        .iter()
+        .flat_map(DataTable::to_rows)
+        .all(|row| row.entity_path() == &EntityPath::from("ok/frame")));
+
+    // Without timestamps, rows are numbered instead:
+    let row_nr = Timeline::new_sequence("row_nr");
+    let row_nrs: Vec<_> = tables
+        .iter()
+        .flat_map(DataTable::to_rows)
+        .map(|row| *row.timepoint().get(&row_nr).unwrap())
+        .collect();
+    assert_eq!(row_nrs, vec![TimeInt::from(0), TimeInt::from(1)]);
+}
+
+#[test]
+fn import_bad_columns() {
+    let (schema, chunk) = build_table();
+
+    let mut config = ImportConfig::new(EntityPathSource::Template("{missing}".to_owned()));
+    let err = import_chunks(&schema, [Ok(chunk.clone())], &config).unwrap_err();
+    assert!(matches!(err, ImportError::MissingColumn(column) if column == "missing"));
+
+    config.entity_path = EntityPathSource::Template("robot".to_owned());
+    config.components = vec![ComponentColumns::Scalar("sensor".to_owned())];
+    let err = import_chunks(&schema, [Ok(chunk)], &config).unwrap_err();
+    assert!(matches!(err, ImportError::UnsupportedColumn { .. }));
+}
+
+#[test]
+fn import_arrow_ipc_file() {
+    use arrow2::io::ipc::write::{FileWriter, WriteOptions};
+
+    let (schema, chunk) = build_table();
+    let mut file = Vec::new();
+    let mut writer = FileWriter::try_new(&mut file, schema, None, WriteOptions::default()).unwrap();
+    writer.write(&chunk, None).unwrap();
+    writer.finish().unwrap();
+
+    let config = ImportConfig::new(EntityPathSource::Template("sensors/{sensor}".to_owned()));
+    let tables = import_arrow_ipc(std::io::Cursor::new(file), &config).unwrap();
+    assert_eq!(tables.iter().map(|table| table.num_rows()).sum::<u32>(), 8);
+
+    let msgs = import_to_log_msgs("imported".into(), &tables).unwrap();
+    assert!(matches!(msgs[0], LogMsg::BeginRecordingMsg(_)));
+    assert_eq!(msgs.len(), 1 + tables.len());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn import_csv_file() {
+    let csv = "\
+time,name,value
+2023-05-01T12:00:00Z,a,1.5
+2023-05-01T12:00:01Z,b,2.5
+";
+    let config = ImportConfig::new(EntityPathSource::Template("{name}".to_owned()));
+    let tables = re_arrow_store::import_csv(std::io::Cursor::new(csv.as_bytes()), &config).unwrap();
+    let rows: Vec<_> = tables.iter().flat_map(DataTable::to_rows).collect();
+
+    let values = rows_at(&rows, "b/value");
+    assert_eq!(values.len(), 1);
+    let time = Timeline::new("time", TimeType::Time);
+    assert_eq!(
+        values[0].timepoint().get(&time),
+        Some(&TimeInt::from(1_682_942_401_000_000_000))
+    );
+}
+
+#[test]
+fn import_format_from_file_name() {
+    assert_eq!(
+        ImportFormat::from_file_name("data/log.CSV"),
+        Some(ImportFormat::Csv)
+    );
+    assert_eq!(
+        ImportFormat::from_file_name("log.parquet"),
+        Some(ImportFormat::Parquet)
+    );
+    assert_eq!(
+        ImportFormat::from_file_name("log.arrow"),
+        Some(ImportFormat::ArrowIpc)
+    );
+    assert_eq!(ImportFormat::from_file_name("log.rrd"), None);
+}
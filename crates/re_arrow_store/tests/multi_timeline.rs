@@ -0,0 +1,160 @@
+//! Queries that filter on several timelines at once.
+
+use re_arrow_store::{test_row, DataStore, MultiLatestAtQuery, MultiRangeQuery};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::{build_frame_nr, build_some_colors, build_some_point2d},
+    Component as _, DataRow, EntityPath, TimeInt, TimePoint, TimeRange, TimeType, Timeline,
+};
+
+struct Rows {
+    /// frame 10, logged at 100: points & colors
+    row1: DataRow,
+    /// frame 20, logged at 300: points
+    row2: DataRow,
+    /// frame 30, logged at 200: colors
+    row3: DataRow,
+    /// frame 25, no log time: points
+    row4: DataRow,
+    /// timeless: colors
+    row_timeless: DataRow,
+}
+
+fn build_log_time(time: i64) -> (Timeline, TimeInt) {
+    (Timeline::log_time(), time.into())
+}
+
+fn insert_rows(store: &mut DataStore, ent_path: &EntityPath) -> Rows {
+    let rows = Rows {
+        row1: test_row!(ent_path @ [build_frame_nr(10.into()), build_log_time(100)] => 2; [
+            build_some_point2d(2), build_some_colors(2),
+        ]),
+        row2: test_row!(ent_path @ [build_frame_nr(20.into()), build_log_time(300)] => 2; [
+            build_some_point2d(2),
+        ]),
+        row3: test_row!(ent_path @ [build_frame_nr(30.into()), build_log_time(200)] => 2; [
+            build_some_colors(2),
+        ]),
+        row4: test_row!(ent_path @ [build_frame_nr(25.into())] => 2; [build_some_point2d(2)]),
+        row_timeless: {
+            let timeless = TimePoint::timeless();
+            test_row!(ent_path @ timeless => 2; [build_some_colors(2)])
+        },
+    };
+
+    for row in [
+        &rows.row1,
+        &rows.row2,
+        &rows.row3,
+        &rows.row4,
+        &rows.row_timeless,
+    ] {
+        store.insert_row(row).unwrap();
+    }
+
+    rows
+}
+
+#[test]
+fn latest_at_multi() {
+    for config in re_arrow_store::test_util::all_configs() {
+        let mut store = DataStore::new(InstanceKey::name(), config.clone());
+        latest_at_multi_impl(&mut store);
+    }
+}
+
+fn latest_at_multi_impl(store: &mut DataStore) {
+    let ent_path = EntityPath::from("this/that");
+    let rows = insert_rows(store, &ent_path);
+
+    let frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+    let log_time = Timeline::log_time();
+    let components = [Point2D::name(), ColorRGBA::name()];
+
+    // Rows logged after 250, or without a log time, are ignored.
+    let query = MultiLatestAtQuery::new(frame_nr, 30.into())
+        .with_filter(log_time, TimeRange::new(TimeInt::MIN, 250.into()));
+    let (row_id, timepoint, cells) = store
+        .latest_at_multi(&query, &ent_path, Point2D::name(), &components)
+        .unwrap();
+    assert_eq!(row_id, rows.row1.row_id());
+    assert_eq!(&timepoint, rows.row1.timepoint());
+    assert!(cells.iter().all(Option::is_some));
+
+    // Without filters, this behaves just like a plain latest-at query: missing components are
+    // filled with timeless data.
+    let query = MultiLatestAtQuery::new(frame_nr, 30.into());
+    let (row_id, timepoint, cells) = store
+        .latest_at_multi(&query, &ent_path, Point2D::name(), &components)
+        .unwrap();
+    assert_eq!(row_id, rows.row4.row_id());
+    assert_eq!(&timepoint, rows.row4.timepoint());
+    assert_eq!(cells[1], Some(rows.row_timeless.cells()[0].clone()));
+
+    // Querying at a full timepoint.
+    let at: TimePoint = [build_frame_nr(25.into()), build_log_time(1_000)].into();
+    let query = MultiLatestAtQuery::at_timepoint(frame_nr, &at).unwrap();
+    let (row_id, _, _) = store
+        .latest_at_multi(&query, &ent_path, Point2D::name(), &components)
+        .unwrap();
+    assert_eq!(row_id, rows.row2.row_id());
+    assert!(MultiLatestAtQuery::at_timepoint(Timeline::new_sequence("other"), &at).is_none());
+
+    // Nothing matches: only timeless data is left.
+    let query = MultiLatestAtQuery::new(frame_nr, 30.into())
+        .with_filter(log_time, TimeRange::new(TimeInt::MIN, 50.into()));
+    assert!(store
+        .latest_at_multi(&query, &ent_path, Point2D::name(), &components)
+        .is_none());
+    let (row_id, timepoint, _) = store
+        .latest_at_multi(&query, &ent_path, ColorRGBA::name(), &components)
+        .unwrap();
+    assert_eq!(row_id, rows.row_timeless.row_id());
+    assert!(timepoint.is_timeless());
+}
+
+#[test]
+fn range_multi() {
+    for config in re_arrow_store::test_util::all_configs() {
+        let mut store = DataStore::new(InstanceKey::name(), config.clone());
+        range_multi_impl(&mut store);
+    }
+}
+
+fn range_multi_impl(store: &mut DataStore) {
+    let ent_path = EntityPath::from("this/that");
+    let rows = insert_rows(store, &ent_path);
+
+    let frame_nr = Timeline::new("frame_nr", TimeType::Sequence);
+    let log_time = Timeline::log_time();
+    let components = [Point2D::name(), ColorRGBA::name()];
+
+    let query = MultiRangeQuery::new(frame_nr, TimeRange::new(0.into(), 30.into()))
+        .with_filter(log_time, TimeRange::new(150.into(), TimeInt::MAX));
+    let results: Vec<_> = store
+        .range_multi(&query, &ent_path, components)
+        .map(|(row_id, timepoint, _)| (row_id, timepoint))
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            (rows.row2.row_id(), rows.row2.timepoint().clone()),
+            (rows.row3.row_id(), rows.row3.timepoint().clone()),
+        ]
+    );
+
+    // Timeless data comes first, and isn't subject to the filters.
+    let query = MultiRangeQuery::new(frame_nr, TimeRange::new(TimeInt::MIN, 15.into()))
+        .with_filter(log_time, TimeRange::new(TimeInt::MIN, 150.into()));
+    let results: Vec<_> = store
+        .range_multi(&query, &ent_path, components)
+        .map(|(row_id, timepoint, _)| (row_id, timepoint))
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            (rows.row_timeless.row_id(), TimePoint::timeless()),
+            (rows.row1.row_id(), rows.row1.timepoint().clone()),
+        ]
+    );
+}
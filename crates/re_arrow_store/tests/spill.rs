@@ -0,0 +1,306 @@
+//! Spilling cold data to disk, and paging it back in.
+
+use std::path::{Path, PathBuf};
+
+use re_arrow_store::{
+    test_row, DataStore, DataStoreConfig, DataStoreStats, GarbageCollectionOptions, LatestAtQuery,
+    SpillError, SpillOptions, SpillTarget, WriteError,
+};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::{build_frame_nr, build_some_colors, build_some_point2d},
+    Component as _, DataCell, EntityPath, RowId, RowSelector, TimeRange, Timeline,
+};
+
+fn spill_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("re_arrow_store-{name}-{}", std::process::id()))
+}
+
+/// Every store writes to its own subdirectory of `dir`.
+fn spill_files(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .flat_map(|entry| {
+            std::fs::read_dir(entry.path())
+                .into_iter()
+                .flatten()
+                .flatten()
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+fn num_spill_files(dir: &Path) -> usize {
+    spill_files(dir).len()
+}
+
+fn num_spill_dirs(dir: &Path) -> usize {
+    std::fs::read_dir(dir).map_or(0, |entries| entries.count())
+}
+
+fn query_all(
+    store: &DataStore,
+    ent_path: &EntityPath,
+) -> Vec<Option<(RowId, [Option<DataCell>; 2])>> {
+    let timeline = Timeline::new_sequence("frame_nr");
+    let components = [Point2D::name(), ColorRGBA::name()];
+    (1..=10)
+        .map(|frame_nr| {
+            let query = LatestAtQuery::new(timeline, frame_nr.into());
+            store.latest_at(&query, ent_path, Point2D::name(), &components)
+        })
+        .collect()
+}
+
+fn build_store(ent_path: &EntityPath) -> DataStore {
+    let mut store = DataStore::new(
+        InstanceKey::name(),
+        DataStoreConfig {
+            indexed_bucket_num_rows: 2,
+            ..Default::default()
+        },
+    );
+
+    for frame_nr in 1..=10 {
+        // Colors only every other frame, so that some columns are sparse.
+        let row = if frame_nr % 2 == 0 {
+            test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => 2; [
+                build_some_point2d(2), build_some_colors(2),
+            ])
+        } else {
+            test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => 2; [build_some_point2d(2)])
+        };
+        store.insert_row(&row).unwrap();
+    }
+
+    store
+}
+
+#[test]
+fn spill_and_page_in() {
+    let ent_path = EntityPath::from("this/that");
+    let mut store = build_store(&ent_path);
+    let dir = spill_dir("spill_and_page_in");
+
+    let expected = query_all(&store, &ent_path);
+
+    let stats_before = DataStoreStats::from_store(&store);
+    assert_eq!(stats_before.spilled.num_bytes, 0);
+
+    let num_bytes_spilled = store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    assert!(num_bytes_spilled > 0);
+    store.sanity_check().unwrap();
+
+    // Spilled data still counts towards the size of the store.
+    let stats_spilled = DataStoreStats::from_store(&store);
+    assert_eq!(stats_spilled.temporal, stats_before.temporal);
+    assert_eq!(stats_spilled.spilled.num_bytes, num_bytes_spilled);
+    assert!(stats_spilled.spilled.num_rows > 0);
+    // The most recent bucket always stays resident.
+    assert!(stats_spilled.spilled.num_rows < stats_spilled.temporal.num_rows);
+    assert!(num_spill_files(&dir) > 0);
+
+    // Spilling again is a no-op: everything that could be spilled already is.
+    let num_bytes_spilled = store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    assert_eq!(num_bytes_spilled, 0);
+
+    // Querying pages everything back in, transparently.
+    assert_eq!(query_all(&store, &ent_path), expected);
+    store.sanity_check().unwrap();
+
+    let stats_paged_in = DataStoreStats::from_store(&store);
+    assert_eq!(stats_paged_in.spilled.num_rows, 0);
+    assert_eq!(stats_paged_in.temporal, stats_before.temporal);
+
+    // The spill files of unmodified buckets are kept around, so spilling them again is free.
+    let num_files = num_spill_files(&dir);
+    assert!(num_files > 0);
+    let num_bytes_spilled = store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    assert_eq!(num_bytes_spilled, stats_spilled.spilled.num_bytes);
+    assert_eq!(num_spill_files(&dir), num_files);
+    assert_eq!(DataStoreStats::from_store(&store), stats_spilled);
+    assert_eq!(query_all(&store, &ent_path), expected);
+
+    // Nothing to do when under the target already.
+    let num_bytes_spilled = store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::MaxResidentBytes(u64::MAX),
+        })
+        .unwrap();
+    assert_eq!(num_bytes_spilled, 0);
+
+    drop(store);
+    assert_eq!(num_spill_dirs(&dir), 0);
+}
+
+#[test]
+fn spill_then_write_and_gc() {
+    let ent_path = EntityPath::from("this/that");
+    let mut store = build_store(&ent_path);
+    let dir = spill_dir("spill_then_write_and_gc");
+
+    store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+
+    // Clones share the spill files.
+    let store2 = store.clone();
+
+    // Out-of-order writes page in the bucket they land in.
+    let row = test_row!(ent_path @ [build_frame_nr(3.into())] => 2; [build_some_point2d(2)]);
+    store.insert_row(&row).unwrap();
+    store.sanity_check().unwrap();
+
+    let (row_ids, _) = store.gc(GarbageCollectionOptions::gc_everything());
+    assert_eq!(row_ids.len(), 11);
+    store.sanity_check().unwrap();
+    assert_eq!(DataStoreStats::from_store(&store).spilled.num_rows, 0);
+
+    // The clone still has its own spilled data...
+    assert!(DataStoreStats::from_store(&store2).spilled.num_rows > 0);
+    assert!(num_spill_files(&dir) > 0);
+
+    // ...until it gets dropped.
+    drop(store2);
+    assert_eq!(num_spill_files(&dir), 0);
+
+    drop(store);
+    assert_eq!(num_spill_dirs(&dir), 0);
+}
+
+#[test]
+fn spill_then_delete() {
+    let ent_path = EntityPath::from("this/that");
+    let mut store = build_store(&ent_path);
+    let mut expected_store = store.clone();
+    let dir = spill_dir("spill_then_delete");
+
+    store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    let stats_spilled = DataStoreStats::from_store(&store);
+
+    let selector = RowSelector::TimeRange {
+        entity_path: ent_path.clone(),
+        timeline: Timeline::new_sequence("frame_nr"),
+        time_range: TimeRange::new(3.into(), 6.into()),
+    };
+    let events = store.delete_rows(&selector);
+    assert_eq!(events.len(), 4);
+    store.sanity_check().unwrap();
+    expected_store.delete_rows(&selector);
+
+    // Rows are dropped from spilled buckets without paging them in...
+    let stats_deleted = DataStoreStats::from_store(&store);
+    assert!(stats_deleted.spilled.num_rows > 0);
+    assert!(stats_deleted.spilled.num_rows < stats_spilled.spilled.num_rows);
+    assert!(stats_deleted.spilled.num_bytes < stats_spilled.spilled.num_bytes);
+    let stats_expected = DataStoreStats::from_store(&expected_store);
+    assert_eq!(
+        stats_deleted.temporal.num_rows,
+        stats_expected.temporal.num_rows
+    );
+
+    // ...and what's left pages in just fine.
+    assert_eq!(
+        query_all(&store, &ent_path),
+        query_all(&expected_store, &ent_path)
+    );
+    store.sanity_check().unwrap();
+}
+
+#[test]
+fn spill_file_unreadable() {
+    let ent_path = EntityPath::from("this/that");
+    let mut store = build_store(&ent_path);
+    let dir = spill_dir("spill_file_unreadable");
+
+    store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    let stats_spilled = DataStoreStats::from_store(&store);
+
+    for path in spill_files(&dir) {
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // Queries skip the buckets that cannot be paged in, which stay spilled...
+    let timeline = Timeline::new_sequence("frame_nr");
+    let query = LatestAtQuery::new(timeline, 1.into());
+    assert!(store
+        .latest_at(&query, &ent_path, Point2D::name(), &[Point2D::name()])
+        .is_none());
+    assert_eq!(DataStoreStats::from_store(&store), stats_spilled);
+
+    // ...while the most recent one is still there.
+    let query = LatestAtQuery::new(timeline, 10.into());
+    assert!(store
+        .latest_at(&query, &ent_path, Point2D::name(), &[Point2D::name()])
+        .is_some());
+
+    // Writing into them fails, and leaves the store untouched.
+    let row = test_row!(ent_path @ [build_frame_nr(1.into())] => 2; [build_some_point2d(2)]);
+    assert!(matches!(
+        store.insert_row(&row),
+        Err(WriteError::Spill(SpillError::Io(_)))
+    ));
+    store.sanity_check().unwrap();
+    assert_eq!(DataStoreStats::from_store(&store), stats_spilled);
+}
+
+#[test]
+fn spill_dir_cleanup() {
+    let ent_path = EntityPath::from("this/that");
+    let mut store = build_store(&ent_path);
+    let dir = spill_dir("spill_dir_cleanup");
+
+    // Left behind by a process that crashed. No such pid can ever be running.
+    let stale_dir = dir.join(format!("rerun-spill-{}-0", u32::MAX));
+    std::fs::create_dir_all(&stale_dir).unwrap();
+    std::fs::write(stale_dir.join("0.arrow"), b"garbage").unwrap();
+    // Not ours to remove.
+    let unrelated_dir = dir.join("something-else");
+    std::fs::create_dir_all(&unrelated_dir).unwrap();
+
+    store
+        .spill(&SpillOptions {
+            dir: dir.clone(),
+            target: SpillTarget::SpillAtLeastFraction(1.0),
+        })
+        .unwrap();
+    assert!(!stale_dir.exists());
+    assert!(unrelated_dir.exists());
+    assert_eq!(num_spill_dirs(&dir), 2);
+
+    drop(store);
+    assert_eq!(num_spill_dirs(&dir), 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,109 @@
+//! Per-entity and per-component breakdowns of the store stats.
+
+use re_arrow_store::{
+    test_row, test_util::sanity_unwrap, DataStore, DataStoreConfig, DataStoreRowStats,
+    DataStoreStats, GarbageCollectionOptions,
+};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::{build_frame_nr, build_some_colors, build_some_point2d},
+    Component as _, EntityPath, RowSelector, TimePoint,
+};
+
+/// The breakdowns must always add up to the overall timeless and temporal stats.
+fn check_breakdowns(stats: &DataStoreStats) {
+    let entities = stats
+        .entities
+        .values()
+        .fold(DataStoreRowStats::default(), |acc, stats| acc + *stats);
+    assert_eq!(stats.timeless + stats.temporal, entities);
+
+    let component_bytes: u64 = stats.components.values().map(|stats| stats.num_bytes).sum();
+    assert!(component_bytes <= entities.num_bytes);
+}
+
+fn num_rows(stats: &DataStoreStats, ent_path: &EntityPath) -> u64 {
+    stats
+        .entities
+        .get(ent_path)
+        .map_or(0, |stats| stats.num_rows)
+}
+
+#[test]
+fn breakdowns() {
+    let mut store = DataStore::new(
+        InstanceKey::name(),
+        DataStoreConfig {
+            indexed_bucket_num_rows: 2,
+            ..Default::default()
+        },
+    );
+
+    let ent_path1 = EntityPath::from("this/that");
+    let ent_path2 = EntityPath::from("this/other");
+
+    let row_timeless = test_row!(ent_path2 @ (TimePoint::timeless()) => 2; [build_some_colors(2)]);
+    store.insert_row(&row_timeless).unwrap();
+    for frame_nr in 1..=10 {
+        // Colors only every other frame, and across bucket splits.
+        let row = if frame_nr % 2 == 0 {
+            test_row!(ent_path1 @ [build_frame_nr(frame_nr.into())] => 2; [
+                build_some_point2d(2), build_some_colors(2),
+            ])
+        } else {
+            test_row!(ent_path1 @ [build_frame_nr(frame_nr.into())] => 2; [build_some_point2d(2)])
+        };
+        store.insert_row(&row).unwrap();
+    }
+    sanity_unwrap(&mut store);
+
+    let stats = DataStoreStats::from_store(&store);
+    check_breakdowns(&stats);
+    assert_eq!(2, stats.entities.len());
+    assert_eq!(10, stats.entities[&ent_path1].num_rows);
+    assert_eq!(1, stats.entities[&ent_path2].num_rows);
+    assert_eq!(10, stats.components[&Point2D::name()].num_rows);
+    assert_eq!(5 + 1, stats.components[&ColorRGBA::name()].num_rows);
+
+    // Garbage collection only drops temporal data.
+    store.gc(GarbageCollectionOptions::gc_everything());
+    sanity_unwrap(&mut store);
+
+    let stats = DataStoreStats::from_store(&store);
+    check_breakdowns(&stats);
+    assert_eq!(0, num_rows(&stats, &ent_path1));
+    assert!(!stats.components.contains_key(&Point2D::name()));
+    assert_eq!(1, stats.components[&ColorRGBA::name()].num_rows);
+
+    // Deleting the last timeless row leaves no component data behind.
+    store.delete_rows(&RowSelector::RowIds(vec![row_timeless.row_id()]));
+    sanity_unwrap(&mut store);
+
+    let stats = DataStoreStats::from_store(&store);
+    check_breakdowns(&stats);
+    assert_eq!(0, num_rows(&stats, &ent_path2));
+    assert!(stats.components.is_empty());
+}
+
+#[test]
+fn breakdowns_diff() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    let ent_path = EntityPath::from("this/that");
+    for frame_nr in 1..=10 {
+        let row =
+            test_row!(ent_path @ [build_frame_nr(frame_nr.into())] => 2; [build_some_point2d(2)]);
+        store.insert_row(&row).unwrap();
+    }
+
+    let stats = DataStoreStats::from_store(&store);
+    let (_, stats_diff) = store.gc(GarbageCollectionOptions::gc_everything());
+    assert_eq!(
+        stats.components[&Point2D::name()],
+        stats_diff.components[&Point2D::name()]
+    );
+
+    // Nothing left to collect: the breakdowns of the diff are empty too.
+    let (_, stats_diff) = store.gc(GarbageCollectionOptions::gc_everything());
+    assert_eq!(DataStoreStats::default(), stats_diff);
+}
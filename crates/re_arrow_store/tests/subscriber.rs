@@ -0,0 +1,155 @@
+//! Subscribing to the changes of a datastore.
+
+use re_arrow_store::{
+    test_row, DataStore, GarbageCollectionOptions, StoreEvent, StoreEventKind, StoreSubscriber,
+    StoreSubscriberHandle,
+};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::{
+        build_frame_nr, build_log_time, build_some_colors, build_some_instances_from,
+        build_some_point2d,
+    },
+    Component as _, DataCell, EntityPath, Time, TimePoint,
+};
+
+#[derive(Default)]
+struct EventRecorder {
+    events: Vec<StoreEvent>,
+    num_calls: usize,
+}
+
+impl StoreSubscriber for EventRecorder {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn on_events(&mut self, events: &[StoreEvent]) {
+        self.events.extend_from_slice(events);
+        self.num_calls += 1;
+    }
+}
+
+fn take_events(store: &mut DataStore, handle: StoreSubscriberHandle) -> Vec<StoreEvent> {
+    store
+        .with_subscriber_mut(handle, |recorder: &mut EventRecorder| {
+            std::mem::take(&mut recorder.events)
+        })
+        .unwrap()
+}
+
+#[test]
+fn insertions_and_clears() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+    let handle = store.register_subscriber(Box::<EventRecorder>::default());
+
+    let ent_path = EntityPath::from("this/that");
+    let row1 = test_row!(ent_path @ [build_frame_nr(1.into())] => 2; [
+        build_some_point2d(2), build_some_colors(2),
+    ]);
+    let row2 = test_row!(ent_path @ [build_frame_nr(2.into())] => 0; [
+        DataCell::from_native_empty::<Point2D>(),
+    ]);
+    let row3 = test_row!(ent_path @ [build_frame_nr(3.into())] => 0; [
+        DataCell::from_native_empty::<Point2D>(),
+    ]);
+    store.insert_row(&row1).unwrap();
+    store.insert_clear_row(&row2).unwrap();
+    store.insert_row(&row3).unwrap();
+
+    let events = take_events(&mut store, handle);
+    assert_eq!(events.len(), 3);
+
+    assert_eq!(events[0].kind, StoreEventKind::Insertion);
+    assert_eq!(events[0].row_id, row1.row_id());
+    assert_eq!(&events[0].timepoint, row1.timepoint());
+    assert_eq!(events[0].entity_path, ent_path);
+    // The cluster key was auto-generated by the store:
+    let mut components = vec![ColorRGBA::name(), Point2D::name()];
+    components.sort();
+    assert_eq!(events[0].components, components);
+
+    assert_eq!(events[1].kind, StoreEventKind::Clear);
+    assert_eq!(events[1].row_id, row2.row_id());
+
+    // Empty rows that weren't inserted as clears are just that: empty.
+    assert_eq!(events[2].kind, StoreEventKind::Insertion);
+    assert_eq!(events[2].row_id, row3.row_id());
+
+    // Clones don't carry the subscribers over:
+    let mut store2 = store.clone();
+    store2.insert_row(&row1).unwrap();
+    assert_eq!(
+        store.with_subscriber(handle, |recorder: &EventRecorder| recorder.num_calls),
+        Some(3)
+    );
+    assert!(store2
+        .with_subscriber(handle, |recorder: &EventRecorder| recorder.num_calls)
+        .is_none());
+
+    // Unregistered subscribers aren't notified anymore:
+    let recorder = store.unregister_subscriber(handle).unwrap();
+    let row4 = test_row!(ent_path @ [build_frame_nr(4.into())] => 2; [build_some_colors(2)]);
+    store.insert_row(&row4).unwrap();
+    let recorder = recorder.as_any().downcast_ref::<EventRecorder>().unwrap();
+    assert_eq!(recorder.num_calls, 3);
+    assert!(store.unregister_subscriber(handle).is_none());
+}
+
+#[test]
+fn deletions() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    let ent_path = EntityPath::from("this/that");
+    let timepoint = [build_frame_nr(1.into()), build_log_time(Time::now())];
+    let row1 = test_row!(ent_path @ timepoint => 2; [
+        build_some_point2d(2), build_some_colors(2),
+    ]);
+    let row2 = test_row!(ent_path @ [build_frame_nr(2.into())] => 2; [
+        build_some_instances_from(0..2), build_some_colors(2),
+    ]);
+    let timeless = TimePoint::timeless();
+    let row3 = test_row!(ent_path @ timeless => 2; [build_some_point2d(2)]);
+    store.insert_row(&row1).unwrap();
+    store.insert_row(&row2).unwrap();
+    store.insert_row(&row3).unwrap();
+
+    // Only the changes that happen after registration are notified.
+    let handle = store.register_subscriber(Box::<EventRecorder>::default());
+
+    let (row_ids, _) = store.gc(GarbageCollectionOptions::gc_everything());
+    let events = take_events(&mut store, handle);
+
+    // A single event per row, even when it's indexed on several timelines.
+    assert_eq!(events.len(), row_ids.len());
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| event.kind == StoreEventKind::Deletion));
+
+    let event1 = events.iter().find(|e| e.row_id == row1.row_id()).unwrap();
+    assert_eq!(&event1.timepoint, row1.timepoint());
+    let mut components = vec![ColorRGBA::name(), Point2D::name()];
+    components.sort();
+    assert_eq!(event1.components, components);
+
+    // The cluster key was logged as part of the row, even though it's identical to the one the
+    // store would have generated:
+    let event2 = events.iter().find(|e| e.row_id == row2.row_id()).unwrap();
+    let mut components = vec![ColorRGBA::name(), InstanceKey::name()];
+    components.sort();
+    assert_eq!(event2.components, components);
+
+    // Wiping timeless data notifies its deletion too.
+    store.wipe_timeless_data();
+    let events = take_events(&mut store, handle);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, StoreEventKind::Deletion);
+    assert_eq!(events[0].row_id, row3.row_id());
+    assert_eq!(events[0].timepoint, TimePoint::timeless());
+    assert_eq!(events[0].components, vec![Point2D::name()]);
+}
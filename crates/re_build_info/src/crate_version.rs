@@ -198,6 +198,26 @@ impl CrateVersion {
     }
 }
 
+impl CrateVersion {
+    /// Key for ordering versions: alphas come before the release they lead up to.
+    fn ordering_key(&self) -> (u8, u8, u8, u16, bool) {
+        let alpha = self.alpha.map_or(u16::MAX, u16::from);
+        (self.major, self.minor, self.patch, alpha, self.prerelease)
+    }
+}
+
+impl PartialOrd for CrateVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CrateVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.ordering_key().cmp(&other.ordering_key())
+    }
+}
+
 impl std::fmt::Display for CrateVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Self {
@@ -306,3 +326,15 @@ fn test_compatibility() {
         "Alphas are always incompatible"
     );
 }
+
+#[test]
+fn test_ordering() {
+    let parse = CrateVersion::parse;
+    assert!(parse("0.2.0") < parse("0.2.1"));
+    assert!(parse("0.2.1") < parse("0.3.0"));
+    assert!(parse("0.9.0") < parse("1.0.0"));
+    assert!(parse("0.3.0-alpha.0") < parse("0.3.0-alpha.1"));
+    assert!(parse("0.3.0-alpha.1") < parse("0.3.0"));
+    assert!(parse("0.2.9") < parse("0.3.0-alpha.0"));
+    assert!(parse("0.3.0") < parse("0.3.0+foo"));
+}
@@ -65,7 +65,8 @@ fn log_messages() {
 
     fn encode_log_msg(log_msg: &LogMsg) -> Vec<u8> {
         let mut bytes = vec![];
-        re_log_encoding::encoder::encode(std::iter::once(log_msg), &mut bytes).unwrap();
+        re_log_encoding::encoder::encode(Default::default(), std::iter::once(log_msg), &mut bytes)
+            .unwrap();
         bytes
     }
 
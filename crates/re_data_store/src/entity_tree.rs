@@ -55,11 +55,9 @@ impl TimesPerTimeline {
         self.0.entry(timeline).or_default().insert(time);
     }
 
-    pub fn purge(&mut self, cutoff_times: &std::collections::BTreeMap<Timeline, TimeInt>) {
-        for (timeline, time_set) in &mut self.0 {
-            if let Some(cutoff_time) = cutoff_times.get(timeline) {
-                time_set.retain(|time| cutoff_time <= time);
-            }
+    pub fn remove(&mut self, timeline: &Timeline, time: TimeInt) {
+        if let Some(time_set) = self.0.get_mut(timeline) {
+            time_set.remove(&time);
         }
     }
 
@@ -171,6 +169,60 @@ impl EntityTree {
         pending_clears
     }
 
+    /// Undoes [`Self::add_data_msg`], e.g. because the corresponding row got deleted.
+    ///
+    /// Does nothing if no data was ever added for that component.
+    pub fn remove_data_msg(&mut self, time_point: &TimePoint, component_path: &ComponentPath) {
+        crate::profile_function!();
+
+        let has_component = self
+            .subtree(&component_path.entity_path)
+            .map_or(false, |leaf| {
+                leaf.components.contains_key(&component_path.component_name)
+            });
+        if has_component {
+            self.remove_data_recursively(
+                component_path.entity_path.as_slice(),
+                0,
+                time_point,
+                &component_path.component_name,
+            );
+        }
+    }
+
+    fn remove_data_recursively(
+        &mut self,
+        full_path: &[EntityPathPart],
+        depth: usize,
+        time_point: &TimePoint,
+        component_name: &ComponentName,
+    ) {
+        // If the time_point is timeless...
+        if time_point.is_timeless() {
+            self.num_timeless_messages = self.num_timeless_messages.saturating_sub(1);
+        } else {
+            for (timeline, time_value) in time_point.iter() {
+                if let Some(histogram) = self.prefix_times.0.get_mut(timeline) {
+                    histogram.decrement(time_value.as_i64(), 1);
+                }
+            }
+        }
+
+        match full_path.get(depth) {
+            None => {
+                // end of path
+                if let Some(stats) = self.components.get_mut(component_name) {
+                    stats.remove(time_point);
+                }
+            }
+            Some(component) => {
+                if let Some(child) = self.children.get_mut(component) {
+                    child.remove_data_recursively(full_path, depth + 1, time_point, component_name);
+                }
+            }
+        }
+    }
+
     /// Add a path operation into the the entity tree.
     ///
     /// Returns a collection of paths to clear as a result of the operation
@@ -282,46 +334,23 @@ impl EntityTree {
         subtree_recursive(self, path.as_slice())
     }
 
-    /// Purge all times before the cutoff, or in the given set
-    pub fn purge(
-        &mut self,
-        cutoff_times: &BTreeMap<Timeline, TimeInt>,
-        drop_row_ids: &ahash::HashSet<RowId>,
-    ) {
+    /// Forgets about the pending clears of the given rows, e.g. because they got deleted.
+    pub fn forget_clears(&mut self, drop_row_ids: &ahash::HashSet<RowId>) {
         let Self {
             path: _,
             children,
-            prefix_times,
+            prefix_times: _,
             num_timeless_messages: _,
             nonrecursive_clears,
             recursive_clears,
-            components: fields,
+            components: _,
         } = self;
 
-        for (timeline, histogram) in &mut prefix_times.0 {
-            crate::profile_scope!("prefix_times");
-            if let Some(cutoff_time) = cutoff_times.get(timeline) {
-                histogram.remove(..cutoff_time.as_i64());
-            }
-        }
-        {
-            crate::profile_scope!("nonrecursive_clears");
-            nonrecursive_clears.retain(|row_id, _| !drop_row_ids.contains(row_id));
-        }
-        {
-            crate::profile_scope!("recursive_clears");
-            recursive_clears.retain(|row_id, _| !drop_row_ids.contains(row_id));
-        }
-
-        {
-            crate::profile_scope!("fields");
-            for columns in fields.values_mut() {
-                columns.purge(cutoff_times);
-            }
-        }
+        nonrecursive_clears.retain(|row_id, _| !drop_row_ids.contains(row_id));
+        recursive_clears.retain(|row_id, _| !drop_row_ids.contains(row_id));
 
         for child in children.values_mut() {
-            child.purge(cutoff_times, drop_row_ids);
+            child.forget_clears(drop_row_ids);
         }
     }
 
@@ -363,15 +392,16 @@ impl ComponentStats {
         }
     }
 
-    pub fn purge(&mut self, cutoff_times: &BTreeMap<Timeline, TimeInt>) {
-        let Self {
-            times,
-            num_timeless_messages: _,
-        } = self;
-
-        for (timeline, histogram) in &mut times.0 {
-            if let Some(cutoff_time) = cutoff_times.get(timeline) {
-                histogram.remove(..cutoff_time.as_i64());
+    /// Undoes [`Self::add`].
+    pub fn remove(&mut self, time_point: &TimePoint) {
+        // If the `time_point` is timeless...
+        if time_point.is_timeless() {
+            self.num_timeless_messages = self.num_timeless_messages.saturating_sub(1);
+        } else {
+            for (timeline, time_value) in time_point.iter() {
+                if let Some(histogram) = self.times.0.get_mut(timeline) {
+                    histogram.decrement(time_value.as_i64(), 1);
+                }
             }
         }
     }
@@ -2,11 +2,13 @@ use std::collections::BTreeMap;
 
 use nohash_hasher::IntMap;
 
-use re_arrow_store::{DataStoreConfig, TimeInt};
+use re_arrow_store::{
+    DataStoreConfig, StoreEvent, StoreEventKind, StoreSubscriber, StoreSubscriberHandle,
+};
 use re_log_types::{
     component_types::InstanceKey, ArrowMsg, BeginRecordingMsg, Component as _, ComponentPath,
     DataCell, DataRow, DataTable, EntityPath, EntityPathHash, EntityPathOpMsg, LogMsg, PathOp,
-    RecordingId, RecordingInfo, RowId, TimePoint, Timeline,
+    RecordingId, RecordingInfo, RowId, RowSelector, TimePoint, Timeline,
 };
 
 use crate::{Error, TimesPerTimeline};
@@ -14,6 +16,9 @@ use crate::{Error, TimesPerTimeline};
 // ----------------------------------------------------------------------------
 
 /// Stored entities with easy indexing of the paths.
+///
+/// Everything besides the [`Self::data_store`] is derived from the changes of the store itself,
+/// as notified to a [`StoreSubscriber`].
 pub struct EntityDb {
     /// In many places we just store the hashes, so we need a way to translate back.
     pub entity_path_from_hash: IntMap<EntityPathHash, EntityPath>,
@@ -26,22 +31,53 @@ pub struct EntityDb {
 
     /// Stores all components for all entities for all timelines.
     pub data_store: re_arrow_store::DataStore,
+
+    /// The [`PendingStoreEvents`] registered with the [`Self::data_store`].
+    store_events: StoreSubscriberHandle,
 }
 
 impl Default for EntityDb {
     fn default() -> Self {
+        let mut data_store =
+            re_arrow_store::DataStore::new(InstanceKey::name(), DataStoreConfig::default());
+        let store_events = data_store.register_subscriber(Box::<PendingStoreEvents>::default());
+
         Self {
             entity_path_from_hash: Default::default(),
             times_per_timeline: Default::default(),
             tree: crate::EntityTree::root(),
-            data_store: re_arrow_store::DataStore::new(
-                InstanceKey::name(),
-                DataStoreConfig::default(),
-            ),
+            data_store,
+            store_events,
         }
     }
 }
 
+/// Buffers the changes of an [`EntityDb::data_store`], until the [`EntityDb`] gets to update
+/// everything it derives from it.
+///
+/// Subscribers cannot modify the store they are notified by, but keeping the [`crate::EntityTree`]
+/// up to date sometimes requires inserting pending clears: hence the buffering.
+#[derive(Default)]
+struct PendingStoreEvents(Vec<StoreEvent>);
+
+impl StoreSubscriber for PendingStoreEvents {
+    fn name(&self) -> String {
+        "rerun.entity_db.pending_store_events".to_owned()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn on_events(&mut self, events: &[StoreEvent]) {
+        self.0.extend_from_slice(events);
+    }
+}
+
 impl EntityDb {
     #[inline]
     pub fn entity_path_from_hash(&self, entity_path_hash: &EntityPathHash) -> Option<&EntityPath> {
@@ -70,88 +106,133 @@ impl EntityDb {
     }
 
     fn try_add_data_row(&mut self, row: &DataRow) -> Result<(), Error> {
-        for (&timeline, &time_int) in row.timepoint().iter() {
-            self.times_per_timeline.insert(timeline, time_int);
-        }
-
-        self.register_entity_path(&row.entity_path);
+        let res = self.data_store.insert_row(row);
+        self.on_store_events();
+        res.map_err(Into::into)
+    }
 
-        for cell in row.cells().iter() {
-            let component_path =
-                ComponentPath::new(row.entity_path().clone(), cell.component_name());
-            let pending_clears = self.tree.add_data_msg(row.timepoint(), &component_path);
+    fn add_path_op(&mut self, row_id: RowId, time_point: &TimePoint, path_op: &PathOp) {
+        let cleared_paths = self.tree.add_path_op(row_id, time_point, path_op);
 
-            for (row_id, time_point) in pending_clears {
+        for component_path in cleared_paths {
+            if let Some(data_type) = self
+                .data_store
+                .lookup_datatype(&component_path.component_name)
+            {
                 // Create and insert an empty component into the arrow store
                 // TODO(jleibs): Faster empty-array creation
                 let cell =
-                    DataCell::from_arrow_empty(cell.component_name(), cell.datatype().clone());
-
+                    DataCell::from_arrow_empty(component_path.component_name, data_type.clone());
                 let row = DataRow::from_cells1(
                     row_id,
-                    row.entity_path.clone(),
+                    component_path.entity_path.clone(),
                     time_point.clone(),
                     cell.num_instances(),
                     cell,
                 );
-                self.data_store.insert_row(&row).ok();
-
-                // Also update the tree with the clear-event
-                self.tree.add_data_msg(&time_point, &component_path);
+                self.data_store.insert_clear_row(&row).ok();
             }
         }
 
-        self.data_store.insert_row(row).map_err(Into::into)
+        self.on_store_events();
     }
 
-    fn add_path_op(&mut self, row_id: RowId, time_point: &TimePoint, path_op: &PathOp) {
-        let cleared_paths = self.tree.add_path_op(row_id, time_point, path_op);
+    /// Deletes rows from the store, see [`re_arrow_store::DataStore::delete_rows`].
+    fn delete_rows(&mut self, selector: &RowSelector) {
+        self.data_store.delete_rows(selector);
+        self.on_store_events();
+    }
 
-        for component_path in cleared_paths {
-            if let Some(data_type) = self
+    /// Updates everything that is derived from the [`Self::data_store`] with the changes it went
+    /// through since the last call.
+    ///
+    /// Must be called after every modification of the store.
+    fn on_store_events(&mut self) {
+        crate::profile_function!();
+
+        // Inserting pending clears yields more events: loop until there's nothing left.
+        loop {
+            let events = self
                 .data_store
-                .lookup_datatype(&component_path.component_name)
-            {
+                .with_subscriber_mut(self.store_events, |pending: &mut PendingStoreEvents| {
+                    std::mem::take(&mut pending.0)
+                })
+                .unwrap_or_default();
+            if events.is_empty() {
+                break;
+            }
+
+            let mut deleted_row_ids = ahash::HashSet::default();
+            for event in &events {
+                match event.kind {
+                    StoreEventKind::Insertion | StoreEventKind::Clear => self.on_insertion(event),
+                    StoreEventKind::Deletion => {
+                        self.on_deletion(event);
+                        deleted_row_ids.insert(event.row_id);
+                    }
+                }
+            }
+
+            if !deleted_row_ids.is_empty() {
+                crate::profile_scope!("forget_clears");
+                self.tree.forget_clears(&deleted_row_ids);
+            }
+        }
+    }
+
+    fn on_insertion(&mut self, event: &StoreEvent) {
+        for (&timeline, &time_int) in event.timepoint.iter() {
+            self.times_per_timeline.insert(timeline, time_int);
+        }
+
+        self.register_entity_path(&event.entity_path);
+
+        for component_name in &event.components {
+            let component_path = ComponentPath::new(event.entity_path.clone(), *component_name);
+            let pending_clears = self.tree.add_data_msg(&event.timepoint, &component_path);
+            if pending_clears.is_empty() {
+                continue;
+            }
+
+            let Some(data_type) = self.data_store.lookup_datatype(component_name).cloned() else {
+                continue;
+            };
+
+            for (row_id, time_point) in pending_clears {
                 // Create and insert an empty component into the arrow store
                 // TODO(jleibs): Faster empty-array creation
-                let cell =
-                    DataCell::from_arrow_empty(component_path.component_name, data_type.clone());
+                let cell = DataCell::from_arrow_empty(*component_name, data_type.clone());
                 let row = DataRow::from_cells1(
                     row_id,
-                    component_path.entity_path.clone(),
-                    time_point.clone(),
+                    event.entity_path.clone(),
+                    time_point,
                     cell.num_instances(),
                     cell,
                 );
-                self.data_store.insert_row(&row).ok();
-                // Also update the tree with the clear-event
-                self.tree.add_data_msg(time_point, &component_path);
+                // The tree gets updated with the clear once its event comes around.
+                self.data_store.insert_clear_row(&row).ok();
             }
         }
     }
 
-    pub fn purge(
-        &mut self,
-        cutoff_times: &std::collections::BTreeMap<Timeline, TimeInt>,
-        drop_row_ids: &ahash::HashSet<RowId>,
-    ) {
-        crate::profile_function!();
-
-        let Self {
-            entity_path_from_hash: _,
-            times_per_timeline,
-            tree,
-            data_store: _, // purged before this function is called
-        } = self;
-
-        {
-            crate::profile_scope!("times_per_timeline");
-            times_per_timeline.purge(cutoff_times);
+    fn on_deletion(&mut self, event: &StoreEvent) {
+        for component_name in &event.components {
+            let component_path = ComponentPath::new(event.entity_path.clone(), *component_name);
+            self.tree.remove_data_msg(&event.timepoint, &component_path);
         }
 
-        {
-            crate::profile_scope!("tree");
-            tree.purge(cutoff_times, drop_row_ids);
+        // Forget about the times that don't have any data left.
+        for (timeline, time) in event.timepoint.iter() {
+            let is_empty = self
+                .tree
+                .prefix_times
+                .get(timeline)
+                .map_or(true, |histogram| {
+                    histogram.range_count(time.as_i64()..=time.as_i64()) == 0
+                });
+            if is_empty {
+                self.times_per_timeline.remove(timeline, *time);
+            }
         }
     }
 }
@@ -227,6 +308,7 @@ impl LogDb {
                 self.entity_db.add_path_op(*row_id, time_point, path_op);
             }
             LogMsg::ArrowMsg(_, inner) => self.entity_db.try_add_arrow_msg(inner)?,
+            LogMsg::DeleteRowsMsg(_, msg) => self.entity_db.delete_rows(&msg.selector),
             LogMsg::Goodbye(_) => {}
         }
 
@@ -247,13 +329,59 @@ impl LogDb {
     }
 
     /// Free up some RAM by forgetting the older parts of all timelines.
-    pub fn purge_fraction_of_ram(&mut self, fraction_to_purge: f32) {
+    ///
+    /// The data of the `protected_paths`, and of all their descendants, is never forgotten.
+    pub fn purge_fraction_of_ram(
+        &mut self,
+        fraction_to_purge: f32,
+        protected_paths: &[EntityPath],
+    ) {
         crate::profile_function!();
         assert!((0.0..=1.0).contains(&fraction_to_purge));
 
-        let (drop_row_ids, stats_diff) = self.entity_db.data_store.gc(
-            re_arrow_store::GarbageCollectionTarget::DropAtLeastFraction(fraction_to_purge as _),
-        );
+        self.gc(re_arrow_store::GarbageCollectionOptions {
+            target: re_arrow_store::GarbageCollectionTarget::DropAtLeastFraction(
+                fraction_to_purge as _,
+            ),
+            // Don't make e.g. poses that were logged only once disappear.
+            protect_latest_at: true,
+            protected_paths: protected_paths.to_vec(),
+        });
+    }
+
+    /// Free up some RAM by spilling the older parts of all timelines to disk, in `dir`.
+    ///
+    /// Unlike [`Self::purge_fraction_of_ram`], nothing is forgotten: the spilled data is paged
+    /// back in whenever it gets queried.
+    ///
+    /// Returns how many bytes were spilled.
+    pub fn spill_fraction_of_ram(
+        &mut self,
+        fraction_to_spill: f32,
+        dir: &std::path::Path,
+    ) -> re_arrow_store::SpillResult<u64> {
+        crate::profile_function!();
+        assert!((0.0..=1.0).contains(&fraction_to_spill));
+
+        self.entity_db
+            .data_store
+            .spill(&re_arrow_store::SpillOptions {
+                dir: dir.to_owned(),
+                target: re_arrow_store::SpillTarget::SpillAtLeastFraction(fraction_to_spill as _),
+            })
+    }
+
+    /// Garbage collects the datastore, then forgets everything that was derived from the
+    /// purged data.
+    ///
+    /// Returns how many rows were dropped.
+    pub fn gc(&mut self, options: re_arrow_store::GarbageCollectionOptions) -> usize {
+        crate::profile_function!();
+
+        let (drop_row_ids, stats_diff) = self.entity_db.data_store.gc(options);
+        if drop_row_ids.is_empty() {
+            return 0;
+        }
         re_log::debug!(
             num_row_ids_dropped = drop_row_ids.len(),
             size_bytes_dropped = re_format::format_bytes(stats_diff.total.num_bytes as _),
@@ -261,7 +389,6 @@ impl LogDb {
         );
 
         let drop_row_ids: ahash::HashSet<_> = drop_row_ids.into_iter().collect();
-        let cutoff_times = self.entity_db.data_store.oldest_time_per_timeline();
 
         let Self {
             entity_op_msgs,
@@ -275,6 +402,8 @@ impl LogDb {
             entity_op_msgs.retain(|row_id, _| !drop_row_ids.contains(row_id));
         }
 
-        entity_db.purge(&cutoff_times, &drop_row_ids);
+        entity_db.on_store_events();
+
+        drop_row_ids.len()
     }
 }
@@ -239,6 +239,8 @@ pub fn select_hovered_on_click(
     }
 
     if response.clicked() {
+        selection_state.set_clicked(items.iter().cloned());
+
         if response.ctx.input(|i| i.modifiers.command) {
             selection_state.toggle_selection(selection_state.hovered().to_vec());
         } else {
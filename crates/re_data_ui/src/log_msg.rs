@@ -1,5 +1,6 @@
 use re_log_types::{
-    ArrowMsg, BeginRecordingMsg, DataTable, EntityPathOpMsg, LogMsg, RecordingInfo,
+    ArrowMsg, BeginRecordingMsg, DataTable, DeleteRowsMsg, EntityPathOpMsg, LogMsg, RecordingInfo,
+    RowSelector,
 };
 use re_viewer_context::{UiVerbosity, ViewerContext};
 
@@ -18,6 +19,7 @@ impl DataUi for LogMsg {
             LogMsg::BeginRecordingMsg(msg) => msg.data_ui(ctx, ui, verbosity, query),
             LogMsg::EntityPathOpMsg(_, msg) => msg.data_ui(ctx, ui, verbosity, query),
             LogMsg::ArrowMsg(_, msg) => msg.data_ui(ctx, ui, verbosity, query),
+            LogMsg::DeleteRowsMsg(_, msg) => msg.data_ui(ctx, ui, verbosity, query),
             LogMsg::Goodbye(_) => {
                 ui.label("Goodbye");
             }
@@ -93,6 +95,53 @@ impl DataUi for EntityPathOpMsg {
     }
 }
 
+impl DataUi for DeleteRowsMsg {
+    fn data_ui(
+        &self,
+        ctx: &mut ViewerContext<'_>,
+        ui: &mut egui::Ui,
+        _verbosity: UiVerbosity,
+        _query: &re_arrow_store::LatestAtQuery,
+    ) {
+        ui.code("DeleteRowsMsg");
+        let DeleteRowsMsg {
+            row_id: _,
+            selector,
+        } = self;
+
+        egui::Grid::new("fields")
+            .num_columns(2)
+            .show(ui, |ui| match selector {
+                RowSelector::RowIds(row_ids) => {
+                    ui.monospace("row_ids:");
+                    ui.label(format!(
+                        "{} row(s)",
+                        re_format::format_number(row_ids.len())
+                    ));
+                    ui.end_row();
+                }
+                RowSelector::TimeRange {
+                    entity_path,
+                    timeline,
+                    time_range,
+                } => {
+                    ui.monospace("entity_path:");
+                    item_ui::entity_path_button(ctx, ui, None, entity_path);
+                    ui.end_row();
+
+                    ui.monospace("time_range:");
+                    ui.label(format!(
+                        "{}: {} - {}",
+                        timeline.name(),
+                        timeline.typ().format(time_range.min),
+                        timeline.typ().format(time_range.max)
+                    ));
+                    ui.end_row();
+                }
+            });
+    }
+}
+
 impl DataUi for ArrowMsg {
     fn data_ui(
         &self,
@@ -132,6 +132,20 @@ impl Int64Histogram {
             .increment(ROOT_LEVEL, u64_key_from_i64_key(key), inc);
     }
 
+    /// Decrement the count for the given key, saturating at zero.
+    ///
+    /// Decrementing with one is similar to removing the key once from a multi-set.
+    ///
+    /// Returns how much count was actually removed.
+    pub fn decrement(&mut self, key: i64, dec: u32) -> u32 {
+        let count = self.remove(key..=key);
+        let remaining = count.saturating_sub(dec as u64);
+        if remaining > 0 {
+            self.increment(key, remaining as u32);
+        }
+        (count - remaining) as u32
+    }
+
     /// Is the total count zero?
     ///
     /// Note that incrementing a key with zero is a no-op and
@@ -911,4 +925,22 @@ mod tests {
             vec![(RangeI64::single(i64::MAX - 1), 2),]
         );
     }
+
+    #[test]
+    fn test_decrement() {
+        let mut set = Int64Histogram::default();
+        set.increment(-10, 3);
+        set.increment(10, 1);
+
+        assert_eq!(set.decrement(-10, 2), 2);
+        assert_eq!(set.range_count(-10..=-10), 1);
+        assert_eq!(set.total_count(), 2);
+
+        assert_eq!(set.decrement(-10, 2), 1);
+        assert_eq!(set.min_key(), Some(10));
+
+        assert_eq!(set.decrement(0, 1), 0);
+        assert_eq!(set.decrement(10, 1), 1);
+        assert!(set.is_empty());
+    }
 }
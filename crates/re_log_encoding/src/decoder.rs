@@ -1,23 +1,67 @@
 //! Decoding [`LogMsg`]:es from `.rrd` files/streams.
 
+use re_build_info::CrateVersion;
 use re_log_types::LogMsg;
 
+use crate::index::{IndexQuery, RrdIndex};
+use crate::migration::{MigrationError, MigrationPlan, MigrationRegistry};
+use crate::options::{Compression, EncodingOptions, FileLayout};
+
 // ----------------------------------------------------------------------------
 
-fn warn_on_version_mismatch(encoded_version: [u8; 4]) {
-    use re_build_info::CrateVersion;
+/// The migrations needed for the messages of a stream written by the given Rerun version.
+///
+/// Warns if the stream was written by an incompatible version that we can't migrate from.
+pub(crate) fn migration_plan(encoded_version: CrateVersion) -> Option<MigrationPlan> {
+    match MigrationRegistry::builtin().plan(encoded_version) {
+        Ok(plan) => {
+            if !plan.is_empty() {
+                re_log::debug!("Migrating log stream from Rerun {encoded_version}");
+            }
+            Some(plan).filter(|plan| !plan.is_empty())
+        }
+        Err(err) => {
+            let local_version = CrateVersion::parse(env!("CARGO_PKG_VERSION"));
+            re_log::warn!("Found log stream with Rerun version {encoded_version}, which is incompatible with the local Rerun version {local_version}: {err}. Loading will try to continue, but might fail in subtle ways.");
+            None
+        }
+    }
+}
 
+fn parse_version(encoded_version: [u8; 4]) -> CrateVersion {
     // We used 0000 for all .rrd files up until 2023-02-27, post 0.2.0 release:
-    let encoded_version = if encoded_version == [0, 0, 0, 0] {
+    if encoded_version == [0, 0, 0, 0] {
         CrateVersion::new(0, 2, 0)
     } else {
         CrateVersion::from_bytes(encoded_version)
-    };
+    }
+}
 
-    let local_version = CrateVersion::parse(env!("CARGO_PKG_VERSION"));
+/// Reads the header of an `.rrd` file/stream.
+pub(crate) fn read_header(
+    read: &mut impl std::io::Read,
+) -> Result<(CrateVersion, EncodingOptions, FileLayout), DecodeError> {
+    let mut magic = [0_u8; 4];
+    read.read_exact(&mut magic).map_err(DecodeError::Read)?;
 
-    if !encoded_version.is_compatible_with(local_version) {
-        re_log::warn!("Found log stream with Rerun version {encoded_version}, which is incompatible with the local Rerun version {local_version}. Loading will try to continue, but might fail in subtle ways.");
+    let mut version = [0_u8; 4];
+    match &magic {
+        crate::options::LEGACY_MAGIC => {
+            read.read_exact(&mut version).map_err(DecodeError::Read)?;
+            Ok((
+                parse_version(version),
+                EncodingOptions::ZSTD,
+                FileLayout::Stream,
+            ))
+        }
+        crate::options::MAGIC => {
+            read.read_exact(&mut version).map_err(DecodeError::Read)?;
+            let mut options = [0_u8; 4];
+            read.read_exact(&mut options).map_err(DecodeError::Read)?;
+            let (options, layout) = EncodingOptions::from_bytes(options)?;
+            Ok((parse_version(version), options, layout))
+        }
+        _ => Err(DecodeError::NotAnRrd),
     }
 }
 
@@ -44,8 +88,32 @@ pub enum DecodeError {
     #[error("Zstd read error: {0}")]
     RuzstdRead(std::io::Error),
 
+    #[error("Lz4 error: {0}")]
+    Lz4(std::io::Error),
+
     #[error("MsgPack error: {0}")]
     MsgPack(#[from] rmp_serde::decode::Error),
+
+    #[error("Bad .rrd header: {0}")]
+    Options(#[from] crate::options::OptionsError),
+
+    #[error("The .rrd file has no index (it was not written using the chunked layout)")]
+    NotIndexed,
+
+    #[error("Corrupt .rrd index")]
+    CorruptIndex,
+
+    #[error("No block with index {0}")]
+    BadBlockIndex(usize),
+
+    #[error("Corrupt .rrd block {0}")]
+    CorruptBlock(usize),
+
+    #[error("Corrupt message length: {0}")]
+    BadMessageLength(u64),
+
+    #[error("Failed to migrate message: {0}")]
+    Migration(#[from] MigrationError),
 }
 
 // ----------------------------------------------------------------------------
@@ -59,13 +127,331 @@ pub fn decode_bytes(bytes: &[u8]) -> Result<Vec<LogMsg>, DecodeError> {
     Ok(msgs)
 }
 
+// ----------------------------------------------------------------------------
+
+#[cfg(not(target_arch = "wasm32"))]
+fn decompress_zstd_block(compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+    zstd::stream::decode_all(compressed)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn decompress_zstd_block(compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Read as _;
+
+    let mut decoder = ruzstd::StreamingDecoder::new(compressed)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))?;
+    let mut decompressed = vec![];
+    decoder.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Decompress a block of a [`FileLayout::Chunked`] file.
+pub(crate) fn decompress_block(
+    compression: Compression,
+    compressed: &[u8],
+) -> std::io::Result<Vec<u8>> {
+    crate::profile_function!();
+    match compression {
+        Compression::Off => Ok(compressed.to_vec()),
+        Compression::Lz4 => lz4_flex::block::decompress_size_prepended(compressed)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string())),
+        Compression::Zstd => decompress_zstd_block(compressed),
+    }
+}
+
+/// Reads the decompressed contents of the blocks of a chunked `.rrd` file, one after the other.
+///
+/// Stops at the end of the last block, without reading the index.
+struct BlockReader<R: std::io::Read> {
+    read: R,
+    compression: Compression,
+    block: std::io::Cursor<Vec<u8>>,
+    reached_end: bool,
+
+    /// Skip blocks that fail to decompress, instead of returning an error.
+    salvage: bool,
+    num_skipped_blocks: u64,
+}
+
+impl<R: std::io::Read> BlockReader<R> {
+    fn new(read: R, compression: Compression) -> Self {
+        Self {
+            read,
+            compression,
+            block: Default::default(),
+            reached_end: false,
+            salvage: false,
+            num_skipped_blocks: 0,
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for BlockReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::io::Read as _;
+
+        loop {
+            let num_read = self.block.read(buf)?;
+            if num_read > 0 || buf.is_empty() || self.reached_end {
+                return Ok(num_read);
+            }
+
+            let mut len = [0_u8; 8];
+            self.read.read_exact(&mut len)?;
+            let len = u64::from_le_bytes(len) as usize;
+
+            if len == 0 {
+                // The index follows.
+                self.reached_end = true;
+                return Ok(0);
+            }
+
+            // A corrupt length could be huge, so don't trust it with an up-front allocation:
+            let mut compressed = vec![];
+            (&mut self.read)
+                .take(len as u64)
+                .read_to_end(&mut compressed)?;
+            if compressed.len() < len {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+
+            match decompress_block(self.compression, &compressed) {
+                Ok(block) => {
+                    self.block = std::io::Cursor::new(block);
+                }
+                Err(err) if self.salvage => {
+                    // Blocks start at message boundaries, so we can just carry on with the next one.
+                    re_log::debug!("Skipping corrupt block: {err}");
+                    self.num_skipped_blocks += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// The decompressed message stream, for any [`FileLayout`] and [`Compression`].
+///
+/// `Z` is the platform-specific zstd stream decoder.
+enum Decompressor<Z: std::io::Read, R: std::io::Read> {
+    Off(R),
+    Lz4(lz4_flex::frame::FrameDecoder<R>),
+    Zstd(Z),
+    Chunked(BlockReader<R>),
+}
+
+impl<Z: std::io::Read, R: std::io::Read> Decompressor<Z, R> {
+    fn set_salvage(&mut self, salvage: bool) {
+        if let Self::Chunked(blocks) = self {
+            blocks.salvage = salvage;
+        }
+    }
+
+    fn num_skipped_blocks(&self) -> u64 {
+        match self {
+            Self::Chunked(blocks) => blocks.num_skipped_blocks,
+            Self::Off(_) | Self::Lz4(_) | Self::Zstd(_) => 0,
+        }
+    }
+
+    /// Wraps a failure to read from this stream.
+    fn error(&self, err: std::io::Error) -> DecodeError {
+        match self {
+            Self::Off(_) | Self::Chunked(_) => DecodeError::Read(err),
+            Self::Lz4(_) => DecodeError::Lz4(err),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Zstd(_) => DecodeError::Zstd(err),
+            #[cfg(target_arch = "wasm32")]
+            Self::Zstd(_) => DecodeError::RuzstdRead(err),
+        }
+    }
+}
+
+impl<Z: std::io::Read, R: std::io::Read> std::io::Read for Decompressor<Z, R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Off(read) => read.read(buf),
+            Self::Lz4(read) => read.read(buf),
+            Self::Zstd(read) => read.read(buf),
+            Self::Chunked(read) => read.read(buf),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// salvaging:
+
+/// How far a [`Decoder`] got through an `.rrd` file/stream, and what it had to skip.
+///
+/// Mostly interesting for salvaging [`Decoder`]s, see [`Decoder::salvaging`].
+#[derive(Debug, Default)]
+pub struct SalvageReport {
+    /// Number of messages that were successfully decoded.
+    pub num_messages: u64,
+
+    /// Number of messages that were skipped because they could not be deserialized.
+    pub num_skipped_messages: u64,
+
+    /// Number of blocks that were skipped because they could not be decompressed.
+    ///
+    /// Only chunked files have blocks.
+    pub num_skipped_blocks: u64,
+
+    /// Offset into the decompressed message stream of the end of the last complete message.
+    ///
+    /// This is where decoding stopped.
+    pub decompressed_offset: u64,
+
+    /// Why decoding stopped before the end of the stream, if it did.
+    pub error: Option<DecodeError>,
+}
+
+impl SalvageReport {
+    /// Was the whole stream decoded, without skipping anything?
+    pub fn is_intact(&self) -> bool {
+        self.error.is_none() && self.num_skipped_messages == 0 && self.num_skipped_blocks == 0
+    }
+}
+
+impl std::fmt::Display for SalvageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            num_messages,
+            num_skipped_messages,
+            num_skipped_blocks,
+            decompressed_offset,
+            error,
+        } = self;
+
+        write!(f, "decoded {num_messages} message(s)")?;
+        if *num_skipped_messages > 0 {
+            write!(f, ", skipped {num_skipped_messages} corrupt message(s)")?;
+        }
+        if *num_skipped_blocks > 0 {
+            write!(f, ", skipped {num_skipped_blocks} corrupt block(s)")?;
+        }
+        if let Some(error) = error {
+            write!(
+                f,
+                ", stopped at decompressed byte offset {decompressed_offset}: {error}"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the length prefix of the next message, or `None` at a clean end of the stream.
+fn read_len(read: &mut impl std::io::Read) -> std::io::Result<Option<u64>> {
+    let mut len = [0_u8; 8];
+    let mut num_read = 0;
+    while num_read < len.len() {
+        match read.read(&mut len[num_read..]) {
+            Ok(0) if num_read == 0 => return Ok(None),
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => num_read += n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Some(u64::from_le_bytes(len)))
+}
+
+/// Decodes the next message of the decompressed stream, shared by the native and wasm [`Decoder`].
+///
+/// When salvaging, errors are recorded in the `report` instead of being returned.
+fn decode_next<Z: std::io::Read, R: std::io::Read>(
+    zdecoder: &mut Decompressor<Z, R>,
+    buffer: &mut Vec<u8>,
+    migration: Option<&MigrationPlan>,
+    salvage: bool,
+    report: &mut SalvageReport,
+) -> Option<Result<LogMsg, DecodeError>> {
+    use std::io::Read as _;
+
+    if report.error.is_some() {
+        return None;
+    }
+
+    loop {
+        let len = read_len(zdecoder);
+        report.num_skipped_blocks = zdecoder.num_skipped_blocks();
+        let len = match len {
+            Ok(Some(len)) => len,
+            Ok(None) => return None,
+            Err(err) => {
+                let err = zdecoder.error(err);
+                if salvage {
+                    report.error = Some(err);
+                    return None;
+                }
+                return Some(Err(err));
+            }
+        };
+
+        {
+            crate::profile_scope!("decompress");
+
+            // A corrupt length could be huge, so don't trust it with an up-front allocation:
+            buffer.clear();
+            let result = zdecoder.by_ref().take(len).read_to_end(buffer);
+            report.num_skipped_blocks = zdecoder.num_skipped_blocks();
+            let result = result.and_then(|num_read| {
+                if (num_read as u64) < len {
+                    Err(std::io::ErrorKind::UnexpectedEof.into())
+                } else {
+                    Ok(())
+                }
+            });
+            if let Err(err) = result {
+                let err = zdecoder.error(err);
+                if salvage {
+                    report.error = Some(err);
+                    return None;
+                }
+                return Some(Err(err));
+            }
+        }
+
+        crate::profile_scope!("MsgPack deser");
+        let msg = rmp_serde::from_read(&mut buffer.as_slice())
+            .map_err(DecodeError::from)
+            .and_then(|mut msg| {
+                if let Some(migration) = migration {
+                    migration.apply(&mut msg)?;
+                }
+                Ok(msg)
+            });
+        report.decompressed_offset += 8 + len;
+        match msg {
+            Ok(msg) => {
+                report.num_messages += 1;
+                return Some(Ok(msg));
+            }
+            Err(err) if salvage => {
+                // The length prefix got us past the broken message, so we can resync at the next one.
+                re_log::debug!("Skipping corrupt message: {err}");
+                report.num_skipped_messages += 1;
+            }
+            Err(err) => return Some(Err(err)),
+        }
+    }
+}
+
 // ----------------------------------------------------------------------------
 // native decode:
 
+/// Decodes [`LogMsg`]es from an `.rrd` file/stream, from start to end.
+///
+/// Handles both the streamed and the chunked layouts.
 #[cfg(not(target_arch = "wasm32"))]
 pub struct Decoder<'r, R: std::io::BufRead> {
-    zdecoder: zstd::stream::Decoder<'r, R>,
+    zdecoder: Decompressor<zstd::stream::Decoder<'r, R>, R>,
     buffer: Vec<u8>,
+    version: CrateVersion,
+    migration: Option<MigrationPlan>,
+    salvage: bool,
+    report: SalvageReport,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -73,20 +459,59 @@ impl<'r, R: std::io::Read> Decoder<'r, std::io::BufReader<R>> {
     pub fn new(mut read: R) -> Result<Self, DecodeError> {
         crate::profile_function!();
 
-        let mut header = [0_u8; 4];
-        read.read_exact(&mut header).map_err(DecodeError::Read)?;
-        if &header != b"RRF0" {
-            return Err(DecodeError::NotAnRrd);
-        }
-        read.read_exact(&mut header).map_err(DecodeError::Read)?;
-        warn_on_version_mismatch(header);
+        let (version, options, layout) = read_header(&mut read)?;
+        let zdecoder = match (layout, options.compression) {
+            (FileLayout::Stream, Compression::Off) => {
+                Decompressor::Off(std::io::BufReader::new(read))
+            }
+            (FileLayout::Stream, Compression::Lz4) => Decompressor::Lz4(
+                lz4_flex::frame::FrameDecoder::new(std::io::BufReader::new(read)),
+            ),
+            (FileLayout::Stream, Compression::Zstd) => Decompressor::Zstd(
+                zstd::stream::read::Decoder::new(read).map_err(DecodeError::Zstd)?,
+            ),
+            (FileLayout::Chunked, compression) => {
+                Decompressor::Chunked(BlockReader::new(std::io::BufReader::new(read), compression))
+            }
+        };
 
-        let zdecoder = zstd::stream::read::Decoder::new(read).map_err(DecodeError::Zstd)?;
         Ok(Self {
             zdecoder,
             buffer: vec![],
+            version,
+            migration: migration_plan(version),
+            salvage: false,
+            report: Default::default(),
         })
     }
+
+    /// Recover as much as possible from a truncated or corrupted file/stream.
+    ///
+    /// A salvaging decoder never yields errors. Instead it yields every message it can decode,
+    /// and records what it skipped, and where and why it stopped, in its [`Self::report`]:
+    /// * Messages that fail to deserialize are skipped.
+    /// * Blocks of chunked files that fail to decompress are skipped.
+    /// * Decoding stops at the first damage to the compressed stream itself, e.g. when a file
+    ///   was truncated because the process writing it crashed.
+    pub fn salvaging(mut self) -> Self {
+        self.salvage = true;
+        self.zdecoder.set_salvage(true);
+        self
+    }
+
+    /// How far decoding has gotten so far.
+    #[inline]
+    pub fn report(&self) -> &SalvageReport {
+        &self.report
+    }
+
+    /// The version of Rerun that wrote the file/stream.
+    ///
+    /// Messages written by older versions are migrated to the current schema as they are decoded.
+    #[inline]
+    pub fn version(&self) -> CrateVersion {
+        self.version
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -95,36 +520,30 @@ impl<'r, R: std::io::BufRead> Iterator for Decoder<'r, R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         crate::profile_function!();
-        use std::io::Read as _;
-
-        let mut len = [0_u8; 8];
-        self.zdecoder.read_exact(&mut len).ok()?;
-        let len = u64::from_le_bytes(len) as usize;
-
-        self.buffer.resize(len, 0);
-
-        {
-            crate::profile_scope!("zstd");
-            if let Err(err) = self.zdecoder.read_exact(&mut self.buffer) {
-                return Some(Err(DecodeError::Zstd(err)));
-            }
-        }
-
-        crate::profile_scope!("MsgPack deser");
-        match rmp_serde::from_read(&mut self.buffer.as_slice()) {
-            Ok(msg) => Some(Ok(msg)),
-            Err(err) => Some(Err(err.into())),
-        }
+        decode_next(
+            &mut self.zdecoder,
+            &mut self.buffer,
+            self.migration.as_ref(),
+            self.salvage,
+            &mut self.report,
+        )
     }
 }
 
 // ----------------------------------------------------------------------------
 // wasm decode:
 
+/// Decodes [`LogMsg`]es from an `.rrd` file/stream, from start to end.
+///
+/// Handles both the streamed and the chunked layouts.
 #[cfg(target_arch = "wasm32")]
 pub struct Decoder<R: std::io::Read> {
-    zdecoder: ruzstd::StreamingDecoder<R>,
+    zdecoder: Decompressor<ruzstd::StreamingDecoder<R>, R>,
     buffer: Vec<u8>,
+    version: CrateVersion,
+    migration: Option<MigrationPlan>,
+    salvage: bool,
+    report: SalvageReport,
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -132,20 +551,57 @@ impl<R: std::io::Read> Decoder<R> {
     pub fn new(mut read: R) -> Result<Self, DecodeError> {
         crate::profile_function!();
 
-        let mut header = [0_u8; 4];
-        read.read_exact(&mut header).map_err(DecodeError::Read)?;
-        if &header != b"RRF0" {
-            return Err(DecodeError::NotAnRrd);
-        }
-        read.read_exact(&mut header).map_err(DecodeError::Read)?;
-        warn_on_version_mismatch(header);
+        let (version, options, layout) = read_header(&mut read)?;
+        let zdecoder = match (layout, options.compression) {
+            (FileLayout::Stream, Compression::Off) => Decompressor::Off(read),
+            (FileLayout::Stream, Compression::Lz4) => {
+                Decompressor::Lz4(lz4_flex::frame::FrameDecoder::new(read))
+            }
+            (FileLayout::Stream, Compression::Zstd) => Decompressor::Zstd(
+                ruzstd::StreamingDecoder::new(read).map_err(DecodeError::RuzstdInit)?,
+            ),
+            (FileLayout::Chunked, compression) => {
+                Decompressor::Chunked(BlockReader::new(read, compression))
+            }
+        };
 
-        let zdecoder = ruzstd::StreamingDecoder::new(read).map_err(DecodeError::RuzstdInit)?;
         Ok(Self {
             zdecoder,
             buffer: vec![],
+            version,
+            migration: migration_plan(version),
+            salvage: false,
+            report: Default::default(),
         })
     }
+
+    /// Recover as much as possible from a truncated or corrupted file/stream.
+    ///
+    /// A salvaging decoder never yields errors. Instead it yields every message it can decode,
+    /// and records what it skipped, and where and why it stopped, in its [`Self::report`]:
+    /// * Messages that fail to deserialize are skipped.
+    /// * Blocks of chunked files that fail to decompress are skipped.
+    /// * Decoding stops at the first damage to the compressed stream itself, e.g. when a file
+    ///   was truncated because the process writing it crashed.
+    pub fn salvaging(mut self) -> Self {
+        self.salvage = true;
+        self.zdecoder.set_salvage(true);
+        self
+    }
+
+    /// How far decoding has gotten so far.
+    #[inline]
+    pub fn report(&self) -> &SalvageReport {
+        &self.report
+    }
+
+    /// The version of Rerun that wrote the file/stream.
+    ///
+    /// Messages written by older versions are migrated to the current schema as they are decoded.
+    #[inline]
+    pub fn version(&self) -> CrateVersion {
+        self.version
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -154,26 +610,155 @@ impl<R: std::io::Read> Iterator for Decoder<R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         crate::profile_function!();
-        use std::io::Read as _;
+        decode_next(
+            &mut self.zdecoder,
+            &mut self.buffer,
+            self.migration.as_ref(),
+            self.salvage,
+            &mut self.report,
+        )
+    }
+}
 
-        let mut len = [0_u8; 8];
-        self.zdecoder.read_exact(&mut len).ok()?;
-        let len = u64::from_le_bytes(len) as usize;
+// ----------------------------------------------------------------------------
+// indexed decode:
 
-        self.buffer.resize(len, 0);
+/// Random access into a chunked `.rrd` file, using its footer [`RrdIndex`].
+///
+/// Only the blocks that are actually requested are read and decompressed.
+pub struct IndexedReader<R: std::io::Read + std::io::Seek> {
+    read: R,
+    version: CrateVersion,
+    migration: Option<MigrationPlan>,
+    options: EncodingOptions,
+    index: RrdIndex,
+}
 
-        {
-            crate::profile_scope!("ruzstd");
-            if let Err(err) = self.zdecoder.read_exact(&mut self.buffer) {
-                return Some(Err(DecodeError::RuzstdRead(err)));
+impl<R: std::io::Read + std::io::Seek> IndexedReader<R> {
+    /// Reads the header and the index, leaving all blocks untouched.
+    ///
+    /// Fails with [`DecodeError::NotIndexed`] for files that use the streamed layout.
+    pub fn new(mut read: R) -> Result<Self, DecodeError> {
+        use std::io::SeekFrom;
+
+        crate::profile_function!();
+
+        read.seek(SeekFrom::Start(0)).map_err(DecodeError::Read)?;
+        let (version, options, layout) = read_header(&mut read)?;
+        if layout != FileLayout::Chunked {
+            return Err(DecodeError::NotIndexed);
+        }
+
+        let file_len = read.seek(SeekFrom::End(0)).map_err(DecodeError::Read)?;
+        if file_len < crate::options::HEADER_SIZE + crate::index::INDEX_TRAILER_SIZE {
+            return Err(DecodeError::CorruptIndex);
+        }
+
+        read.seek(SeekFrom::End(-(crate::index::INDEX_TRAILER_SIZE as i64)))
+            .map_err(DecodeError::Read)?;
+        let mut index_len = [0_u8; 8];
+        read.read_exact(&mut index_len).map_err(DecodeError::Read)?;
+        let mut magic = [0_u8; 4];
+        read.read_exact(&mut magic).map_err(DecodeError::Read)?;
+        if &magic != crate::index::INDEX_MAGIC {
+            return Err(DecodeError::CorruptIndex);
+        }
+
+        let index_len = u64::from_le_bytes(index_len);
+        if index_len > file_len - crate::index::INDEX_TRAILER_SIZE {
+            return Err(DecodeError::CorruptIndex);
+        }
+        read.seek(SeekFrom::End(
+            -((index_len + crate::index::INDEX_TRAILER_SIZE) as i64),
+        ))
+        .map_err(DecodeError::Read)?;
+        let mut index = vec![0_u8; index_len as usize];
+        read.read_exact(&mut index).map_err(DecodeError::Read)?;
+        let index = rmp_serde::from_slice(&index)?;
+
+        Ok(Self {
+            read,
+            version,
+            migration: migration_plan(version),
+            options,
+            index,
+        })
+    }
+
+    /// The version of Rerun that wrote the file.
+    #[inline]
+    pub fn version(&self) -> CrateVersion {
+        self.version
+    }
+
+    #[inline]
+    pub fn options(&self) -> EncodingOptions {
+        self.options
+    }
+
+    #[inline]
+    pub fn index(&self) -> &RrdIndex {
+        &self.index
+    }
+
+    /// Reads and decodes all the messages of a single block.
+    pub fn read_block(&mut self, block_idx: usize) -> Result<Vec<LogMsg>, DecodeError> {
+        use std::io::SeekFrom;
+
+        crate::profile_function!();
+
+        let block = self
+            .index
+            .blocks
+            .get(block_idx)
+            .ok_or(DecodeError::BadBlockIndex(block_idx))?;
+
+        self.read
+            .seek(SeekFrom::Start(block.byte_offset))
+            .map_err(DecodeError::Read)?;
+        let mut compressed = vec![0_u8; block.byte_len as usize];
+        self.read
+            .read_exact(&mut compressed)
+            .map_err(DecodeError::Read)?;
+        let decompressed =
+            decompress_block(self.options.compression, &compressed).map_err(DecodeError::Read)?;
+
+        let mut msgs = Vec::with_capacity(block.num_messages as usize);
+        let mut remaining = decompressed.as_slice();
+        while !remaining.is_empty() {
+            if remaining.len() < 8 {
+                return Err(DecodeError::CorruptBlock(block_idx));
+            }
+            let (len, rest) = remaining.split_at(8);
+            let len = u64::from_le_bytes(len.try_into().unwrap()) as usize;
+            if rest.len() < len {
+                return Err(DecodeError::CorruptBlock(block_idx));
+            }
+            let (msg, rest) = rest.split_at(len);
+            let mut msg = rmp_serde::from_slice(msg)?;
+            if let Some(migration) = &self.migration {
+                migration.apply(&mut msg)?;
             }
+            msgs.push(msg);
+            remaining = rest;
         }
 
-        crate::profile_scope!("MsgPack deser");
-        match rmp_serde::from_read(&mut self.buffer.as_slice()) {
-            Ok(msg) => Some(Ok(msg)),
-            Err(err) => Some(Err(err.into())),
+        Ok(msgs)
+    }
+
+    /// Reads and decodes the messages of all blocks matching the given query, in file order.
+    ///
+    /// The filtering is done at the granularity of blocks: the returned messages may include
+    /// data outside of the queried time range or entity subtree.
+    pub fn read_matching(&mut self, query: &IndexQuery) -> Result<Vec<LogMsg>, DecodeError> {
+        crate::profile_function!();
+
+        let block_indices = self.index.matching_blocks(query).collect::<Vec<_>>();
+        let mut msgs = vec![];
+        for block_idx in block_indices {
+            msgs.extend(self.read_block(block_idx)?);
         }
+        Ok(msgs)
     }
 }
 
@@ -201,8 +786,57 @@ fn test_encode_decode() {
         },
     })];
 
-    let mut file = vec![];
-    crate::encoder::encode(messages.iter(), &mut file).unwrap();
+    for options in [
+        EncodingOptions::UNCOMPRESSED,
+        EncodingOptions::LZ4,
+        EncodingOptions::ZSTD,
+        EncodingOptions::zstd(19),
+    ] {
+        let mut file = vec![];
+        crate::encoder::encode(options, messages.iter(), &mut file).unwrap();
+
+        let decoded_messages = Decoder::new(&mut file.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<LogMsg>, DecodeError>>()
+            .unwrap();
+
+        assert_eq!(messages, decoded_messages);
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "decoder", feature = "encoder"))]
+#[test]
+fn test_decode_legacy_header() {
+    use re_log_types::{ApplicationId, LogMsg, RecordingInfo, RecordingSource, Time};
+
+    let messages = vec![LogMsg::BeginRecordingMsg(re_log_types::BeginRecordingMsg {
+        row_id: re_log_types::RowId::random(),
+        info: RecordingInfo {
+            application_id: ApplicationId("test".to_owned()),
+            recording_id: re_log_types::RecordingId::random(),
+            is_official_example: true,
+            started: Time::now(),
+            recording_source: RecordingSource::RustSdk {
+                rustc_version: String::new(),
+                llvm_version: String::new(),
+            },
+        },
+    })];
+
+    // What files looked like before the encoding options were added to the header:
+    let mut file = crate::options::LEGACY_MAGIC.to_vec();
+    file.extend_from_slice(
+        &re_build_info::CrateVersion::parse(env!("CARGO_PKG_VERSION")).to_bytes(),
+    );
+    {
+        let mut zstd = zstd::stream::Encoder::new(&mut file, 3).unwrap();
+        for msg in &messages {
+            let bytes = rmp_serde::to_vec(msg).unwrap();
+            std::io::Write::write_all(&mut zstd, &(bytes.len() as u64).to_le_bytes()).unwrap();
+            std::io::Write::write_all(&mut zstd, &bytes).unwrap();
+        }
+        zstd.finish().unwrap();
+    }
 
     let decoded_messages = Decoder::new(&mut file.as_slice())
         .unwrap()
@@ -211,3 +845,272 @@ fn test_encode_decode() {
 
     assert_eq!(messages, decoded_messages);
 }
+
+#[cfg(all(feature = "decoder", feature = "encoder"))]
+#[test]
+fn test_default_options_use_legacy_header() {
+    let messages = crate::test_util::fake_messages(3);
+
+    let encode = |options: EncodingOptions| {
+        let mut file = vec![];
+        crate::encoder::encode(options, messages.iter(), &mut file).unwrap();
+        file
+    };
+
+    // Readable by viewers that predate the encoding options:
+    for options in [EncodingOptions::default(), EncodingOptions::zstd(19)] {
+        assert_eq!(&encode(options)[..4], crate::options::LEGACY_MAGIC);
+    }
+
+    // Explicitly opting into something the legacy header can't describe:
+    for options in [EncodingOptions::UNCOMPRESSED, EncodingOptions::LZ4] {
+        assert_eq!(&encode(options)[..4], crate::options::MAGIC);
+    }
+
+    let mut file = vec![];
+    crate::encoder::encode_chunked(EncodingOptions::default(), messages.iter(), &mut file).unwrap();
+    assert_eq!(&file[..4], crate::options::MAGIC);
+}
+
+#[cfg(all(feature = "decoder", feature = "encoder"))]
+#[cfg(test)]
+mod chunked_tests {
+    use super::*;
+
+    use re_log_types::{EntityPath, TimeRange, Timeline};
+
+    use crate::test_util::fake_messages;
+
+    pub(super) fn encode_chunked(
+        options: EncodingOptions,
+        messages: &[LogMsg],
+        max_block_size: usize,
+    ) -> Vec<u8> {
+        let mut file = vec![];
+        {
+            let mut encoder = crate::encoder::ChunkedEncoder::with_max_block_size(
+                options,
+                &mut file,
+                max_block_size,
+            )
+            .unwrap();
+            for msg in messages {
+                encoder.append(msg).unwrap();
+            }
+            encoder.finish().unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn chunked_roundtrip() {
+        let messages = fake_messages(100);
+
+        for options in [
+            EncodingOptions::UNCOMPRESSED,
+            EncodingOptions::LZ4,
+            EncodingOptions::ZSTD,
+        ] {
+            for max_block_size in [1, 1024, usize::MAX] {
+                let file = encode_chunked(options, &messages, max_block_size);
+
+                let decoded_messages = Decoder::new(&mut file.as_slice())
+                    .unwrap()
+                    .collect::<Result<Vec<LogMsg>, DecodeError>>()
+                    .unwrap();
+                assert_eq!(messages, decoded_messages);
+
+                let mut reader = IndexedReader::new(std::io::Cursor::new(&file)).unwrap();
+                assert_eq!(options, reader.options());
+                assert_eq!(100, reader.index().num_messages());
+                let read_messages = reader.read_matching(&IndexQuery::default()).unwrap();
+                assert_eq!(messages, read_messages);
+            }
+        }
+    }
+
+    #[test]
+    fn indexed_queries() {
+        let messages = fake_messages(100);
+
+        // One message per block:
+        let file = encode_chunked(EncodingOptions::default(), &messages, 1);
+        let mut reader = IndexedReader::new(std::io::Cursor::new(&file)).unwrap();
+        assert_eq!(100, reader.index().blocks.len());
+
+        let frame_nr = Timeline::new_sequence("frame_nr");
+
+        let query =
+            IndexQuery::default().with_time_range(frame_nr, TimeRange::new(10.into(), 19.into()));
+        let read_messages = reader.read_matching(&query).unwrap();
+        assert_eq!(&messages[10..20], read_messages.as_slice());
+
+        let query = IndexQuery::default().with_entity_subtree(EntityPath::from("world"));
+        let read_messages = reader.read_matching(&query).unwrap();
+        assert_eq!(50, read_messages.len());
+        assert!(read_messages
+            .iter()
+            .zip(messages.iter().step_by(2))
+            .all(|(a, b)| a == b));
+
+        let query = IndexQuery::default()
+            .with_time_range(frame_nr, TimeRange::new(10.into(), 19.into()))
+            .with_entity_subtree(EntityPath::from("camera/image"));
+        let read_messages = reader.read_matching(&query).unwrap();
+        assert_eq!(5, read_messages.len());
+
+        let query = IndexQuery::default().with_entity_subtree(EntityPath::from("nothing/here"));
+        assert!(reader.read_matching(&query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stream_layout_is_not_indexed() {
+        let messages = fake_messages(10);
+
+        let mut file = vec![];
+        crate::encoder::encode(EncodingOptions::default(), messages.iter(), &mut file).unwrap();
+
+        assert!(matches!(
+            IndexedReader::new(std::io::Cursor::new(&file)),
+            Err(DecodeError::NotIndexed)
+        ));
+    }
+}
+
+#[cfg(all(feature = "decoder", feature = "encoder"))]
+#[cfg(test)]
+mod salvage_tests {
+    use rand::{Rng as _, SeedableRng as _};
+
+    use super::chunked_tests::encode_chunked;
+    use super::*;
+    use crate::test_util::fake_messages;
+
+    fn salvage(file: &[u8]) -> (Vec<LogMsg>, SalvageReport) {
+        let mut decoder = Decoder::new(file).unwrap().salvaging();
+        let messages = (&mut decoder)
+            .collect::<Result<Vec<LogMsg>, DecodeError>>()
+            .unwrap();
+        let Decoder { report, .. } = decoder;
+        (messages, report)
+    }
+
+    #[test]
+    fn salvage_randomly_truncated_files() {
+        let messages = fake_messages(100);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        for options in [
+            EncodingOptions::UNCOMPRESSED,
+            EncodingOptions::LZ4,
+            EncodingOptions::ZSTD,
+        ] {
+            let mut stream = vec![];
+            crate::encoder::encode(options, messages.iter(), &mut stream).unwrap();
+            let chunked = encode_chunked(options, &messages, 1024);
+            let index = IndexedReader::new(std::io::Cursor::new(&chunked))
+                .unwrap()
+                .index()
+                .clone();
+
+            for _ in 0..50 {
+                let cut = rng.gen_range(crate::options::HEADER_SIZE as usize..stream.len());
+                let (salvaged, report) = salvage(&stream[..cut]);
+                assert_eq!(&messages[..salvaged.len()], salvaged.as_slice());
+                if options.compression == Compression::Zstd {
+                    // lz4 frames and raw streams can end cleanly at a block/message boundary.
+                    assert!(report.error.is_some(), "{options:?}, cut at {cut}");
+                }
+
+                let cut = rng.gen_range(crate::options::HEADER_SIZE as usize..chunked.len());
+                let (salvaged, report) = salvage(&chunked[..cut]);
+                assert_eq!(&messages[..salvaged.len()], salvaged.as_slice());
+                let num_complete: u64 = index
+                    .blocks
+                    .iter()
+                    .filter(|block| block.byte_offset + block.byte_len <= cut as u64)
+                    .map(|block| block.num_messages)
+                    .sum();
+                assert!(salvaged.len() as u64 >= num_complete);
+                assert!(
+                    salvaged.len() == messages.len() || report.error.is_some(),
+                    "{options:?}, cut at {cut}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn salvage_skips_corrupt_blocks() {
+        let messages = fake_messages(100);
+
+        // One message per block:
+        let mut file = encode_chunked(EncodingOptions::ZSTD, &messages, 1);
+        let index = IndexedReader::new(std::io::Cursor::new(&file))
+            .unwrap()
+            .index()
+            .clone();
+
+        // Break the zstd magic of two blocks:
+        for block_idx in [10, 50] {
+            let offset = index.blocks[block_idx].byte_offset as usize;
+            file[offset..offset + 4].fill(0);
+        }
+
+        assert!(Decoder::new(file.as_slice())
+            .unwrap()
+            .any(|msg| msg.is_err()));
+
+        let (salvaged, report) = salvage(&file);
+        let expected = messages
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 10 && *i != 50)
+            .map(|(_, msg)| msg.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(expected, salvaged);
+        assert_eq!(2, report.num_skipped_blocks);
+        assert!(report.error.is_none());
+        assert!(!report.is_intact());
+    }
+
+    #[test]
+    fn salvage_skips_corrupt_messages() {
+        let messages = fake_messages(10);
+
+        let mut file = vec![];
+        crate::encoder::encode(EncodingOptions::UNCOMPRESSED, messages.iter(), &mut file).unwrap();
+
+        // Find the payload of the fourth message, and make it invalid MsgPack:
+        let encoded_len = |msg: &LogMsg| rmp_serde::to_vec_named(msg).unwrap().len();
+        let offset = crate::options::HEADER_SIZE as usize
+            + messages[..3]
+                .iter()
+                .map(|msg| 8 + encoded_len(msg))
+                .sum::<usize>();
+        let len = u64::from_le_bytes(file[offset..offset + 8].try_into().unwrap());
+        assert_eq!(encoded_len(&messages[3]) as u64, len);
+        file[offset + 8] = 0xc1; // never used in MsgPack
+
+        let (salvaged, report) = salvage(&file);
+        assert_eq!(&messages[..3], &salvaged[..3]);
+        assert_eq!(&messages[4..], &salvaged[3..]);
+        assert!(!salvaged.contains(&messages[3]));
+        assert_eq!(9, report.num_messages);
+        assert_eq!(1, report.num_skipped_messages);
+        assert!(report.error.is_none());
+    }
+
+    #[test]
+    fn intact_files_are_intact() {
+        let messages = fake_messages(10);
+
+        let mut file = vec![];
+        crate::encoder::encode(EncodingOptions::ZSTD, messages.iter(), &mut file).unwrap();
+
+        let (salvaged, report) = salvage(&file);
+        assert_eq!(messages, salvaged);
+        assert!(report.is_intact());
+        assert_eq!(10, report.num_messages);
+    }
+}
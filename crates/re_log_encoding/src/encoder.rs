@@ -1,11 +1,12 @@
 //! Encoding of [`LogMsg`]es as a binary stream, e.g. to store in an `.rrd` file, or send over network.
 
-// TODO(1316): switch to using lz4flex - see https://github.com/rerun-io/rerun/issues/1316#issuecomment-1510967893
-
 use std::io::Write as _;
 
 use re_log_types::LogMsg;
 
+use crate::index::{BlockInfo, RrdIndex};
+use crate::options::{Compression, EncodingOptions, FileLayout};
+
 /// On failure to encode or serialize a [`LogMsg`].
 #[derive(thiserror::Error, Debug)]
 pub enum EncodeError {
@@ -15,9 +16,15 @@ pub enum EncodeError {
     #[error("Zstd error: {0}")]
     Zstd(std::io::Error),
 
+    #[error("Lz4 error: {0}")]
+    Lz4(std::io::Error),
+
     #[error("MsgPack error: {0}")]
     MsgPack(#[from] rmp_serde::encode::Error),
 
+    #[error("Failed to index data table: {0}")]
+    DataTable(#[from] re_log_types::DataTableError),
+
     #[error("Called append on already finished encoder")]
     AlreadyFinished,
 }
@@ -25,11 +32,12 @@ pub enum EncodeError {
 // ----------------------------------------------------------------------------
 
 pub fn encode_to_bytes<'a>(
+    options: EncodingOptions,
     msgs: impl IntoIterator<Item = &'a LogMsg>,
 ) -> Result<Vec<u8>, EncodeError> {
     let mut bytes: Vec<u8> = vec![];
     {
-        let mut encoder = Encoder::new(std::io::Cursor::new(&mut bytes))?;
+        let mut encoder = Encoder::new(options, std::io::Cursor::new(&mut bytes))?;
         for msg in msgs {
             encoder.append(msg)?;
         }
@@ -38,18 +46,102 @@ pub fn encode_to_bytes<'a>(
     Ok(bytes)
 }
 
+/// Writes the header of an `.rrd` file/stream.
+///
+/// Sticks to the legacy header whenever possible, so that older viewers can still read the file.
+/// See [`crate::options`].
+fn write_header(
+    write: &mut impl std::io::Write,
+    options: EncodingOptions,
+    layout: FileLayout,
+) -> Result<(), EncodeError> {
+    let rerun_version = re_build_info::CrateVersion::parse(env!("CARGO_PKG_VERSION"));
+
+    if options.is_legacy_compatible(layout) {
+        write
+            .write_all(crate::options::LEGACY_MAGIC)
+            .map_err(EncodeError::Write)?;
+        return write
+            .write_all(&rerun_version.to_bytes())
+            .map_err(EncodeError::Write);
+    }
+
+    write
+        .write_all(crate::options::MAGIC)
+        .map_err(EncodeError::Write)?;
+    write
+        .write_all(&rerun_version.to_bytes())
+        .map_err(EncodeError::Write)?;
+    write
+        .write_all(&options.to_bytes(layout))
+        .map_err(EncodeError::Write)
+}
+
+// ----------------------------------------------------------------------------
+
+/// The compressed stream of a [`FileLayout::Stream`] file.
+enum Compressor<W: std::io::Write> {
+    Off(W),
+    Lz4(lz4_flex::frame::FrameEncoder<W>),
+    Zstd(zstd::stream::Encoder<'static, W>),
+}
+
+impl<W: std::io::Write> Compressor<W> {
+    fn new(options: EncodingOptions, write: W) -> Result<Self, EncodeError> {
+        Ok(match options.compression {
+            Compression::Off => Self::Off(write),
+            Compression::Lz4 => Self::Lz4(lz4_flex::frame::FrameEncoder::new(write)),
+            Compression::Zstd => Self::Zstd(
+                zstd::stream::Encoder::new(write, options.level).map_err(EncodeError::Zstd)?,
+            ),
+        })
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        match self {
+            Self::Off(write) => write.write_all(bytes).map_err(EncodeError::Write),
+            Self::Lz4(write) => write.write_all(bytes).map_err(EncodeError::Lz4),
+            Self::Zstd(write) => write.write_all(bytes).map_err(EncodeError::Zstd),
+        }
+    }
+
+    fn finish(self) -> Result<(), EncodeError> {
+        match self {
+            Self::Off(mut write) => write.flush().map_err(EncodeError::Write),
+            Self::Lz4(write) => write
+                .finish()
+                .map(|_| ())
+                .map_err(|err| EncodeError::Lz4(err.into())),
+            Self::Zstd(write) => write.finish().map(|_| ()).map_err(EncodeError::Zstd),
+        }
+    }
+}
+
+/// Compress a block of a [`FileLayout::Chunked`] file.
+fn compress_block(options: EncodingOptions, block: &[u8]) -> Result<Vec<u8>, EncodeError> {
+    crate::profile_function!();
+    match options.compression {
+        Compression::Off => Ok(block.to_vec()),
+        Compression::Lz4 => Ok(lz4_flex::block::compress_prepend_size(block)),
+        Compression::Zstd => zstd::bulk::compress(block, options.level).map_err(EncodeError::Zstd),
+    }
+}
+
 // ----------------------------------------------------------------------------
 
 /// Encode a stream of [`LogMsg`] into an `.rrd` file.
+///
+/// With the default [`EncodingOptions`] the file can still be read by viewers older than 0.6.
+/// Other compressions can't, see [`crate::options`].
 pub struct Encoder<W: std::io::Write> {
     /// Set to None when finished.
-    zstd_encoder: Option<zstd::stream::Encoder<'static, W>>,
+    compressor: Option<Compressor<W>>,
     buffer: Vec<u8>,
 }
 
 impl<W: std::io::Write> Drop for Encoder<W> {
     fn drop(&mut self) {
-        if self.zstd_encoder.is_some() {
+        if self.compressor.is_some() {
             re_log::warn!("Encoder dropped without calling finish()!");
             if let Err(err) = self.finish() {
                 re_log::error!("Failed to finish encoding: {err}");
@@ -59,37 +151,24 @@ impl<W: std::io::Write> Drop for Encoder<W> {
 }
 
 impl<W: std::io::Write> Encoder<W> {
-    pub fn new(mut write: W) -> Result<Self, EncodeError> {
-        let rerun_version = re_build_info::CrateVersion::parse(env!("CARGO_PKG_VERSION"));
-
-        write.write_all(b"RRF0").map_err(EncodeError::Write)?;
-        write
-            .write_all(&rerun_version.to_bytes())
-            .map_err(EncodeError::Write)?;
-
-        let level = 3;
-        let zstd_encoder = zstd::stream::Encoder::new(write, level).map_err(EncodeError::Zstd)?;
+    pub fn new(options: EncodingOptions, mut write: W) -> Result<Self, EncodeError> {
+        write_header(&mut write, options, FileLayout::Stream)?;
 
         Ok(Self {
-            zstd_encoder: Some(zstd_encoder),
+            compressor: Some(Compressor::new(options, write)?),
             buffer: vec![],
         })
     }
 
     pub fn append(&mut self, message: &LogMsg) -> Result<(), EncodeError> {
-        let Self {
-            zstd_encoder,
-            buffer,
-        } = self;
+        let Self { compressor, buffer } = self;
 
-        if let Some(zstd_encoder) = zstd_encoder {
+        if let Some(compressor) = compressor {
             buffer.clear();
             rmp_serde::encode::write_named(buffer, message)?;
 
-            zstd_encoder
-                .write_all(&(buffer.len() as u64).to_le_bytes())
-                .map_err(EncodeError::Zstd)?;
-            zstd_encoder.write_all(buffer).map_err(EncodeError::Zstd)?;
+            compressor.write_all(&(buffer.len() as u64).to_le_bytes())?;
+            compressor.write_all(buffer)?;
 
             Ok(())
         } else {
@@ -98,9 +177,8 @@ impl<W: std::io::Write> Encoder<W> {
     }
 
     pub fn finish(&mut self) -> Result<(), EncodeError> {
-        if let Some(zstd_encoder) = self.zstd_encoder.take() {
-            zstd_encoder.finish().map_err(EncodeError::Zstd)?;
-            Ok(())
+        if let Some(compressor) = self.compressor.take() {
+            compressor.finish()
         } else {
             re_log::warn!("Encoder::finish called twice");
             Ok(())
@@ -108,11 +186,163 @@ impl<W: std::io::Write> Encoder<W> {
     }
 }
 
+// ----------------------------------------------------------------------------
+
+/// Encode a stream of [`LogMsg`] into a chunked, seekable `.rrd` file.
+///
+/// Viewers older than 0.6 can't read chunked files, see [`crate::options`].
+///
+/// Messages are grouped into independently compressed blocks of roughly `max_block_size` bytes
+/// (before compression), and an [`RrdIndex`] of all blocks is written as a footer on
+/// [`Self::finish`].
+///
+/// See [`crate::index`] for the exact layout, and [`crate::decoder::IndexedReader`] for reading
+/// back only parts of the file.
+pub struct ChunkedEncoder<W: std::io::Write> {
+    /// Set to None when finished.
+    write: Option<W>,
+
+    options: EncodingOptions,
+
+    /// Number of bytes written to `write` so far.
+    num_bytes_written: u64,
+
+    max_block_size: usize,
+
+    /// The uncompressed contents of the current block.
+    block: Vec<u8>,
+    block_info: BlockInfo,
+
+    index: RrdIndex,
+}
+
+impl<W: std::io::Write> Drop for ChunkedEncoder<W> {
+    fn drop(&mut self) {
+        if self.write.is_some() {
+            re_log::warn!("ChunkedEncoder dropped without calling finish()!");
+            if let Err(err) = self.finish() {
+                re_log::error!("Failed to finish encoding: {err}");
+            }
+        }
+    }
+}
+
+impl<W: std::io::Write> ChunkedEncoder<W> {
+    /// Default uncompressed size of a block.
+    pub const DEFAULT_MAX_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+    pub fn new(options: EncodingOptions, write: W) -> Result<Self, EncodeError> {
+        Self::with_max_block_size(options, write, Self::DEFAULT_MAX_BLOCK_SIZE)
+    }
+
+    /// A block is closed as soon as its uncompressed size reaches `max_block_size` bytes.
+    pub fn with_max_block_size(
+        options: EncodingOptions,
+        mut write: W,
+        max_block_size: usize,
+    ) -> Result<Self, EncodeError> {
+        write_header(&mut write, options, FileLayout::Chunked)?;
+
+        Ok(Self {
+            write: Some(write),
+            options,
+            num_bytes_written: crate::options::HEADER_SIZE,
+            max_block_size,
+            block: vec![],
+            block_info: BlockInfo::default(),
+            index: RrdIndex::default(),
+        })
+    }
+
+    pub fn append(&mut self, message: &LogMsg) -> Result<(), EncodeError> {
+        if self.write.is_none() {
+            return Err(EncodeError::AlreadyFinished);
+        }
+
+        self.block_info.add_msg(message)?;
+
+        let len_offset = self.block.len();
+        self.block.extend_from_slice(&0_u64.to_le_bytes());
+        rmp_serde::encode::write_named(&mut self.block, message)?;
+        let len = (self.block.len() - len_offset - 8) as u64;
+        self.block[len_offset..len_offset + 8].copy_from_slice(&len.to_le_bytes());
+
+        if self.block.len() >= self.max_block_size {
+            self.flush_block()?;
+        }
+
+        Ok(())
+    }
+
+    /// Compress and write the current block, if any.
+    fn flush_block(&mut self) -> Result<(), EncodeError> {
+        crate::profile_function!();
+
+        let Some(write) = &mut self.write else {
+            return Err(EncodeError::AlreadyFinished);
+        };
+
+        if self.block_info.num_messages == 0 {
+            return Ok(());
+        }
+
+        let compressed = compress_block(self.options, &self.block)?;
+
+        write
+            .write_all(&(compressed.len() as u64).to_le_bytes())
+            .map_err(EncodeError::Write)?;
+        write.write_all(&compressed).map_err(EncodeError::Write)?;
+
+        let mut block_info = std::mem::take(&mut self.block_info);
+        block_info.byte_offset = self.num_bytes_written + 8;
+        block_info.byte_len = compressed.len() as u64;
+        self.index.blocks.push(block_info);
+
+        self.num_bytes_written += 8 + compressed.len() as u64;
+        self.block.clear();
+
+        Ok(())
+    }
+
+    /// Writes the last block and the index.
+    pub fn finish(&mut self) -> Result<(), EncodeError> {
+        if self.write.is_none() {
+            re_log::warn!("ChunkedEncoder::finish called twice");
+            return Ok(());
+        }
+
+        self.flush_block()?;
+
+        if let Some(mut write) = self.write.take() {
+            let index = rmp_serde::encode::to_vec_named(&self.index)?;
+
+            // Terminates the sequence of blocks:
+            write
+                .write_all(&0_u64.to_le_bytes())
+                .map_err(EncodeError::Write)?;
+
+            write.write_all(&index).map_err(EncodeError::Write)?;
+            write
+                .write_all(&(index.len() as u64).to_le_bytes())
+                .map_err(EncodeError::Write)?;
+            write
+                .write_all(crate::index::INDEX_MAGIC)
+                .map_err(EncodeError::Write)?;
+            write.flush().map_err(EncodeError::Write)?;
+        }
+
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 pub fn encode<'a>(
+    options: EncodingOptions,
     messages: impl Iterator<Item = &'a LogMsg>,
     write: &mut impl std::io::Write,
 ) -> Result<(), EncodeError> {
-    let mut encoder = Encoder::new(write)?;
+    let mut encoder = Encoder::new(options, write)?;
     for message in messages {
         encoder.append(message)?;
     }
@@ -120,12 +350,25 @@ pub fn encode<'a>(
 }
 
 pub fn encode_owned(
+    options: EncodingOptions,
     messages: impl Iterator<Item = LogMsg>,
     write: impl std::io::Write,
 ) -> Result<(), EncodeError> {
-    let mut encoder = Encoder::new(write)?;
+    let mut encoder = Encoder::new(options, write)?;
     for message in messages {
         encoder.append(&message)?;
     }
     encoder.finish()
 }
+
+pub fn encode_chunked<'a>(
+    options: EncodingOptions,
+    messages: impl Iterator<Item = &'a LogMsg>,
+    write: &mut impl std::io::Write,
+) -> Result<(), EncodeError> {
+    let mut encoder = ChunkedEncoder::new(options, write)?;
+    for message in messages {
+        encoder.append(message)?;
+    }
+    encoder.finish()
+}
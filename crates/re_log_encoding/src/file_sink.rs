@@ -39,6 +39,14 @@ impl Drop for FileSink {
 impl FileSink {
     /// Start writing log messages to a file at the given path.
     pub fn new(path: impl Into<std::path::PathBuf>) -> Result<Self, FileSinkError> {
+        Self::with_options(path, crate::EncodingOptions::default())
+    }
+
+    /// Start writing log messages to a file at the given path, using the given encoding options.
+    pub fn with_options(
+        path: impl Into<std::path::PathBuf>,
+        options: crate::EncodingOptions,
+    ) -> Result<Self, FileSinkError> {
         let (tx, rx) = std::sync::mpsc::channel();
 
         let path = path.into();
@@ -47,7 +55,7 @@ impl FileSink {
 
         let file = std::fs::File::create(&path)
             .map_err(|err| FileSinkError::CreateFile(path.clone(), err))?;
-        let mut encoder = crate::encoder::Encoder::new(file)?;
+        let mut encoder = crate::encoder::Encoder::new(options, file)?;
 
         let join_handle = std::thread::Builder::new()
             .name("file_writer".into())
@@ -0,0 +1,212 @@
+//! The footer index ("table of contents") of chunked `.rrd` files.
+//!
+//! A chunked `.rrd` file looks like this:
+//! ```text
+//! header (see [`crate::options`])
+//! (block_len: u64 | compressed block)*
+//! 0: u64
+//! index: MsgPack-encoded [`RrdIndex`] | index_len: u64 | "RRIX"
+//! ```
+//!
+//! Each block is an independently compressed (using the [`crate::options::EncodingOptions`] of
+//! the header) sequence of `(len: u64, MsgPack-encoded LogMsg)`.
+//! All integers are little-endian.
+//!
+//! The index at the end of the file lists the byte ranges of all blocks, together with a summary
+//! of their contents, so that a reader can seek directly to the blocks it is interested in.
+//! A reader that doesn't care about the index (e.g. when streaming) can simply read blocks until
+//! it hits the zero-length terminator.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use re_log_types::{DataTable, DataTableResult, EntityPath, LogMsg, TimeRange, Timeline};
+
+// ----------------------------------------------------------------------------
+
+/// Magic bytes at the very end of a chunked `.rrd` file, following the index.
+pub const INDEX_MAGIC: &[u8; 4] = b"RRIX";
+
+/// Size of the trailer following the index: `index_len: u64` + [`INDEX_MAGIC`].
+pub const INDEX_TRAILER_SIZE: u64 = 8 + 4;
+
+// ----------------------------------------------------------------------------
+
+/// The table of contents of a chunked `.rrd` file.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RrdIndex {
+    /// All blocks in the file, in the order they were written.
+    pub blocks: Vec<BlockInfo>,
+}
+
+impl RrdIndex {
+    /// Indices of all the blocks that may contain data relevant to the given query.
+    pub fn matching_blocks<'a>(
+        &'a self,
+        query: &'a IndexQuery,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.matches(query))
+            .map(|(i, _)| i)
+    }
+
+    /// Total number of [`LogMsg`]es across all blocks.
+    pub fn num_messages(&self) -> u64 {
+        self.blocks.iter().map(|block| block.num_messages).sum()
+    }
+}
+
+/// Location and summarized contents of a single block of a chunked `.rrd` file.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BlockInfo {
+    /// Offset of the compressed block from the start of the file, in bytes.
+    pub byte_offset: u64,
+
+    /// Length of the compressed block, in bytes.
+    pub byte_len: u64,
+
+    /// Number of [`LogMsg`]es in this block.
+    pub num_messages: u64,
+
+    /// The range of times covered by this block, for each timeline.
+    pub time_ranges: BTreeMap<Timeline, TimeRange>,
+
+    /// All entity paths with data or operations in this block.
+    pub entity_paths: BTreeSet<EntityPath>,
+
+    /// Does this block contain any timeless data?
+    ///
+    /// Timeless data is relevant to all time queries.
+    pub has_timeless: bool,
+
+    /// Does this block contain any control messages (e.g. [`LogMsg::BeginRecordingMsg`])?
+    ///
+    /// Control messages are not tied to any entity or time, so these blocks are always loaded.
+    pub has_control_msgs: bool,
+}
+
+impl BlockInfo {
+    /// Could this block contain data relevant to the given query?
+    ///
+    /// This is conservative: it may return `true` for blocks that end up having nothing of
+    /// interest, but never `false` for blocks that do.
+    pub fn matches(&self, query: &IndexQuery) -> bool {
+        if self.has_control_msgs {
+            return true;
+        }
+
+        let IndexQuery {
+            time_range,
+            entity_subtree,
+        } = query;
+
+        if let Some((timeline, time_range)) = time_range {
+            let overlaps = self
+                .time_ranges
+                .get(timeline)
+                .map_or(false, |range| range.intersects(*time_range));
+            if !overlaps && !self.has_timeless {
+                return false;
+            }
+        }
+
+        if let Some(entity_subtree) = entity_subtree {
+            let in_subtree = self.entity_paths.iter().any(|entity_path| {
+                entity_path == entity_subtree || entity_path.is_descendant_of(entity_subtree)
+            });
+            if !in_subtree {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Accumulates the summary of `msg` into this block's metadata.
+    pub(crate) fn add_msg(&mut self, msg: &LogMsg) -> DataTableResult<()> {
+        self.num_messages += 1;
+
+        match msg {
+            // NOTE: Deletions must always be replayed alongside the data they apply to.
+            LogMsg::BeginRecordingMsg(_) | LogMsg::DeleteRowsMsg(_, _) | LogMsg::Goodbye(_) => {
+                self.has_control_msgs = true;
+            }
+            LogMsg::EntityPathOpMsg(_, msg) => {
+                self.entity_paths.insert(msg.path_op.entity_path().clone());
+                if msg.time_point.is_timeless() {
+                    self.has_timeless = true;
+                }
+                for (timeline, time) in msg.time_point.iter() {
+                    self.add_time(*timeline, TimeRange::point(*time));
+                }
+            }
+            LogMsg::ArrowMsg(_, msg) => {
+                let table = DataTable::from_arrow_msg(msg)?;
+
+                self.entity_paths
+                    .extend(table.col_entity_path.iter().cloned());
+
+                let num_rows = table.num_rows() as usize;
+                let has_timeless_rows = (0..num_rows).any(|i| {
+                    table
+                        .col_timelines
+                        .values()
+                        .all(|col_time| col_time[i].is_none())
+                });
+                if has_timeless_rows {
+                    self.has_timeless = true;
+                }
+
+                for (timeline, col_time) in &table.col_timelines {
+                    let mut times = col_time.iter().flatten().copied();
+                    if let Some(first) = times.next() {
+                        let (min, max) = times.fold((first, first), |(min, max), time| {
+                            (min.min(time), max.max(time))
+                        });
+                        self.add_time(*timeline, TimeRange::new(min.into(), max.into()));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_time(&mut self, timeline: Timeline, range: TimeRange) {
+        self.time_ranges
+            .entry(timeline)
+            .and_modify(|existing| *existing = existing.union(range))
+            .or_insert(range);
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Selects which blocks of a chunked `.rrd` file to load.
+///
+/// An empty query (the default) matches all blocks.
+#[derive(Clone, Debug, Default)]
+pub struct IndexQuery {
+    /// Only load blocks with data on this timeline within this (inclusive) range.
+    ///
+    /// Blocks with timeless data always match.
+    pub time_range: Option<(Timeline, TimeRange)>,
+
+    /// Only load blocks with data for this entity, or any of its descendants.
+    pub entity_subtree: Option<EntityPath>,
+}
+
+impl IndexQuery {
+    #[inline]
+    pub fn with_time_range(mut self, timeline: Timeline, time_range: TimeRange) -> Self {
+        self.time_range = Some((timeline, time_range));
+        self
+    }
+
+    #[inline]
+    pub fn with_entity_subtree(mut self, entity_path: impl Into<EntityPath>) -> Self {
+        self.entity_subtree = Some(entity_path.into());
+        self
+    }
+}
@@ -10,11 +10,27 @@ pub mod encoder;
 #[cfg(not(target_arch = "wasm32"))]
 mod file_sink;
 
+#[cfg(any(feature = "decoder", feature = "encoder"))]
+pub mod index;
+
+#[cfg(feature = "decoder")]
+pub mod migration;
+
+pub mod options;
+
+#[cfg(feature = "decoder")]
+pub mod stream_decoder;
+
 #[cfg(feature = "decoder")]
 pub mod stream_rrd_from_http;
 
+#[cfg(all(test, feature = "encoder"))]
+mod test_util;
+
 // ---------------------------------------------------------------------
 
+pub use options::{Compression, EncodingOptions};
+
 #[cfg(feature = "encoder")]
 #[cfg(not(target_arch = "wasm32"))]
 pub use file_sink::{FileSink, FileSinkError};
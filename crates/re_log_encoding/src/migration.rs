@@ -0,0 +1,628 @@
+//! Upgrading [`LogMsg`]es written by older versions of Rerun to the current schema.
+//!
+//! Each [`Migration`] describes how the schema changed in some Rerun version, e.g. that a
+//! component got renamed. When decoding a recording written by an older version, the
+//! [`MigrationRegistry`] collects all migrations introduced since then into a [`MigrationPlan`],
+//! which is then applied to each message as it is decoded.
+//!
+//! When changing the name or datatype of a builtin component, add a [`Migration`] to
+//! [`MigrationRegistry::builtin`].
+
+use re_build_info::CrateVersion;
+use re_log_types::{
+    external::arrow2::{
+        self,
+        array::{
+            Array, BinaryArray, BooleanArray, FixedSizeListArray, ListArray, PrimitiveArray,
+            StructArray, UnionArray, Utf8Array,
+        },
+        chunk::Chunk,
+        datatypes::{DataType, Field, PhysicalType, PrimitiveType},
+    },
+    ArrowMsg, LogMsg,
+};
+
+/// Arrow metadata key for the name of an extension type.
+const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+
+/// On failure to migrate a [`LogMsg`].
+#[derive(thiserror::Error, Debug)]
+pub enum MigrationError {
+    #[error("Recordings from Rerun {version} are too old to be migrated (the oldest supported version is {oldest_supported})")]
+    TooOld {
+        version: CrateVersion,
+        oldest_supported: CrateVersion,
+    },
+
+    #[error(
+        "Recordings from Rerun {version} are newer than this version of Rerun ({local_version})"
+    )]
+    TooNew {
+        version: CrateVersion,
+        local_version: CrateVersion,
+    },
+
+    #[error("Column {0:?} is not a list of component values")]
+    NotAList(String),
+
+    #[error("Can't migrate arrow arrays of type {0:?}")]
+    UnsupportedDatatype(DataType),
+
+    #[error(transparent)]
+    Arrow(#[from] arrow2::error::Error),
+}
+
+/// Converts the values of a component from its old datatype to its new one.
+pub type ConvertFn = fn(&dyn Array) -> arrow2::error::Result<Box<dyn Array>>;
+
+/// A single change to the schema.
+#[derive(Clone, Debug)]
+pub enum MigrationStep {
+    /// A component was renamed, without changing its datatype.
+    ///
+    /// If the component uses an arrow extension type named after the component,
+    /// the extension type is renamed too.
+    RenameComponent {
+        from: &'static str,
+        to: &'static str,
+    },
+
+    /// The datatype of a component changed.
+    ConvertComponent {
+        component: &'static str,
+        convert: ConvertFn,
+    },
+}
+
+/// The changes to the schema introduced in some version of Rerun.
+#[derive(Clone, Debug)]
+pub struct Migration {
+    /// Recordings written by versions before this one need this migration.
+    pub introduced_in: CrateVersion,
+
+    /// What changed, for the logs.
+    pub description: &'static str,
+
+    pub steps: Vec<MigrationStep>,
+}
+
+// ----------------------------------------------------------------------------
+
+/// All known [`Migration`]s, and how far back they go.
+#[derive(Clone, Debug)]
+pub struct MigrationRegistry {
+    /// Recordings written by versions older than this are not supported.
+    oldest_supported: CrateVersion,
+
+    /// Sorted by [`Migration::introduced_in`].
+    migrations: Vec<Migration>,
+}
+
+impl MigrationRegistry {
+    /// A registry without any migrations.
+    pub fn new(oldest_supported: CrateVersion) -> Self {
+        Self {
+            oldest_supported,
+            migrations: vec![],
+        }
+    }
+
+    /// The migrations of the builtin components.
+    pub fn builtin() -> Self {
+        // No migrations have been written yet, so only recordings with the current schema are
+        // supported. Older recordings still load without being migrated, with a warning, like
+        // they always have: nobody has checked their schema against real recordings of those
+        // releases.
+        //
+        // Along with the first migration, check in an `.rrd` fixture written by each of the
+        // `schema_versions`, and decode them all in a test.
+        Self::new(CrateVersion::parse("0.6.0-alpha.0"))
+    }
+
+    pub fn register(&mut self, migration: Migration) {
+        assert!(
+            self.oldest_supported < migration.introduced_in,
+            "Migration {:?} predates the oldest supported version {}",
+            migration.description,
+            self.oldest_supported
+        );
+        let idx = self
+            .migrations
+            .partition_point(|m| m.introduced_in <= migration.introduced_in);
+        self.migrations.insert(idx, migration);
+    }
+
+    #[inline]
+    pub fn oldest_supported(&self) -> CrateVersion {
+        self.oldest_supported
+    }
+
+    #[inline]
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// The versions in which the schema changed, starting with the oldest supported one.
+    ///
+    /// Recordings written by any supported version have the same schema as those written by
+    /// one of these.
+    pub fn schema_versions(&self) -> Vec<CrateVersion> {
+        let mut versions = vec![self.oldest_supported];
+        for migration in &self.migrations {
+            if versions.last() != Some(&migration.introduced_in) {
+                versions.push(migration.introduced_in);
+            }
+        }
+        versions
+    }
+
+    /// All the steps needed to bring a recording written by `version` up to date.
+    pub fn plan(&self, version: CrateVersion) -> Result<MigrationPlan, MigrationError> {
+        self.plan_to(version, CrateVersion::parse(env!("CARGO_PKG_VERSION")))
+    }
+
+    fn plan_to(
+        &self,
+        version: CrateVersion,
+        local_version: CrateVersion,
+    ) -> Result<MigrationPlan, MigrationError> {
+        let steps = if version.is_compatible_with(local_version) {
+            vec![]
+        } else if local_version < version {
+            return Err(MigrationError::TooNew {
+                version,
+                local_version,
+            });
+        } else if version < self.oldest_supported {
+            return Err(MigrationError::TooOld {
+                version,
+                oldest_supported: self.oldest_supported,
+            });
+        } else {
+            self.migrations
+                .iter()
+                .filter(|migration| version < migration.introduced_in)
+                .inspect(|migration| {
+                    re_log::debug!(
+                        "Migrating data from Rerun {version}: {}",
+                        migration.description
+                    );
+                })
+                .flat_map(|migration| migration.steps.iter().cloned())
+                .collect()
+        };
+
+        Ok(MigrationPlan { version, steps })
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// The steps to bring the messages of a recording written by some older version up to date.
+#[derive(Clone, Debug)]
+pub struct MigrationPlan {
+    /// The version that wrote the recording.
+    version: CrateVersion,
+    steps: Vec<MigrationStep>,
+}
+
+impl MigrationPlan {
+    /// The version that wrote the recording.
+    #[inline]
+    pub fn version(&self) -> CrateVersion {
+        self.version
+    }
+
+    /// Does nothing?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn apply(&self, msg: &mut LogMsg) -> Result<(), MigrationError> {
+        match msg {
+            LogMsg::ArrowMsg(_, arrow_msg) => {
+                crate::profile_function!();
+                for step in &self.steps {
+                    apply_step(step, arrow_msg)?;
+                }
+                Ok(())
+            }
+            LogMsg::BeginRecordingMsg(_)
+            | LogMsg::EntityPathOpMsg(_, _)
+            | LogMsg::DeleteRowsMsg(_, _)
+            | LogMsg::Goodbye(_) => Ok(()),
+        }
+    }
+}
+
+fn apply_step(step: &MigrationStep, msg: &mut ArrowMsg) -> Result<(), MigrationError> {
+    let component = match step {
+        MigrationStep::RenameComponent { from, .. } => from,
+        MigrationStep::ConvertComponent { component, .. } => component,
+    };
+
+    let is_data_column = |field: &Field| {
+        field.name == *component
+            && field
+                .metadata
+                .get(re_log_types::METADATA_KIND)
+                .map(String::as_str)
+                == Some(re_log_types::METADATA_KIND_DATA)
+    };
+    let Some(column_idx) = msg.schema.fields.iter().position(is_data_column) else {
+        return Ok(()); // this table doesn't have the component
+    };
+
+    let field = &mut msg.schema.fields[column_idx];
+    let list = msg.chunk.arrays()[column_idx]
+        .as_any()
+        .downcast_ref::<ListArray<i32>>()
+        .ok_or_else(|| MigrationError::NotAList(field.name.clone()))?;
+    let DataType::List(item) = list.data_type() else {
+        return Err(MigrationError::NotAList(field.name.clone()));
+    };
+    let mut item = (**item).clone();
+
+    let values = match step {
+        MigrationStep::RenameComponent { from, to } => {
+            field.name = (*to).to_owned();
+            if let DataType::Extension(name, inner, metadata) = &item.data_type {
+                if name == from {
+                    item.data_type =
+                        DataType::Extension((*to).to_owned(), inner.clone(), metadata.clone());
+                }
+            }
+            if item.metadata.get(EXTENSION_NAME_KEY).map(String::as_str) == Some(*from) {
+                item.metadata
+                    .insert(EXTENSION_NAME_KEY.to_owned(), (*to).to_owned());
+            }
+            with_datatype(list.values().as_ref(), item.data_type.clone())?
+        }
+        MigrationStep::ConvertComponent { convert, .. } => {
+            let values = convert(list.values().as_ref())?;
+            item.data_type = values.data_type().clone();
+            if let DataType::Extension(name, _, _) = &item.data_type {
+                item.metadata
+                    .insert(EXTENSION_NAME_KEY.to_owned(), name.clone());
+            }
+            values
+        }
+    };
+
+    let list = ListArray::<i32>::try_new(
+        DataType::List(Box::new(item)),
+        list.offsets().clone(),
+        values,
+        list.validity().cloned(),
+    )?;
+    field.data_type = list.data_type().clone();
+
+    let mut columns = msg.chunk.arrays().to_vec();
+    columns[column_idx] = list.boxed();
+    msg.chunk = Chunk::try_new(columns)?;
+
+    Ok(())
+}
+
+/// The same array, with a different (but physically identical) datatype.
+///
+/// Used for renaming extension types.
+fn with_datatype(array: &dyn Array, datatype: DataType) -> Result<Box<dyn Array>, MigrationError> {
+    fn downcast<A: 'static>(array: &dyn Array) -> &A {
+        // Can't fail: we matched on the physical type.
+        array.as_any().downcast_ref::<A>().unwrap()
+    }
+
+    macro_rules! primitive {
+        ($T:ty) => {
+            downcast::<PrimitiveArray<$T>>(array)
+                .clone()
+                .to(datatype)
+                .boxed()
+        };
+    }
+
+    Ok(match array.data_type().to_physical_type() {
+        PhysicalType::Boolean => {
+            let array = downcast::<BooleanArray>(array);
+            BooleanArray::try_new(datatype, array.values().clone(), array.validity().cloned())?
+                .boxed()
+        }
+        PhysicalType::Primitive(primitive) => match primitive {
+            PrimitiveType::Int8 => primitive!(i8),
+            PrimitiveType::Int16 => primitive!(i16),
+            PrimitiveType::Int32 => primitive!(i32),
+            PrimitiveType::Int64 => primitive!(i64),
+            PrimitiveType::UInt8 => primitive!(u8),
+            PrimitiveType::UInt16 => primitive!(u16),
+            PrimitiveType::UInt32 => primitive!(u32),
+            PrimitiveType::UInt64 => primitive!(u64),
+            PrimitiveType::Float32 => primitive!(f32),
+            PrimitiveType::Float64 => primitive!(f64),
+            _ => {
+                return Err(MigrationError::UnsupportedDatatype(
+                    array.data_type().clone(),
+                ))
+            }
+        },
+        PhysicalType::Utf8 => {
+            let array = downcast::<Utf8Array<i32>>(array);
+            Utf8Array::<i32>::try_new(
+                datatype,
+                array.offsets().clone(),
+                array.values().clone(),
+                array.validity().cloned(),
+            )?
+            .boxed()
+        }
+        PhysicalType::Binary => {
+            let array = downcast::<BinaryArray<i32>>(array);
+            BinaryArray::<i32>::try_new(
+                datatype,
+                array.offsets().clone(),
+                array.values().clone(),
+                array.validity().cloned(),
+            )?
+            .boxed()
+        }
+        PhysicalType::List => {
+            let array = downcast::<ListArray<i32>>(array);
+            ListArray::<i32>::try_new(
+                datatype,
+                array.offsets().clone(),
+                array.values().clone(),
+                array.validity().cloned(),
+            )?
+            .boxed()
+        }
+        PhysicalType::FixedSizeList => {
+            let array = downcast::<FixedSizeListArray>(array);
+            FixedSizeListArray::try_new(
+                datatype,
+                array.values().clone(),
+                array.validity().cloned(),
+            )?
+            .boxed()
+        }
+        PhysicalType::Struct => {
+            let array = downcast::<StructArray>(array);
+            StructArray::try_new(datatype, array.values().to_vec(), array.validity().cloned())?
+                .boxed()
+        }
+        PhysicalType::Union => {
+            let array = downcast::<UnionArray>(array);
+            UnionArray::try_new(
+                datatype,
+                array.types().clone(),
+                array.fields().to_vec(),
+                array.offsets().cloned(),
+            )?
+            .boxed()
+        }
+        _ => {
+            return Err(MigrationError::UnsupportedDatatype(
+                array.data_type().clone(),
+            ))
+        }
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use re_log_types::{
+        component_types::InstanceKey, datagen::build_frame_nr, Component as _, DataCell, DataRow,
+        DataTable, RecordingId, RowId, TableId,
+    };
+
+    /// A table with one `InstanceKey` cell and one custom `f64`/`f32` cell.
+    fn table_msg(component: &str, confidence: Box<dyn Array>) -> LogMsg {
+        let row = DataRow::from_cells(
+            RowId::ZERO,
+            [build_frame_nr(42.into())],
+            "world/points",
+            2,
+            [
+                DataCell::from_native(&[InstanceKey(0), InstanceKey(1)]),
+                DataCell::from_arrow(component.into(), confidence),
+            ],
+        );
+        let table = DataTable::from_rows(TableId::ZERO, [row]);
+        LogMsg::ArrowMsg(RecordingId::ZERO, table.to_arrow_msg().unwrap())
+    }
+
+    fn f64_to_f32(array: &dyn Array) -> arrow2::error::Result<Box<dyn Array>> {
+        let array = array
+            .as_any()
+            .downcast_ref::<PrimitiveArray<f64>>()
+            .ok_or_else(|| arrow2::error::Error::oos("expected f64"))?;
+        Ok(array
+            .iter()
+            .map(|value| value.map(|value| *value as f32))
+            .collect::<PrimitiveArray<f32>>()
+            .boxed())
+    }
+
+    fn test_registry() -> MigrationRegistry {
+        let mut registry = MigrationRegistry::new(CrateVersion::new(0, 2, 0));
+        registry.register(Migration {
+            introduced_in: CrateVersion::new(0, 4, 0),
+            description: "`ext.confidence` is now an f32",
+            steps: vec![MigrationStep::ConvertComponent {
+                component: "ext.confidence",
+                convert: f64_to_f32,
+            }],
+        });
+        registry.register(Migration {
+            introduced_in: CrateVersion::new(0, 3, 0),
+            description: "`ext.conf` was renamed to `ext.confidence`",
+            steps: vec![MigrationStep::RenameComponent {
+                from: "ext.conf",
+                to: "ext.confidence",
+            }],
+        });
+        registry
+    }
+
+    #[test]
+    fn migrations_are_applied_in_order() {
+        let registry = test_registry();
+        let local_version = CrateVersion::new(0, 5, 0);
+        assert_eq!(
+            registry.schema_versions(),
+            vec![
+                CrateVersion::new(0, 2, 0),
+                CrateVersion::new(0, 3, 0),
+                CrateVersion::new(0, 4, 0)
+            ]
+        );
+
+        let expected = table_msg(
+            "ext.confidence",
+            PrimitiveArray::<f32>::from_slice([0.5, 1.0]).boxed(),
+        );
+
+        // The schema as written by each supported version:
+        let fixtures = [
+            (
+                CrateVersion::new(0, 2, 0),
+                table_msg(
+                    "ext.conf",
+                    PrimitiveArray::<f64>::from_slice([0.5, 1.0]).boxed(),
+                ),
+            ),
+            (
+                CrateVersion::new(0, 3, 1),
+                table_msg(
+                    "ext.confidence",
+                    PrimitiveArray::<f64>::from_slice([0.5, 1.0]).boxed(),
+                ),
+            ),
+            (CrateVersion::new(0, 4, 0), expected.clone()),
+            (CrateVersion::new(0, 5, 0), expected.clone()),
+        ];
+
+        for (version, mut msg) in fixtures {
+            let plan = registry.plan_to(version, local_version).unwrap();
+            plan.apply(&mut msg).unwrap();
+            assert_eq!(msg, expected, "Migrating from {version}");
+        }
+    }
+
+    #[test]
+    fn migrated_tables_can_be_deserialized() {
+        let registry = test_registry();
+        let plan = registry
+            .plan_to(CrateVersion::new(0, 2, 0), CrateVersion::new(0, 5, 0))
+            .unwrap();
+
+        let mut msg = table_msg(
+            "ext.conf",
+            PrimitiveArray::<f64>::from_slice([0.5, 1.0]).boxed(),
+        );
+        plan.apply(&mut msg).unwrap();
+
+        let LogMsg::ArrowMsg(_, arrow_msg) = &msg else {
+            unreachable!()
+        };
+        let table = DataTable::from_arrow_msg(arrow_msg).unwrap();
+        let row = table.to_rows().next().unwrap();
+        let names: Vec<_> = row.component_names().collect();
+        assert!(names.contains(&"ext.confidence".into()));
+        assert!(names.contains(&InstanceKey::name()));
+    }
+
+    #[test]
+    fn renaming_extension_types() {
+        let mut registry = MigrationRegistry::new(CrateVersion::new(0, 2, 0));
+        registry.register(Migration {
+            introduced_in: CrateVersion::new(0, 3, 0),
+            description: "`ext.confidence` was renamed to `ext.certainty`",
+            steps: vec![MigrationStep::RenameComponent {
+                from: "ext.confidence",
+                to: "ext.certainty",
+            }],
+        });
+        let plan = registry
+            .plan_to(CrateVersion::new(0, 2, 0), CrateVersion::new(0, 3, 0))
+            .unwrap();
+
+        let extension =
+            |name: &str| DataType::Extension(name.to_owned(), Box::new(DataType::Float32), None);
+        let mut msg = table_msg(
+            "ext.confidence",
+            PrimitiveArray::<f32>::from_slice([0.5, 1.0])
+                .to(extension("ext.confidence"))
+                .boxed(),
+        );
+        plan.apply(&mut msg).unwrap();
+
+        let LogMsg::ArrowMsg(_, arrow_msg) = &msg else {
+            unreachable!()
+        };
+        let column_idx = arrow_msg
+            .schema
+            .fields
+            .iter()
+            .position(|field| field.name == "ext.certainty")
+            .unwrap();
+        let DataType::List(item) = &arrow_msg.schema.fields[column_idx].data_type else {
+            panic!("Expected a list");
+        };
+        assert_eq!(item.data_type, extension("ext.certainty"));
+        assert_eq!(
+            item.metadata.get(EXTENSION_NAME_KEY).map(String::as_str),
+            Some("ext.certainty")
+        );
+
+        let list = arrow_msg.chunk.arrays()[column_idx]
+            .as_any()
+            .downcast_ref::<ListArray<i32>>()
+            .unwrap();
+        assert_eq!(list.values().data_type(), &extension("ext.certainty"));
+    }
+
+    #[test]
+    fn builtin_registry() {
+        let registry = MigrationRegistry::builtin();
+
+        // Only the current schema is supported, see `MigrationRegistry::builtin`:
+        assert!(registry.migrations().is_empty());
+        assert_eq!(
+            registry.schema_versions(),
+            vec![registry.oldest_supported()]
+        );
+        assert!(registry
+            .plan(CrateVersion::parse(env!("CARGO_PKG_VERSION")))
+            .unwrap()
+            .is_empty());
+
+        // Still written with `MsgBundle`s:
+        assert!(matches!(
+            registry.plan(CrateVersion::new(0, 4, 0)),
+            Err(MigrationError::TooOld { .. })
+        ));
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let registry = test_registry();
+        let local_version = CrateVersion::new(0, 5, 0);
+        assert!(matches!(
+            registry.plan_to(CrateVersion::new(0, 1, 0), local_version),
+            Err(MigrationError::TooOld { .. })
+        ));
+        assert!(matches!(
+            registry.plan_to(CrateVersion::new(0, 6, 0), local_version),
+            Err(MigrationError::TooNew { .. })
+        ));
+        assert!(registry
+            .plan_to(CrateVersion::new(0, 5, 1), local_version)
+            .unwrap()
+            .is_empty());
+    }
+}
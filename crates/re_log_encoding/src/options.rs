@@ -0,0 +1,218 @@
+//! The header of `.rrd` files/streams, and the encoding options stored in it.
+//!
+//! ```text
+//! "RRF1" | version: [u8; 4] | options: [compression, level, layout, 0]
+//! ```
+//!
+//! Files written before the options were introduced start with `"RRF0" | version: [u8; 4]`,
+//! and are always a single zstd stream.
+//!
+//! Viewers older than 0.6 only understand the legacy header, so the encoders keep writing it
+//! whenever the file can be described by it, i.e. for a zstd-compressed [`FileLayout::Stream`].
+//! Only opting into another [`Compression`] or into [`FileLayout::Chunked`] produces an `"RRF1"`
+//! file, which these old viewers will refuse to open.
+
+/// Magic bytes at the start of legacy `.rrd` files, which have no encoding options.
+pub const LEGACY_MAGIC: &[u8; 4] = b"RRF0";
+
+/// Magic bytes at the start of `.rrd` files with [`EncodingOptions`] in their header.
+pub const MAGIC: &[u8; 4] = b"RRF1";
+
+/// Size of the header: magic + version + options.
+pub const HEADER_SIZE: u64 = 4 + 4 + 4;
+
+/// Size of the legacy header: magic + version.
+pub const LEGACY_HEADER_SIZE: u64 = 4 + 4;
+
+/// Errors when parsing the options of an `.rrd` header.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsError {
+    #[error("Unknown compression: {0}")]
+    UnknownCompression(u8),
+
+    #[error("Unknown file layout: {0}")]
+    UnknownLayout(u8),
+}
+
+// ----------------------------------------------------------------------------
+
+/// How the messages of an `.rrd` file are compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Compression {
+    /// No compression at all.
+    Off = 0,
+
+    /// Very fast compression and decompression with a moderate compression ratio.
+    ///
+    /// Useful when logging at high rates from CPU-bound processes.
+    Lz4 = 1,
+
+    /// Slower but stronger compression, tuned by [`EncodingOptions::level`].
+    #[default]
+    Zstd = 2,
+}
+
+impl Compression {
+    /// All the supported compressions, from weakest to strongest.
+    pub const ALL: [Self; 3] = [Self::Off, Self::Lz4, Self::Zstd];
+
+    pub fn from_u8(value: u8) -> Result<Self, OptionsError> {
+        match value {
+            0 => Ok(Self::Off),
+            1 => Ok(Self::Lz4),
+            2 => Ok(Self::Zstd),
+            _ => Err(OptionsError::UnknownCompression(value)),
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => "off".fmt(f),
+            Self::Lz4 => "lz4".fmt(f),
+            Self::Zstd => "zstd".fmt(f),
+        }
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "lz4" => Ok(Self::Lz4),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "Unknown compression {s:?}. Expected one of 'off', 'lz4', 'zstd'"
+            )),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// How the messages are laid out in an `.rrd` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FileLayout {
+    /// A single compressed stream of messages.
+    Stream = 0,
+
+    /// Independently compressed blocks, followed by an index.
+    ///
+    /// See [`crate::index`].
+    Chunked = 1,
+}
+
+impl FileLayout {
+    fn from_u8(value: u8) -> Result<Self, OptionsError> {
+        match value {
+            0 => Ok(Self::Stream),
+            1 => Ok(Self::Chunked),
+            _ => Err(OptionsError::UnknownLayout(value)),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// How to encode an `.rrd` file/stream.
+///
+/// These are stored in the header, so decoders pick the right codec automatically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodingOptions {
+    pub compression: Compression,
+
+    /// Compression level.
+    ///
+    /// Only used by [`Compression::Zstd`], where it goes from 1 (fastest) to 22 (strongest).
+    /// Must fit in an `i8`.
+    pub level: i32,
+}
+
+impl Default for EncodingOptions {
+    fn default() -> Self {
+        Self::ZSTD
+    }
+}
+
+impl EncodingOptions {
+    /// Don't compress at all.
+    pub const UNCOMPRESSED: Self = Self {
+        compression: Compression::Off,
+        level: 0,
+    };
+
+    /// Fast compression using lz4.
+    pub const LZ4: Self = Self {
+        compression: Compression::Lz4,
+        level: 0,
+    };
+
+    /// zstd at its default level.
+    pub const ZSTD: Self = Self {
+        compression: Compression::Zstd,
+        level: 3,
+    };
+
+    /// zstd at the given level, from 1 (fastest) to 22 (strongest).
+    #[inline]
+    pub fn zstd(level: i32) -> Self {
+        Self {
+            compression: Compression::Zstd,
+            level,
+        }
+    }
+
+    /// Can a file with these options and `layout` use the legacy `"RRF0"` header?
+    ///
+    /// The legacy header has no room for options, and implies a zstd [`FileLayout::Stream`].
+    /// The zstd level isn't needed for decoding, so it is lost.
+    #[inline]
+    pub fn is_legacy_compatible(self, layout: FileLayout) -> bool {
+        self.compression == Compression::Zstd && layout == FileLayout::Stream
+    }
+
+    /// The options part of the header of an `.rrd` file.
+    pub(crate) fn to_bytes(self, layout: FileLayout) -> [u8; 4] {
+        let level = self.level.clamp(i8::MIN as i32, i8::MAX as i32) as i8;
+        [self.compression as u8, level as u8, layout as u8, 0]
+    }
+
+    /// Parses the options part of the header of an `.rrd` file.
+    pub(crate) fn from_bytes(bytes: [u8; 4]) -> Result<(Self, FileLayout), OptionsError> {
+        let [compression, level, layout, _reserved] = bytes;
+        let options = Self {
+            compression: Compression::from_u8(compression)?,
+            level: level as i8 as i32,
+        };
+        Ok((options, FileLayout::from_u8(layout)?))
+    }
+}
+
+#[test]
+fn test_options_roundtrip() {
+    for options in [
+        EncodingOptions::UNCOMPRESSED,
+        EncodingOptions::LZ4,
+        EncodingOptions::ZSTD,
+        EncodingOptions::zstd(-5),
+        EncodingOptions::zstd(22),
+    ] {
+        for layout in [FileLayout::Stream, FileLayout::Chunked] {
+            let bytes = options.to_bytes(layout);
+            assert_eq!(
+                EncodingOptions::from_bytes(bytes).unwrap(),
+                (options, layout)
+            );
+        }
+    }
+
+    assert_eq!(
+        EncodingOptions::from_bytes([7, 0, 0, 0]),
+        Err(OptionsError::UnknownCompression(7))
+    );
+}
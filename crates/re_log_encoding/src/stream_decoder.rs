@@ -0,0 +1,615 @@
+//! Incremental decoding of `.rrd` streams, for when the bytes arrive one chunk at a time.
+//!
+//! The [`crate::decoder::Decoder`] pulls its bytes from a [`std::io::Read`], blocking until they
+//! arrive. That doesn't work on the web, where we can't block waiting for the next chunk of a
+//! download. Instead, a [`StreamDecoder`] is pushed each chunk as it arrives, and hands back all
+//! the messages that have become complete.
+
+use re_log_types::LogMsg;
+
+use crate::decoder::DecodeError;
+use crate::options::{Compression, EncodingOptions, FileLayout};
+
+// ----------------------------------------------------------------------------
+
+/// Bytes that have been received, but not yet consumed.
+#[derive(Default)]
+struct ByteQueue {
+    bytes: Vec<u8>,
+    start: usize,
+}
+
+impl ByteQueue {
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        &self.bytes[self.start..]
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.bytes.len() - self.start
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        // Reclaim the consumed space every now and then, without moving bytes around on each push:
+        if self.start > 0 && self.start >= self.bytes.len() / 2 {
+            self.bytes.drain(..self.start);
+            self.start = 0;
+        }
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    #[inline]
+    fn consume(&mut self, num_bytes: usize) {
+        debug_assert!(num_bytes <= self.len());
+        self.start += num_bytes;
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Magic number at the start of each lz4 frame.
+const LZ4_MAGIC: u32 = 0x184D_2204;
+
+/// Incremental decoder of the lz4 frame format, as written by [`lz4_flex::frame::FrameEncoder`].
+///
+/// Only supports frames with independent blocks, which is all that encoder writes.
+#[derive(Default)]
+struct Lz4FrameDecoder {
+    /// `None` while waiting for the header of the next frame.
+    header: Option<Lz4FrameHeader>,
+    scratch: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+struct Lz4FrameHeader {
+    max_block_size: usize,
+    block_checksums: bool,
+    content_checksum: bool,
+}
+
+fn lz4_error(msg: impl Into<String>) -> DecodeError {
+    DecodeError::Lz4(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        msg.into(),
+    ))
+}
+
+impl Lz4FrameDecoder {
+    fn decompress(
+        &mut self,
+        input: &mut ByteQueue,
+        output: &mut ByteQueue,
+    ) -> Result<bool, DecodeError> {
+        let bytes = input.as_slice();
+
+        let Some(header) = self.header else {
+            // magic + FLG + BD + HC:
+            if bytes.len() < 7 {
+                return Ok(false);
+            }
+            if u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) != LZ4_MAGIC {
+                return Err(lz4_error("Bad lz4 frame magic"));
+            }
+
+            let (flg, bd) = (bytes[4], bytes[5]);
+            if flg & 0x20 == 0 {
+                return Err(lz4_error("Linked lz4 blocks are not supported"));
+            }
+            let has_content_size = flg & 0x08 != 0;
+            let has_dict_id = flg & 0x01 != 0;
+            let header_len = 7 + 8 * has_content_size as usize + 4 * has_dict_id as usize;
+            if bytes.len() < header_len {
+                return Ok(false);
+            }
+
+            let max_block_size = match (bd >> 4) & 0x07 {
+                4 => 64 * 1024,
+                5 => 256 * 1024,
+                6 => 1024 * 1024,
+                7 => 4 * 1024 * 1024,
+                _ => return Err(lz4_error("Bad lz4 block size")),
+            };
+
+            self.header = Some(Lz4FrameHeader {
+                max_block_size,
+                block_checksums: flg & 0x10 != 0,
+                content_checksum: flg & 0x04 != 0,
+            });
+            input.consume(header_len);
+            return Ok(true);
+        };
+
+        if bytes.len() < 4 {
+            return Ok(false);
+        }
+        let block_len = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        if block_len == 0 {
+            // End mark, and the end of this frame:
+            let len = 4 + 4 * header.content_checksum as usize;
+            if bytes.len() < len {
+                return Ok(false);
+            }
+            self.header = None;
+            input.consume(len);
+            return Ok(true);
+        }
+
+        let is_compressed = block_len & 0x8000_0000 == 0;
+        let data_len = (block_len & 0x7FFF_FFFF) as usize;
+        let len = 4 + data_len + 4 * header.block_checksums as usize;
+        if bytes.len() < len {
+            return Ok(false);
+        }
+
+        let data = &bytes[4..4 + data_len];
+        if is_compressed {
+            self.scratch.resize(header.max_block_size, 0);
+            let num_bytes = lz4_flex::block::decompress_into(data, &mut self.scratch)
+                .map_err(|err| lz4_error(err.to_string()))?;
+            output.extend(&self.scratch[..num_bytes]);
+        } else {
+            output.extend(data);
+        }
+        input.consume(len);
+        Ok(true)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Size of the scratch buffer zstd decompresses into.
+const ZSTD_SCRATCH_SIZE: usize = 128 * 1024;
+
+#[cfg(not(target_arch = "wasm32"))]
+struct ZstdDecoder {
+    decoder: zstd::stream::raw::Decoder<'static>,
+    scratch: Vec<u8>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ZstdDecoder {
+    fn new() -> Result<Self, DecodeError> {
+        Ok(Self {
+            decoder: zstd::stream::raw::Decoder::new().map_err(DecodeError::Zstd)?,
+            scratch: vec![0; ZSTD_SCRATCH_SIZE],
+        })
+    }
+
+    fn decompress(
+        &mut self,
+        input: &mut ByteQueue,
+        output: &mut ByteQueue,
+    ) -> Result<bool, DecodeError> {
+        use zstd::stream::raw::Operation as _;
+
+        let status = self
+            .decoder
+            .run_on_buffers(input.as_slice(), &mut self.scratch)
+            .map_err(DecodeError::Zstd)?;
+        input.consume(status.bytes_read);
+        output.extend(&self.scratch[..status.bytes_written]);
+        Ok(status.bytes_read > 0 || status.bytes_written > 0)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+struct ZstdDecoder {
+    decoder: ruzstd::FrameDecoder,
+    initialized: bool,
+    scratch: Vec<u8>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl ZstdDecoder {
+    /// Largest possible zstd frame header.
+    const MAX_FRAME_HEADER_SIZE: usize = 18;
+
+    fn new() -> Result<Self, DecodeError> {
+        Ok(Self {
+            decoder: ruzstd::FrameDecoder::new(),
+            initialized: false,
+            scratch: vec![0; ZSTD_SCRATCH_SIZE],
+        })
+    }
+
+    fn decompress(
+        &mut self,
+        input: &mut ByteQueue,
+        output: &mut ByteQueue,
+    ) -> Result<bool, DecodeError> {
+        if !self.initialized {
+            // ruzstd reads the frame header from a `Read`, so make sure it's all there:
+            if input.len() < Self::MAX_FRAME_HEADER_SIZE {
+                return Ok(false);
+            }
+            let mut bytes = input.as_slice();
+            let len_before = bytes.len();
+            self.decoder
+                .init(&mut bytes)
+                .map_err(DecodeError::RuzstdInit)?;
+            let num_read = len_before - bytes.len();
+            input.consume(num_read);
+            self.initialized = true;
+            return Ok(true);
+        }
+
+        let (num_read, num_written) = self
+            .decoder
+            .decode_from_to(input.as_slice(), &mut self.scratch)
+            .map_err(|err| {
+                DecodeError::RuzstdRead(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    err.to_string(),
+                ))
+            })?;
+        input.consume(num_read);
+        output.extend(&self.scratch[..num_written]);
+
+        if self.decoder.is_finished() && self.decoder.can_collect() == 0 {
+            // Get ready for the next frame, if any.
+            self.initialized = false;
+        }
+
+        Ok(num_read > 0 || num_written > 0)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Incremental decompression, for any [`FileLayout`] and [`Compression`].
+enum Decompressor {
+    Off,
+    Lz4(Lz4FrameDecoder),
+    Zstd(ZstdDecoder),
+    Chunked {
+        compression: Compression,
+
+        /// Number of blocks decompressed so far.
+        num_blocks: usize,
+
+        /// Set once we've reached the end of the blocks. What follows is the index.
+        reached_end: bool,
+    },
+}
+
+impl Decompressor {
+    fn new(options: EncodingOptions, layout: FileLayout) -> Result<Self, DecodeError> {
+        Ok(match (layout, options.compression) {
+            (FileLayout::Stream, Compression::Off) => Self::Off,
+            (FileLayout::Stream, Compression::Lz4) => Self::Lz4(Default::default()),
+            (FileLayout::Stream, Compression::Zstd) => Self::Zstd(ZstdDecoder::new()?),
+            (FileLayout::Chunked, compression) => Self::Chunked {
+                compression,
+                num_blocks: 0,
+                reached_end: false,
+            },
+        })
+    }
+
+    /// Decompress as much of the `input` as possible into the `output`.
+    ///
+    /// Returns `false` if no progress can be made until more input arrives.
+    fn decompress(
+        &mut self,
+        input: &mut ByteQueue,
+        output: &mut ByteQueue,
+    ) -> Result<bool, DecodeError> {
+        crate::profile_function!();
+
+        match self {
+            Self::Off => {
+                if input.is_empty() {
+                    return Ok(false);
+                }
+                output.extend(input.as_slice());
+                input.consume(input.len());
+                Ok(true)
+            }
+
+            Self::Lz4(decoder) => decoder.decompress(input, output),
+
+            // Even without new input, the decoder may have more output buffered up:
+            Self::Zstd(decoder) => decoder.decompress(input, output),
+
+            Self::Chunked {
+                compression,
+                num_blocks,
+                reached_end,
+            } => {
+                if *reached_end {
+                    // We don't need the index when streaming.
+                    input.consume(input.len());
+                    return Ok(false);
+                }
+
+                let bytes = input.as_slice();
+                if bytes.len() < 8 {
+                    return Ok(false);
+                }
+                let mut len = [0_u8; 8];
+                len.copy_from_slice(&bytes[..8]);
+                let len = u64::from_le_bytes(len);
+
+                if len == 0 {
+                    *reached_end = true;
+                    input.consume(8);
+                    return Ok(true);
+                }
+                let total_len = prefixed_len(len).ok_or(DecodeError::CorruptBlock(*num_blocks))?;
+                if bytes.len() < total_len {
+                    return Ok(false);
+                }
+
+                let block = crate::decoder::decompress_block(*compression, &bytes[8..total_len])
+                    .map_err(|_err| DecodeError::CorruptBlock(*num_blocks))?;
+                output.extend(&block);
+                input.consume(total_len);
+                *num_blocks += 1;
+                Ok(true)
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+enum State {
+    /// Waiting for the header.
+    Header,
+
+    /// Decompressing and decoding messages.
+    Messages(Decompressor),
+}
+
+/// Decodes [`LogMsg`]es from an `.rrd` stream that arrives one chunk at a time.
+///
+/// Handles all layouts and compressions, on both native and web.
+///
+/// ```ignore
+/// let mut decoder = StreamDecoder::new();
+/// while let Some(chunk) = next_chunk() {
+///     decoder.push_chunk(&chunk);
+///     while let Some(msg) = decoder.try_read()? {
+///         on_msg(msg);
+///     }
+/// }
+/// ```
+pub struct StreamDecoder {
+    state: State,
+
+    /// Received bytes that haven't been decompressed yet.
+    input: ByteQueue,
+
+    /// Decompressed bytes that haven't been decoded yet.
+    decompressed: ByteQueue,
+
+    num_bytes_received: u64,
+    num_messages: u64,
+
+    /// Migrations for streams written by older versions of Rerun, once we know the version.
+    migration: Option<crate::migration::MigrationPlan>,
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamDecoder {
+    pub fn new() -> Self {
+        Self {
+            state: State::Header,
+            input: Default::default(),
+            decompressed: Default::default(),
+            num_bytes_received: 0,
+            num_messages: 0,
+            migration: None,
+        }
+    }
+
+    /// Add the next chunk of the stream.
+    ///
+    /// Call [`Self::try_read`] afterwards to get the messages that it completed.
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        self.num_bytes_received += chunk.len() as u64;
+        self.input.extend(chunk);
+    }
+
+    /// The next complete message, or `None` if more chunks are needed first.
+    ///
+    /// Messages that fail to deserialize or migrate are skipped, so it is fine to keep calling this
+    /// after a [`DecodeError::MsgPack`] or [`DecodeError::Migration`].
+    /// After any other error the stream can't be decoded any further.
+    pub fn try_read(&mut self) -> Result<Option<LogMsg>, DecodeError> {
+        loop {
+            if let Some(msg) = self.try_decode_msg()? {
+                self.num_messages += 1;
+                return Ok(Some(msg));
+            }
+
+            match &mut self.state {
+                State::Header => {
+                    let bytes = self.input.as_slice();
+                    if bytes.len() < 4 {
+                        return Ok(None);
+                    }
+                    let header_len = if &bytes[..4] == crate::options::LEGACY_MAGIC {
+                        crate::options::LEGACY_HEADER_SIZE as usize
+                    } else {
+                        crate::options::HEADER_SIZE as usize
+                    };
+                    if bytes.len() < header_len {
+                        return Ok(None);
+                    }
+
+                    let (version, options, layout) =
+                        crate::decoder::read_header(&mut &bytes[..header_len])?;
+                    self.input.consume(header_len);
+                    self.migration = crate::decoder::migration_plan(version);
+                    self.state = State::Messages(Decompressor::new(options, layout)?);
+                }
+
+                State::Messages(decompressor) => {
+                    if !decompressor.decompress(&mut self.input, &mut self.decompressed)? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_decode_msg(&mut self) -> Result<Option<LogMsg>, DecodeError> {
+        let bytes = self.decompressed.as_slice();
+        if bytes.len() < 8 {
+            return Ok(None);
+        }
+        let mut len = [0_u8; 8];
+        len.copy_from_slice(&bytes[..8]);
+        let len = u64::from_le_bytes(len);
+        let total_len = prefixed_len(len).ok_or(DecodeError::BadMessageLength(len))?;
+        if bytes.len() < total_len {
+            return Ok(None);
+        }
+
+        crate::profile_scope!("MsgPack deser");
+        let msg = rmp_serde::from_slice(&bytes[8..total_len]);
+        self.decompressed.consume(total_len);
+        let mut msg = msg?;
+        if let Some(migration) = &self.migration {
+            migration.apply(&mut msg)?;
+        }
+        Ok(Some(msg))
+    }
+
+    /// Total number of bytes pushed so far.
+    #[inline]
+    pub fn num_bytes_received(&self) -> u64 {
+        self.num_bytes_received
+    }
+
+    /// Number of messages decoded so far.
+    #[inline]
+    pub fn num_messages(&self) -> u64 {
+        self.num_messages
+    }
+
+    /// Are there bytes left over that don't make up a complete message (yet)?
+    ///
+    /// If so at the end of the stream, the stream was cut short.
+    pub fn has_pending_bytes(&self) -> bool {
+        let pending_input = match &self.state {
+            State::Header => !self.input.is_empty(),
+            State::Messages(Decompressor::Chunked {
+                reached_end: true, ..
+            }) => false,
+            State::Messages(_) => !self.input.is_empty(),
+        };
+        pending_input || !self.decompressed.is_empty()
+    }
+}
+
+/// The size of a `u64` length prefix plus the `len` bytes following it.
+///
+/// The length comes straight from the stream, so a corrupt one may not even fit in a `usize`.
+fn prefixed_len(len: u64) -> Option<usize> {
+    usize::try_from(len).ok()?.checked_add(8)
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(all(test, feature = "encoder"))]
+mod tests {
+    use super::*;
+
+    use crate::test_util::fake_messages;
+
+    fn encode(options: EncodingOptions, layout: FileLayout, messages: &[LogMsg]) -> Vec<u8> {
+        let mut file = vec![];
+        match layout {
+            FileLayout::Stream => {
+                crate::encoder::encode(options, messages.iter(), &mut file).unwrap();
+            }
+            FileLayout::Chunked => {
+                let mut encoder =
+                    crate::encoder::ChunkedEncoder::with_max_block_size(options, &mut file, 2048)
+                        .unwrap();
+                for msg in messages {
+                    encoder.append(msg).unwrap();
+                }
+                encoder.finish().unwrap();
+            }
+        }
+        file
+    }
+
+    fn decode_in_chunks(file: &[u8], chunk_size: usize) -> Vec<LogMsg> {
+        let mut decoder = StreamDecoder::new();
+        let mut decoded = vec![];
+        for chunk in file.chunks(chunk_size) {
+            decoder.push_chunk(chunk);
+            while let Some(msg) = decoder.try_read().unwrap() {
+                decoded.push(msg);
+            }
+        }
+        assert!(!decoder.has_pending_bytes());
+        assert_eq!(file.len() as u64, decoder.num_bytes_received());
+        assert_eq!(decoded.len() as u64, decoder.num_messages());
+        decoded
+    }
+
+    #[test]
+    fn stream_decode_in_chunks() {
+        let messages = fake_messages(30);
+
+        for options in [
+            EncodingOptions::UNCOMPRESSED,
+            EncodingOptions::LZ4,
+            EncodingOptions::ZSTD,
+        ] {
+            for layout in [FileLayout::Stream, FileLayout::Chunked] {
+                let file = encode(options, layout, &messages);
+                for chunk_size in [1, 7, 1000, usize::MAX] {
+                    assert_eq!(
+                        messages,
+                        decode_in_chunks(&file, chunk_size),
+                        "{options:?} {layout:?}, chunks of {chunk_size}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stream_decode_is_incremental() {
+        let messages = fake_messages(30);
+
+        for options in [
+            EncodingOptions::UNCOMPRESSED,
+            EncodingOptions::LZ4,
+            EncodingOptions::ZSTD,
+        ] {
+            let file = encode(options, FileLayout::Chunked, &messages);
+
+            // Half the file should give us some, but not all, of the messages:
+            let mut decoder = StreamDecoder::new();
+            decoder.push_chunk(&file[..file.len() / 2]);
+            let mut num_decoded = 0;
+            while decoder.try_read().unwrap().is_some() {
+                num_decoded += 1;
+            }
+            assert!(
+                0 < num_decoded && num_decoded < messages.len(),
+                "{options:?}"
+            );
+            assert!(decoder.has_pending_bytes());
+        }
+    }
+}
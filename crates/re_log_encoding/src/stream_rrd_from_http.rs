@@ -2,6 +2,36 @@ use std::sync::Arc;
 
 use re_log_types::LogMsg;
 
+use crate::stream_decoder::StreamDecoder;
+
+/// How far along the download of an `.rrd` stream is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of bytes received so far.
+    pub bytes_downloaded: u64,
+
+    /// Total size of the download, if the server told us.
+    pub total_bytes: Option<u64>,
+
+    /// Number of messages decoded so far.
+    pub num_messages: u64,
+
+    /// Set once the download has ended, successfully or not.
+    pub finished: bool,
+}
+
+impl Progress {
+    /// How far along we are, in `[0, 1]`, if the total size is known.
+    pub fn fraction(&self) -> Option<f32> {
+        let total_bytes = self.total_bytes?;
+        if total_bytes == 0 {
+            Some(1.0)
+        } else {
+            Some((self.bytes_downloaded as f64 / total_bytes as f64).min(1.0) as f32)
+        }
+    }
+}
+
 pub fn stream_rrd_from_http_to_channel(url: String) -> re_smart_channel::Receiver<LogMsg> {
     let (tx, rx) = re_smart_channel::smart_channel(re_smart_channel::Source::RrdHttpStream {
         url: url.clone(),
@@ -16,26 +46,147 @@ pub fn stream_rrd_from_http_to_channel(url: String) -> re_smart_channel::Receive
 }
 
 pub fn stream_rrd_from_http(url: String, on_msg: Arc<dyn Fn(LogMsg) + Send + Sync>) {
+    stream_rrd_from_http_with_progress(url, on_msg, Arc::new(|_progress| {}));
+}
+
+/// Download an `.rrd` file, decoding the messages as the bytes arrive.
+///
+/// `on_msg` is called for each message, and `on_progress` after each received chunk of bytes,
+/// and then once more when the download has finished.
+///
+/// Returns immediately; the download happens on a background thread (native)
+/// or in a background task (web).
+pub fn stream_rrd_from_http_with_progress(
+    url: String,
+    on_msg: Arc<dyn Fn(LogMsg) + Send + Sync>,
+    on_progress: Arc<dyn Fn(Progress) + Send + Sync>,
+) {
     re_log::debug!("Downloading .rrd file from {url:?}…");
 
-    // TODO(emilk): stream the http request, progressively decoding the .rrd file.
-    ehttp::fetch(ehttp::Request::get(&url), move |result| match result {
-        Ok(response) => {
-            if response.ok {
-                re_log::debug!("Decoding .rrd file from {url:?}…");
-                decode_rrd(response.bytes, on_msg);
-            } else {
+    #[cfg(not(target_arch = "wasm32"))]
+    native_stream::stream_rrd(url, on_msg, on_progress);
+
+    #[cfg(target_arch = "wasm32")]
+    web_decode::stream_rrd(url, on_msg, on_progress);
+}
+
+/// Pass on all messages that the decoder has been able to decode so far.
+///
+/// Returns `false` if the stream is broken beyond repair, and decoding should stop.
+fn decode_available(decoder: &mut StreamDecoder, on_msg: &dyn Fn(LogMsg)) -> bool {
+    loop {
+        match decoder.try_read() {
+            Ok(Some(msg)) => {
+                on_msg(msg);
+            }
+            Ok(None) => return true,
+            Err(
+                err @ (crate::decoder::DecodeError::MsgPack(_)
+                | crate::decoder::DecodeError::Migration(_)),
+            ) => {
+                re_log::warn_once!("Failed to decode message: {err}");
+            }
+            Err(err) => {
+                re_log::error!("Failed to decode .rrd: {err}");
+                return false;
+            }
+        }
+    }
+}
+
+fn warn_if_truncated(decoder: &StreamDecoder, source: &str) {
+    if decoder.has_pending_bytes() {
+        re_log::warn!(
+            "The .rrd stream from {source} ended in the middle of a message. It may have been truncated."
+        );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native_stream {
+    use std::io::Read as _;
+    use std::sync::Arc;
+
+    use re_log_types::LogMsg;
+
+    use super::{decode_available, warn_if_truncated, Progress};
+    use crate::stream_decoder::StreamDecoder;
+
+    /// How many bytes we read from the socket at a time.
+    const READ_SIZE: usize = 64 * 1024;
+
+    pub fn stream_rrd(
+        url: String,
+        on_msg: Arc<dyn Fn(LogMsg) + Send + Sync>,
+        on_progress: Arc<dyn Fn(Progress) + Send + Sync>,
+    ) {
+        std::thread::Builder::new()
+            .name("stream_rrd_from_http".to_owned())
+            .spawn(move || {
+                let mut progress = Progress::default();
+                stream_rrd_blocking(&url, on_msg.as_ref(), on_progress.as_ref(), &mut progress);
+                progress.finished = true;
+                on_progress(progress);
+            })
+            .expect("Failed to spawn thread");
+    }
+
+    fn stream_rrd_blocking(
+        url: &str,
+        on_msg: &dyn Fn(LogMsg),
+        on_progress: &dyn Fn(Progress),
+        progress: &mut Progress,
+    ) {
+        let response = match ureq::get(url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
                 re_log::error!(
-                    "Failed to fetch .rrd file from {url}: {} {}",
-                    response.status,
-                    response.status_text
+                    "Failed to fetch .rrd file from {url}: {status} {}",
+                    response.status_text()
                 );
+                return;
+            }
+            Err(err) => {
+                re_log::error!("Failed to fetch .rrd file from {url}: {err}");
+                return;
+            }
+        };
+
+        progress.total_bytes = response
+            .header("Content-Length")
+            .and_then(|len| len.parse().ok());
+
+        re_log::debug!("Decoding .rrd file from {url:?}…");
+
+        let mut reader = response.into_reader();
+        let mut decoder = StreamDecoder::new();
+        let mut buffer = vec![0_u8; READ_SIZE];
+
+        loop {
+            let num_bytes = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(num_bytes) => num_bytes,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    re_log::error!("Failed to read .rrd file from {url}: {err}");
+                    break;
+                }
+            };
+
+            decoder.push_chunk(&buffer[..num_bytes]);
+            let ok = decode_available(&mut decoder, on_msg);
+
+            progress.bytes_downloaded = decoder.num_bytes_received();
+            progress.num_messages = decoder.num_messages();
+            on_progress(*progress);
+
+            if !ok {
+                return;
             }
         }
-        Err(err) => {
-            re_log::error!("Failed to fetch .rrd file from {url}: {err}");
-        }
-    });
+
+        warn_if_truncated(&decoder, url);
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -79,32 +230,106 @@ mod web_event_listener {
 #[cfg(target_arch = "wasm32")]
 pub use web_event_listener::stream_rrd_from_event_listener;
 
-#[cfg(not(target_arch = "wasm32"))]
-#[allow(clippy::needless_pass_by_value)] // must match wasm version
-fn decode_rrd(rrd_bytes: Vec<u8>, on_msg: Arc<dyn Fn(LogMsg) + Send>) {
-    match crate::decoder::Decoder::new(rrd_bytes.as_slice()) {
-        Ok(decoder) => {
-            for msg in decoder {
-                match msg {
-                    Ok(msg) => {
-                        on_msg(msg);
-                    }
-                    Err(err) => {
-                        re_log::warn_once!("Failed to decode message: {err}");
-                    }
-                }
+#[cfg(target_arch = "wasm32")]
+mod web_decode {
+    use std::sync::Arc;
+
+    use js_sys::Uint8Array;
+    use re_log_types::LogMsg;
+    use wasm_bindgen::{JsCast as _, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    use super::{decode_available, warn_if_truncated, Progress};
+    use crate::stream_decoder::StreamDecoder;
+
+    /// How many bytes we decode between each yield to the ui task.
+    const DECODE_CHUNK_SIZE: usize = 64 * 1024;
+
+    pub fn stream_rrd(
+        url: String,
+        on_msg: Arc<dyn Fn(LogMsg) + Send + Sync>,
+        on_progress: Arc<dyn Fn(Progress) + Send + Sync>,
+    ) {
+        wasm_bindgen_futures::spawn_local(async move {
+            let mut progress = Progress::default();
+            if let Err(err) =
+                stream_rrd_async(&url, on_msg.as_ref(), on_progress.as_ref(), &mut progress).await
+            {
+                re_log::error!(
+                    "Failed to fetch .rrd file from {url}: {}",
+                    string_from_js(&err)
+                );
             }
+            progress.finished = true;
+            on_progress(progress);
+        });
+    }
+
+    /// Fetches the file, decoding each chunk as it arrives.
+    async fn stream_rrd_async(
+        url: &str,
+        on_msg: &dyn Fn(LogMsg),
+        on_progress: &dyn Fn(Progress),
+        progress: &mut Progress,
+    ) -> Result<(), JsValue> {
+        let window = web_sys::window().expect("no global `window` exists");
+        let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url))
+            .await?
+            .dyn_into()?;
+
+        if !response.ok() {
+            re_log::error!(
+                "Failed to fetch .rrd file from {url}: {} {}",
+                response.status(),
+                response.status_text()
+            );
+            return Ok(());
         }
-        Err(err) => {
-            re_log::error!("Failed to decode .rrd: {err}");
+
+        progress.total_bytes = response
+            .headers()
+            .get("Content-Length")?
+            .and_then(|len| len.parse().ok());
+
+        let Some(body) = response.body() else {
+            re_log::error!("Fetching .rrd file from {url} returned no body");
+            return Ok(());
+        };
+        let reader: web_sys::ReadableStreamDefaultReader = body.get_reader().unchecked_into();
+
+        re_log::debug!("Decoding .rrd file from {url:?}…");
+
+        let mut decoder = StreamDecoder::new();
+
+        loop {
+            let result = JsFuture::from(reader.read()).await?;
+            let done = js_sys::Reflect::get(&result, &JsValue::from_str("done"))?;
+            if done.as_bool().unwrap_or(false) {
+                break;
+            }
+            let chunk = js_sys::Reflect::get(&result, &JsValue::from_str("value"))?;
+            let chunk = Uint8Array::new(&chunk).to_vec();
+
+            // Decode big chunks in pieces, so we don't block the ui for too long:
+            for piece in chunk.chunks(DECODE_CHUNK_SIZE) {
+                decoder.push_chunk(piece);
+                let ok = decode_available(&mut decoder, on_msg);
+
+                progress.bytes_downloaded = decoder.num_bytes_received();
+                progress.num_messages = decoder.num_messages();
+                on_progress(*progress);
+
+                if !ok {
+                    return Ok(());
+                }
+
+                yield_().await;
+            }
         }
-    }
-}
 
-#[cfg(target_arch = "wasm32")]
-mod web_decode {
-    use re_log_types::LogMsg;
-    use std::sync::Arc;
+        warn_if_truncated(&decoder, url);
+        Ok(())
+    }
 
     pub fn decode_rrd(rrd_bytes: Vec<u8>, on_msg: Arc<dyn Fn(LogMsg) + Send>) {
         wasm_bindgen_futures::spawn_local(decode_rrd_async(rrd_bytes, on_msg));
@@ -114,31 +339,23 @@ mod web_decode {
     ///
     /// This is cooperative multi-tasking.
     async fn decode_rrd_async(rrd_bytes: Vec<u8>, on_msg: Arc<dyn Fn(LogMsg) + Send>) {
-        let mut last_yield = instant::Instant::now();
-
-        match crate::decoder::Decoder::new(rrd_bytes.as_slice()) {
-            Ok(decoder) => {
-                for msg in decoder {
-                    match msg {
-                        Ok(msg) => {
-                            on_msg(msg);
-                        }
-                        Err(err) => {
-                            re_log::warn_once!("Failed to decode message: {err}");
-                        }
-                    }
+        let mut decoder = StreamDecoder::new();
 
-                    if last_yield.elapsed() > instant::Duration::from_millis(10) {
-                        // yield to the ui task
-                        yield_().await;
-                        last_yield = instant::Instant::now();
-                    }
-                }
-            }
-            Err(err) => {
-                re_log::error!("Failed to decode .rrd: {err}");
+        for piece in rrd_bytes.chunks(DECODE_CHUNK_SIZE) {
+            decoder.push_chunk(piece);
+            if !decode_available(&mut decoder, on_msg.as_ref()) {
+                return;
             }
+
+            // yield to the ui task
+            yield_().await;
         }
+
+        warn_if_truncated(&decoder, "the event listener");
+    }
+
+    fn string_from_js(value: &JsValue) -> String {
+        value.as_string().unwrap_or_else(|| format!("{value:?}"))
     }
 
     // Yield to other tasks
@@ -162,3 +379,97 @@ mod web_decode {
 
 #[cfg(target_arch = "wasm32")]
 use web_decode::decode_rrd;
+
+// ----------------------------------------------------------------------------
+
+#[cfg(all(test, feature = "encoder", not(target_arch = "wasm32")))]
+mod tests {
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::net::TcpListener;
+
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::test_util::fake_messages;
+
+    /// A stand-in http server which serves `body` once, in small pieces.
+    fn serve_once(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            // Skip the request:
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            for piece in body.chunks(1000) {
+                stream.write_all(piece).unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+        });
+
+        format!("http://{addr}/recording.rrd")
+    }
+
+    #[test]
+    fn stream_rrd_from_local_server() {
+        let messages = fake_messages(100);
+
+        let mut file = vec![];
+        crate::encoder::encode(Default::default(), messages.iter(), &mut file).unwrap();
+        let file_len = file.len() as u64;
+        let url = serve_once(file);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let progress_reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        stream_rrd_from_http_with_progress(
+            url,
+            Arc::new({
+                let received = received.clone();
+                move |msg| received.lock().push(msg)
+            }),
+            Arc::new({
+                let progress_reports = progress_reports.clone();
+                move |progress| progress_reports.lock().push(progress)
+            }),
+        );
+
+        let start = std::time::Instant::now();
+        while !progress_reports.lock().last().map_or(false, |p| p.finished) {
+            assert!(
+                start.elapsed() < std::time::Duration::from_secs(30),
+                "Timed out"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        assert_eq!(*received.lock(), messages);
+
+        let progress_reports = progress_reports.lock();
+        assert!(
+            progress_reports.len() > 2,
+            "Expected the download to be decoded in several chunks"
+        );
+        assert!(progress_reports
+            .windows(2)
+            .all(|w| w[0].bytes_downloaded <= w[1].bytes_downloaded));
+
+        let last = progress_reports.last().unwrap();
+        assert_eq!(last.bytes_downloaded, file_len);
+        assert_eq!(last.total_bytes, Some(file_len));
+        assert_eq!(last.num_messages, messages.len() as u64);
+        assert_eq!(last.fraction(), Some(1.0));
+    }
+}
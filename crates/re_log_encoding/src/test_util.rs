@@ -0,0 +1,28 @@
+//! Helpers shared by the tests of this crate.
+
+use re_log_types::{
+    datagen::{build_frame_nr, build_some_point2d},
+    DataRow, LogMsg, RecordingId, RowId,
+};
+
+/// One message per frame, alternating between two entities.
+pub fn fake_messages(num_frames: i64) -> Vec<LogMsg> {
+    let recording_id = RecordingId::random();
+    (0..num_frames)
+        .map(|frame_nr| {
+            let entity_path = if frame_nr % 2 == 0 {
+                "world/points"
+            } else {
+                "camera/image"
+            };
+            let row = DataRow::from_cells1(
+                RowId::random(),
+                entity_path,
+                [build_frame_nr(frame_nr.into())],
+                1,
+                build_some_point2d(1),
+            );
+            LogMsg::ArrowMsg(recording_id, row.into_table().to_arrow_msg().unwrap())
+        })
+        .collect()
+}
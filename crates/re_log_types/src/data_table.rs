@@ -989,7 +989,7 @@ impl DataTable {
     }
 
     /// Deserializes a sparse data column.
-    fn deserialize_data_column(
+    pub fn deserialize_data_column(
         component: ComponentName,
         column: &dyn Array,
     ) -> DataTableResult<DataCellColumn> {
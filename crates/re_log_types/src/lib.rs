@@ -46,7 +46,7 @@ pub use self::data_table::{
     DataCellColumn, DataCellOptVec, DataTable, DataTableError, DataTableResult, EntityPathVec,
     ErasedTimeVec, NumInstancesVec, RowIdVec, TableId, TimePointVec, COLUMN_ENTITY_PATH,
     COLUMN_INSERT_ID, COLUMN_NUM_INSTANCES, COLUMN_ROW_ID, COLUMN_TIMEPOINT, METADATA_KIND,
-    METADATA_KIND_CONTROL, METADATA_KIND_DATA,
+    METADATA_KIND_CONTROL, METADATA_KIND_DATA, METADATA_KIND_TIME,
 };
 pub use self::index::*;
 pub use self::path::*;
@@ -194,6 +194,9 @@ pub enum LogMsg {
     /// Log an entity using an [`ArrowMsg`].
     ArrowMsg(RecordingId, ArrowMsg),
 
+    /// Delete rows that were logged before, e.g. to retract mislabeled data.
+    DeleteRowsMsg(RecordingId, DeleteRowsMsg),
+
     /// Sent when the client shuts down the connection.
     Goodbye(RowId),
 }
@@ -202,9 +205,9 @@ impl LogMsg {
     pub fn recording_id(&self) -> Option<&RecordingId> {
         match self {
             Self::BeginRecordingMsg(msg) => Some(&msg.info.recording_id),
-            Self::EntityPathOpMsg(recording_id, _) | Self::ArrowMsg(recording_id, _) => {
-                Some(recording_id)
-            }
+            Self::EntityPathOpMsg(recording_id, _)
+            | Self::ArrowMsg(recording_id, _)
+            | Self::DeleteRowsMsg(recording_id, _) => Some(recording_id),
             Self::Goodbye(_) => None,
         }
     }
@@ -349,6 +352,36 @@ impl PathOp {
     }
 }
 
+/// Deletes rows from the store, see [`LogMsg::DeleteRowsMsg`].
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DeleteRowsMsg {
+    /// A unique id per [`DeleteRowsMsg`].
+    pub row_id: RowId,
+
+    /// Which rows to delete.
+    pub selector: RowSelector,
+}
+
+/// Which rows to delete, see [`DeleteRowsMsg`].
+///
+/// A row is always deleted from all of its timelines at once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum RowSelector {
+    /// These exact rows, timeless or not.
+    RowIds(Vec<RowId>),
+
+    /// All the rows of an [`EntityPath`], but not of its descendants, that were logged on
+    /// `timeline` within `time_range`.
+    TimeRange {
+        entity_path: EntityPath,
+        timeline: Timeline,
+        time_range: TimeRange,
+    },
+}
+
 // ---------------------------------------------------------------------------
 
 /// Profiling macro for feature "puffin"
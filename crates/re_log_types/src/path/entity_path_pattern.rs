@@ -0,0 +1,148 @@
+use crate::{EntityPath, EntityPathPart};
+
+use super::{parse_entity_path_pattern, PathParseError};
+
+/// A part of an [`EntityPathPattern`].
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum EntityPathPatternPart {
+    /// Matches this exact part.
+    Exact(EntityPathPart),
+
+    /// `*`: matches any single part.
+    AnyPart,
+
+    /// `**`: matches any number of parts, including none at all.
+    AnyDepth,
+}
+
+impl std::fmt::Display for EntityPathPatternPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(part) => part.fmt(f),
+            Self::AnyPart => f.write_str("*"),
+            Self::AnyDepth => f.write_str("**"),
+        }
+    }
+}
+
+/// A glob-like pattern that matches [`EntityPath`]s, e.g. `robots/*/lidar` or `world/**`.
+///
+/// `*` matches any single part, `**` matches any number of parts, including none at all.
+/// Use a quoted string index (`"*"`) to match a literal star.
+///
+/// See [`parse_entity_path_pattern`] for the syntax.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct EntityPathPattern {
+    parts: Vec<EntityPathPatternPart>,
+}
+
+impl EntityPathPattern {
+    #[inline]
+    pub fn new(parts: Vec<EntityPathPatternPart>) -> Self {
+        Self { parts }
+    }
+
+    #[inline]
+    pub fn parts(&self) -> &[EntityPathPatternPart] {
+        &self.parts
+    }
+
+    /// Does this pattern match a single, exact path?
+    ///
+    /// If so, returns that path.
+    pub fn as_exact(&self) -> Option<EntityPath> {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                EntityPathPatternPart::Exact(part) => Some(part.clone()),
+                EntityPathPatternPart::AnyPart | EntityPathPatternPart::AnyDepth => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(EntityPath::from)
+    }
+
+    pub fn matches(&self, entity_path: &EntityPath) -> bool {
+        matches_parts(&self.parts, entity_path.as_slice())
+    }
+}
+
+fn matches_parts(pattern: &[EntityPathPatternPart], parts: &[EntityPathPart]) -> bool {
+    match pattern.split_first() {
+        None => parts.is_empty(),
+        Some((EntityPathPatternPart::AnyDepth, rest)) => {
+            (0..=parts.len()).any(|skipped| matches_parts(rest, &parts[skipped..]))
+        }
+        Some((EntityPathPatternPart::AnyPart, rest)) => {
+            !parts.is_empty() && matches_parts(rest, &parts[1..])
+        }
+        Some((EntityPathPatternPart::Exact(expected), rest)) => {
+            parts.first() == Some(expected) && matches_parts(rest, &parts[1..])
+        }
+    }
+}
+
+impl std::str::FromStr for EntityPathPattern {
+    type Err = PathParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_entity_path_pattern(s).map(Self::new)
+    }
+}
+
+impl From<&EntityPath> for EntityPathPattern {
+    #[inline]
+    fn from(entity_path: &EntityPath) -> Self {
+        Self::new(
+            entity_path
+                .iter()
+                .cloned()
+                .map(EntityPathPatternPart::Exact)
+                .collect(),
+        )
+    }
+}
+
+impl std::fmt::Display for EntityPathPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::fmt::Write as _;
+
+        let mut iter = self.parts.iter();
+        if let Some(first_part) = iter.next() {
+            first_part.fmt(f)?;
+            for part in iter {
+                f.write_char('/')?;
+                part.fmt(f)?;
+            }
+            Ok(())
+        } else {
+            f.write_char('/') // root
+        }
+    }
+}
+
+#[test]
+fn test_entity_path_pattern_matches() {
+    let pattern = |s: &str| s.parse::<EntityPathPattern>().unwrap();
+    let path = |s: &str| EntityPath::from(s);
+
+    assert!(pattern("robots/*/lidar").matches(&path("robots/r2d2/lidar")));
+    assert!(!pattern("robots/*/lidar").matches(&path("robots/lidar")));
+    assert!(!pattern("robots/*/lidar").matches(&path("robots/r2d2/arm/lidar")));
+
+    assert!(pattern("robots/**/lidar").matches(&path("robots/lidar")));
+    assert!(pattern("robots/**/lidar").matches(&path("robots/r2d2/arm/lidar")));
+    assert!(!pattern("robots/**/lidar").matches(&path("robots/r2d2/lidar/points")));
+
+    assert!(pattern("**").matches(&EntityPath::root()));
+    assert!(pattern("**").matches(&path("world/points")));
+    assert!(pattern("world/**").matches(&path("world")));
+    assert!(!pattern("world/*").matches(&path("world")));
+
+    assert!(pattern("robots/#1/*").matches(&path("robots/#1/camera")));
+    assert!(!pattern(r#"robots/"*""#).matches(&path("robots/r2d2")));
+
+    assert_eq!(pattern("robots/r2d2").as_exact(), Some(path("robots/r2d2")));
+    assert_eq!(pattern("robots/*").as_exact(), None);
+    assert_eq!(pattern("robots/**/lidar").to_string(), "robots/**/lidar");
+}
@@ -10,13 +10,15 @@ mod component_name;
 mod component_path;
 mod entity_path;
 mod entity_path_impl;
+mod entity_path_pattern;
 mod parse_path;
 
 pub use component_name::ComponentName;
 pub use component_path::ComponentPath;
 pub use entity_path::{EntityPath, EntityPathHash};
 pub use entity_path_impl::EntityPathImpl;
-pub use parse_path::{parse_entity_path, PathParseError};
+pub use entity_path_pattern::{EntityPathPattern, EntityPathPatternPart};
+pub use parse_path::{parse_entity_path, parse_entity_path_pattern, PathParseError};
 
 use re_string_interner::InternedString;
 
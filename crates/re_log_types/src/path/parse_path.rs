@@ -1,4 +1,4 @@
-use crate::{EntityPathPart, Index};
+use crate::{EntityPathPart, EntityPathPatternPart, Index};
 
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum PathParseError {
@@ -90,6 +90,31 @@ pub fn parse_entity_path(path: &str) -> Result<Vec<EntityPathPart>, PathParseErr
     Ok(parts)
 }
 
+/// Parses an entity path pattern, e.g. `robots/*/lidar` or `world/**/points`.
+///
+/// This is the same syntax as [`parse_entity_path`], except that:
+/// * `*` matches any single part, and `**` any number of parts (see
+///   [`crate::EntityPathPattern`]),
+/// * a leading slash is allowed, and ignored.
+pub fn parse_entity_path_pattern(path: &str) -> Result<Vec<EntityPathPatternPart>, PathParseError> {
+    let path = match path.strip_prefix('/') {
+        Some("") | None => path,
+        Some(path) => path,
+    };
+
+    // Quoted stars are string indices, and thus never end up as wildcards.
+    let parts = parse_entity_path(path)?
+        .into_iter()
+        .map(|part| match part {
+            EntityPathPart::Name(name) if name.as_str() == "*" => EntityPathPatternPart::AnyPart,
+            EntityPathPart::Name(name) if name.as_str() == "**" => EntityPathPatternPart::AnyDepth,
+            part => EntityPathPatternPart::Exact(part),
+        })
+        .collect();
+
+    Ok(parts)
+}
+
 fn parse_part(s: &str) -> Result<EntityPathPart, PathParseError> {
     use std::str::FromStr as _;
 
@@ -173,3 +198,42 @@ fn test_parse_path() {
         Err(PathParseError::MissingSlash)
     );
 }
+
+#[test]
+fn test_parse_path_pattern() {
+    use crate::entity_path_vec;
+
+    let exact = |parts: Vec<EntityPathPart>| {
+        parts
+            .into_iter()
+            .map(EntityPathPatternPart::Exact)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        parse_entity_path_pattern(""),
+        Err(PathParseError::EmptyString)
+    );
+    assert_eq!(parse_entity_path_pattern("/"), Ok(vec![]));
+    assert_eq!(
+        parse_entity_path_pattern("/foo/bar"),
+        Ok(exact(entity_path_vec!("foo", "bar")))
+    );
+    assert_eq!(
+        parse_entity_path_pattern("robots/*/lidar/**"),
+        Ok(vec![
+            EntityPathPatternPart::Exact("robots".into()),
+            EntityPathPatternPart::AnyPart,
+            EntityPathPatternPart::Exact("lidar".into()),
+            EntityPathPatternPart::AnyDepth,
+        ])
+    );
+    assert_eq!(
+        parse_entity_path_pattern(r#"robots/"*""#),
+        Ok(exact(entity_path_vec!("robots", Index::String("*".into()))))
+    );
+    assert_eq!(
+        parse_entity_path_pattern("robots//*"),
+        Err(PathParseError::DoubleSlash)
+    );
+}
@@ -10,7 +10,7 @@ use re_log_types::{
     datagen::{build_frame_nr, build_some_colors, build_some_point2d, build_some_vec3d},
     entity_path, Component, DataRow, EntityPath, Index, RowId, TimeType, Timeline,
 };
-use re_query::query_entity_with_primary;
+use re_query::query_entity_joined;
 
 // ---
 
@@ -182,17 +182,11 @@ fn query_and_visit_points(store: &mut DataStore, paths: &[EntityPath]) -> Vec<Sa
 
     // TODO(jleibs): Add Radius once we have support for it in field_types
     for path in paths.iter() {
-        query_entity_with_primary::<Point2D>(store, &query, path, &[ColorRGBA::name()])
-            .and_then(|entity_view| {
-                entity_view.visit2(|_: InstanceKey, pos: Point2D, color: Option<ColorRGBA>| {
-                    points.push(SavePoint {
-                        _pos: pos,
-                        _color: color,
-                    });
-                })
-            })
-            .ok()
-            .unwrap();
+        let rows = query_entity_joined::<Point2D, (ColorRGBA,)>(store, &query, path).unwrap();
+        points.extend(rows.map(|(_, pos, (color,))| SavePoint {
+            _pos: pos,
+            _color: color,
+        }));
     }
     assert_eq!(NUM_POINTS as usize, points.len());
     points
@@ -209,14 +203,8 @@ fn query_and_visit_vecs(store: &mut DataStore, paths: &[EntityPath]) -> Vec<Save
     let mut rects = Vec::with_capacity(NUM_VECS as _);
 
     for path in paths.iter() {
-        query_entity_with_primary::<Vec3D>(store, &query, path, &[])
-            .and_then(|entity_view| {
-                entity_view.visit1(|_: InstanceKey, vec: Vec3D| {
-                    rects.push(SaveVec { _vec: vec });
-                })
-            })
-            .ok()
-            .unwrap();
+        let rows = query_entity_joined::<Vec3D, ()>(store, &query, path).unwrap();
+        rects.extend(rows.map(|(_, vec, ())| SaveVec { _vec: vec }));
     }
     assert_eq!(NUM_VECS as usize, rects.len());
     rects
@@ -56,14 +56,16 @@ fn main() {
     let ent_views = range_entity_with_primary::<Rect2D, 3>(&store, &query, &ent_path, components);
     for (time, ent_view) in ent_views {
         eprintln!(
-            "Found data at time {} from {}'s PoV:\n{}",
+            "Found data at time {} from {}'s PoV:",
             time.map_or_else(
                 || "<timeless>".into(),
                 |time| TimeType::Sequence.format(time)
             ),
             Rect2D::name(),
-            &ent_view.as_df2::<Point2D>().unwrap()
         );
+        for (instance_key, rect2d, (point2d,)) in ent_view.join::<(Point2D,)>().unwrap() {
+            eprintln!("  {instance_key:?}: {rect2d:?} {point2d:?}");
+        }
     }
 
     println!("\n-----\n");
@@ -72,13 +74,15 @@ fn main() {
     let ent_views = range_entity_with_primary::<Point2D, 3>(&store, &query, &ent_path, components);
     for (time, ent_view) in ent_views {
         eprintln!(
-            "Found data at time {} from {}'s PoV:\n{}",
+            "Found data at time {} from {}'s PoV:",
             time.map_or_else(
                 || "<timeless>".into(),
                 |time| TimeType::Sequence.format(time)
             ),
             Point2D::name(),
-            &ent_view.as_df2::<Rect2D>().unwrap()
         );
+        for (instance_key, point2d, (rect2d,)) in ent_view.join::<(Rect2D,)>().unwrap() {
+            eprintln!("  {instance_key:?}: {point2d:?} {rect2d:?}");
+        }
     }
 }
@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use re_arrow_store::{DataStore, RangeQuery, ScalarSummary, TimeInt, TimeRange};
+use re_log_types::{ComponentName, EntityPath};
+
+// ---
+
+/// The scalar values that fall within a single time bucket, see [`aggregate_scalars`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalarAggregate {
+    /// The time bucket, i.e. `[k * bucket_width, (k + 1) * bucket_width - 1]` for some `k`.
+    pub time_range: TimeRange,
+
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+
+    /// How many values were aggregated, never zero.
+    pub count: u64,
+}
+
+/// Aggregates the scalar values of `component` for the given entity into time buckets of
+/// `bucket_width`, over the time range of `query`.
+///
+/// Returns the min, max, mean and count of every non-empty bucket, in increasing time order.
+/// Buckets are aligned on multiples of `bucket_width`, so that the same data always yields the
+/// same buckets no matter the query range; the first and last buckets might thus only be
+/// partially covered by the query.
+///
+/// Picking `bucket_width` as the time range covered by a single pixel (or a handful of them)
+/// makes it possible to plot series of any length with a bounded number of points, e.g.
+/// using the min and max of each bucket as an envelope.
+///
+/// This is much cheaper than [`crate::range_entity_with_primary`] for large series: store
+/// buckets that fall entirely within a single time bucket are aggregated from their cached
+/// summaries (see [`DataStore::range_scalar_summaries`]), without ever looking at their rows.
+///
+/// Every instance counts as a value on its own; nulls and `NaN`s are ignored, as is timeless
+/// data.
+///
+/// # Panics
+///
+/// Panics if `bucket_width` is zero.
+pub fn aggregate_scalars(
+    store: &DataStore,
+    query: &RangeQuery,
+    ent_path: &EntityPath,
+    component: ComponentName,
+    bucket_width: u64,
+) -> Vec<ScalarAggregate> {
+    crate::profile_function!();
+
+    assert!(bucket_width > 0, "bucket width must be strictly positive");
+    let bucket_width = bucket_width.min(i64::MAX as u64) as i64;
+    let bucket_index = |time: TimeInt| time.as_i64().div_euclid(bucket_width);
+
+    let summaries =
+        store.range_scalar_summaries(query, ent_path, component, |time_range: TimeRange| {
+            bucket_index(time_range.min) == bucket_index(time_range.max)
+        });
+
+    let mut buckets: BTreeMap<i64, ScalarSummary> = BTreeMap::new();
+    for (time_range, summary) in summaries {
+        // NOTE: Either a single row, or a whole store bucket that fits in a single time bucket.
+        buckets
+            .entry(bucket_index(time_range.min))
+            .or_default()
+            .merge(&summary);
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(index, summary)| {
+            let mean = summary.mean()?;
+            let min = index.saturating_mul(bucket_width);
+            let max = min.saturating_add(bucket_width - 1);
+            Some(ScalarAggregate {
+                time_range: TimeRange::new(min.into(), max.into()),
+                min: summary.min,
+                max: summary.max,
+                mean,
+                count: summary.count,
+            })
+        })
+        .collect()
+}
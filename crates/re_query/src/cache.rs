@@ -0,0 +1,401 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+
+use re_arrow_store::{DataStore, LatestAtQuery, RangeQuery, TimeInt, TimeRange, Timeline};
+use re_data_store::ExtraQueryHistory;
+use re_log_types::{Component, ComponentName, EntityPath, EntityPathHash, RowId};
+
+use crate::{ComponentWithInstances, EntityView, QueryError};
+
+// ---
+
+/// Caches the results of [`crate::query_entity_with_primary`] and
+/// [`crate::range_entity_with_primary`], so that data that hasn't changed doesn't get queried
+/// over and over again, e.g. every frame.
+///
+/// Results are invalidated on a per-entity basis, whenever the data of that entity changes
+/// (see [`DataStore::entity_generation`]), be it because of an insertion or a garbage collection.
+///
+/// A cached latest-at result is reused as-is when querying the same entity at a different time,
+/// as long as the entity has no data in between the two times.
+///
+/// Range results are cached per entity and timeline, regardless of the time range: when the range
+/// moves (e.g. the sliding window of a time series plot), only the rows that entered it are
+/// queried, and those that left it are dropped.
+///
+/// ⚠ A `QueryCache` must only ever be used with a single [`DataStore`]!
+#[derive(Default)]
+pub struct QueryCache {
+    latest_at: HashMap<LatestAtKey, LatestAtEntry>,
+    range: HashMap<RangeKey, RangeEntry>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct LatestAtKey {
+    ent_path: EntityPathHash,
+    timeline: Timeline,
+    primary: ComponentName,
+    components: Vec<ComponentName>,
+}
+
+struct LatestAtEntry {
+    /// The [`DataStore::entity_generation`] this result was computed at.
+    generation: u64,
+
+    /// The most recent time this result is known to be valid at.
+    time: TimeInt,
+
+    /// `None` if the primary component couldn't be found.
+    view: Option<CachedEntityView>,
+
+    /// Was this entry used since the last [`QueryCache::begin_frame`]?
+    used: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RangeKey {
+    ent_path: EntityPathHash,
+    timeline: Timeline,
+    primary: ComponentName,
+    components: Vec<ComponentName>,
+}
+
+type CachedRow = (Option<TimeInt>, CachedEntityView);
+
+struct RangeEntry {
+    /// The [`DataStore::entity_generation`] these results were computed at.
+    generation: u64,
+
+    /// The time range covered by `head` and `rows`.
+    range: TimeRange,
+
+    /// The latest-at state at the start of `range`, which
+    /// [`crate::range_entity_with_primary`] yields first.
+    head: Option<CachedRow>,
+
+    /// The views of all the rows within `range`, sorted by time.
+    ///
+    /// Shared with the iterators returned by [`QueryCache::range_entity_with_primary`].
+    rows: Arc<VecDeque<CachedRow>>,
+
+    /// Was this entry used since the last [`QueryCache::begin_frame`]?
+    used: bool,
+}
+
+impl RangeEntry {
+    /// Moves the cached rows over to `range`: drops the rows that left it, and queries the ones
+    /// that entered it using `rows_in`.
+    ///
+    /// This works because the views of a streaming-join only depend on the latest-at state of
+    /// each component at their time, no matter where the range started.
+    ///
+    /// Returns `false`, leaving the entry untouched, if the two ranges don't overlap.
+    fn slide_to(
+        &mut self,
+        range: TimeRange,
+        rows_in: impl Fn(TimeRange) -> Vec<CachedRow>,
+    ) -> bool {
+        let cached = self.range;
+        if range == cached {
+            return true;
+        }
+        if range.max < cached.min || cached.max < range.min {
+            return false;
+        }
+
+        let rows = Arc::make_mut(&mut self.rows);
+
+        if cached.min < range.min {
+            while rows
+                .front()
+                .map_or(false, |(time, _)| *time < Some(range.min))
+            {
+                rows.pop_front();
+            }
+        } else if range.min < cached.min {
+            let before = TimeRange::new(range.min, (cached.min.as_i64() - 1).into());
+            for row in rows_in(before).into_iter().rev() {
+                rows.push_front(row);
+            }
+        }
+
+        if range.max < cached.max {
+            while rows
+                .back()
+                .map_or(false, |(time, _)| *time > Some(range.max))
+            {
+                rows.pop_back();
+            }
+        } else if cached.max < range.max {
+            let after = TimeRange::new((cached.max.as_i64() + 1).into(), range.max);
+            rows.extend(rows_in(after));
+        }
+
+        self.range = range;
+        true
+    }
+}
+
+/// An [`EntityView`] without its `Primary` type.
+#[derive(Clone)]
+struct CachedEntityView {
+    row_id: RowId,
+    primary: ComponentWithInstances,
+    components: BTreeMap<ComponentName, ComponentWithInstances>,
+}
+
+impl CachedEntityView {
+    fn from_view<Primary: Component>(view: &EntityView<Primary>) -> Self {
+        Self {
+            row_id: view.row_id,
+            primary: view.primary.clone(),       /* shallow */
+            components: view.components.clone(), /* shallow */
+        }
+    }
+
+    fn to_view<Primary: Component>(&self) -> EntityView<Primary> {
+        EntityView {
+            row_id: self.row_id,
+            primary: self.primary.clone(),       /* shallow */
+            components: self.components.clone(), /* shallow */
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl QueryCache {
+    /// Call once per frame: forgets all the results that weren't used since the last call.
+    pub fn begin_frame(&mut self) {
+        crate::profile_function!();
+
+        self.latest_at
+            .retain(|_, entry| std::mem::take(&mut entry.used));
+        self.range
+            .retain(|_, entry| std::mem::take(&mut entry.used));
+    }
+
+    /// Forgets all cached results.
+    pub fn clear(&mut self) {
+        self.latest_at.clear();
+        self.range.clear();
+    }
+
+    /// The number of cached query results.
+    pub fn len(&self) -> usize {
+        self.latest_at.len() + self.range.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cached version of [`crate::query_entity_with_primary`].
+    pub fn query_entity_with_primary<Primary: Component>(
+        &mut self,
+        store: &DataStore,
+        query: &LatestAtQuery,
+        ent_path: &EntityPath,
+        components: &[ComponentName],
+    ) -> crate::Result<EntityView<Primary>> {
+        crate::profile_function!();
+
+        let key = LatestAtKey {
+            ent_path: ent_path.hash(),
+            timeline: query.timeline,
+            primary: Primary::name(),
+            components: components.to_vec(),
+        };
+        let generation = store.entity_generation(&key.ent_path);
+
+        if let Some(entry) = self.latest_at.get_mut(&key) {
+            // The result still holds if no data was logged in between the two query times.
+            let is_valid = entry.generation == generation
+                && (entry.time == query.at || {
+                    let (min, max) = if entry.time < query.at {
+                        (entry.time, query.at)
+                    } else {
+                        (query.at, entry.time)
+                    };
+                    let range = TimeRange::new(min.as_i64().saturating_add(1).into(), max);
+                    !store.has_data_in_range(query.timeline, ent_path, range)
+                });
+
+            if is_valid {
+                entry.time = query.at;
+                entry.used = true;
+                return entry
+                    .view
+                    .as_ref()
+                    .map(CachedEntityView::to_view::<Primary>)
+                    .ok_or(QueryError::PrimaryNotFound);
+            }
+        }
+
+        let result =
+            crate::query_entity_with_primary::<Primary>(store, query, ent_path, components);
+
+        let view = match &result {
+            Ok(view) => Some(CachedEntityView::from_view(view)),
+            Err(QueryError::PrimaryNotFound) => None,
+            Err(_) => return result, // don't cache actual errors
+        };
+        self.latest_at.insert(
+            key,
+            LatestAtEntry {
+                generation,
+                time: query.at,
+                view,
+                used: true,
+            },
+        );
+
+        result
+    }
+
+    /// Cached version of [`crate::range_entity_with_primary`].
+    ///
+    /// The returned iterator shares the cached results rather than copying them, so it doesn't
+    /// borrow the cache.
+    pub fn range_entity_with_primary<Primary: Component, const N: usize>(
+        &mut self,
+        store: &DataStore,
+        query: &RangeQuery,
+        ent_path: &EntityPath,
+        components: [ComponentName; N],
+    ) -> impl Iterator<Item = (Option<TimeInt>, EntityView<Primary>)> {
+        crate::profile_function!();
+
+        let key = RangeKey {
+            ent_path: ent_path.hash(),
+            timeline: query.timeline,
+            primary: Primary::name(),
+            components: components.to_vec(),
+        };
+        let generation = store.entity_generation(&key.ent_path);
+
+        let rows_in = |range: TimeRange| {
+            range_rows::<Primary, N>(store, query.timeline, range, ent_path, components)
+        };
+        let head_at = |min: TimeInt| {
+            range_head::<Primary, N>(store, query.timeline, min, ent_path, components)
+        };
+
+        let entry = match self.range.entry(key) {
+            Entry::Occupied(entry) if entry.get().generation == generation => {
+                let entry = entry.into_mut();
+                let previous_min = entry.range.min;
+                if entry.slide_to(query.range, &rows_in) {
+                    if entry.range.min != previous_min {
+                        entry.head = head_at(entry.range.min);
+                    }
+                } else {
+                    entry.head = head_at(query.range.min);
+                    entry.rows = Arc::new(rows_in(query.range).into());
+                    entry.range = query.range;
+                }
+                entry
+            }
+            entry => {
+                let new_entry = RangeEntry {
+                    generation,
+                    range: query.range,
+                    head: head_at(query.range.min),
+                    rows: Arc::new(rows_in(query.range).into()),
+                    used: false,
+                };
+                match entry {
+                    Entry::Occupied(mut entry) => {
+                        entry.insert(new_entry);
+                        entry.into_mut()
+                    }
+                    Entry::Vacant(entry) => entry.insert(new_entry),
+                }
+            }
+        };
+        entry.used = true;
+
+        let head = entry.head.clone();
+        let rows = entry.rows.clone();
+        let num_rows = rows.len();
+
+        head.into_iter()
+            .map(|(time, view)| (time, view.to_view::<Primary>()))
+            .chain((0..num_rows).map(move |i| {
+                let (time, view) = &rows[i];
+                (*time, view.to_view::<Primary>())
+            }))
+    }
+
+    /// Cached version of [`crate::query_primary_with_history`].
+    pub fn query_primary_with_history<Primary: Component, const N: usize>(
+        &mut self,
+        store: &DataStore,
+        timeline: &Timeline,
+        time: &TimeInt,
+        history: &ExtraQueryHistory,
+        ent_path: &EntityPath,
+        components: [ComponentName; N],
+    ) -> crate::Result<impl Iterator<Item = EntityView<Primary>>> {
+        let visible_history = match timeline.typ() {
+            re_log_types::TimeType::Time => history.nanos,
+            re_log_types::TimeType::Sequence => history.sequences,
+        };
+
+        if visible_history == 0 {
+            let latest_query = LatestAtQuery::new(*timeline, *time);
+            let latest = self.query_entity_with_primary::<Primary>(
+                store,
+                &latest_query,
+                ent_path,
+                &components,
+            )?;
+
+            Ok(itertools::Either::Left(std::iter::once(latest)))
+        } else {
+            let min_time = *time - TimeInt::from(visible_history);
+            let range_query = RangeQuery::new(*timeline, TimeRange::new(min_time, *time));
+
+            let range = self.range_entity_with_primary::<Primary, N>(
+                store,
+                &range_query,
+                ent_path,
+                components,
+            );
+
+            Ok(itertools::Either::Right(range.map(|(_, entity)| entity)))
+        }
+    }
+}
+
+/// The views that [`crate::range_entity_with_primary`] yields for the rows within `range`, i.e.
+/// all but the initial latest-at one.
+fn range_rows<Primary: Component, const N: usize>(
+    store: &DataStore,
+    timeline: Timeline,
+    range: TimeRange,
+    ent_path: &EntityPath,
+    components: [ComponentName; N],
+) -> Vec<CachedRow> {
+    let query = RangeQuery::new(timeline, range);
+    crate::range_entity_with_primary::<Primary, N>(store, &query, ent_path, components)
+        .filter(|(time, _)| time.map_or(true, |time| range.min <= time))
+        .map(|(time, view)| (time, CachedEntityView::from_view(&view)))
+        .collect()
+}
+
+/// The initial latest-at view that [`crate::range_entity_with_primary`] yields for a range
+/// starting at `min`, if any.
+fn range_head<Primary: Component, const N: usize>(
+    store: &DataStore,
+    timeline: Timeline,
+    min: TimeInt,
+    ent_path: &EntityPath,
+    components: [ComponentName; N],
+) -> Option<CachedRow> {
+    let latest_time = TimeInt::from(min.as_i64().checked_sub(1)?);
+    let query = RangeQuery::new(timeline, TimeRange::new(min, min));
+    crate::range_entity_with_primary::<Primary, N>(store, &query, ent_path, components)
+        .next()
+        .filter(|(time, _)| *time == Some(latest_time))
+        .map(|(time, view)| (time, CachedEntityView::from_view(&view)))
+}
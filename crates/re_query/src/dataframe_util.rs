@@ -1,3 +1,7 @@
+//! Helpers to convert query results to and from polars dataframes.
+//!
+//! To join components without going through polars, see [`crate::join`] instead.
+
 use arrow2::{
     array::{Array, StructArray},
     datatypes::PhysicalType,
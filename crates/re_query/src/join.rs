@@ -0,0 +1,208 @@
+//! Join any number of components onto a primary component, without going through dataframes.
+//!
+//! The components to join are given as a tuple of component types, see [`ComponentJoin`].
+//! Every joined row is made of an [`InstanceKey`], the value of the primary component for that
+//! instance, and a tuple with the (optional) values of the joined components for that instance.
+//!
+//! The join follows the exact same semantics as [`EntityView::iter_component`]: components are
+//! aligned on the instance keys of the primary, splats are repeated for every instance, and rows
+//! where the primary itself is null are skipped.
+//!
+//! # Usage
+//! ```
+//! # use re_arrow_store::LatestAtQuery;
+//! # use re_log_types::{Timeline, component_types::{ColorRGBA, Point2D}};
+//! # let store = re_query::__populate_example_store();
+//!
+//! let ent_path = "point".into();
+//! let query = LatestAtQuery::new(Timeline::new_sequence("frame_nr"), 123.into());
+//!
+//! let rows = re_query::query_entity_joined::<Point2D, (ColorRGBA,)>(&store, &query, &ent_path)
+//!     .unwrap();
+//!
+//! for (instance_key, point, (color,)) in rows {
+//!     println!("{instance_key:?}: {point:?} {color:?}");
+//! }
+//! ```
+
+use re_arrow_store::{DataStore, LatestAtQuery, RangeQuery, TimeInt};
+use re_log_types::{
+    component_types::InstanceKey, Component, ComponentName, DeserializableComponent, EntityPath,
+    SerializableComponent,
+};
+
+use crate::{query_entity_with_primary, range_entity_with_primary, EntityView};
+
+/// A tuple of components to join onto a primary component, e.g. `(ColorRGBA, Radius)`.
+///
+/// Implemented for tuples of 1 to 6 components, and for the empty tuple, which only yields the
+/// instance keys and primary values.
+pub trait ComponentJoin {
+    /// The joined values for a single instance, e.g. `(Option<ColorRGBA>, Option<Radius>)`.
+    type Values;
+
+    /// Iterates over [`Self::Values`], one per primary instance.
+    type Iter: Iterator<Item = Self::Values>;
+
+    /// The names of the joined components, in order.
+    fn names() -> Vec<ComponentName>;
+
+    /// Joins the components onto the primary component of `view`.
+    ///
+    /// Yields exactly one item per primary instance, including null ones.
+    fn iter_joined<Primary>(view: &EntityView<Primary>) -> crate::Result<Self::Iter>
+    where
+        Primary: SerializableComponent + DeserializableComponent,
+        for<'a> &'a Primary::ArrayType: IntoIterator;
+
+    /// Runs [`range_entity_with_primary`] for the primary component, the cluster key, and all
+    /// the joined components.
+    fn range<'a, Primary: Component + 'a>(
+        store: &'a DataStore,
+        query: &RangeQuery,
+        ent_path: &'a EntityPath,
+    ) -> Box<dyn Iterator<Item = (Option<TimeInt>, EntityView<Primary>)> + 'a>;
+}
+
+/// A single joined row: an instance key, the primary value, and the joined values.
+pub type JoinedRow<Primary, Cs> = (InstanceKey, Primary, <Cs as ComponentJoin>::Values);
+
+fn range_boxed<'a, Primary: Component + 'a, const N: usize>(
+    store: &'a DataStore,
+    query: &RangeQuery,
+    ent_path: &'a EntityPath,
+    components: [ComponentName; N],
+) -> Box<dyn Iterator<Item = (Option<TimeInt>, EntityView<Primary>)> + 'a> {
+    Box::new(range_entity_with_primary::<Primary, N>(
+        store, query, ent_path, components,
+    ))
+}
+
+macro_rules! impl_component_join {
+    // $CC: List of names of the component types, e.g., C1, C2
+    ($($CC:ident),+) => {
+        impl<$($CC,)+> ComponentJoin for ($($CC,)+)
+        where $(
+            $CC: DeserializableComponent + Clone,
+            for<'a> &'a $CC::ArrayType: IntoIterator,
+        )+
+        {
+            type Values = ($(Option<$CC>,)+);
+            type Iter = itertools::Zip<($(std::vec::IntoIter<Option<$CC>>,)+)>;
+
+            fn names() -> Vec<ComponentName> {
+                vec![$($CC::name(),)+]
+            }
+
+            fn iter_joined<Primary>(view: &EntityView<Primary>) -> crate::Result<Self::Iter>
+            where
+                Primary: SerializableComponent + DeserializableComponent,
+                for<'a> &'a Primary::ArrayType: IntoIterator,
+            {
+                Ok(itertools::multizip(($(
+                    view.iter_component::<$CC>()?.collect::<Vec<_>>().into_iter(),
+                )+)))
+            }
+
+            fn range<'a, Primary: Component + 'a>(
+                store: &'a DataStore,
+                query: &RangeQuery,
+                ent_path: &'a EntityPath,
+            ) -> Box<dyn Iterator<Item = (Option<TimeInt>, EntityView<Primary>)> + 'a> {
+                let components = [store.cluster_key(), Primary::name(), $($CC::name(),)+];
+                range_boxed(store, query, ent_path, components)
+            }
+        }
+    };
+}
+
+impl ComponentJoin for () {
+    type Values = ();
+    type Iter = std::iter::Take<std::iter::Repeat<()>>;
+
+    fn names() -> Vec<ComponentName> {
+        Vec::new()
+    }
+
+    fn iter_joined<Primary>(view: &EntityView<Primary>) -> crate::Result<Self::Iter>
+    where
+        Primary: SerializableComponent + DeserializableComponent,
+        for<'a> &'a Primary::ArrayType: IntoIterator,
+    {
+        Ok(std::iter::repeat(()).take(view.num_instances()))
+    }
+
+    fn range<'a, Primary: Component + 'a>(
+        store: &'a DataStore,
+        query: &RangeQuery,
+        ent_path: &'a EntityPath,
+    ) -> Box<dyn Iterator<Item = (Option<TimeInt>, EntityView<Primary>)> + 'a> {
+        let components = [store.cluster_key(), Primary::name()];
+        range_boxed(store, query, ent_path, components)
+    }
+}
+
+impl_component_join!(C1);
+impl_component_join!(C1, C2);
+impl_component_join!(C1, C2, C3);
+impl_component_join!(C1, C2, C3, C4);
+impl_component_join!(C1, C2, C3, C4, C5);
+impl_component_join!(C1, C2, C3, C4, C5, C6);
+
+impl<Primary: SerializableComponent + DeserializableComponent> EntityView<Primary>
+where
+    for<'a> &'a Primary::ArrayType: IntoIterator,
+{
+    /// Joins the components `Cs` onto the primary component. See [`crate::join`].
+    pub fn join<Cs: ComponentJoin>(
+        &self,
+    ) -> crate::Result<impl Iterator<Item = JoinedRow<Primary, Cs>>> {
+        crate::profile_function!();
+
+        let instance_keys: Vec<_> = self.iter_instance_keys()?.collect();
+        let primaries: Vec<_> = self.iter_primary()?.collect();
+        let values = Cs::iter_joined(self)?;
+
+        Ok(itertools::izip!(instance_keys, primaries, values)
+            .filter_map(|(instance_key, primary, values)| Some((instance_key, primary?, values))))
+    }
+}
+
+/// Joins the components `Cs` onto the `Primary` component, as of the given latest-at `query`.
+///
+/// See [`crate::join`] and [`query_entity_with_primary`].
+pub fn query_entity_joined<Primary, Cs>(
+    store: &DataStore,
+    query: &LatestAtQuery,
+    ent_path: &EntityPath,
+) -> crate::Result<impl Iterator<Item = JoinedRow<Primary, Cs>>>
+where
+    Primary: SerializableComponent + DeserializableComponent,
+    for<'a> &'a Primary::ArrayType: IntoIterator,
+    Cs: ComponentJoin,
+{
+    query_entity_with_primary::<Primary>(store, query, ent_path, &Cs::names())?.join::<Cs>()
+}
+
+/// Joins the components `Cs` onto the `Primary` component, for every entity-view yielded by the
+/// given range `query`.
+///
+/// See [`crate::join`] and [`range_entity_with_primary`] for the (subtle!) range semantics.
+pub fn range_entity_joined<'a, Primary, Cs>(
+    store: &'a DataStore,
+    query: &RangeQuery,
+    ent_path: &'a EntityPath,
+) -> impl Iterator<
+    Item = crate::Result<(
+        Option<TimeInt>,
+        impl Iterator<Item = JoinedRow<Primary, Cs>>,
+    )>,
+> + 'a
+where
+    Primary: SerializableComponent + DeserializableComponent + 'a,
+    for<'b> &'b Primary::ArrayType: IntoIterator,
+    Cs: ComponentJoin + 'a,
+{
+    Cs::range::<Primary>(store, query, ent_path)
+        .map(|(time, view)| view.join::<Cs>().map(|rows| (time, rows)))
+}
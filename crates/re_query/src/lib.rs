@@ -6,7 +6,10 @@
 
 // TODO(jleibs) better crate documentation.
 
+mod aggregate;
+mod cache;
 mod entity_view;
+pub mod join;
 mod query;
 mod range;
 mod util;
@@ -15,7 +18,10 @@ pub mod visit;
 #[cfg(feature = "polars")]
 pub mod dataframe_util;
 
+pub use self::aggregate::{aggregate_scalars, ScalarAggregate};
+pub use self::cache::QueryCache;
 pub use self::entity_view::{ComponentWithInstances, EntityView};
+pub use self::join::{query_entity_joined, range_entity_joined, ComponentJoin, JoinedRow};
 pub use self::query::{get_component_with_instances, query_entity_with_primary};
 pub use self::range::range_entity_with_primary;
 pub use self::util::query_primary_with_history;
@@ -4,6 +4,8 @@
 //! the first argument, the primary [`Component`] for the second argument,
 //! and then any additional components as `Option`s.
 //!
+//! See [`crate::join`] for an iterator-based alternative.
+//!
 //! # Usage
 //! ```
 //! # use re_query::EntityView;
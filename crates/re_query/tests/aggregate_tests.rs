@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use re_arrow_store::{
+    DataStore, DataStoreConfig, GarbageCollectionOptions, RangeQuery, TimeInt, TimeRange,
+};
+use re_log_types::{
+    component_types::{InstanceKey, Point2D, Scalar},
+    datagen::build_frame_nr,
+    Component, DataRow, EntityPath, RowId, Timeline,
+};
+use re_query::{aggregate_scalars, ScalarAggregate};
+
+fn insert_scalars(store: &mut DataStore, ent_path: &EntityPath, frame_nr: i64, values: &[f64]) {
+    let scalars: Vec<_> = values.iter().copied().map(Scalar).collect();
+    let row = DataRow::from_cells1_sized(
+        RowId::random(),
+        ent_path.clone(),
+        [build_frame_nr(frame_nr.into())],
+        scalars.len() as _,
+        scalars,
+    );
+    store.insert_row(&row).unwrap();
+}
+
+/// Aggregates the given values the naive way.
+fn expected_aggregates(
+    values: &[(i64, f64)],
+    time_range: TimeRange,
+    bucket_width: i64,
+) -> Vec<ScalarAggregate> {
+    let mut buckets: BTreeMap<i64, Vec<f64>> = BTreeMap::new();
+    for (time, value) in values {
+        if time_range.contains((*time).into()) {
+            buckets
+                .entry(time.div_euclid(bucket_width))
+                .or_default()
+                .push(*value);
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(index, values)| {
+            let sum: f64 = values.iter().sum();
+            ScalarAggregate {
+                time_range: TimeRange::new(
+                    (index * bucket_width).into(),
+                    (index * bucket_width + bucket_width - 1).into(),
+                ),
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                mean: sum / values.len() as f64,
+                count: values.len() as _,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn aggregate_simple() {
+    let mut store = DataStore::new(
+        InstanceKey::name(),
+        DataStoreConfig {
+            indexed_bucket_num_rows: 4,
+            ..Default::default()
+        },
+    );
+    let ent_path: EntityPath = "scalar".into();
+
+    let mut values = Vec::new();
+    // Out of order on purpose, with two instances on every tenth frame.
+    for frame_nr in (0..100).rev() {
+        let value = ((frame_nr * 7) % 13) as f64;
+        if frame_nr % 10 == 0 {
+            insert_scalars(&mut store, &ent_path, frame_nr, &[value, -value]);
+            values.push((frame_nr, -value));
+        } else {
+            insert_scalars(&mut store, &ent_path, frame_nr, &[value]);
+        }
+        values.push((frame_nr, value));
+    }
+
+    let timeline = Timeline::new_sequence("frame_nr");
+    for time_range in [
+        TimeRange::new(TimeInt::MIN, TimeInt::MAX),
+        TimeRange::new(15.into(), 54.into()),
+        TimeRange::new(42.into(), 42.into()),
+        TimeRange::new(200.into(), 300.into()),
+    ] {
+        let query = RangeQuery::new(timeline, time_range);
+        for bucket_width in [1, 3, 10, 64, 1000] {
+            let aggregates =
+                aggregate_scalars(&store, &query, &ent_path, Scalar::name(), bucket_width);
+            let expected = expected_aggregates(&values, time_range, bucket_width as _);
+            assert_eq!(expected, aggregates, "{time_range:?} / {bucket_width}");
+        }
+    }
+
+    // Components that aren't scalars don't yield anything.
+    let query = RangeQuery::new(timeline, TimeRange::new(TimeInt::MIN, TimeInt::MAX));
+    assert!(aggregate_scalars(&store, &query, &ent_path, Point2D::name(), 10).is_empty());
+}
+
+#[test]
+fn aggregate_after_changes() {
+    let mut store = DataStore::new(
+        InstanceKey::name(),
+        DataStoreConfig {
+            indexed_bucket_num_rows: 4,
+            ..Default::default()
+        },
+    );
+    let ent_path: EntityPath = "scalar".into();
+    let timeline = Timeline::new_sequence("frame_nr");
+    let query = RangeQuery::new(timeline, TimeRange::new(TimeInt::MIN, TimeInt::MAX));
+
+    let mut values = Vec::new();
+    for frame_nr in 0..20 {
+        insert_scalars(&mut store, &ent_path, frame_nr, &[frame_nr as f64]);
+        values.push((frame_nr, frame_nr as f64));
+    }
+    let aggregates = aggregate_scalars(&store, &query, &ent_path, Scalar::name(), 100);
+    assert_eq!(expected_aggregates(&values, query.range, 100), aggregates);
+
+    // New data must invalidate the cached summaries.
+    insert_scalars(&mut store, &ent_path, 5, &[1000.0]);
+    values.push((5, 1000.0));
+    let aggregates = aggregate_scalars(&store, &query, &ent_path, Scalar::name(), 100);
+    assert_eq!(expected_aggregates(&values, query.range, 100), aggregates);
+    assert_eq!(aggregates[0].max, 1000.0);
+
+    // And so must garbage collection.
+    store.gc(GarbageCollectionOptions::gc_everything());
+    let aggregates = aggregate_scalars(&store, &query, &ent_path, Scalar::name(), 100);
+    assert!(aggregates.is_empty());
+}
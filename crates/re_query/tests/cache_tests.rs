@@ -0,0 +1,194 @@
+use re_arrow_store::{DataStore, GarbageCollectionOptions, LatestAtQuery, RangeQuery, TimeRange};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Point2D},
+    datagen::build_frame_nr,
+    Component, DataRow, EntityPath, RowId,
+};
+use re_query::{QueryCache, QueryError};
+
+fn insert_points(store: &mut DataStore, ent_path: &EntityPath, frame_nr: i64) -> RowId {
+    let row_id = RowId::random();
+    let points = vec![Point2D { x: 1.0, y: 2.0 }, Point2D { x: 3.0, y: 4.0 }];
+    let row = DataRow::from_cells1_sized(
+        row_id,
+        ent_path.clone(),
+        [build_frame_nr(frame_nr.into())],
+        2,
+        points,
+    );
+    store.insert_row(&row).unwrap();
+    row_id
+}
+
+#[test]
+fn latest_at_cache() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+    let mut cache = QueryCache::default();
+
+    let ent_path = EntityPath::from("point");
+    let other = EntityPath::from("other");
+    let timeline = build_frame_nr(0.into()).0;
+
+    let row_id1 = insert_points(&mut store, &ent_path, 10);
+    insert_points(&mut store, &other, 10);
+
+    let mut query_at = |store: &DataStore, frame_nr: i64| {
+        let query = LatestAtQuery::new(timeline, frame_nr.into());
+        let cached = cache
+            .query_entity_with_primary::<Point2D>(store, &query, &ent_path, &[ColorRGBA::name()])
+            .map(|view| view.row_id());
+        let uncached = re_query::query_entity_with_primary::<Point2D>(
+            store,
+            &query,
+            &ent_path,
+            &[ColorRGBA::name()],
+        )
+        .map(|view| view.row_id());
+        assert_eq!(cached.as_ref().ok(), uncached.as_ref().ok());
+        cached
+    };
+
+    assert!(matches!(
+        query_at(&store, 5),
+        Err(QueryError::PrimaryNotFound)
+    ));
+    assert_eq!(query_at(&store, 10).unwrap(), row_id1);
+    assert_eq!(query_at(&store, 20).unwrap(), row_id1);
+
+    // Writing to another entity doesn't invalidate anything:
+    insert_points(&mut store, &other, 15);
+    assert_eq!(query_at(&store, 30).unwrap(), row_id1);
+
+    // New data in between the cached time and the queried time:
+    let row_id2 = insert_points(&mut store, &ent_path, 40);
+    assert_eq!(query_at(&store, 50).unwrap(), row_id2);
+    assert_eq!(query_at(&store, 35).unwrap(), row_id1);
+    assert!(matches!(
+        query_at(&store, 0),
+        Err(QueryError::PrimaryNotFound)
+    ));
+
+    // Garbage collection invalidates the results too:
+    store.gc(GarbageCollectionOptions::gc_everything());
+    assert!(matches!(
+        query_at(&store, 50),
+        Err(QueryError::PrimaryNotFound)
+    ));
+}
+
+#[test]
+fn range_cache() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+    let mut cache = QueryCache::default();
+
+    let ent_path = EntityPath::from("point");
+    let timeline = build_frame_nr(0.into()).0;
+    let components = [InstanceKey::name(), Point2D::name(), ColorRGBA::name()];
+    let query = RangeQuery::new(timeline, TimeRange::new(0.into(), 100.into()));
+
+    let mut range = |store: &DataStore| {
+        cache
+            .range_entity_with_primary::<Point2D, 3>(store, &query, &ent_path, components)
+            .map(|(time, view)| (time, view.row_id()))
+            .collect::<Vec<_>>()
+    };
+
+    let row_id1 = insert_points(&mut store, &ent_path, 10);
+    assert_eq!(range(&store), vec![(Some(10.into()), row_id1)]);
+    assert_eq!(range(&store), vec![(Some(10.into()), row_id1)]);
+
+    let row_id2 = insert_points(&mut store, &ent_path, 20);
+    assert_eq!(
+        range(&store),
+        vec![(Some(10.into()), row_id1), (Some(20.into()), row_id2)]
+    );
+}
+
+#[test]
+fn range_cache_sliding_window() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+    let mut cache = QueryCache::default();
+
+    let ent_path = EntityPath::from("point");
+    let timeline = build_frame_nr(0.into()).0;
+    let components = [InstanceKey::name(), Point2D::name(), ColorRGBA::name()];
+
+    for frame_nr in (0..100).step_by(10) {
+        insert_points(&mut store, &ent_path, frame_nr);
+    }
+    // A secondary component that only shows up in the latest-at state of some windows:
+    let colors = vec![ColorRGBA(0xff000000), ColorRGBA(0x00ff0000)];
+    let row = DataRow::from_cells1_sized(
+        RowId::random(),
+        ent_path.clone(),
+        [build_frame_nr(45.into())],
+        2,
+        colors,
+    );
+    store.insert_row(&row).unwrap();
+
+    let mut check = |min: i64, max: i64| {
+        let query = RangeQuery::new(timeline, TimeRange::new(min.into(), max.into()));
+        let summarize = |(time, view): (_, re_query::EntityView<Point2D>)| {
+            let colors = view
+                .iter_component::<ColorRGBA>()
+                .unwrap()
+                .collect::<Vec<_>>();
+            (time, view.row_id(), colors)
+        };
+        let cached = cache
+            .range_entity_with_primary::<Point2D, 3>(&store, &query, &ent_path, components)
+            .map(summarize)
+            .collect::<Vec<_>>();
+        let uncached = re_query::range_entity_with_primary::<Point2D, 3>(
+            &store, &query, &ent_path, components,
+        )
+        .map(summarize)
+        .collect::<Vec<_>>();
+        assert_eq!(uncached, cached, "{min}..={max}");
+    };
+
+    // Sliding forwards, backwards, growing, shrinking, and jumping away:
+    for (min, max) in [
+        (0, 30),
+        (5, 35),
+        (15, 50),
+        (46, 60),
+        (40, 55),
+        (0, 99),
+        (50, 60),
+        (52, 58),
+        (200, 300),
+        (i64::MIN, 20),
+        (10, 20),
+    ] {
+        check(min, max);
+    }
+
+    // A single entry, no matter the range:
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn begin_frame_forgets_unused_results() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+    let mut cache = QueryCache::default();
+
+    let ent_path = EntityPath::from("point");
+    let timeline = build_frame_nr(0.into()).0;
+    insert_points(&mut store, &ent_path, 10);
+
+    let query = LatestAtQuery::new(timeline, 10.into());
+    cache
+        .query_entity_with_primary::<Point2D>(&store, &query, &ent_path, &[])
+        .unwrap();
+    assert_eq!(cache.len(), 1);
+
+    // Used during the last frame:
+    cache.begin_frame();
+    assert_eq!(cache.len(), 1);
+
+    // Not used anymore:
+    cache.begin_frame();
+    assert!(cache.is_empty());
+}
@@ -0,0 +1,180 @@
+use itertools::Itertools as _;
+use re_arrow_store::{DataStore, LatestAtQuery, RangeQuery, TimeInt, TimeRange};
+use re_log_types::{
+    component_types::{ColorRGBA, InstanceKey, Label, Point2D, Radius},
+    datagen::build_frame_nr,
+    Component as _, DataRow, EntityPath, RowId,
+};
+use re_query::{query_entity_joined, range_entity_joined};
+
+fn points() -> Vec<Point2D> {
+    vec![
+        Point2D { x: 1.0, y: 2.0 },
+        Point2D { x: 3.0, y: 4.0 },
+        Point2D { x: 5.0, y: 6.0 },
+    ]
+}
+
+#[test]
+fn latest_at_join() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    let ent_path: EntityPath = "point".into();
+    let timepoint = [build_frame_nr(123.into())];
+
+    // Points with implicit instances..
+    let row = DataRow::from_cells1_sized(RowId::random(), ent_path.clone(), timepoint, 3, points());
+    store.insert_row(&row).unwrap();
+
+    // ..a color for the last one only..
+    let row = DataRow::from_cells2_sized(
+        RowId::random(),
+        ent_path.clone(),
+        timepoint,
+        1,
+        (vec![InstanceKey(2)], vec![ColorRGBA(0xff000000)]),
+    );
+    store.insert_row(&row).unwrap();
+
+    // ..and a splatted label.
+    let row = DataRow::from_cells2_sized(
+        RowId::random(),
+        ent_path.clone(),
+        timepoint,
+        1,
+        (vec![InstanceKey::SPLAT], vec![Label("hey".into())]),
+    );
+    store.insert_row(&row).unwrap();
+
+    let query = LatestAtQuery::new(timepoint[0].0, timepoint[0].1);
+    let rows = query_entity_joined::<Point2D, (ColorRGBA, Label)>(&store, &query, &ent_path)
+        .unwrap()
+        .collect_vec();
+
+    let label = Some(Label("hey".into()));
+    let expected = points()
+        .into_iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let color = (i == 2).then_some(ColorRGBA(0xff000000));
+            (InstanceKey(i as _), point, (color, label.clone()))
+        })
+        .collect_vec();
+    assert_eq!(rows, expected);
+
+    // Missing components are just nulls.
+    let query = LatestAtQuery::new(timepoint[0].0, timepoint[0].1);
+    let rows = query_entity_joined::<ColorRGBA, (Point2D,)>(&store, &query, &ent_path)
+        .unwrap()
+        .collect_vec();
+    assert_eq!(
+        rows,
+        vec![(
+            InstanceKey(2),
+            ColorRGBA(0xff000000),
+            (Some(points()[2].clone()),)
+        )]
+    );
+    let rows = query_entity_joined::<Point2D, (Radius,)>(&store, &query, &ent_path)
+        .unwrap()
+        .collect_vec();
+    assert!(rows.iter().all(|(_, _, (radius,))| radius.is_none()));
+    assert_eq!(rows.len(), 3);
+
+    // Joining nothing at all still yields the primary.
+    let rows = query_entity_joined::<Point2D, ()>(&store, &query, &ent_path)
+        .unwrap()
+        .collect_vec();
+    let expected = points()
+        .into_iter()
+        .enumerate()
+        .map(|(i, point)| (InstanceKey(i as _), point, ()))
+        .collect_vec();
+    assert_eq!(rows, expected);
+}
+
+#[test]
+fn timeless_join() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    let ent_path: EntityPath = "point".into();
+    let timepoint = [build_frame_nr(123.into())];
+
+    // Points with implicit instances..
+    let row = DataRow::from_cells1_sized(RowId::random(), ent_path.clone(), timepoint, 3, points());
+    store.insert_row(&row).unwrap();
+
+    // ..and a color for one of them, timelessly.
+    let row = DataRow::from_cells2_sized(
+        RowId::random(),
+        ent_path.clone(),
+        [],
+        1,
+        (vec![InstanceKey(1)], vec![ColorRGBA(0xff000000)]),
+    );
+    store.insert_row(&row).unwrap();
+
+    let query = LatestAtQuery::new(timepoint[0].0, timepoint[0].1);
+    let rows = query_entity_joined::<Point2D, (ColorRGBA,)>(&store, &query, &ent_path)
+        .unwrap()
+        .collect_vec();
+
+    let expected = points()
+        .into_iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let color = (i == 1).then_some(ColorRGBA(0xff000000));
+            (InstanceKey(i as _), point, (color,))
+        })
+        .collect_vec();
+    assert_eq!(rows, expected);
+}
+
+#[test]
+fn range_join() {
+    let mut store = DataStore::new(InstanceKey::name(), Default::default());
+
+    let ent_path: EntityPath = "point".into();
+
+    let timepoint1 = [build_frame_nr(123.into())];
+    let row =
+        DataRow::from_cells1_sized(RowId::random(), ent_path.clone(), timepoint1, 3, points());
+    store.insert_row(&row).unwrap();
+
+    let timepoint2 = [build_frame_nr(223.into())];
+    let row = DataRow::from_cells2_sized(
+        RowId::random(),
+        ent_path.clone(),
+        timepoint2,
+        1,
+        (vec![InstanceKey::SPLAT], vec![ColorRGBA(0xff000000)]),
+    );
+    store.insert_row(&row).unwrap();
+
+    let timepoint3 = [build_frame_nr(323.into())];
+    let row =
+        DataRow::from_cells1_sized(RowId::random(), ent_path.clone(), timepoint3, 3, points());
+    store.insert_row(&row).unwrap();
+
+    let query = RangeQuery::new(timepoint1[0].0, TimeRange::new(0.into(), 1000.into()));
+    let results = range_entity_joined::<Point2D, (ColorRGBA,)>(&store, &query, &ent_path)
+        .map(|res| {
+            let (time, rows) = res.unwrap();
+            let colors = rows.map(|(_, _, (color,))| color).collect_vec();
+            (time, colors)
+        })
+        .collect_vec();
+
+    // Only changes to the primary component yield new rows, but the splatted color gets
+    // accumulated into the next ones.
+    assert_eq!(
+        results,
+        vec![
+            (Some(TimeInt::from(123)), vec![None; 3]),
+            (
+                Some(TimeInt::from(323)),
+                vec![Some(ColorRGBA(0xff000000)); 3]
+            ),
+        ]
+    );
+}
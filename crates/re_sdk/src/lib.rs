@@ -48,6 +48,14 @@ pub mod demo_util;
 pub mod sink {
     pub use crate::log_sink::{BufferedSink, LogSink, MemorySink, MemorySinkStorage, TcpSink};
 
+    pub use re_sdk_comms::{
+        BackPressurePolicy, ClientOptions, DroppedMessages, EntityInstance, ViewerEvent,
+        ViewerEventKind,
+    };
+
+    #[cfg(feature = "tls")]
+    pub use re_sdk_comms::ClientTls;
+
     #[cfg(not(target_arch = "wasm32"))]
     pub use re_log_encoding::{FileSink, FileSinkError};
 }
@@ -33,6 +33,20 @@ pub trait LogSink: Send + Sync + 'static {
     /// flush it for any reason (e.g. a broken TCP connection for a [`TcpSink`]).
     #[inline]
     fn drop_if_disconnected(&self) {}
+
+    /// Counts the messages that this sink had to drop, for sinks that can drop messages at all
+    /// (e.g. a [`TcpSink`] running out of memory budget).
+    #[inline]
+    fn dropped_messages(&self) -> Option<Arc<re_sdk_comms::DroppedMessages>> {
+        None
+    }
+
+    /// The events sent back by the viewer on the other end of this sink, for sinks that are
+    /// connected to one (i.e. a [`TcpSink`]).
+    #[inline]
+    fn viewer_events(&self) -> Option<crossbeam::channel::Receiver<re_sdk_comms::ViewerEvent>> {
+        None
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -128,8 +142,17 @@ impl MemorySinkStorage {
     /// Convert the stored messages into an in-memory Rerun log file.
     #[inline]
     pub fn rrd_as_bytes(&self) -> Result<Vec<u8>, re_log_encoding::encoder::EncodeError> {
+        self.rrd_as_bytes_with_options(Default::default())
+    }
+
+    /// Convert the stored messages into an in-memory Rerun log file, using the given encoding options.
+    #[inline]
+    pub fn rrd_as_bytes_with_options(
+        &self,
+        options: re_log_encoding::EncodingOptions,
+    ) -> Result<Vec<u8>, re_log_encoding::encoder::EncodeError> {
         let mut buffer = std::io::Cursor::new(Vec::new());
-        re_log_encoding::encoder::encode(self.read().iter(), &mut buffer)?;
+        re_log_encoding::encoder::encode(options, self.read().iter(), &mut buffer)?;
         Ok(buffer.into_inner())
     }
 }
@@ -150,6 +173,21 @@ impl TcpSink {
             client: re_sdk_comms::Client::new(addr),
         }
     }
+
+    /// Connect to the given address in a background thread, with the given options.
+    ///
+    /// See [`re_sdk_comms::ClientOptions`] for how to bound the memory used by the sink when the
+    /// server can't keep up.
+    /// Retries until successful.
+    #[inline]
+    pub fn new_with_options(
+        addr: std::net::SocketAddr,
+        options: re_sdk_comms::ClientOptions,
+    ) -> Self {
+        Self {
+            client: re_sdk_comms::Client::new_with_options(addr, options),
+        }
+    }
 }
 
 impl LogSink for TcpSink {
@@ -167,4 +205,14 @@ impl LogSink for TcpSink {
     fn drop_if_disconnected(&self) {
         self.client.drop_if_disconnected();
     }
+
+    #[inline]
+    fn dropped_messages(&self) -> Option<Arc<re_sdk_comms::DroppedMessages>> {
+        Some(self.client.dropped_messages().clone())
+    }
+
+    #[inline]
+    fn viewer_events(&self) -> Option<crossbeam::channel::Receiver<re_sdk_comms::ViewerEvent>> {
+        Some(self.client.viewer_events().clone())
+    }
 }
@@ -52,6 +52,9 @@ pub struct RecordingStreamBuilder {
 
     batcher_config: Option<DataTableBatcherConfig>,
 
+    auth_token: Option<String>,
+    buttons: Vec<String>,
+
     is_official_example: bool,
 }
 
@@ -81,6 +84,8 @@ impl RecordingStreamBuilder {
             enabled: None,
 
             batcher_config: None,
+            auth_token: None,
+            buttons: Vec::new(),
             is_official_example,
         }
     }
@@ -127,6 +132,24 @@ impl RecordingStreamBuilder {
         self
     }
 
+    /// The token to authenticate with when connecting to a Rerun server that requires one, see
+    /// [`Self::connect`].
+    ///
+    /// Takes precedence over [`re_sdk_comms::ClientOptions::auth_token`].
+    pub fn auth_token(mut self, auth_token: impl Into<String>) -> Self {
+        self.auth_token = Some(auth_token.into());
+        self
+    }
+
+    /// Adds a button to the viewer that this stream connects to, see [`Self::connect`].
+    ///
+    /// Presses come back as [`re_sdk_comms::ViewerEventKind::ButtonPressed`], see
+    /// [`RecordingStream::poll_viewer_events`].
+    pub fn button(mut self, name: impl Into<String>) -> Self {
+        self.buttons.push(name.into());
+        self
+    }
+
     #[doc(hidden)]
     pub fn recording_source(mut self, recording_source: RecordingSource) -> Self {
         self.recording_source = Some(recording_source);
@@ -197,12 +220,40 @@ impl RecordingStreamBuilder {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn connect(self, addr: std::net::SocketAddr) -> RecordingStreamResult<RecordingStream> {
+        self.connect_opts(addr, Default::default())
+    }
+
+    /// Creates a new [`RecordingStream`] that is pre-configured to stream the data through to a
+    /// remote Rerun instance, using the given [`re_sdk_comms::ClientOptions`].
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let options = re_sdk::sink::ClientOptions {
+    ///     max_bytes_in_flight: Some(512 * 1024 * 1024),
+    ///     back_pressure: re_sdk::sink::BackPressurePolicy::DropOldest,
+    ///     ..Default::default()
+    /// };
+    /// let rec_stream = re_sdk::RecordingStreamBuilder::new("my_app")
+    ///     .connect_opts(re_sdk::default_server_addr(), options)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn connect_opts(
+        mut self,
+        addr: std::net::SocketAddr,
+        mut options: re_sdk_comms::ClientOptions,
+    ) -> RecordingStreamResult<RecordingStream> {
+        if let Some(auth_token) = self.auth_token.take() {
+            options.auth_token = Some(auth_token);
+        }
+        options.buttons.append(&mut self.buttons);
+
         let (enabled, recording_info, batcher_config) = self.into_args();
         if enabled {
             RecordingStream::new(
                 recording_info,
                 batcher_config,
-                Box::new(crate::log_sink::TcpSink::new(addr)),
+                Box::new(crate::log_sink::TcpSink::new_with_options(addr, options)),
             )
         } else {
             re_log::debug!("Rerun disabled - call to connect() ignored");
@@ -251,6 +302,8 @@ impl RecordingStreamBuilder {
             default_enabled,
             enabled,
             batcher_config,
+            auth_token: _,
+            buttons: _,
             is_official_example,
         } = self;
 
@@ -328,6 +381,12 @@ struct RecordingStreamInner {
 
     batcher: DataTableBatcher,
     batcher_to_sink_handle: Option<std::thread::JoinHandle<()>>,
+
+    /// The drop counters of the current sink, if it keeps any.
+    dropped_messages: parking_lot::RwLock<Option<Arc<re_sdk_comms::DroppedMessages>>>,
+
+    /// The events sent back by the viewer on the other end of the current sink, if any.
+    viewer_events: parking_lot::RwLock<Option<Receiver<re_sdk_comms::ViewerEvent>>>,
 }
 
 impl Drop for RecordingStreamInner {
@@ -367,6 +426,8 @@ impl RecordingStreamInner {
             );
         }
 
+        let dropped_messages = sink.dropped_messages();
+        let viewer_events = sink.viewer_events();
         let (cmds_tx, cmds_rx) = crossbeam::channel::unbounded();
 
         let batcher_to_sink_handle = {
@@ -390,6 +451,8 @@ impl RecordingStreamInner {
             cmds_tx,
             batcher,
             batcher_to_sink_handle: Some(batcher_to_sink_handle),
+            dropped_messages: parking_lot::RwLock::new(dropped_messages),
+            viewer_events: parking_lot::RwLock::new(viewer_events),
         })
     }
 }
@@ -567,6 +630,62 @@ impl RecordingStream {
     pub fn recording_info(&self) -> Option<&RecordingInfo> {
         (*self.inner).as_ref().map(|inner| &inner.info)
     }
+
+    /// How many messages the current sink had to drop so far, e.g. because a
+    /// [`crate::sink::TcpSink`] ran out of memory budget.
+    ///
+    /// See [`re_sdk_comms::ClientOptions`].
+    #[inline]
+    pub fn num_dropped_msgs(&self) -> u64 {
+        self.dropped_messages()
+            .map_or(0, |dropped_messages| dropped_messages.num_msgs())
+    }
+
+    /// How many bytes worth of messages the current sink had to drop so far.
+    ///
+    /// See [`Self::num_dropped_msgs`].
+    #[inline]
+    pub fn num_dropped_bytes(&self) -> u64 {
+        self.dropped_messages()
+            .map_or(0, |dropped_messages| dropped_messages.num_bytes())
+    }
+
+    fn dropped_messages(&self) -> Option<Arc<re_sdk_comms::DroppedMessages>> {
+        (*self.inner)
+            .as_ref()
+            .and_then(|inner| inner.dropped_messages.read().clone())
+    }
+
+    /// Returns the events that the viewer sent back so far about this recording, e.g. because
+    /// its user selected or clicked something, without blocking.
+    ///
+    /// Only a connected sink receives events (see [`RecordingStreamBuilder::connect`]), and the
+    /// events that aren't polled in time are dropped.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// # let rec_stream = re_sdk::RecordingStream::disabled();
+    /// use re_sdk::sink::ViewerEventKind;
+    ///
+    /// for event in rec_stream.poll_viewer_events() {
+    ///     if let ViewerEventKind::Clicked(instances) = event.kind {
+    ///         println!("Clicked {instances:?}");
+    ///     }
+    /// }
+    /// ```
+    pub fn poll_viewer_events(&self) -> impl Iterator<Item = re_sdk_comms::ViewerEvent> {
+        let this = (*self.inner).as_ref();
+        let recording_id = this.map(|inner| inner.info.recording_id);
+        let viewer_events = this.and_then(|inner| inner.viewer_events.read().clone());
+
+        std::iter::from_fn(move || loop {
+            let event = viewer_events.as_ref()?.try_recv().ok()?;
+            if recording_id.map_or(false, |recording_id| event.is_for(recording_id)) {
+                return Some(event);
+            }
+        })
+    }
 }
 
 impl RecordingStream {
@@ -609,6 +728,37 @@ impl RecordingStream {
         ));
     }
 
+    /// Deletes all the rows matching the given [`re_log_types::RowSelector`] from the recording.
+    ///
+    /// Unlike a [`re_log_types::PathOp`], which logs new data that hides the old, this retracts
+    /// the data altogether, e.g. because it was mislabeled.
+    ///
+    /// Any data that is still pending in the batcher is flushed first, so that the deletion is
+    /// guaranteed to apply to everything that was logged before this call.
+    pub fn record_delete_rows(&self, selector: re_log_types::RowSelector) {
+        let Some(this) = &*self.inner else {
+            re_log::warn_once!("Recording disabled - call to record_delete_rows() ignored");
+            return;
+        };
+
+        // NOTE: Internal channels can never be closed outside of the `Drop` impl, all these sends
+        // are safe.
+
+        // 1. Flush the batcher down the table channel
+        this.batcher.flush_blocking();
+
+        // 2. Drain all pending tables from the batcher's channel _before_ the deletion
+        this.cmds_tx.send(Command::PopPendingTables).ok();
+
+        self.record_msg(LogMsg::DeleteRowsMsg(
+            this.info.recording_id,
+            re_log_types::DeleteRowsMsg {
+                row_id: re_log_types::RowId::random(),
+                selector,
+            },
+        ));
+    }
+
     /// Records a single [`DataRow`].
     ///
     /// Internally, incoming [`DataRow`]s are automatically coalesced into larger [`DataTable`]s to
@@ -662,6 +812,8 @@ impl RecordingStream {
         this.cmds_tx.send(Command::PopPendingTables).ok();
 
         // 3. Swap the sink, which will internally make sure to re-ingest the backlog if needed
+        let dropped_messages = sink.dropped_messages();
+        let viewer_events = sink.viewer_events();
         this.cmds_tx.send(Command::SwapSink(sink)).ok();
 
         // 4. Before we give control back to the caller, we need to make sure that the swap has
@@ -669,6 +821,9 @@ impl RecordingStream {
         let (cmd, oneshot) = Command::flush();
         this.cmds_tx.send(cmd).ok();
         oneshot.recv().ok();
+
+        *this.dropped_messages.write() = dropped_messages;
+        *this.viewer_events.write() = viewer_events;
     }
 
     /// Initiates a flush of the pipeline and returns immediately.
@@ -732,7 +887,17 @@ impl RecordingStream {
     /// terms of data durability and ordering.
     /// See [`Self::set_sink`] for more information.
     pub fn connect(&self, addr: std::net::SocketAddr) {
-        self.set_sink(Box::new(crate::log_sink::TcpSink::new(addr)));
+        self.connect_opts(addr, Default::default());
+    }
+
+    /// Swaps the underlying sink for a [`crate::log_sink::TcpSink`] sink pre-configured to use
+    /// the specified address and [`re_sdk_comms::ClientOptions`].
+    ///
+    /// See [`Self::connect`] for more information.
+    pub fn connect_opts(&self, addr: std::net::SocketAddr, options: re_sdk_comms::ClientOptions) {
+        self.set_sink(Box::new(crate::log_sink::TcpSink::new_with_options(
+            addr, options,
+        )));
     }
 
     /// Swaps the underlying sink for a [`crate::sink::MemorySink`] sink and returns the associated
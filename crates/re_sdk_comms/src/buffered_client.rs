@@ -1,8 +1,116 @@
-use std::{net::SocketAddr, thread::JoinHandle};
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::JoinHandle,
+};
 
 use crossbeam::channel::{select, Receiver, Sender};
 
-use re_log_types::{LogMsg, RowId};
+use re_log_encoding::EncodingOptions;
+use re_log_types::{LogMsg, RecordingId, RowId, SizeBytes as _};
+
+use crate::ViewerEvent;
+
+/// How many [`ViewerEvent`]s can be waiting in [`Client::viewer_events`] before we start dropping
+/// them.
+const MAX_PENDING_VIEWER_EVENTS: usize = 1024;
+
+/// What a [`Client`] does with new messages once it has run out of memory budget, see
+/// [`ClientOptions::max_bytes_in_flight`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackPressurePolicy {
+    /// Block the caller of [`Client::send`] until enough messages have been sent.
+    #[default]
+    Block,
+
+    /// Drop the oldest pending messages to make room for the new one.
+    DropOldest,
+
+    /// Drop the new message.
+    DropNewest,
+}
+
+/// Options for a [`Client`].
+#[derive(Clone, Debug, Default)]
+pub struct ClientOptions {
+    /// How to encode and compress messages.
+    pub encoding: EncodingOptions,
+
+    /// How many bytes worth of messages can be pending within the client at any time, i.e. sent
+    /// to it but not written to the socket yet.
+    ///
+    /// `None` means unbounded, in which case the process will grow for as long as the server
+    /// can't keep up.
+    pub max_bytes_in_flight: Option<u64>,
+
+    /// What to do once [`Self::max_bytes_in_flight`] has been reached.
+    pub back_pressure: BackPressurePolicy,
+
+    /// How many bytes worth of the most recently sent messages to keep around, so that they can
+    /// be sent again after reconnecting to a server, e.g. because the viewer was restarted.
+    ///
+    /// Reconnecting to the same server only sends again the packets it didn't receive before the
+    /// connection was lost (e.g. those still in the kernel's buffers), as long as they are still
+    /// kept around. The recording infos ([`re_log_types::BeginRecordingMsg`]) are always sent again to a new
+    /// server, no matter this setting.
+    ///
+    /// This memory comes on top of [`Self::max_bytes_in_flight`]. Defaults to 0.
+    pub max_replay_bytes: u64,
+
+    /// The token to authenticate with, for servers that require one.
+    pub auth_token: Option<String>,
+
+    /// Encrypt the connection with TLS. The server must have been started with TLS too.
+    #[cfg(feature = "tls")]
+    pub tls: Option<crate::ClientTls>,
+
+    /// Buttons to show in the viewer, whose presses come back as
+    /// [`crate::ViewerEventKind::ButtonPressed`], see [`Client::viewer_events`].
+    pub buttons: Vec<String>,
+}
+
+impl From<EncodingOptions> for ClientOptions {
+    #[inline]
+    fn from(encoding: EncodingOptions) -> Self {
+        Self {
+            encoding,
+            ..Default::default()
+        }
+    }
+}
+
+/// Counts the messages that a [`Client`] had to drop because of back-pressure, see
+/// [`BackPressurePolicy`].
+#[derive(Debug, Default)]
+pub struct DroppedMessages {
+    num_msgs: AtomicU64,
+    num_bytes: AtomicU64,
+}
+
+impl DroppedMessages {
+    /// How many messages have been dropped so far.
+    #[inline]
+    pub fn num_msgs(&self) -> u64 {
+        self.num_msgs.load(Ordering::Relaxed)
+    }
+
+    /// How many bytes worth of messages have been dropped so far.
+    ///
+    /// These are estimates for the messages that were dropped before being encoded.
+    #[inline]
+    pub fn num_bytes(&self) -> u64 {
+        self.num_bytes.load(Ordering::Relaxed)
+    }
+
+    fn add(&self, num_bytes: u64) {
+        self.num_msgs.fetch_add(1, Ordering::Relaxed);
+        self.num_bytes.fetch_add(num_bytes, Ordering::Relaxed);
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 struct FlushedMsg;
@@ -25,22 +133,139 @@ enum InterruptMsg {
 }
 
 enum MsgMsg {
-    LogMsg(LogMsg),
+    /// A message along with the number of bytes it was charged against the [`MemoryBudget`].
+    LogMsg(LogMsg, u64),
     Flush,
 }
 
 enum PacketMsg {
     Packet(Vec<u8>),
+
+    /// An encoded [`re_log_types::BeginRecordingMsg`], which is never dropped.
+    RecordingInfo(RecordingId, Vec<u8>),
+
     Flush,
 }
 
+/// An order-preserving queue between two threads, from which the [`Client`] can also take out
+/// the messages it drops because of back-pressure, wherever they are in the queue.
+///
+/// Every push rings the doorbell once, so that the receiving thread can `select!` on it along
+/// with its other channels. The item a ring was for might have been dropped in the meantime, in
+/// which case the receiver finds the queue empty and just waits for the next ring.
+struct Pipe<T> {
+    queue: Mutex<VecDeque<T>>,
+    doorbell_tx: Sender<()>,
+    doorbell_rx: Receiver<()>,
+}
+
+impl<T> Pipe<T> {
+    fn new() -> Self {
+        let (doorbell_tx, doorbell_rx) = crossbeam::channel::unbounded();
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            doorbell_tx,
+            doorbell_rx,
+        }
+    }
+
+    fn push(&self, item: T) {
+        self.queue.lock().unwrap().push_back(item);
+        self.doorbell_tx.send(()).ok();
+    }
+
+    fn pop(&self) -> Option<T> {
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    /// Takes out the oldest item matching `predicate`, leaving all the others where they are.
+    fn remove_first(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        let mut queue = self.queue.lock().unwrap();
+        let index = queue.iter().position(predicate)?;
+        queue.remove(index)
+    }
+
+    /// Rings once for every item pushed.
+    fn doorbell(&self) -> &Receiver<()> {
+        &self.doorbell_rx
+    }
+}
+
+/// Keeps track of how many bytes worth of messages are pending in the client's pipes.
+///
+/// Messages are charged with an estimate of their size when sent to the client, then with their
+/// actual size once encoded, and released once written to the socket (or dropped).
+struct MemoryBudget {
+    max_bytes: Option<u64>,
+    num_bytes: Mutex<u64>,
+    released: Condvar,
+}
+
+impl MemoryBudget {
+    fn new(max_bytes: Option<u64>) -> Self {
+        Self {
+            max_bytes,
+            num_bytes: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Whether `num_bytes` more bytes would fit within the budget.
+    ///
+    /// A message always fits when nothing else is pending, no matter how big it is.
+    fn has_room_for(&self, num_bytes: u64) -> bool {
+        let cur_num_bytes = *self.num_bytes.lock().unwrap();
+        self.fits(cur_num_bytes, num_bytes)
+    }
+
+    fn fits(&self, cur_num_bytes: u64, num_bytes: u64) -> bool {
+        self.max_bytes.map_or(true, |max_bytes| {
+            cur_num_bytes == 0 || cur_num_bytes.saturating_add(num_bytes) <= max_bytes
+        })
+    }
+
+    /// Blocks until `num_bytes` more bytes fit within the budget, then charges them.
+    fn charge_blocking(&self, num_bytes: u64) {
+        let mut cur_num_bytes = self
+            .released
+            .wait_while(self.num_bytes.lock().unwrap(), |cur_num_bytes| {
+                !self.fits(*cur_num_bytes, num_bytes)
+            })
+            .unwrap();
+        *cur_num_bytes += num_bytes;
+    }
+
+    fn charge(&self, num_bytes: u64) {
+        *self.num_bytes.lock().unwrap() += num_bytes;
+    }
+
+    fn release(&self, num_bytes: u64) {
+        {
+            let mut cur_num_bytes = self.num_bytes.lock().unwrap();
+            *cur_num_bytes = cur_num_bytes.saturating_sub(num_bytes);
+        }
+        self.released.notify_all();
+    }
+}
+
 /// Send [`LogMsg`]es to a server over TCP.
 ///
 /// The messages are encoded and sent on separate threads
-/// so that calling [`Client::send`] is non-blocking.
+/// so that calling [`Client::send`] is non-blocking, unless the client has run out of memory
+/// budget, see [`ClientOptions`].
 pub struct Client {
-    msg_tx: Sender<MsgMsg>,
+    back_pressure: BackPressurePolicy,
+    budget: Arc<MemoryBudget>,
+    dropped: Arc<DroppedMessages>,
+
+    // Shared with the threads, so that we can drop the oldest messages ourselves.
+    msgs: Arc<Pipe<MsgMsg>>,
+    packets: Arc<Pipe<PacketMsg>>,
+
+    msg_drop_tx: Sender<MsgMsg>,
     flushed_rx: Receiver<FlushedMsg>,
+    viewer_events_rx: Receiver<ViewerEvent>,
+
     encode_quit_tx: Sender<QuitMsg>,
     send_quit_tx: Sender<InterruptMsg>,
     drop_quit_tx: Sender<QuitMsg>,
@@ -58,13 +283,48 @@ impl Default for Client {
 impl Client {
     /// Connect via TCP to this log server.
     pub fn new(addr: SocketAddr) -> Self {
+        Self::new_with_options(addr, Default::default())
+    }
+
+    /// Connect via TCP to this log server, with the given options.
+    pub fn new_with_options(addr: SocketAddr, options: ClientOptions) -> Self {
         re_log::debug!("Connecting to remote {addr}…");
 
-        // TODO(emilk): keep track of how much memory is in each pipe
-        // and apply back-pressure to not use too much RAM.
-        let (msg_tx, msg_rx) = crossbeam::channel::unbounded();
+        let ClientOptions {
+            encoding,
+            max_bytes_in_flight,
+            back_pressure,
+            max_replay_bytes,
+            auth_token,
+            #[cfg(feature = "tls")]
+            tls,
+            buttons,
+        } = options;
+
+        #[cfg(feature = "tls")]
+        let encrypted = tls.is_some();
+        #[cfg(not(feature = "tls"))]
+        let encrypted = false;
+        if auth_token.is_some() && !encrypted && !addr.ip().is_loopback() {
+            re_log::warn!(
+                "Sending the auth token to the Rerun server at {addr} unencrypted: anyone on the \
+                network can read it. Set `ClientOptions::tls` to encrypt the connection."
+            );
+        }
+
+        let (viewer_events_tx, viewer_events_rx) =
+            crossbeam::channel::bounded(MAX_PENDING_VIEWER_EVENTS);
+        let tcp_client = crate::tcp_client::TcpClient::new(addr, encoding.compression)
+            .with_auth_token(auth_token)
+            .with_buttons(buttons)
+            .with_viewer_events(viewer_events_tx);
+        #[cfg(feature = "tls")]
+        let tcp_client = tcp_client.with_tls(tls);
+        let budget = Arc::new(MemoryBudget::new(max_bytes_in_flight));
+
+        let msgs = Arc::new(Pipe::new());
+        let packets = Arc::new(Pipe::new());
         let (msg_drop_tx, msg_drop_rx) = crossbeam::channel::unbounded();
-        let (packet_tx, packet_rx) = crossbeam::channel::unbounded();
         let (flushed_tx, flushed_rx) = crossbeam::channel::unbounded();
         let (encode_quit_tx, encode_quit_rx) = crossbeam::channel::unbounded();
         let (send_quit_tx, send_quit_rx) = crossbeam::channel::unbounded();
@@ -72,17 +332,41 @@ impl Client {
 
         let encode_join = std::thread::Builder::new()
             .name("msg_encoder".into())
-            .spawn(move || {
-                msg_encode(&msg_rx, &msg_drop_tx, &encode_quit_rx, &packet_tx);
-                re_log::debug!("Shutting down msg encoder thread");
+            .spawn({
+                let msgs = msgs.clone();
+                let msg_drop_tx = msg_drop_tx.clone();
+                let packets = packets.clone();
+                let budget = budget.clone();
+                move || {
+                    msg_encode(
+                        encoding,
+                        &budget,
+                        &msgs,
+                        &msg_drop_tx,
+                        &encode_quit_rx,
+                        &packets,
+                    );
+                    re_log::debug!("Shutting down msg encoder thread");
+                }
             })
             .expect("Failed to spawn thread");
 
         let send_join = std::thread::Builder::new()
             .name("tcp_sender".into())
-            .spawn(move || {
-                tcp_sender(addr, &packet_rx, &send_quit_rx, &flushed_tx);
-                re_log::debug!("Shutting down TCP sender thread");
+            .spawn({
+                let packets = packets.clone();
+                let budget = budget.clone();
+                move || {
+                    tcp_sender(
+                        tcp_client,
+                        &budget,
+                        Replay::new(max_replay_bytes),
+                        &packets,
+                        &send_quit_rx,
+                        &flushed_tx,
+                    );
+                    re_log::debug!("Shutting down TCP sender thread");
+                }
             })
             .expect("Failed to spawn thread");
 
@@ -95,8 +379,14 @@ impl Client {
             .expect("Failed to spawn thread");
 
         Self {
-            msg_tx,
+            back_pressure,
+            budget,
+            dropped: Default::default(),
+            msgs,
+            packets,
+            msg_drop_tx,
             flushed_rx,
+            viewer_events_rx,
             encode_quit_tx,
             send_quit_tx,
             drop_quit_tx,
@@ -106,8 +396,54 @@ impl Client {
         }
     }
 
+    /// Queues the given message to be sent.
+    ///
+    /// This only ever blocks if the client has run out of memory budget and is configured to
+    /// wait for room in that case, see [`ClientOptions`].
     pub fn send(&self, log_msg: LogMsg) {
-        self.send_msg_msg(MsgMsg::LogMsg(log_msg));
+        let num_bytes = approx_size_bytes(&log_msg);
+
+        match self.back_pressure {
+            BackPressurePolicy::Block => self.budget.charge_blocking(num_bytes),
+            // Servers can't make sense of anything else without the recording infos.
+            _ if !is_droppable(&log_msg) => self.budget.charge(num_bytes),
+            BackPressurePolicy::DropOldest => {
+                while !self.budget.has_room_for(num_bytes) {
+                    if !self.drop_oldest() {
+                        // Everything that's left is being written to the socket right now.
+                        break;
+                    }
+                }
+                self.budget.charge(num_bytes);
+            }
+            BackPressurePolicy::DropNewest => {
+                if !self.budget.has_room_for(num_bytes) {
+                    re_log::debug_once!("Dropping messages because the client is out of memory.");
+                    self.dropped.add(num_bytes);
+                    self.msg_drop_tx.send(MsgMsg::LogMsg(log_msg, 0)).ok();
+                    return;
+                }
+                self.budget.charge(num_bytes);
+            }
+        }
+
+        self.send_msg_msg(MsgMsg::LogMsg(log_msg, num_bytes));
+    }
+
+    /// Counts the messages that were dropped because of back-pressure.
+    ///
+    /// Shared with whoever wants to keep an eye on them, even after the client is gone.
+    #[inline]
+    pub fn dropped_messages(&self) -> &Arc<DroppedMessages> {
+        &self.dropped
+    }
+
+    /// The events sent back by the viewer, e.g. when its user selects something.
+    ///
+    /// Events are dropped when more than a thousand or so of them pile up in there.
+    #[inline]
+    pub fn viewer_events(&self) -> &Receiver<ViewerEvent> {
+        &self.viewer_events_rx
     }
 
     /// Stall until all messages so far has been sent.
@@ -137,8 +473,38 @@ impl Client {
     }
 
     fn send_msg_msg(&self, msg: MsgMsg) {
-        // ignoring errors, because Ctrl-C can shut down the receiving end.
-        self.msg_tx.send(msg).ok();
+        self.msgs.push(msg);
+    }
+
+    /// Drops the oldest pending message, i.e. the first already encoded one if any.
+    ///
+    /// Flush markers and recording infos are never dropped, and everything else stays in the
+    /// order it was sent in.
+    ///
+    /// Returns `false` if there was nothing left to drop.
+    fn drop_oldest(&self) -> bool {
+        if let Some(PacketMsg::Packet(packet)) = self
+            .packets
+            .remove_first(|packet_msg| matches!(packet_msg, PacketMsg::Packet(_)))
+        {
+            re_log::debug_once!("Dropping messages because the client is out of memory.");
+            let num_bytes = packet.len() as u64;
+            self.budget.release(num_bytes);
+            self.dropped.add(num_bytes);
+            return true;
+        }
+
+        if let Some(MsgMsg::LogMsg(log_msg, num_bytes)) = self.msgs.remove_first(
+            |msg_msg| matches!(msg_msg, MsgMsg::LogMsg(log_msg, _) if is_droppable(log_msg)),
+        ) {
+            re_log::debug_once!("Dropping messages because the client is out of memory.");
+            self.budget.release(num_bytes);
+            self.dropped.add(num_bytes);
+            self.msg_drop_tx.send(MsgMsg::LogMsg(log_msg, 0)).ok();
+            return true;
+        }
+
+        false
     }
 }
 
@@ -146,7 +512,11 @@ impl Drop for Client {
     /// Wait until everything has been sent.
     fn drop(&mut self) {
         re_log::debug!("Shutting down the client connection…");
-        self.send(LogMsg::Goodbye(RowId::random()));
+        // The goodbye must make it through, no matter the back-pressure policy.
+        let goodbye = LogMsg::Goodbye(RowId::random());
+        let num_bytes = approx_size_bytes(&goodbye);
+        self.budget.charge(num_bytes);
+        self.send_msg_msg(MsgMsg::LogMsg(goodbye, num_bytes));
         self.flush();
         // First shut down the encoder:
         self.encode_quit_tx.send(QuitMsg).ok();
@@ -179,25 +549,60 @@ fn msg_drop(msg_drop_rx: &Receiver<MsgMsg>, quit_rx: &Receiver<QuitMsg>) {
     }
 }
 
+/// Whether the given message can be dropped because of back-pressure.
+fn is_droppable(log_msg: &LogMsg) -> bool {
+    !matches!(log_msg, LogMsg::BeginRecordingMsg(_))
+}
+
+/// A cheap estimate of how much memory the given message holds on to.
+fn approx_size_bytes(log_msg: &LogMsg) -> u64 {
+    let heap_size_bytes = match log_msg {
+        LogMsg::ArrowMsg(_, msg) => msg
+            .chunk
+            .arrays()
+            .iter()
+            .map(|array| array.as_ref().heap_size_bytes())
+            .sum(),
+        LogMsg::BeginRecordingMsg(_)
+        | LogMsg::EntityPathOpMsg(_, _)
+        | LogMsg::DeleteRowsMsg(_, _)
+        | LogMsg::Goodbye(_) => 0,
+    };
+    std::mem::size_of::<LogMsg>() as u64 + heap_size_bytes
+}
+
 fn msg_encode(
-    msg_rx: &Receiver<MsgMsg>,
+    options: EncodingOptions,
+    budget: &MemoryBudget,
+    msgs: &Pipe<MsgMsg>,
     msg_drop_tx: &Sender<MsgMsg>,
     quit_rx: &Receiver<QuitMsg>,
-    packet_tx: &Sender<PacketMsg>,
+    packets: &Pipe<PacketMsg>,
 ) {
     loop {
         select! {
-            recv(msg_rx) -> msg_msg => {
-                let Ok(msg_msg) = msg_msg else {
-                    return; // channel has closed
+            recv(msgs.doorbell()) -> _ => {
+                let Some(msg_msg) = msgs.pop() else {
+                    continue; // dropped because of back-pressure
                 };
 
                 let packet_msg = match &msg_msg {
-                    MsgMsg::LogMsg(log_msg) => {
-                        match re_log_encoding::encoder::encode_to_bytes(std::iter::once(log_msg)) {
+                    MsgMsg::LogMsg(log_msg, num_bytes) => {
+                        let packet = re_log_encoding::encoder::encode_to_bytes(
+                            options,
+                            std::iter::once(log_msg),
+                        );
+                        // From now on, the packet is what's taking up memory.
+                        budget.release(*num_bytes);
+                        match packet {
                             Ok(packet) => {
                                 re_log::trace!("Encoded message of size {}", packet.len());
-                                Some(PacketMsg::Packet(packet))
+                                budget.charge(packet.len() as _);
+                                if let LogMsg::BeginRecordingMsg(msg) = log_msg {
+                                    Some(PacketMsg::RecordingInfo(msg.info.recording_id, packet))
+                                } else {
+                                    Some(PacketMsg::Packet(packet))
+                                }
                             }
                             Err(err) => {
                                 re_log::error_once!("Failed to encode log message: {err}");
@@ -209,10 +614,7 @@ fn msg_encode(
                 };
 
                 if let Some(packet_msg) = packet_msg {
-                    if packet_tx.send(packet_msg).is_err() {
-                        re_log::error!("Failed to send message to tcp_sender thread. Likely a shutdown race-condition.");
-                        return;
-                    }
+                    packets.push(packet_msg);
                 }
                 if msg_drop_tx.send(msg_msg).is_err() {
                     re_log::error!("Failed to send message to msg_drop thread. Likely a shutdown race-condition");
@@ -226,40 +628,148 @@ fn msg_encode(
     }
 }
 
+/// What a server needs to be sent again after reconnecting to it, so that it can pick up where
+/// the previous one left off.
+///
+/// Every packet is kept along with its sequence number, see [`crate::Hello::next_seq`].
+struct Replay {
+    /// The sequence number of the next packet to send.
+    next_seq: u64,
+
+    /// The encoded [`re_log_types::BeginRecordingMsg`] of every recording, always replayed.
+    recording_infos: Vec<(RecordingId, u64, Vec<u8>)>,
+
+    /// The most recently sent packets, oldest first.
+    recent: VecDeque<(u64, Vec<u8>)>,
+    num_recent_bytes: u64,
+    max_recent_bytes: u64,
+}
+
+impl Replay {
+    fn new(max_recent_bytes: u64) -> Self {
+        Self {
+            next_seq: 0,
+            recording_infos: Vec::new(),
+            recent: VecDeque::new(),
+            num_recent_bytes: 0,
+            max_recent_bytes,
+        }
+    }
+
+    /// The sequence number of a new packet.
+    fn take_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
+    fn set_recording_info(&mut self, recording_id: RecordingId, seq: u64, packet: Vec<u8>) {
+        if let Some((_, cur_seq, cur_packet)) = self
+            .recording_infos
+            .iter_mut()
+            .find(|(cur_recording_id, _, _)| *cur_recording_id == recording_id)
+        {
+            *cur_seq = seq;
+            *cur_packet = packet;
+        } else {
+            self.recording_infos.push((recording_id, seq, packet));
+        }
+    }
+
+    /// Keeps a packet that was just sent, forgetting about the oldest ones if needed.
+    fn push(&mut self, seq: u64, packet: Vec<u8>) {
+        if self.max_recent_bytes == 0 {
+            return;
+        }
+
+        self.num_recent_bytes += packet.len() as u64;
+        self.recent.push_back((seq, packet));
+        while self.num_recent_bytes > self.max_recent_bytes {
+            let Some((_, oldest)) = self.recent.pop_front() else {
+                break;
+            };
+            self.num_recent_bytes -= oldest.len() as u64;
+        }
+    }
+
+    /// Gives new sequence numbers to all the packets, recording infos first, so that they can be
+    /// sent to a new server in that order.
+    fn renumber(&mut self) {
+        let seqs = self
+            .recording_infos
+            .iter_mut()
+            .map(|(_, seq, _)| seq)
+            .chain(self.recent.iter_mut().map(|(seq, _)| seq));
+        for seq in seqs {
+            *seq = self.next_seq;
+            self.next_seq += 1;
+        }
+    }
+
+    /// The packets starting at sequence number `next_seq`, in order.
+    fn packets_from(&self, next_seq: u64) -> Vec<(u64, &[u8])> {
+        let mut packets: Vec<_> = self
+            .recording_infos
+            .iter()
+            .map(|(_, seq, packet)| (*seq, packet.as_slice()))
+            .chain(
+                self.recent
+                    .iter()
+                    .map(|(seq, packet)| (*seq, packet.as_slice())),
+            )
+            .filter(|(seq, _)| next_seq <= *seq)
+            .collect();
+        packets.sort_by_key(|(seq, _)| *seq);
+        packets
+    }
+}
+
 fn tcp_sender(
-    addr: SocketAddr,
-    packet_rx: &Receiver<PacketMsg>,
+    mut tcp_client: crate::tcp_client::TcpClient,
+    budget: &MemoryBudget,
+    mut replay: Replay,
+    packets: &Pipe<PacketMsg>,
     quit_rx: &Receiver<InterruptMsg>,
     flushed_tx: &Sender<FlushedMsg>,
 ) {
-    let mut tcp_client = crate::tcp_client::TcpClient::new(addr);
     // Once this flag has been set, we will drop all messages if the tcp_client is
     // no longer connected.
     let mut drop_if_disconnected = false;
 
     loop {
         select! {
-            recv(packet_rx) -> packet_msg => {
-                if let Ok(packet_msg) = packet_msg {
-                    match packet_msg {
+            recv(packets.doorbell()) -> _ => {
+                if let Some(packet_msg) = packets.pop() {
+                    let interrupt = match packet_msg {
                         PacketMsg::Packet(packet) => {
-                            match send_until_success(&mut tcp_client, drop_if_disconnected, &packet, quit_rx) {
-                                Some(InterruptMsg::Quit) => {return;}
-                                Some(InterruptMsg::DropIfDisconnected) => {
-                                    drop_if_disconnected = true;
-                                }
-                                None => {}
-                            }
+                            let mut seq = replay.take_seq();
+                            let interrupt = send_until_success(&mut tcp_client, drop_if_disconnected, &mut replay, &mut seq, &packet, quit_rx);
+                            budget.release(packet.len() as _);
+                            replay.push(seq, packet);
+                            interrupt
+                        }
+                        PacketMsg::RecordingInfo(recording_id, packet) => {
+                            let mut seq = replay.take_seq();
+                            let interrupt = send_until_success(&mut tcp_client, drop_if_disconnected, &mut replay, &mut seq, &packet, quit_rx);
+                            budget.release(packet.len() as _);
+                            replay.set_recording_info(recording_id, seq, packet);
+                            interrupt
                         }
                         PacketMsg::Flush => {
                             tcp_client.flush();
                             flushed_tx
                                 .send(FlushedMsg)
                                 .expect("Main thread should still be alive");
+                            None
+                        }
+                    };
+                    match interrupt {
+                        Some(InterruptMsg::Quit) => {return;}
+                        Some(InterruptMsg::DropIfDisconnected) => {
+                            drop_if_disconnected = true;
                         }
+                        None => {}
                     }
-                } else {
-                    return; // channel has closed
                 }
             },
             recv(quit_rx) -> quit_msg => { match quit_msg {
@@ -269,14 +779,69 @@ fn tcp_sender(
                     drop_if_disconnected = true;
                 }
                 _ => return,
-            }}
+            }},
+        }
+    }
+}
+
+/// Sends the given packet, after replaying whatever the server needs to know if this requires
+/// reconnecting.
+///
+/// A new server is sent everything again, under new sequence numbers (hence `seq` may change),
+/// while the same server is only sent again what it didn't receive before the connection was lost.
+fn send_packet(
+    tcp_client: &mut crate::tcp_client::TcpClient,
+    replay: &mut Replay,
+    seq: &mut u64,
+    packet: &[u8],
+) -> anyhow::Result<()> {
+    let server_id = tcp_client.server_id();
+    let was_connected = tcp_client.is_connected();
+    tcp_client.connect()?;
+
+    if server_id.is_some() && tcp_client.server_id() != server_id {
+        replay.renumber();
+        *seq = replay.take_seq();
+
+        let replayed = replay.packets_from(0);
+        re_log::info!(
+            "Reconnected to a new Rerun server, replaying {} packets…",
+            replayed.len()
+        );
+        for (replayed_seq, replayed) in replayed {
+            tcp_client.send(replayed_seq, replayed)?;
+        }
+    } else if server_id.is_some() && !was_connected {
+        let next_seq = tcp_client.server_next_seq();
+        let missed = replay.packets_from(next_seq);
+
+        let first_kept_seq = missed.first().map_or(*seq, |(missed_seq, _)| *missed_seq);
+        if next_seq < first_kept_seq {
+            re_log::warn!(
+                "Reconnected to the Rerun server, which lost {} packets that are no longer kept \
+                around. Increase `ClientOptions::max_replay_bytes` to keep more of them.",
+                first_kept_seq - next_seq
+            );
+        }
+
+        re_log::debug!(
+            "Reconnected to the Rerun server, sending {} packets again…",
+            missed.len()
+        );
+        for (missed_seq, missed) in missed {
+            tcp_client.send(missed_seq, missed)?;
         }
     }
+
+    // If the server already got it before we lost the connection, it ignores it.
+    tcp_client.send(*seq, packet)
 }
 
 fn send_until_success(
     tcp_client: &mut crate::tcp_client::TcpClient,
     drop_if_disconnected: bool,
+    replay: &mut Replay,
+    seq: &mut u64,
     packet: &[u8],
     quit_rx: &Receiver<InterruptMsg>,
 ) -> Option<InterruptMsg> {
@@ -286,7 +851,7 @@ fn send_until_success(
         return None;
     }
 
-    if let Err(err) = tcp_client.send(packet) {
+    if let Err(err) = send_packet(tcp_client, replay, seq, packet) {
         if drop_if_disconnected {
             re_log::debug_once!("Dropping messages because we're disconnected.");
             return None;
@@ -303,7 +868,7 @@ fn send_until_success(
                     return Some(_quit_msg.unwrap_or(InterruptMsg::Quit));
                 }
                 default(std::time::Duration::from_millis(sleep_ms)) => {
-                    if let Err(new_err) = tcp_client.send(packet) {
+                    if let Err(new_err) = send_packet(tcp_client, replay, seq, packet) {
                         const MAX_SLEEP_MS : u64 = 3000;
 
                         sleep_ms = (sleep_ms * 2).min(MAX_SLEEP_MS);
@@ -322,3 +887,321 @@ fn send_until_success(
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read as _, Write as _};
+    use std::net::{TcpListener, TcpStream};
+
+    use re_log_types::{
+        ApplicationId, BeginRecordingMsg, EntityPathOpMsg, PathOp, RecordingInfo, RecordingSource,
+        Time, TimePoint,
+    };
+
+    use super::*;
+
+    /// A stand-in for [`crate::serve`] that can be killed and restarted at will.
+    struct FakeServer {
+        stream: TcpStream,
+
+        /// See [`crate::Hello::next_seq`].
+        next_seq: u64,
+    }
+
+    impl FakeServer {
+        /// Accepts whichever client is on the other end of `stream`, as the server with the given
+        /// [`crate::Hello::server_id`], which already received the packets before `next_seq`.
+        fn new(mut stream: TcpStream, server_id: u64, next_seq: u64) -> Self {
+            stream.set_nonblocking(false).unwrap();
+
+            let mut prefix = [0_u8; crate::HELLO_PREFIX_SIZE];
+            stream.read_exact(&mut prefix).unwrap();
+            let (_, crate_version, len) = crate::Hello::parse_prefix(prefix);
+            let mut rest = vec![0_u8; len as usize];
+            stream.read_exact(&mut rest).unwrap();
+            let client_hello = crate::Hello::parse_rest(crate_version, &rest).unwrap();
+
+            stream
+                .write_all(&crate::encode_reply(
+                    &client_hello,
+                    server_id,
+                    next_seq,
+                    None,
+                ))
+                .unwrap();
+
+            Self { stream, next_seq }
+        }
+
+        fn send_event(&mut self, event: &ViewerEvent) {
+            let bytes = event.to_bytes();
+            self.stream
+                .write_all(&(bytes.len() as u32).to_le_bytes())
+                .unwrap();
+            self.stream.write_all(&bytes).unwrap();
+        }
+
+        /// Receives the next packet, skipping those already received.
+        fn recv(&mut self) -> Vec<LogMsg> {
+            loop {
+                let mut packet_size = [0_u8; 4];
+                self.stream.read_exact(&mut packet_size).unwrap();
+                let mut seq = [0_u8; 8];
+                self.stream.read_exact(&mut seq).unwrap();
+                let seq = u64::from_le_bytes(seq);
+                let mut packet = vec![0_u8; u32::from_le_bytes(packet_size) as usize];
+                self.stream.read_exact(&mut packet).unwrap();
+
+                if self.next_seq <= seq {
+                    self.next_seq = seq + 1;
+                    return re_log_encoding::decoder::decode_bytes(&packet).unwrap();
+                }
+            }
+        }
+
+        /// Receives messages until the one with the given `row_id`, and returns the row ids of
+        /// all the path operations received along the way.
+        fn recv_until(&mut self, row_id: RowId) -> Vec<RowId> {
+            let mut row_ids = Vec::new();
+            while row_ids.last() != Some(&row_id) {
+                for msg in self.recv() {
+                    if let LogMsg::EntityPathOpMsg(_, msg) = msg {
+                        row_ids.push(msg.row_id);
+                    }
+                }
+            }
+            row_ids
+        }
+    }
+
+    fn begin_recording(recording_id: RecordingId) -> LogMsg {
+        LogMsg::BeginRecordingMsg(BeginRecordingMsg {
+            row_id: RowId::random(),
+            info: RecordingInfo {
+                application_id: ApplicationId::unknown(),
+                recording_id,
+                is_official_example: false,
+                started: Time::now(),
+                recording_source: RecordingSource::Unknown,
+            },
+        })
+    }
+
+    fn clear(recording_id: RecordingId, row_id: RowId) -> LogMsg {
+        LogMsg::EntityPathOpMsg(
+            recording_id,
+            EntityPathOpMsg {
+                row_id,
+                time_point: TimePoint::default(),
+                path_op: PathOp::clear(false, "points".into()),
+            },
+        )
+    }
+
+    /// Kills the server listening on `listener`, and restarts it while the client still believes
+    /// it's connected to it.
+    ///
+    /// Returns the new listener and the stream of the client once it has reconnected, along with
+    /// the row ids of the messages sent in the meantime.
+    fn restart_server(
+        listener: TcpListener,
+        client: &Client,
+        recording_id: RecordingId,
+    ) -> (TcpListener, TcpStream, Vec<RowId>) {
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let listener = TcpListener::bind(addr).unwrap();
+        listener.set_nonblocking(true).unwrap();
+
+        // Messages sent right after the restart might go down the old, dead connection.
+        let mut row_ids = Vec::new();
+        let stream = loop {
+            let row_id = RowId::random();
+            client.send(clear(recording_id, row_id));
+            row_ids.push(row_id);
+
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                Err(err) => panic!("{err}"),
+            }
+        };
+
+        (listener, stream, row_ids)
+    }
+
+    #[test]
+    fn reconnect_and_replay() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = Client::new_with_options(
+            addr,
+            ClientOptions {
+                max_replay_bytes: 1024 * 1024,
+                ..Default::default()
+            },
+        );
+
+        let recording_id = RecordingId::random();
+        let mut row_ids: Vec<_> = (0..3).map(|_| RowId::random()).collect();
+        client.send(begin_recording(recording_id));
+        for row_id in &row_ids {
+            client.send(clear(recording_id, *row_id));
+        }
+
+        let mut server = FakeServer::new(listener.accept().unwrap().0, 1, 0);
+        assert!(matches!(server.recv()[0], LogMsg::BeginRecordingMsg(_)));
+        assert_eq!(row_ids, server.recv_until(row_ids[2]));
+
+        drop(server);
+        let (listener, stream, new_row_ids) = restart_server(listener, &client, recording_id);
+        row_ids.extend(new_row_ids);
+
+        let last_row_id = RowId::random();
+        client.send(clear(recording_id, last_row_id));
+        row_ids.push(last_row_id);
+
+        // The new server gets the recording info first, then everything it might have missed.
+        let mut server = FakeServer::new(stream, 2, 0);
+        assert!(matches!(server.recv()[0], LogMsg::BeginRecordingMsg(_)));
+        let received = server.recv_until(last_row_id);
+        for row_id in &row_ids {
+            assert!(received.contains(row_id), "{row_id} was lost");
+        }
+
+        drop(server);
+        drop(listener);
+        client.drop_if_disconnected();
+    }
+
+    #[test]
+    fn reconnect_to_same_server_and_resend_what_it_missed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let client = Client::new_with_options(
+            listener.local_addr().unwrap(),
+            ClientOptions {
+                max_replay_bytes: 1024 * 1024,
+                ..Default::default()
+            },
+        );
+
+        let recording_id = RecordingId::random();
+        let row_ids: Vec<_> = (0..3).map(|_| RowId::random()).collect();
+        client.send(begin_recording(recording_id));
+        for row_id in &row_ids {
+            client.send(clear(recording_id, *row_id));
+        }
+
+        let mut server = FakeServer::new(listener.accept().unwrap().0, 1, 0);
+        assert!(matches!(server.recv()[0], LogMsg::BeginRecordingMsg(_)));
+        assert_eq!(row_ids, server.recv_until(row_ids[2]));
+
+        // Same server id: it's the same server, which already has everything it received, but
+        // none of what was left unread when the connection was lost.
+        let next_seq = server.next_seq;
+        drop(server);
+        let (listener, stream, new_row_ids) = restart_server(listener, &client, recording_id);
+
+        let last_row_id = RowId::random();
+        client.send(clear(recording_id, last_row_id));
+
+        let mut server = FakeServer::new(stream, 1, next_seq);
+        let received = server.recv_until(last_row_id);
+        for row_id in &row_ids {
+            assert!(!received.contains(row_id), "{row_id} was replayed");
+        }
+        for row_id in &new_row_ids {
+            assert!(received.contains(row_id), "{row_id} was lost");
+        }
+
+        drop(server);
+        drop(listener);
+        client.drop_if_disconnected();
+    }
+
+    #[test]
+    fn receive_viewer_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = Client::new(listener.local_addr().unwrap());
+
+        let recording_id = RecordingId::random();
+        client.send(begin_recording(recording_id));
+
+        let mut server = FakeServer::new(listener.accept().unwrap().0, 1, 0);
+        assert!(matches!(server.recv()[0], LogMsg::BeginRecordingMsg(_)));
+
+        let events = [
+            ViewerEvent {
+                recording_id: Some(recording_id),
+                kind: crate::ViewerEventKind::SelectionChanged(vec![crate::EntityInstance {
+                    entity_path: "world/points".into(),
+                    instance_key: re_log_types::component_types::InstanceKey(3),
+                }]),
+            },
+            ViewerEvent {
+                recording_id: None,
+                kind: crate::ViewerEventKind::ButtonPressed("Reset".to_owned()),
+            },
+        ];
+        for event in &events {
+            server.send_event(event);
+        }
+
+        let timeout = std::time::Duration::from_secs(10);
+        for event in events {
+            assert_eq!(event, client.viewer_events().recv_timeout(timeout).unwrap());
+        }
+
+        drop(server);
+        client.drop_if_disconnected();
+    }
+
+    #[test]
+    fn pipe_keeps_order_when_dropping() {
+        let pipe = Pipe::new();
+        for i in 0..5 {
+            pipe.push(i);
+        }
+
+        assert_eq!(Some(1), pipe.remove_first(|i| i % 2 == 1));
+        assert_eq!(Some(3), pipe.remove_first(|i| i % 2 == 1));
+        assert_eq!(None, pipe.remove_first(|i| *i > 10));
+
+        // The rings for the removed items are still there, and just find nothing.
+        let mut popped = Vec::new();
+        while pipe.doorbell().try_recv().is_ok() {
+            popped.extend(pipe.pop());
+        }
+        assert_eq!(vec![0, 2, 4], popped);
+    }
+
+    #[test]
+    fn drop_newest_when_out_of_budget() {
+        // Nothing is listening there, so nothing ever leaves the client.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let client = Client::new_with_options(
+            addr,
+            ClientOptions {
+                max_bytes_in_flight: Some(1),
+                back_pressure: BackPressurePolicy::DropNewest,
+                ..Default::default()
+            },
+        );
+
+        // The first message always fits, no matter the budget.
+        for _ in 0..10 {
+            client.send(LogMsg::Goodbye(RowId::random()));
+        }
+        assert_eq!(9, client.dropped_messages().num_msgs());
+
+        client.drop_if_disconnected();
+    }
+}
@@ -0,0 +1,476 @@
+//! The handshake that opens every connection between a logging SDK and a server.
+//!
+//! The client starts by sending a [`Hello`], to which the server answers with its own [`Hello`]
+//! followed by its verdict:
+//!
+//! ```text
+//! hello:   PROTOCOL_VERSION: u16 | crate version: [u8; 4] | len: u32 | rest: [u8; len]
+//! rest:    compression: u8 | num_codecs: u8 | codecs: [u8; num_codecs] | auth token: str
+//!          | num_buttons: u16 | buttons: [str; num_buttons] | server id: u64
+//!          | session id: u64 | next seq: u64
+//! str:     len: u16 | utf8: [u8; len]
+//! verdict: len: u32 | reason for rejecting the client: [u8; len] (empty if accepted)
+//! ```
+//!
+//! Once accepted, the client sends its packets, while the server sends [`crate::ViewerEvent`]s
+//! whenever it has some, see [`crate::ViewerEvent::to_bytes`]:
+//!
+//! ```text
+//! packet:  len: u32 | seq: u64 | packet: [u8; len]
+//! ```
+//!
+//! The sequence numbers of the packets of a session only ever go up, so a server acknowledges
+//! everything it received by answering a reconnecting client with the [`Hello::next_seq`] it
+//! expects next, and ignores any packet it already received.
+//!
+//! The start of a [`Hello`] (protocol and crate versions) will never change, so that both sides
+//! can always tell what the other one is running, even across incompatible versions.
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use re_build_info::CrateVersion;
+use re_log_encoding::Compression;
+
+use crate::ViewerEvent;
+
+/// The version of the Rerun crates on this side of the connection.
+pub const CRATE_VERSION: CrateVersion = CrateVersion::parse(env!("CARGO_PKG_VERSION"));
+
+/// Size of the part of a [`Hello`] that never changes: protocol version, crate version and the
+/// length of the rest.
+pub const HELLO_PREFIX_SIZE: usize = 2 + 4 + 4;
+
+/// What each side of a connection tells the other about itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hello {
+    pub protocol_version: u16,
+    pub crate_version: CrateVersion,
+
+    /// The compression of the messages sent by this side.
+    pub compression: Compression,
+
+    /// All the compressions this side can decode.
+    pub codecs: Vec<Compression>,
+
+    /// The token the client authenticates itself with, if any.
+    ///
+    /// Always `None` for servers.
+    pub auth_token: Option<String>,
+
+    /// The buttons the client offers the user of the viewer, see
+    /// [`crate::ViewerEventKind::ButtonPressed`].
+    ///
+    /// Always empty for servers.
+    pub buttons: Vec<String>,
+
+    /// Picked at random whenever a server starts, so that a client that reconnects can tell a
+    /// new server (which must be sent everything again) from the one it lost its connection to.
+    ///
+    /// Always 0 for clients.
+    pub server_id: u64,
+
+    /// Picked at random whenever a client is created, and kept across its reconnections, so that
+    /// a server can tell which of its packets it already received.
+    ///
+    /// Always 0 for servers.
+    pub session_id: u64,
+
+    /// The sequence number of the next packet the server expects from the client's session,
+    /// i.e. everything before it was received.
+    ///
+    /// Always 0 for clients.
+    pub next_seq: u64,
+}
+
+impl Hello {
+    /// What we are running, compressing our messages with the given compression.
+    pub fn local(compression: Compression) -> Self {
+        Self {
+            protocol_version: crate::PROTOCOL_VERSION,
+            crate_version: CRATE_VERSION,
+            compression,
+            codecs: Compression::ALL.to_vec(),
+            auth_token: None,
+            buttons: Vec::new(),
+            server_id: 0,
+            session_id: 0,
+            next_seq: 0,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut rest = Vec::new();
+        rest.push(self.compression as u8);
+        rest.push(self.codecs.len() as u8);
+        rest.extend(self.codecs.iter().map(|codec| *codec as u8));
+        write_str(&mut rest, self.auth_token.as_deref().unwrap_or_default());
+        rest.extend_from_slice(&(self.buttons.len() as u16).to_le_bytes());
+        for button in &self.buttons {
+            write_str(&mut rest, button);
+        }
+        rest.extend_from_slice(&self.server_id.to_le_bytes());
+        rest.extend_from_slice(&self.session_id.to_le_bytes());
+        rest.extend_from_slice(&self.next_seq.to_le_bytes());
+
+        let mut bytes = Vec::with_capacity(HELLO_PREFIX_SIZE + rest.len());
+        bytes.extend_from_slice(&self.protocol_version.to_le_bytes());
+        bytes.extend_from_slice(&self.crate_version.to_bytes());
+        bytes.extend_from_slice(&(rest.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&rest);
+        bytes
+    }
+
+    /// Parses the part of a [`Hello`] that never changes.
+    ///
+    /// Returns the protocol version, the crate version, and the length of the rest.
+    pub fn parse_prefix(bytes: [u8; HELLO_PREFIX_SIZE]) -> (u16, CrateVersion, u32) {
+        let [v0, v1, c0, c1, c2, c3, l0, l1, l2, l3] = bytes;
+        (
+            u16::from_le_bytes([v0, v1]),
+            CrateVersion::from_bytes([c0, c1, c2, c3]),
+            u32::from_le_bytes([l0, l1, l2, l3]),
+        )
+    }
+
+    /// Parses the rest of a [`Hello`], as sent by a peer speaking our own protocol version.
+    ///
+    /// Codecs we don't know about are ignored.
+    pub fn parse_rest(crate_version: CrateVersion, rest: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader(rest);
+
+        let compression = Compression::from_u8(reader.read(1)?[0])?;
+        let num_codecs = reader.read(1)?[0];
+        let codecs = reader
+            .read(num_codecs as usize)?
+            .iter()
+            .filter_map(|codec| Compression::from_u8(*codec).ok())
+            .collect();
+        let auth_token = reader.read_str()?;
+        let num_buttons = u16::from_le_bytes(reader.read(2)?.try_into()?);
+        let buttons = (0..num_buttons)
+            .map(|_| reader.read_str().map(ToOwned::to_owned))
+            .collect::<anyhow::Result<_>>()?;
+        let server_id = u64::from_le_bytes(reader.read(8)?.try_into()?);
+        let session_id = u64::from_le_bytes(reader.read(8)?.try_into()?);
+        let next_seq = u64::from_le_bytes(reader.read(8)?.try_into()?);
+
+        Ok(Self {
+            protocol_version: crate::PROTOCOL_VERSION,
+            crate_version,
+            compression,
+            codecs,
+            auth_token: (!auth_token.is_empty()).then(|| auth_token.to_owned()),
+            buttons,
+            server_id,
+            session_id,
+            next_seq,
+        })
+    }
+
+    /// Why the server should reject a client that said this, if it should.
+    ///
+    /// If the server expects an auth token, the client must have sent that exact token.
+    pub fn reject_reason(&self, expected_auth_token: Option<&str>) -> Option<String> {
+        let local = Self::local(Default::default());
+
+        if self.protocol_version != local.protocol_version {
+            let age = if self.protocol_version < local.protocol_version {
+                "an older"
+            } else {
+                "a newer"
+            };
+            return Some(format!(
+                "Rerun SDK {} is using {age} protocol version ({}) than the Rerun server {} ({})",
+                self.crate_version,
+                self.protocol_version,
+                local.crate_version,
+                local.protocol_version,
+            ));
+        }
+
+        if let Some(expected_auth_token) = expected_auth_token {
+            let auth_token = self.auth_token.as_deref().unwrap_or_default();
+            // Constant time, so as to not leak how much of a guess was right.
+            if !constant_time_eq::constant_time_eq(
+                auth_token.as_bytes(),
+                expected_auth_token.as_bytes(),
+            ) {
+                return Some(if self.auth_token.is_none() {
+                    "The Rerun server requires an auth token".to_owned()
+                } else {
+                    "Invalid auth token".to_owned()
+                });
+            }
+        }
+
+        if !self.crate_version.is_compatible_with(local.crate_version) {
+            return Some(format!(
+                "Rerun SDK {} is not compatible with the Rerun server {}. \
+                Make sure both are running the same version of Rerun",
+                self.crate_version, local.crate_version,
+            ));
+        }
+
+        if !local.codecs.contains(&self.compression) {
+            return Some(format!(
+                "The Rerun server doesn't support {} compression (supported: {})",
+                self.compression,
+                format_codecs(&local.codecs),
+            ));
+        }
+
+        None
+    }
+}
+
+/// What a server answers to a client that said `client_hello`: the server's own [`Hello`] (which
+/// acknowledges the client's compression and carries the given [`Hello::server_id`] and
+/// [`Hello::next_seq`]), followed by the verdict.
+pub fn encode_reply(
+    client_hello: &Hello,
+    server_id: u64,
+    next_seq: u64,
+    reject_reason: Option<&str>,
+) -> Vec<u8> {
+    let reject_reason = reject_reason.unwrap_or_default().as_bytes();
+    let server_hello = Hello {
+        server_id,
+        next_seq,
+        ..Hello::local(client_hello.compression)
+    };
+    let mut bytes = server_hello.to_bytes();
+    bytes.extend_from_slice(&(reject_reason.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(reject_reason);
+    bytes
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    let mut len = s.len().min(u16::MAX as usize);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    bytes.extend_from_slice(&(len as u16).to_le_bytes());
+    bytes.extend_from_slice(&s.as_bytes()[..len]);
+}
+
+/// Reads the rest of a [`Hello`], front to back.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(self.0.len() >= len, "Truncated handshake");
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn read_str(&mut self) -> anyhow::Result<&'a str> {
+        let len = u16::from_le_bytes(self.read(2)?.try_into()?);
+        std::str::from_utf8(self.read(len as usize)?)
+            .map_err(|err| anyhow::anyhow!("Invalid string in handshake: {err}"))
+    }
+}
+
+fn format_codecs(codecs: &[Compression]) -> String {
+    codecs
+        .iter()
+        .map(|codec| codec.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// ----------------------------------------------------------------------------
+
+/// What a server knows about a connected SDK.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// `None` if the address of the peer couldn't be determined.
+    pub addr: Option<SocketAddr>,
+
+    /// What the peer said, minus its auth token.
+    pub hello: Hello,
+}
+
+impl PeerInfo {
+    pub fn new(addr: Option<SocketAddr>, hello: Hello) -> Self {
+        Self {
+            addr,
+            hello: Hello {
+                auth_token: None,
+                ..hello
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for PeerInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { addr, hello } = self;
+        if let Some(addr) = addr {
+            write!(f, "{addr}: ")?;
+        }
+        write!(
+            f,
+            "Rerun SDK {} (protocol version {}, {} compression)",
+            hello.crate_version, hello.protocol_version, hello.compression
+        )
+    }
+}
+
+/// The SDKs connected to a server, shared between the server and whoever wants to display them
+/// or send them [`ViewerEvent`]s.
+///
+/// Cheap to clone.
+#[derive(Clone, Default)]
+pub struct ConnectedPeers(Arc<Mutex<Peers>>);
+
+#[derive(Default)]
+struct Peers {
+    next_id: u64,
+    peers: Vec<Peer>,
+}
+
+struct Peer {
+    id: u64,
+    info: PeerInfo,
+
+    /// Encoded [`ViewerEvent`]s, on their way to the peer.
+    events_tx: tokio::sync::mpsc::Sender<Arc<[u8]>>,
+}
+
+impl ConnectedPeers {
+    /// How many events can be waiting to be sent to a peer before we start dropping them.
+    const MAX_PENDING_EVENTS: usize = 1024;
+
+    /// The currently connected peers and their ids, in order of connection.
+    pub fn peers(&self) -> Vec<(u64, PeerInfo)> {
+        self.0
+            .lock()
+            .unwrap()
+            .peers
+            .iter()
+            .map(|peer| (peer.id, peer.info.clone()))
+            .collect()
+    }
+
+    /// Returns an id to [`Self::remove`] the peer with, and the encoded events to send to it,
+    /// see [`ViewerEvent::to_bytes`].
+    pub fn add(&self, info: PeerInfo) -> (u64, tokio::sync::mpsc::Receiver<Arc<[u8]>>) {
+        let (events_tx, events_rx) = tokio::sync::mpsc::channel(Self::MAX_PENDING_EVENTS);
+
+        let mut peers = self.0.lock().unwrap();
+        let id = peers.next_id;
+        peers.next_id += 1;
+        peers.peers.push(Peer {
+            id,
+            info,
+            events_tx,
+        });
+
+        (id, events_rx)
+    }
+
+    pub fn remove(&self, id: u64) {
+        self.0.lock().unwrap().peers.retain(|peer| peer.id != id);
+    }
+
+    /// Sends the event to all the connected peers.
+    pub fn send_event(&self, event: &ViewerEvent) {
+        let peers = self.0.lock().unwrap();
+        if peers.peers.is_empty() {
+            return;
+        }
+
+        let bytes: Arc<[u8]> = event.to_bytes().into();
+        for peer in &peers.peers {
+            peer.send(bytes.clone());
+        }
+    }
+
+    /// Sends the event to the peer with the given id, if it is still connected.
+    pub fn send_event_to(&self, id: u64, event: &ViewerEvent) {
+        let peers = self.0.lock().unwrap();
+        if let Some(peer) = peers.peers.iter().find(|peer| peer.id == id) {
+            peer.send(event.to_bytes().into());
+        }
+    }
+}
+
+impl Peer {
+    fn send(&self, bytes: Arc<[u8]>) {
+        if let Err(tokio::sync::mpsc::error::TrySendError::Full(_)) = self.events_tx.try_send(bytes)
+        {
+            re_log::warn_once!(
+                "{} isn't keeping up with viewer events, dropping some",
+                self.info
+            );
+        }
+    }
+}
+
+impl std::fmt::Debug for ConnectedPeers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.peers().into_iter().map(|(_, info)| info))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello_roundtrip() {
+        let hello = Hello {
+            buttons: vec!["Reset".to_owned(), "Étape suivante".to_owned()],
+            server_id: 42,
+            session_id: 7,
+            next_seq: 1_000,
+            ..Hello::local(Compression::Lz4)
+        };
+        let bytes = hello.to_bytes();
+
+        let (protocol_version, crate_version, len) =
+            Hello::parse_prefix(bytes[..HELLO_PREFIX_SIZE].try_into().unwrap());
+        assert_eq!(crate::PROTOCOL_VERSION, protocol_version);
+        assert_eq!(bytes.len(), HELLO_PREFIX_SIZE + len as usize);
+
+        let parsed = Hello::parse_rest(crate_version, &bytes[HELLO_PREFIX_SIZE..]).unwrap();
+        assert_eq!(hello, parsed);
+        assert_eq!(None, parsed.reject_reason(None));
+
+        let incompatible = Hello {
+            crate_version: CrateVersion::new(0, 1, 0),
+            ..hello
+        };
+        assert!(incompatible.reject_reason(None).is_some());
+    }
+
+    #[test]
+    fn hello_auth_token() {
+        let hello = Hello {
+            auth_token: Some("secret".to_owned()),
+            ..Hello::local(Compression::Off)
+        };
+        let bytes = hello.to_bytes();
+        let (_, crate_version, _) =
+            Hello::parse_prefix(bytes[..HELLO_PREFIX_SIZE].try_into().unwrap());
+        let parsed = Hello::parse_rest(crate_version, &bytes[HELLO_PREFIX_SIZE..]).unwrap();
+        assert_eq!(hello, parsed);
+
+        assert_eq!(None, parsed.reject_reason(None));
+        assert_eq!(None, parsed.reject_reason(Some("secret")));
+        assert!(parsed.reject_reason(Some("secreT")).is_some());
+        assert!(parsed.reject_reason(Some("secret2")).is_some());
+        assert!(Hello::local(Compression::Off)
+            .reject_reason(Some("secret"))
+            .is_some());
+
+        let peer = PeerInfo::new(None, parsed);
+        assert_eq!(None, peer.hello.auth_token);
+    }
+}
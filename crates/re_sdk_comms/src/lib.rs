@@ -4,6 +4,23 @@
 #![doc = document_features::document_features!()]
 //!
 
+mod handshake;
+mod viewer_event;
+
+pub use handshake::{
+    encode_reply, ConnectedPeers, Hello, PeerInfo, CRATE_VERSION, HELLO_PREFIX_SIZE,
+};
+pub use viewer_event::{EntityInstance, ViewerEvent, ViewerEventKind};
+
+#[cfg(feature = "tls")]
+mod tls;
+
+#[cfg(all(feature = "tls", feature = "client"))]
+pub use tls::ClientTls;
+
+#[cfg(all(feature = "tls", feature = "server"))]
+pub use tls::load_server_config;
+
 #[cfg(feature = "client")]
 pub(crate) mod tcp_client;
 
@@ -11,7 +28,7 @@ pub(crate) mod tcp_client;
 mod buffered_client;
 
 #[cfg(feature = "client")]
-pub use buffered_client::Client;
+pub use buffered_client::{BackPressurePolicy, Client, ClientOptions, DroppedMessages};
 
 #[cfg(feature = "server")]
 mod server;
@@ -21,7 +38,12 @@ pub use server::{serve, ServerOptions};
 
 pub type Result<T> = anyhow::Result<T>;
 
-pub const PROTOCOL_VERSION: u16 = 0;
+/// Version of the protocol spoken between SDKs and servers, see [`Hello`].
+///
+/// Version 0 had no handshake at all, version 1 had no auth token, version 2 had no
+/// [`ViewerEvent`]s, version 3 had no [`Hello::server_id`], version 4 had no
+/// [`Hello::session_id`] nor packet sequence numbers.
+pub const PROTOCOL_VERSION: u16 = 5;
 
 pub const DEFAULT_SERVER_PORT: u16 = 9876;
 
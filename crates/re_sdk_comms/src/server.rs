@@ -1,15 +1,25 @@
 //! TODO(emilk): use tokio instead
 
-use std::time::Instant;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use anyhow::Context;
 use rand::{Rng as _, SeedableRng};
 
 use re_log_types::{LogMsg, TimePoint, TimeType, TimelineName};
 use re_smart_channel::{Receiver, Sender};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use crate::{ConnectedPeers, Hello, PeerInfo, HELLO_PREFIX_SIZE};
+
+#[derive(Clone)]
 pub struct ServerOptions {
     /// If the latency in the [`LogMsg`] channel is greater than this,
     /// then start dropping messages in order to keep up.
@@ -17,6 +27,16 @@ pub struct ServerOptions {
 
     /// Turns `info`-level logs into `debug`-level logs.
     pub quiet: bool,
+
+    /// Keeps track of the SDKs that are currently connected, e.g. for display in the viewer.
+    pub peers: ConnectedPeers,
+
+    /// If set, only the SDKs that authenticate with this exact token are accepted.
+    pub auth_token: Option<String>,
+
+    /// Encrypt all connections with TLS, see [`crate::load_server_config`].
+    #[cfg(feature = "tls")]
+    pub tls: Option<std::sync::Arc<rustls::ServerConfig>>,
 }
 
 impl Default for ServerOptions {
@@ -24,13 +44,79 @@ impl Default for ServerOptions {
         Self {
             max_latency_sec: f32::INFINITY,
             quiet: false,
+            peers: Default::default(),
+            auth_token: None,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 }
 
+impl std::fmt::Debug for ServerOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            max_latency_sec,
+            quiet,
+            peers,
+            auth_token,
+            #[cfg(feature = "tls")]
+            tls,
+        } = self;
+
+        let mut s = f.debug_struct("ServerOptions");
+        s.field("max_latency_sec", max_latency_sec)
+            .field("quiet", quiet)
+            .field("peers", peers)
+            .field("auth_token", &auth_token.as_ref().map(|_| "<redacted>"));
+        #[cfg(feature = "tls")]
+        s.field("tls", &tls.is_some());
+        s.finish()
+    }
+}
+
+/// What all the connections to a server share.
+struct ServerState {
+    /// See [`Hello::server_id`].
+    id: u64,
+
+    /// The [`Hello::next_seq`] of every session we received packets from, by
+    /// [`Hello::session_id`].
+    next_seqs: Mutex<HashMap<u64, u64>>,
+}
+
+impl ServerState {
+    fn next_seq(&self, session_id: u64) -> u64 {
+        self.next_seqs
+            .lock()
+            .unwrap()
+            .get(&session_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns `false` if we already received that packet, or one sent after it.
+    ///
+    /// NOTE: the connection a client just gave up on can still be draining when it reconnects,
+    /// so both connections check in here to make sure each packet is only forwarded once.
+    fn receive(&self, session_id: u64, seq: u64) -> bool {
+        let mut next_seqs = self.next_seqs.lock().unwrap();
+        let next_seq = next_seqs.entry(session_id).or_default();
+        if seq < *next_seq {
+            return false;
+        }
+        *next_seq = seq + 1;
+        true
+    }
+
+    fn end_session(&self, session_id: u64) {
+        self.next_seqs.lock().unwrap().remove(&session_id);
+    }
+}
+
 async fn listen_for_new_clients(
     listener: TcpListener,
     options: ServerOptions,
+    server: Arc<ServerState>,
     tx: Sender<LogMsg>,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
 ) {
@@ -44,7 +130,7 @@ async fn listen_for_new_clients(
         match incoming {
             Ok((stream, _)) => {
                 let tx = tx.clone();
-                spawn_client(stream, tx, options);
+                spawn_client(stream, tx, options.clone(), server.clone());
             }
             Err(err) => {
                 re_log::warn!("Failed to accept incoming SDK client: {err}");
@@ -87,58 +173,181 @@ pub async fn serve(
         );
     }
 
-    tokio::spawn(listen_for_new_clients(listener, options, tx, shutdown_rx));
+    let server = Arc::new(ServerState {
+        // Lets the clients that reconnect to us know that we don't have any of their data yet.
+        id: rand::thread_rng().gen_range(1..=u64::MAX),
+        next_seqs: Default::default(),
+    });
+
+    tokio::spawn(listen_for_new_clients(
+        listener,
+        options,
+        server,
+        tx,
+        shutdown_rx,
+    ));
 
     Ok(rx)
 }
 
-fn spawn_client(stream: TcpStream, tx: Sender<LogMsg>, options: ServerOptions) {
+fn spawn_client(
+    stream: TcpStream,
+    tx: Sender<LogMsg>,
+    options: ServerOptions,
+    server: Arc<ServerState>,
+) {
     tokio::spawn(async move {
-        let addr_string = stream
-            .peer_addr()
-            .map_or_else(|_| "(unknown ip)".to_owned(), |addr| addr.to_string());
+        let addr = stream.peer_addr().ok();
+        let addr_string = addr.map_or_else(|| "(unknown ip)".to_owned(), |addr| addr.to_string());
         if options.quiet {
             re_log::debug!("New SDK client connected: {addr_string}");
         } else {
             re_log::info!("New SDK client connected: {addr_string}");
         }
-        if let Err(err) = run_client(stream, &tx, options).await {
+        if let Err(err) = accept_client(stream, addr, &tx, options, &server).await {
             re_log::warn!("Closing connection to client: {err}");
         }
     });
 }
 
+/// Sets up encryption if needed, then runs the client.
+async fn accept_client(
+    stream: TcpStream,
+    addr: Option<SocketAddr>,
+    tx: &Sender<LogMsg>,
+    options: ServerOptions,
+    server: &ServerState,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "tls")]
+    if let Some(tls) = options.tls.clone() {
+        let stream = tokio_rustls::TlsAcceptor::from(tls)
+            .accept(stream)
+            .await
+            .context("TLS handshake failed")?;
+        return run_client(stream, addr, tx, options, server).await;
+    }
+
+    run_client(stream, addr, tx, options, server).await
+}
+
 async fn run_client(
-    mut stream: TcpStream,
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    addr: Option<SocketAddr>,
     tx: &Sender<LogMsg>,
     options: ServerOptions,
+    server: &ServerState,
 ) -> anyhow::Result<()> {
-    #![allow(clippy::read_zero_byte_vec)] // false positive: https://github.com/rust-lang/rust-clippy/issues/9274
+    let client_hello = handshake(&mut stream, options.auth_token.as_deref(), server).await?;
+    let session_id = client_hello.session_id;
+
+    let peer = PeerInfo::new(addr, client_hello);
+    re_log::debug!("SDK handshake complete: {peer}");
+
+    let (peer_id, events_rx) = options.peers.add(peer);
+    let (read_half, write_half) = tokio::io::split(stream);
+    let result = tokio::select! {
+        result = receive_packets(read_half, tx, &options, server, session_id) => result,
+        result = send_events(write_half, events_rx) => result,
+    };
+    options.peers.remove(peer_id);
+    result
+}
 
-    use tokio::io::AsyncReadExt as _;
+/// Forwards the [`crate::ViewerEvent`]s meant for this client, until the client goes away.
+async fn send_events(
+    mut stream: impl AsyncWrite + Unpin,
+    mut events_rx: tokio::sync::mpsc::Receiver<std::sync::Arc<[u8]>>,
+) -> anyhow::Result<()> {
+    use tokio::io::AsyncWriteExt as _;
+
+    while let Some(event) = events_rx.recv().await {
+        stream
+            .write_all(&(event.len() as u32).to_le_bytes())
+            .await?;
+        stream.write_all(&event).await?;
+        stream.flush().await?;
+    }
 
-    let mut client_version = [0_u8; 2];
-    stream.read_exact(&mut client_version).await?;
-    let client_version = u16::from_le_bytes(client_version);
+    // Only happens once the peer has been removed: leave it to `receive_packets` to finish.
+    std::future::pending().await
+}
 
-    match client_version.cmp(&crate::PROTOCOL_VERSION) {
-        std::cmp::Ordering::Less => {
-            anyhow::bail!(
-                "sdk client is using an older protocol version ({}) than the sdk server ({}).",
-                client_version,
-                crate::PROTOCOL_VERSION
-            );
-        }
-        std::cmp::Ordering::Equal => {}
-        std::cmp::Ordering::Greater => {
-            anyhow::bail!(
-                "sdk client is using a newer protocol version ({}) than the sdk server ({}).",
-                client_version,
-                crate::PROTOCOL_VERSION
-            );
+/// Finds out who the client is, and tells it whether we accept it.
+async fn handshake(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    expected_auth_token: Option<&str>,
+    server: &ServerState,
+) -> anyhow::Result<Hello> {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    /// The rest of a [`Hello`] is small, anything bigger than this is garbage.
+    const MAX_HELLO_SIZE: u32 = 64 * 1024;
+
+    let mut prefix = [0_u8; HELLO_PREFIX_SIZE];
+
+    // Clients from before the handshake only send their protocol version, and don't listen.
+    stream.read_exact(&mut prefix[..2]).await?;
+    let client_version = u16::from_le_bytes([prefix[0], prefix[1]]);
+    if client_version == 0 {
+        anyhow::bail!(
+            "sdk client is using an older protocol version ({}) than the sdk server ({}).",
+            client_version,
+            crate::PROTOCOL_VERSION
+        );
+    }
+
+    stream.read_exact(&mut prefix[2..]).await?;
+    let (protocol_version, crate_version, len) = Hello::parse_prefix(prefix);
+    anyhow::ensure!(len <= MAX_HELLO_SIZE, "Invalid handshake of size {len}");
+    let mut rest = vec![0_u8; len as usize];
+    stream.read_exact(&mut rest).await?;
+
+    let client_hello = if protocol_version == crate::PROTOCOL_VERSION {
+        Hello::parse_rest(crate_version, &rest)?
+    } else {
+        // We can't make sense of the rest, but we know enough to reject the client.
+        Hello {
+            protocol_version,
+            crate_version,
+            compression: Default::default(),
+            codecs: Vec::new(),
+            auth_token: None,
+            buttons: Vec::new(),
+            server_id: 0,
+            session_id: 0,
+            next_seq: 0,
         }
+    };
+
+    let reject_reason = client_hello.reject_reason(expected_auth_token);
+    stream
+        .write_all(&crate::encode_reply(
+            &client_hello,
+            server.id,
+            server.next_seq(client_hello.session_id),
+            reject_reason.as_deref(),
+        ))
+        .await?;
+    stream.flush().await?;
+
+    if let Some(reject_reason) = reject_reason {
+        anyhow::bail!("{reject_reason}");
     }
 
+    Ok(client_hello)
+}
+
+async fn receive_packets(
+    mut stream: impl AsyncRead + Unpin,
+    tx: &Sender<LogMsg>,
+    options: &ServerOptions,
+    server: &ServerState,
+    session_id: u64,
+) -> anyhow::Result<()> {
+    #![allow(clippy::read_zero_byte_vec)] // false positive: https://github.com/rust-lang/rust-clippy/issues/9274
+
+    use tokio::io::AsyncReadExt as _;
+
     let mut congestion_manager = CongestionManager::new(options.max_latency_sec);
 
     let mut packet = Vec::new();
@@ -148,16 +357,26 @@ async fn run_client(
         stream.read_exact(&mut packet_size).await?;
         let packet_size = u32::from_le_bytes(packet_size);
 
+        let mut seq = [0_u8; 8];
+        stream.read_exact(&mut seq).await?;
+        let seq = u64::from_le_bytes(seq);
+
         packet.resize(packet_size as usize, 0_u8);
         stream.read_exact(&mut packet).await?;
 
-        re_log::trace!("Received packet of size {packet_size}.");
+        if !server.receive(session_id, seq) {
+            re_log::debug!("Ignoring packet {seq}, which we already received.");
+            continue;
+        }
+
+        re_log::trace!("Received packet {seq} of size {packet_size}.");
 
         congestion_manager.register_latency(tx.latency_sec());
 
         for msg in re_log_encoding::decoder::decode_bytes(&packet)? {
             if matches!(msg, LogMsg::Goodbye(_)) {
                 re_log::debug!("Received goodbye message.");
+                server.end_session(session_id);
                 tx.send(msg)?;
                 return Ok(());
             }
@@ -210,9 +429,10 @@ impl CongestionManager {
         #[allow(clippy::match_same_arms)]
         match msg {
             // we don't want to drop any of these
-            LogMsg::BeginRecordingMsg(_) | LogMsg::EntityPathOpMsg(_, _) | LogMsg::Goodbye(_) => {
-                true
-            }
+            LogMsg::BeginRecordingMsg(_)
+            | LogMsg::EntityPathOpMsg(_, _)
+            | LogMsg::DeleteRowsMsg(_, _)
+            | LogMsg::Goodbye(_) => true,
 
             LogMsg::ArrowMsg(_, arrow_msg) => self.should_send_time_point(&arrow_msg.timepoint_max),
         }
@@ -1,8 +1,143 @@
 use std::{
-    io::Write,
-    net::{SocketAddr, TcpStream},
+    io::{Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream},
 };
 
+use crossbeam::channel::Sender;
+use re_log_encoding::Compression;
+
+use crate::{Hello, ViewerEvent, HELLO_PREFIX_SIZE};
+
+/// How long to wait for the server to answer our [`Hello`].
+const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The stream a [`TcpClient`] talks to the server through, encrypted or not.
+///
+/// Once connected, the [`ViewerEvent`]s are read on their own thread, through
+/// [`Stream::event_reader`], while everything else only ever writes to the stream.
+enum Stream {
+    Plain(TcpStream),
+
+    /// The TLS state is shared with the event reader, which only locks it to decrypt what it has
+    /// already received, never while waiting on the socket.
+    #[cfg(feature = "tls")]
+    Tls(
+        std::sync::Arc<std::sync::Mutex<rustls::ClientConnection>>,
+        TcpStream,
+    ),
+}
+
+impl Stream {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Self::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            Self::Tls(_, stream) => stream,
+        }
+    }
+
+    /// Reads whatever the server sends from now on, blocking until it does.
+    fn event_reader(&self) -> std::io::Result<Box<dyn Read + Send>> {
+        match self {
+            Self::Plain(stream) => Ok(Box::new(stream.try_clone()?)),
+            #[cfg(feature = "tls")]
+            Self::Tls(conn, stream) => Ok(Box::new(TlsReader {
+                conn: conn.clone(),
+                stream: stream.try_clone()?,
+            })),
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        // Wakes up the event reader, which holds on to a clone of the socket.
+        self.tcp().shutdown(Shutdown::Both).ok();
+    }
+}
+
+/// Only used during the handshake: the event reader takes over afterwards.
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(conn, stream) => {
+                rustls::Stream::new(&mut *conn.lock().unwrap(), stream).read(buf)
+            }
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(conn, stream) => {
+                let mut conn = conn.lock().unwrap();
+                let len = conn.writer().write(buf)?;
+                while conn.wants_write() {
+                    conn.write_tls(stream)?;
+                }
+                Ok(len)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Self::Tls(conn, stream) => {
+                let mut conn = conn.lock().unwrap();
+                while conn.wants_write() {
+                    conn.write_tls(stream)?;
+                }
+                stream.flush()
+            }
+        }
+    }
+}
+
+/// Decrypts what the server sends, see [`Stream::event_reader`].
+#[cfg(feature = "tls")]
+struct TlsReader {
+    conn: std::sync::Arc<std::sync::Mutex<rustls::ClientConnection>>,
+    stream: TcpStream,
+}
+
+#[cfg(feature = "tls")]
+impl Read for TlsReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            match self.conn.lock().unwrap().reader().read(buf) {
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+
+            // Wait for more without holding the lock, so that sending isn't held up.
+            let mut encrypted = [0_u8; 4096];
+            let len = self.stream.read(&mut encrypted)?;
+            if len == 0 {
+                return Ok(0);
+            }
+
+            let mut conn = self.conn.lock().unwrap();
+            let mut encrypted = &encrypted[..len];
+            while !encrypted.is_empty() {
+                conn.read_tls(&mut encrypted)?;
+                conn.process_new_packets()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+            }
+            // E.g. the answer to a key update.
+            while conn.wants_write() {
+                conn.write_tls(&mut self.stream)?;
+            }
+        }
+    }
+}
+
 /// State of the [`TcpStream`]
 ///
 /// Because the [`TcpClient`] lazily connects on [`TcpClient::send`], it needs a
@@ -27,7 +162,7 @@ enum TcpStreamState {
     ///
     /// Transitions:
     ///  - Connected -> Disconnected on send error
-    Connected(TcpStream),
+    Connected(Stream),
 
     /// A broken [`TcpStream`] which experienced a failure to connect or send.
     ///
@@ -44,22 +179,78 @@ enum TcpStreamState {
 pub struct TcpClient {
     addrs: Vec<SocketAddr>,
     stream_state: TcpStreamState,
+
+    /// What we tell the server during the handshake, including the compression of the packets
+    /// we send and our auth token.
+    hello: Hello,
+
+    /// Encrypt the connection, if set.
+    #[cfg(feature = "tls")]
+    tls: Option<crate::ClientTls>,
+
+    /// The [`Hello::server_id`] of the last server we connected to, if any.
+    server_id: Option<u64>,
+
+    /// The [`Hello::next_seq`] the last server we connected to expected from us.
+    server_next_seq: u64,
+
+    /// Where to send the [`ViewerEvent`]s the server sends us, if anywhere.
+    events_tx: Option<Sender<ViewerEvent>>,
 }
 
 impl Default for TcpClient {
     fn default() -> Self {
-        Self::new(crate::default_server_addr())
+        Self::new(crate::default_server_addr(), Default::default())
     }
 }
 
 impl TcpClient {
-    pub fn new(addr: SocketAddr) -> Self {
+    pub fn new(addr: SocketAddr, compression: Compression) -> Self {
+        use rand::Rng as _;
+
         Self {
             addrs: vec![addr],
             stream_state: TcpStreamState::Pending,
+            hello: Hello {
+                session_id: rand::thread_rng().gen_range(1..=u64::MAX),
+                ..Hello::local(compression)
+            },
+            #[cfg(feature = "tls")]
+            tls: None,
+            server_id: None,
+            server_next_seq: 0,
+            events_tx: None,
         }
     }
 
+    /// Offer these buttons to the user of the viewer, see [`Hello::buttons`].
+    pub fn with_buttons(mut self, buttons: Vec<String>) -> Self {
+        self.hello.buttons = buttons;
+        self
+    }
+
+    /// Authenticate with the given token, if the server requires one.
+    pub fn with_auth_token(mut self, auth_token: Option<String>) -> Self {
+        self.hello.auth_token = auth_token;
+        self
+    }
+
+    /// Forward the [`ViewerEvent`]s the server sends to `events_tx`.
+    ///
+    /// They are read on a thread of their own for as long as the connection lasts. Events are
+    /// dropped when `events_tx` is full.
+    pub fn with_viewer_events(mut self, events_tx: Sender<ViewerEvent>) -> Self {
+        self.events_tx = Some(events_tx);
+        self
+    }
+
+    /// Encrypt the connection with TLS.
+    #[cfg(feature = "tls")]
+    pub fn with_tls(mut self, tls: Option<crate::ClientTls>) -> Self {
+        self.tls = tls;
+        self
+    }
+
     /// Returns `false` on failure. Does nothing if already connected.
     ///
     /// [`Self::send`] will call this.
@@ -69,15 +260,26 @@ impl TcpClient {
         } else {
             re_log::debug!("Connecting to {:?}…", self.addrs);
             match TcpStream::connect(&self.addrs[..]) {
-                Ok(mut stream) => {
-                    if let Err(err) = stream.write(&crate::PROTOCOL_VERSION.to_le_bytes()) {
-                        self.stream_state = TcpStreamState::Disconnected;
-                        anyhow::bail!("Failed to send to Rerun server at {:?}: {err}", self.addrs);
-                    } else {
+                Ok(stream) => match self.open(stream) {
+                    Ok((stream, server_hello)) => {
+                        re_log::debug!(
+                            "Connected to Rerun server {} at {:?}",
+                            server_hello.crate_version,
+                            self.addrs
+                        );
                         self.stream_state = TcpStreamState::Connected(stream);
+                        self.server_id = Some(server_hello.server_id);
+                        self.server_next_seq = server_hello.next_seq;
                         Ok(())
                     }
-                }
+                    Err(err) => {
+                        self.stream_state = TcpStreamState::Disconnected;
+                        anyhow::bail!(
+                            "Failed to connect to Rerun server at {:?}: {err}",
+                            self.addrs
+                        );
+                    }
+                },
                 Err(err) => {
                     self.stream_state = TcpStreamState::Disconnected;
                     anyhow::bail!(
@@ -89,20 +291,53 @@ impl TcpClient {
         }
     }
 
+    /// Sets up encryption if needed, does the handshake, then starts reading events.
+    fn open(&self, stream: TcpStream) -> anyhow::Result<(Stream, Hello)> {
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+        #[cfg(feature = "tls")]
+        let mut stream = if let Some(tls) = &self.tls {
+            let conn = rustls::ClientConnection::new(tls.config.clone(), tls.server_name.clone())?;
+            Stream::Tls(std::sync::Arc::new(std::sync::Mutex::new(conn)), stream)
+        } else {
+            Stream::Plain(stream)
+        };
+        #[cfg(not(feature = "tls"))]
+        let mut stream = Stream::Plain(stream);
+
+        let server_hello = handshake(&mut stream, &self.hello)?;
+
+        if let Some(events_tx) = &self.events_tx {
+            // The event reader waits for as long as it takes.
+            stream.tcp().set_read_timeout(None)?;
+            let reader = stream.event_reader()?;
+            let events_tx = events_tx.clone();
+            let addrs = self.addrs.clone();
+            std::thread::Builder::new()
+                .name("viewer_events".into())
+                .spawn(move || read_events(reader, &events_tx, &addrs))?;
+        }
+
+        Ok((stream, server_hello))
+    }
+
     /// blocks until it is sent
-    pub fn send(&mut self, packet: &[u8]) -> anyhow::Result<()> {
+    ///
+    /// `seq` must be greater than that of any packet sent before in this session, except when
+    /// sending a packet again, in which case the server ignores it if it already received it.
+    pub fn send(&mut self, seq: u64, packet: &[u8]) -> anyhow::Result<()> {
         use std::io::Write as _;
 
         self.connect()?;
 
         if let TcpStreamState::Connected(stream) = &mut self.stream_state {
-            re_log::trace!("Sending a packet of size {}…", packet.len());
-            if let Err(err) = stream.write(&(packet.len() as u32).to_le_bytes()) {
-                self.stream_state = TcpStreamState::Disconnected;
-                anyhow::bail!("Failed to send to Rerun server at {:?}: {err}", self.addrs);
-            }
-
-            if let Err(err) = stream.write(packet) {
+            re_log::trace!("Sending packet {seq} of size {}…", packet.len());
+            let result = stream
+                .write_all(&(packet.len() as u32).to_le_bytes())
+                .and_then(|()| stream.write_all(&seq.to_le_bytes()))
+                .and_then(|()| stream.write_all(packet))
+                .and_then(|()| stream.flush());
+            if let Err(err) = result {
                 self.stream_state = TcpStreamState::Disconnected;
                 anyhow::bail!("Failed to send to Rerun server at {:?}: {err}", self.addrs);
             }
@@ -124,6 +359,24 @@ impl TcpClient {
         re_log::trace!("TCP stream flushed.");
     }
 
+    /// The [`Hello::server_id`] of the last server we connected to, if any.
+    ///
+    /// This changes when reconnecting to a server that was restarted in the meantime.
+    pub fn server_id(&self) -> Option<u64> {
+        self.server_id
+    }
+
+    /// The [`Hello::next_seq`] the last server we connected to expected from us: it received
+    /// every packet of ours with a lower sequence number, and none of the others.
+    pub fn server_next_seq(&self) -> u64 {
+        self.server_next_seq
+    }
+
+    /// Whether the underlying [`TcpStream`] is in the [`TcpStreamState::Connected`] state.
+    pub fn is_connected(&self) -> bool {
+        matches!(self.stream_state, TcpStreamState::Connected(_))
+    }
+
     /// Check if the underlying [`TcpStream`] has entered the [`TcpStreamState::Disconnected`] state
     ///
     /// Note that this only occurs after a failure to connect or a failure to send.
@@ -134,3 +387,83 @@ impl TcpClient {
         }
     }
 }
+
+/// Forwards the [`ViewerEvent`]s the server sends, until the connection is closed by either side.
+fn read_events(
+    mut reader: Box<dyn Read + Send>,
+    events_tx: &Sender<ViewerEvent>,
+    addrs: &[SocketAddr],
+) {
+    loop {
+        let mut len = [0_u8; 4];
+        if let Err(err) = reader.read_exact(&mut len) {
+            if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                re_log::debug!("The Rerun server at {addrs:?} hung up");
+            } else {
+                re_log::debug!("Stopped receiving from the Rerun server at {addrs:?}: {err}");
+            }
+            return;
+        }
+
+        let mut bytes = vec![0_u8; u32::from_le_bytes(len) as usize];
+        if let Err(err) = reader.read_exact(&mut bytes) {
+            re_log::debug!("Stopped receiving from the Rerun server at {addrs:?}: {err}");
+            return;
+        }
+
+        match ViewerEvent::from_bytes(&bytes) {
+            Ok(event) => {
+                if events_tx.try_send(event).is_err() {
+                    re_log::debug_once!("Nobody is listening to viewer events, dropping them.");
+                }
+            }
+            Err(err) => re_log::warn_once!("Ignoring unknown viewer event: {err}"),
+        }
+    }
+}
+
+/// Introduces ourselves to the server, and finds out whether it accepts us.
+fn handshake(stream: &mut Stream, hello: &Hello) -> anyhow::Result<Hello> {
+    stream.write_all(&hello.to_bytes())?;
+    stream.flush()?;
+
+    let mut prefix = [0_u8; HELLO_PREFIX_SIZE];
+    if let Err(err) = stream.read_exact(&mut prefix) {
+        if err.kind() == std::io::ErrorKind::UnexpectedEof {
+            anyhow::bail!(
+                "the server hung up during the handshake. \
+                It is probably running an older version of Rerun than this SDK ({}), \
+                or expects a TLS connection",
+                crate::CRATE_VERSION
+            );
+        }
+        return Err(err.into());
+    }
+
+    let (protocol_version, crate_version, len) = Hello::parse_prefix(prefix);
+    if protocol_version != crate::PROTOCOL_VERSION {
+        anyhow::bail!(
+            "the server is running Rerun {crate_version}, which uses protocol version \
+            {protocol_version}, but this SDK is running Rerun {} (protocol version {})",
+            crate::CRATE_VERSION,
+            crate::PROTOCOL_VERSION
+        );
+    }
+
+    let mut rest = vec![0_u8; len as usize];
+    stream.read_exact(&mut rest)?;
+    let server_hello = Hello::parse_rest(crate_version, &rest)?;
+
+    let mut reason_len = [0_u8; 4];
+    stream.read_exact(&mut reason_len)?;
+    let mut reason = vec![0_u8; u32::from_le_bytes(reason_len) as usize];
+    stream.read_exact(&mut reason)?;
+    if !reason.is_empty() {
+        anyhow::bail!(
+            "the server rejected this SDK: {}",
+            String::from_utf8_lossy(&reason)
+        );
+    }
+
+    Ok(server_hello)
+}
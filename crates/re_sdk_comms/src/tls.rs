@@ -0,0 +1,193 @@
+//! Optional TLS encryption of the connections between SDKs and servers.
+
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+
+use anyhow::Context as _;
+
+/// Loads the PEM-encoded certificate chain and private key a server identifies itself with.
+#[cfg(feature = "server")]
+pub fn load_server_config(
+    cert_path: &Path,
+    key_path: &Path,
+) -> anyhow::Result<Arc<rustls::ServerConfig>> {
+    let certs = load_certs(cert_path)?;
+    let key = load_private_key(key_path)?;
+
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid TLS certificate or private key")?;
+
+    Ok(Arc::new(config))
+}
+
+/// How a client connects to a server over TLS.
+#[cfg(feature = "client")]
+#[derive(Clone)]
+pub struct ClientTls {
+    pub config: Arc<rustls::ClientConfig>,
+
+    /// The name the certificate of the server must be valid for.
+    pub server_name: rustls::ServerName,
+}
+
+#[cfg(feature = "client")]
+impl ClientTls {
+    /// Trusts the servers whose certificate is valid for `server_name` (e.g. `localhost`), and
+    /// signed by one of the certificates in the PEM file at `ca_cert_path`.
+    ///
+    /// Without `ca_cert_path`, the usual root certificate authorities of the web are trusted.
+    pub fn new(server_name: &str, ca_cert_path: Option<&Path>) -> anyhow::Result<Self> {
+        let mut roots = rustls::RootCertStore::empty();
+        if let Some(ca_cert_path) = ca_cert_path {
+            for cert in load_certs(ca_cert_path)? {
+                roots
+                    .add(&cert)
+                    .with_context(|| format!("Invalid CA certificate in {ca_cert_path:?}"))?;
+            }
+        } else {
+            roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+                rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    ta.subject,
+                    ta.spki,
+                    ta.name_constraints,
+                )
+            }));
+        }
+
+        let config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        let server_name = rustls::ServerName::try_from(server_name)
+            .with_context(|| format!("Invalid TLS server name {server_name:?}"))?;
+
+        Ok(Self {
+            config: Arc::new(config),
+            server_name,
+        })
+    }
+}
+
+#[cfg(feature = "client")]
+impl std::fmt::Debug for ClientTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientTls")
+            .field("server_name", &self.server_name)
+            .finish_non_exhaustive()
+    }
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<rustls::Certificate>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to read certificates from {path:?}"))?;
+    anyhow::ensure!(!certs.is_empty(), "No certificates found in {path:?}");
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+#[cfg(feature = "server")]
+fn load_private_key(path: &Path) -> anyhow::Result<rustls::PrivateKey> {
+    let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let items = rustls_pemfile::read_all(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to read private key from {path:?}"))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+            _ => None,
+        })
+        .with_context(|| format!("No private key found in {path:?}"))
+}
+
+#[cfg(all(test, feature = "client", feature = "server"))]
+mod tests {
+    use std::time::Duration;
+
+    use re_log_types::{
+        ApplicationId, BeginRecordingMsg, LogMsg, RecordingId, RecordingInfo, RecordingSource,
+        RowId, Time,
+    };
+
+    use super::*;
+
+    fn begin_recording() -> LogMsg {
+        LogMsg::BeginRecordingMsg(BeginRecordingMsg {
+            row_id: RowId::random(),
+            info: RecordingInfo {
+                application_id: ApplicationId::unknown(),
+                recording_id: RecordingId::random(),
+                is_official_example: false,
+                started: Time::now(),
+                recording_source: RecordingSource::Unknown,
+            },
+        })
+    }
+
+    /// An SDK that authenticates over TLS gets its messages through, one that doesn't use TLS
+    /// gets nothing through.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tls_end_to_end() {
+        let dir = std::env::temp_dir().join(format!("rerun-tls-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+        std::fs::write(&cert_path, cert.serialize_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let (_shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
+        let rx = crate::serve(
+            port,
+            crate::ServerOptions {
+                auth_token: Some("secret".to_owned()),
+                tls: Some(load_server_config(&cert_path, &key_path).unwrap()),
+                ..Default::default()
+            },
+            shutdown_rx,
+        )
+        .await
+        .unwrap();
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+
+        tokio::task::spawn_blocking(move || {
+            let plain_client = crate::Client::new_with_options(
+                addr,
+                crate::ClientOptions {
+                    auth_token: Some("secret".to_owned()),
+                    ..Default::default()
+                },
+            );
+            plain_client.send(begin_recording());
+            assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+            plain_client.drop_if_disconnected();
+            drop(plain_client);
+
+            let client = crate::Client::new_with_options(
+                addr,
+                crate::ClientOptions {
+                    auth_token: Some("secret".to_owned()),
+                    tls: Some(ClientTls::new("localhost", Some(&cert_path)).unwrap()),
+                    ..Default::default()
+                },
+            );
+            let msg = begin_recording();
+            client.send(msg.clone());
+            client.flush();
+            assert_eq!(msg, rx.recv_timeout(Duration::from_secs(10)).unwrap());
+        })
+        .await
+        .unwrap();
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,87 @@
+//! Events sent back by a viewer to the SDKs connected to it, e.g. when its user selects
+//! something.
+
+use re_log_types::{component_types::InstanceKey, EntityPath, RecordingId, TimeInt, Timeline};
+
+/// Something the user of a viewer did.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ViewerEvent {
+    /// The recording that was being viewed, if the event is about one.
+    pub recording_id: Option<RecordingId>,
+
+    pub kind: ViewerEventKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ViewerEventKind {
+    /// The selection changed: these are the instances that are now selected, if any.
+    SelectionChanged(Vec<EntityInstance>),
+
+    /// The user clicked these instances, e.g. in a 3D view, closest first.
+    Clicked(Vec<EntityInstance>),
+
+    /// The time cursor moved.
+    TimeChanged { timeline: Timeline, time: TimeInt },
+
+    /// The user pressed one of the buttons the SDK offered, see [`crate::Hello::buttons`].
+    ButtonPressed(String),
+}
+
+/// One instance of an entity, or all of them ([`InstanceKey::SPLAT`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub struct EntityInstance {
+    pub entity_path: EntityPath,
+    pub instance_key: InstanceKey,
+}
+
+impl std::fmt::Display for EntityInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.instance_key.is_splat() {
+            self.entity_path.fmt(f)
+        } else {
+            write!(f, "{}[{}]", self.entity_path, self.instance_key)
+        }
+    }
+}
+
+impl ViewerEvent {
+    /// Whether a recording stream with the given id should hear about this event.
+    #[inline]
+    pub fn is_for(&self, recording_id: RecordingId) -> bool {
+        self.recording_id.map_or(true, |id| id == recording_id)
+    }
+
+    /// On the wire, each event is preceded by its length as a little-endian `u32`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Viewer events can always be serialized")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewer_event_roundtrip() {
+        let event = ViewerEvent {
+            recording_id: Some(RecordingId::random()),
+            kind: ViewerEventKind::Clicked(vec![EntityInstance {
+                entity_path: "world/points".into(),
+                instance_key: InstanceKey(42),
+            }]),
+        };
+        assert_eq!(event, ViewerEvent::from_bytes(&event.to_bytes()).unwrap());
+
+        assert!(event.is_for(event.recording_id.unwrap()));
+        assert!(!event.is_for(RecordingId::random()));
+        assert!(ViewerEvent {
+            recording_id: None,
+            kind: ViewerEventKind::ButtonPressed("Reset".to_owned()),
+        }
+        .is_for(RecordingId::random()));
+    }
+}
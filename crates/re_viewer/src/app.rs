@@ -10,7 +10,7 @@ use poll_promise::Promise;
 use re_arrow_store::{DataStoreConfig, DataStoreStats};
 use re_data_store::log_db::LogDb;
 use re_format::format_number;
-use re_log_types::{ApplicationId, LogMsg, RecordingId};
+use re_log_types::{ApplicationId, EntityPath, LogMsg, RecordingId, TimeType, TimelineName};
 use re_renderer::WgpuResourcePoolStatistics;
 use re_smart_channel::Receiver;
 use re_ui::{toasts, Command};
@@ -39,10 +39,52 @@ enum TimeControlCommand {
 // ----------------------------------------------------------------------------
 
 /// Settings set once at startup (e.g. via command-line options) and not serialized.
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Default)]
 pub struct StartupOptions {
     pub memory_limit: re_memory::MemoryLimit,
     pub persist_state: bool,
+    pub retention: RetentionOptions,
+
+    /// When set, cold data is spilled to this directory rather than purged when running low on
+    /// memory, one subdirectory per recording.
+    pub spill_dir: Option<std::path::PathBuf>,
+
+    /// The SDKs connected to our TCP server, if we're running one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub sdk_peers: Option<re_sdk_comms::ConnectedPeers>,
+}
+
+/// What data to keep around, regardless of the [`StartupOptions::memory_limit`].
+#[derive(Clone, Default)]
+pub struct RetentionOptions {
+    /// Only keep the most recent part of these timelines, e.g. the last 10 seconds of a live
+    /// stream.
+    pub time_windows: Vec<(TimelineName, TimeWindow)>,
+
+    /// Keep the data of each of these entities, and of all their descendants, under the given
+    /// number of bytes.
+    pub entity_quotas: Vec<(EntityPath, u64)>,
+
+    /// Never purge the data of these entities, nor of their descendants.
+    pub protected_paths: Vec<EntityPath>,
+}
+
+impl RetentionOptions {
+    fn is_empty(&self) -> bool {
+        self.time_windows.is_empty() && self.entity_quotas.is_empty()
+    }
+}
+
+/// How much of a timeline to keep, see [`RetentionOptions::time_windows`].
+///
+/// Which one applies depends on the type of the timeline, which is only known once data arrives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeWindow {
+    /// A duration in nanoseconds, for temporal timelines.
+    Duration(i64),
+
+    /// A number of steps, for sequence timelines.
+    Sequence(i64),
 }
 
 // ----------------------------------------------------------------------------
@@ -86,6 +128,9 @@ pub struct App {
 
     latest_queue_interest: instant::Instant,
 
+    /// When we last applied the [`RetentionOptions`].
+    latest_retention: Option<instant::Instant>,
+
     /// Measures how long a frame takes to paint
     frame_time_history: egui::util::History<f32>,
 
@@ -94,6 +139,10 @@ pub struct App {
     cmd_palette: re_ui::CommandPalette,
 
     analytics: ViewerAnalytics,
+
+    /// Tells the SDKs connected to our TCP server what the user is doing.
+    #[cfg(not(target_arch = "wasm32"))]
+    viewer_event_sender: crate::viewer_events::ViewerEventSender,
 }
 
 impl App {
@@ -143,6 +192,7 @@ impl App {
             memory_panel_open: false,
 
             latest_queue_interest: instant::Instant::now(), // TODO(emilk): `Instant::MIN` when we have our own `Instant` that supports it.
+            latest_retention: None,
 
             frame_time_history: egui::util::History::new(1..100, 0.5),
 
@@ -150,6 +200,9 @@ impl App {
             cmd_palette: Default::default(),
 
             analytics,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            viewer_event_sender: Default::default(),
         }
     }
 
@@ -483,6 +536,7 @@ impl eframe::App for App {
 
         self.check_keyboard_shortcuts(egui_ctx);
 
+        self.apply_retention_options();
         self.purge_memory_if_needed();
 
         self.state.cache.begin_frame();
@@ -560,6 +614,18 @@ impl eframe::App for App {
                 }
             });
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(sdk_peers) = &self.startup_options.sdk_peers {
+            if let Some(rec_cfg) = self
+                .state
+                .recording_configs
+                .get(&self.state.selected_rec_id)
+            {
+                self.viewer_event_sender
+                    .update(sdk_peers, self.state.selected_rec_id, rec_cfg);
+            }
+        }
+
         if re_ui::CUSTOM_WINDOW_DECORATIONS {
             // Paint the main window frame on top of everything else
             paint_native_window_frame(egui_ctx);
@@ -760,6 +826,79 @@ impl App {
         }
     }
 
+    /// Drops the data that falls outside of the [`RetentionOptions`].
+    fn apply_retention_options(&mut self) {
+        crate::profile_function!();
+
+        let retention = &self.startup_options.retention;
+        if retention.is_empty() {
+            return;
+        }
+
+        // No need to do this every frame.
+        if let Some(latest_retention) = self.latest_retention {
+            if latest_retention.elapsed() < std::time::Duration::from_secs(1) {
+                return;
+            }
+        }
+        self.latest_retention = Some(Instant::now());
+
+        let mut num_rows_dropped = 0;
+        for log_db in self.log_dbs.values_mut() {
+            let mut targets = Vec::new();
+            for (timeline_name, window) in &retention.time_windows {
+                // Only the timelines that this recording actually uses.
+                let timeline = log_db
+                    .timelines()
+                    .find(|timeline| timeline.name() == timeline_name)
+                    .copied();
+                let Some(timeline) = timeline else {
+                    continue;
+                };
+                let window = match (timeline.typ(), *window) {
+                    (TimeType::Time, TimeWindow::Duration(window))
+                    | (TimeType::Sequence, TimeWindow::Sequence(window)) => window,
+                    (TimeType::Time, TimeWindow::Sequence(_)) => {
+                        re_log::warn_once!(
+                            "Not limiting {timeline_name:?}: it is a temporal timeline, so its window needs a unit, e.g. 10s"
+                        );
+                        continue;
+                    }
+                    (TimeType::Sequence, TimeWindow::Duration(_)) => {
+                        re_log::warn_once!(
+                            "Not limiting {timeline_name:?}: it is a sequence timeline, so its window must be a plain number"
+                        );
+                        continue;
+                    }
+                };
+                targets.push(re_arrow_store::GarbageCollectionTarget::KeepTimeWindow {
+                    timeline,
+                    window,
+                });
+            }
+            for (entity_path, max_bytes) in &retention.entity_quotas {
+                targets.push(re_arrow_store::GarbageCollectionTarget::EntityQuota {
+                    entity_path: entity_path.clone(),
+                    max_bytes: *max_bytes,
+                });
+            }
+
+            for target in targets {
+                num_rows_dropped += log_db.gc(re_arrow_store::GarbageCollectionOptions {
+                    target,
+                    // Data that is still current, e.g. a pose logged once, outlives the window.
+                    protect_latest_at: true,
+                    protected_paths: retention.protected_paths.clone(),
+                });
+            }
+        }
+
+        // Don't keep e.g. decoded images of the dropped data around, same as when purging memory.
+        if num_rows_dropped > 0 {
+            self.state.cache.purge_memory();
+        }
+    }
+
     fn purge_memory_if_needed(&mut self) {
         crate::profile_function!();
 
@@ -797,8 +936,25 @@ impl App {
                         format_bytes(counted as f64 * fraction_to_purge as f64)
                     );
                 }
-                for log_db in self.log_dbs.values_mut() {
-                    log_db.purge_fraction_of_ram(fraction_to_purge);
+                for (recording_id, log_db) in &mut self.log_dbs {
+                    if let Some(spill_dir) = &self.startup_options.spill_dir {
+                        let dir = spill_dir.join(recording_id.to_string());
+                        match log_db.spill_fraction_of_ram(fraction_to_purge, &dir) {
+                            Ok(num_bytes) if num_bytes > 0 => continue,
+                            // Nothing left to spill: purge instead.
+                            Ok(_) => {}
+                            Err(err) => {
+                                re_log::warn_once!(
+                                    "Failed to spill data to {dir:?}, purging it instead: {err}"
+                                );
+                            }
+                        }
+                    }
+
+                    log_db.purge_fraction_of_ram(
+                        fraction_to_purge,
+                        &self.startup_options.retention.protected_paths,
+                    );
                 }
                 self.state.cache.purge_memory();
             }
@@ -863,8 +1019,7 @@ impl App {
         }
         if let Some(file) = egui_ctx.input(|i| i.raw.dropped_files.first().cloned()) {
             if let Some(bytes) = &file.bytes {
-                let mut bytes: &[u8] = &(*bytes)[..];
-                if let Some(log_db) = load_file_contents(&file.name, &mut bytes) {
+                if let Some(log_db) = load_file_contents(&file.name, &bytes[..]) {
                     self.show_log_db(log_db);
 
                     #[allow(clippy::needless_return)] // false positive on wasm32
@@ -1237,6 +1392,8 @@ fn top_bar_ui(
         frame_time_label_ui(ui, app);
         memory_use_label_ui(ui, gpu_resource_stats);
         input_latency_label_ui(ui, app);
+        #[cfg(not(target_arch = "wasm32"))]
+        sdk_peers_label_ui(ui, app);
     }
 
     if let Some(log_db) = app.log_dbs.get(&app.state.selected_rec_id) {
@@ -1467,6 +1624,44 @@ fn input_latency_label_ui(ui: &mut egui::Ui, app: &mut App) {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn sdk_peers_label_ui(ui: &mut egui::Ui, app: &App) {
+    let Some(sdk_peers) = &app.startup_options.sdk_peers else {
+        return;
+    };
+    let peers = sdk_peers.peers();
+    if peers.is_empty() {
+        return;
+    }
+
+    ui.separator();
+    let text = if peers.len() == 1 {
+        "1 SDK connected".to_owned()
+    } else {
+        format!("{} SDKs connected", format_number(peers.len()))
+    };
+    ui.menu_button(text, |ui| {
+        for (id, peer) in &peers {
+            ui.label(peer.to_string());
+
+            // The buttons the SDK offers, whose presses are sent back to it.
+            ui.horizontal_wrapped(|ui| {
+                for button in &peer.hello.buttons {
+                    if ui.button(button).clicked() {
+                        sdk_peers.send_event_to(
+                            *id,
+                            &re_sdk_comms::ViewerEvent {
+                                recording_id: None,
+                                kind: re_sdk_comms::ViewerEventKind::ButtonPressed(button.clone()),
+                            },
+                        );
+                    }
+                }
+            });
+        }
+    });
+}
+
 // ----------------------------------------------------------------------------
 
 const FILE_SAVER_PROMISE: &str = "file_saver";
@@ -1562,6 +1757,7 @@ fn save_buttons_ui(ui: &mut egui::Ui, app: &mut App) {
 fn open(app: &mut App) {
     if let Some(path) = rfd::FileDialog::new()
         .add_filter("rerun data file", &["rrd"])
+        .add_filter("table", &["csv", "parquet", "arrow", "feather", "ipc"])
         .pick_file()
     {
         if let Some(log_db) = load_file_path(&path) {
@@ -1862,7 +2058,7 @@ fn save_database_to_file(
         let file = std::fs::File::create(path.as_path())
             .with_context(|| format!("Failed to create file at {path:?}"))?;
 
-        re_log_encoding::encoder::encode_owned(msgs, file)
+        re_log_encoding::encoder::encode_owned(Default::default(), msgs, file)
             .map(|_| path)
             .context("Message encode")
     })
@@ -1872,12 +2068,51 @@ fn save_database_to_file(
 fn load_rrd_to_log_db(mut read: impl std::io::Read) -> anyhow::Result<LogDb> {
     crate::profile_function!();
 
-    let decoder = re_log_encoding::decoder::Decoder::new(read)?;
+    // Salvage what we can from files that were cut short, e.g. by a crashing logger, but make
+    // sure the user knows that they are not looking at the whole recording:
+    let mut decoder = re_log_encoding::decoder::Decoder::new(read)?.salvaging();
 
     let mut log_db = LogDb::default();
-    for msg in decoder {
+    for msg in &mut decoder {
         log_db.add(&msg?)?;
     }
+
+    let report = decoder.report();
+    if !report.is_intact() {
+        let msg = format!("The recording is damaged, and was only partially loaded: {report}");
+        re_log::warn!("{msg}");
+        rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Warning)
+            .set_description(&msg)
+            .show();
+    }
+
+    Ok(log_db)
+}
+
+/// Imports a CSV, Parquet or Arrow IPC table as a new recording.
+///
+/// Timestamp columns become timelines, and the other columns are logged to entities named after
+/// the file, see [`re_arrow_store::ImportConfig`].
+fn load_table_to_log_db(
+    name: &str,
+    format: re_arrow_store::ImportFormat,
+    read: impl std::io::Read + std::io::Seek,
+) -> anyhow::Result<LogDb> {
+    crate::profile_function!();
+
+    let stem = std::path::Path::new(name).file_stem().map_or_else(
+        || "table".to_owned(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+    let config =
+        re_arrow_store::ImportConfig::new(re_arrow_store::EntityPathSource::Template(stem.clone()));
+    let tables = re_arrow_store::import_file(format, read, &config)?;
+
+    let mut log_db = LogDb::default();
+    for msg in re_arrow_store::import_to_log_msgs(stem.into(), &tables)? {
+        log_db.add(&msg)?;
+    }
     Ok(log_db)
 }
 
@@ -1888,7 +2123,18 @@ fn load_file_path(path: &std::path::Path) -> Option<LogDb> {
         crate::profile_function!();
         use anyhow::Context as _;
         let file = std::fs::File::open(path).context("Failed to open file")?;
-        load_rrd_to_log_db(file)
+        let format = path
+            .to_str()
+            .and_then(re_arrow_store::ImportFormat::from_file_name);
+        if let Some(format) = format {
+            load_table_to_log_db(
+                &path.to_string_lossy(),
+                format,
+                std::io::BufReader::new(file),
+            )
+        } else {
+            load_rrd_to_log_db(file)
+        }
     }
 
     re_log::info!("Loading {path:?}…");
@@ -1912,8 +2158,14 @@ fn load_file_path(path: &std::path::Path) -> Option<LogDb> {
 }
 
 #[must_use]
-fn load_file_contents(name: &str, read: impl std::io::Read) -> Option<LogDb> {
-    match load_rrd_to_log_db(read) {
+fn load_file_contents(name: &str, bytes: &[u8]) -> Option<LogDb> {
+    let result = if let Some(format) = re_arrow_store::ImportFormat::from_file_name(name) {
+        load_table_to_log_db(name, format, std::io::Cursor::new(bytes))
+    } else {
+        load_rrd_to_log_db(bytes)
+    };
+
+    match result {
         Ok(mut log_db) => {
             re_log::info!("Loaded {name:?}");
             log_db.data_source = Some(re_smart_channel::Source::File { path: name.into() });
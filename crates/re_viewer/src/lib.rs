@@ -15,7 +15,7 @@ pub(crate) use misc::mesh_loader;
 use re_log_types::PythonVersion;
 pub(crate) use ui::{memory_panel, selection_panel, time_panel};
 
-pub use app::{App, StartupOptions};
+pub use app::{App, RetentionOptions, StartupOptions, TimeWindow};
 pub use remote_viewer_app::RemoteViewerApp;
 
 pub mod external {
@@ -30,6 +30,8 @@ pub mod external {
 #[cfg(not(target_arch = "wasm32"))]
 mod native;
 #[cfg(not(target_arch = "wasm32"))]
+mod viewer_events;
+#[cfg(not(target_arch = "wasm32"))]
 pub use native::{run_native_app, run_native_viewer_with_messages};
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -2,6 +2,7 @@ pub mod format_time;
 mod mesh_cache;
 pub(crate) mod mesh_loader;
 pub mod queries;
+mod query_caches;
 pub(crate) mod space_info;
 mod space_view_highlights;
 mod time_control_ui;
@@ -13,6 +14,7 @@ pub mod instance_hash_conversions;
 pub(crate) mod profiler;
 
 pub use mesh_cache::MeshCache;
+pub use query_caches::QueryCaches;
 pub use time_control_ui::TimeControlUi;
 pub use transform_cache::{TransformCache, UnreachableTransform};
 
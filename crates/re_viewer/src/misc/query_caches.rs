@@ -0,0 +1,33 @@
+use re_log_types::RecordingId;
+use re_query::QueryCache;
+use re_viewer_context::Cache;
+
+// ----------------------------------------------------------------------------
+
+/// One [`QueryCache`] per recording, since a [`QueryCache`] must only ever be used with a single
+/// store.
+#[derive(Default)]
+pub struct QueryCaches(ahash::HashMap<RecordingId, QueryCache>);
+
+impl QueryCaches {
+    pub fn get(&mut self, recording_id: RecordingId) -> &mut QueryCache {
+        self.0.entry(recording_id).or_default()
+    }
+}
+
+impl Cache for QueryCaches {
+    fn begin_frame(&mut self) {
+        for cache in self.0.values_mut() {
+            cache.begin_frame();
+        }
+        self.0.retain(|_, cache| !cache.is_empty());
+    }
+
+    fn purge_memory(&mut self) {
+        self.0.clear();
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
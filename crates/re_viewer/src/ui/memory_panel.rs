@@ -218,11 +218,15 @@ impl MemoryPanel {
                     type_registry,
                     metadata_registry,
                     autogenerated,
+                    deduplicated,
                     timeless,
                     temporal,
                     temporal_buckets,
+                    spilled,
                     total,
-                } = *store_stats;
+                    entities: _,
+                    components: _,
+                } = store_stats;
 
                 ui.label(egui::RichText::new("Stats").italics());
                 ui.label("Buckets");
@@ -230,46 +234,95 @@ impl MemoryPanel {
                 ui.label("Size");
                 ui.end_row();
 
-                fn label_row_stats(ui: &mut egui::Ui, row_stats: DataStoreRowStats) {
-                    let DataStoreRowStats {
-                        num_rows,
-                        num_bytes,
-                    } = row_stats;
-
-                    ui.label(re_format::format_number(num_rows as _));
-                    ui.label(re_format::format_bytes(num_bytes as _));
-                }
-
                 ui.label("Type registry:");
                 ui.label("");
-                label_row_stats(ui, type_registry);
+                label_row_stats(ui, *type_registry);
                 ui.end_row();
 
                 ui.label("Metadata registry:");
                 ui.label("");
-                label_row_stats(ui, metadata_registry);
+                label_row_stats(ui, *metadata_registry);
                 ui.end_row();
 
                 ui.label("Cluster cache:");
                 ui.label("");
-                label_row_stats(ui, autogenerated);
+                label_row_stats(ui, *autogenerated);
+                ui.end_row();
+
+                ui.label("Deduplicated (saved):");
+                ui.label("");
+                label_row_stats(ui, *deduplicated);
                 ui.end_row();
 
                 ui.label("Timeless:");
                 ui.label("");
-                label_row_stats(ui, timeless);
+                label_row_stats(ui, *timeless);
                 ui.end_row();
 
                 ui.label("Temporal:");
-                ui.label(re_format::format_number(temporal_buckets as _));
-                label_row_stats(ui, temporal);
+                ui.label(re_format::format_number(*temporal_buckets as _));
+                label_row_stats(ui, *temporal);
+                ui.end_row();
+
+                ui.label("Spilled to disk:");
+                ui.label("");
+                label_row_stats(ui, *spilled);
                 ui.end_row();
 
                 ui.label("Total");
-                ui.label(re_format::format_number(temporal_buckets as _));
-                label_row_stats(ui, total);
+                ui.label(re_format::format_number(*temporal_buckets as _));
+                label_row_stats(ui, *total);
                 ui.end_row();
             });
+
+        Self::top_consumers(
+            ui,
+            "Top entities",
+            "Entity",
+            store_stats
+                .entities
+                .iter()
+                .map(|(ent_path, stats)| (ent_path.to_string(), *stats)),
+        );
+        Self::top_consumers(
+            ui,
+            "Top components",
+            "Component",
+            store_stats
+                .components
+                .iter()
+                .map(|(component, stats)| (component.short_name().to_owned(), *stats)),
+        );
+    }
+
+    /// Lists the biggest consumers of memory within the store, biggest first.
+    fn top_consumers(
+        ui: &mut egui::Ui,
+        title: &str,
+        kind: &str,
+        consumers: impl Iterator<Item = (String, DataStoreRowStats)>,
+    ) {
+        const MAX_CONSUMERS: usize = 20;
+
+        let mut consumers: Vec<_> = consumers.collect();
+        consumers.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.num_bytes));
+
+        egui::CollapsingHeader::new(title)
+            .default_open(false)
+            .show(ui, |ui| {
+                egui::Grid::new(title).num_columns(3).show(ui, |ui| {
+                    ui.label(egui::RichText::new(kind).italics());
+                    ui.label("Rows");
+                    ui.label("Size");
+                    ui.end_row();
+
+                    for (name, stats) in consumers.into_iter().take(MAX_CONSUMERS) {
+                        ui.label(name);
+                        label_row_stats(ui, stats);
+                        ui.end_row();
+                    }
+                });
+            });
     }
 
     fn tracking_stats(
@@ -444,3 +497,13 @@ fn summarize_callstack(callstack: &str) -> String {
 
     all_summaries.join(", ")
 }
+
+fn label_row_stats(ui: &mut egui::Ui, row_stats: DataStoreRowStats) {
+    let DataStoreRowStats {
+        num_rows,
+        num_bytes,
+    } = row_stats;
+
+    ui.label(re_format::format_number(num_rows as _));
+    ui.label(re_format::format_bytes(num_bytes as _));
+}
@@ -207,7 +207,7 @@ impl SpaceView {
 
             ViewCategory::TimeSeries => {
                 let mut scene = view_time_series::SceneTimeSeries::default();
-                scene.load(ctx, &query);
+                scene.load(ctx, &query, &self.view_state.state_time_series);
                 self.view_state.ui_time_series(ctx, ui, &scene);
             }
 
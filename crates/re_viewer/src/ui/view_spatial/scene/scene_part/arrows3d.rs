@@ -3,12 +3,12 @@ use re_log_types::{
     component_types::{ColorRGBA, InstanceKey, Label, Radius},
     Arrow3D, Component,
 };
-use re_query::{query_primary_with_history, EntityView, QueryError};
+use re_query::{EntityView, QueryError};
 use re_renderer::{renderer::LineStripFlags, Size};
 use re_viewer_context::{DefaultColor, SceneQuery, ViewerContext};
 
 use crate::{
-    misc::{SpaceViewHighlights, TransformCache},
+    misc::{QueryCaches, SpaceViewHighlights, TransformCache},
     ui::view_spatial::{scene::EntityDepthOffsets, SceneSpatial},
 };
 
@@ -39,11 +39,9 @@ impl Arrows3DPart {
             .outline_mask_ids(entity_highlight.overall)
             .picking_object_id(re_renderer::PickingLayerObjectId(ent_path.hash64()));
 
-        let visitor = |instance_key: InstanceKey,
-                       arrow: Arrow3D,
-                       color: Option<ColorRGBA>,
-                       radius: Option<Radius>,
-                       _label: Option<Label>| {
+        for (instance_key, arrow, (color, radius, _label)) in
+            entity_view.join::<(ColorRGBA, Radius, Label)>()?
+        {
             // TODO(andreas): support labels
             // TODO(andreas): support class ids for arrows
             let annotation_info = annotations.class_description(None).annotation_info();
@@ -78,9 +76,7 @@ impl Arrows3DPart {
             if let Some(outline_mask_ids) = entity_highlight.instances.get(&instance_key) {
                 segment.outline_mask_ids(*outline_mask_ids);
             }
-        };
-
-        entity_view.visit4(visitor)?;
+        }
 
         Ok(())
     }
@@ -103,32 +99,36 @@ impl ScenePart for Arrows3DPart {
                 continue;
             };
 
-            match query_primary_with_history::<Arrow3D, 5>(
-                &ctx.log_db.entity_db.data_store,
-                &query.timeline,
-                &query.latest_at,
-                &props.visible_history,
-                ent_path,
-                [
-                    Arrow3D::name(),
-                    InstanceKey::name(),
-                    ColorRGBA::name(),
-                    Radius::name(),
-                    Label::name(),
-                ],
-            )
-            .and_then(|entities| {
-                for entity in entities {
-                    Self::process_entity_view(
-                        scene,
-                        &entity,
-                        ent_path,
-                        world_from_obj,
-                        highlights,
-                    )?;
-                }
-                Ok(())
-            }) {
+            match ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .query_primary_with_history::<Arrow3D, 5>(
+                    &ctx.log_db.entity_db.data_store,
+                    &query.timeline,
+                    &query.latest_at,
+                    &props.visible_history,
+                    ent_path,
+                    [
+                        Arrow3D::name(),
+                        InstanceKey::name(),
+                        ColorRGBA::name(),
+                        Radius::name(),
+                        Label::name(),
+                    ],
+                )
+                .and_then(|entities| {
+                    for entity in entities {
+                        Self::process_entity_view(
+                            scene,
+                            &entity,
+                            ent_path,
+                            world_from_obj,
+                            highlights,
+                        )?;
+                    }
+                    Ok(())
+                }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
                     re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
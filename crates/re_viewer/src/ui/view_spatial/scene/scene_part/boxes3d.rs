@@ -3,12 +3,12 @@ use re_log_types::{
     component_types::{Box3D, ClassId, ColorRGBA, InstanceKey, Label, Quaternion, Radius, Vec3D},
     Component,
 };
-use re_query::{query_primary_with_history, EntityView, QueryError};
+use re_query::{EntityView, QueryError};
 use re_renderer::Size;
 use re_viewer_context::{DefaultColor, SceneQuery, ViewerContext};
 
 use crate::{
-    misc::{SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
+    misc::{QueryCaches, SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
     ui::view_spatial::{scene::EntityDepthOffsets, SceneSpatial, UiLabel, UiLabelTarget},
 };
 
@@ -36,14 +36,9 @@ impl Boxes3DPart {
             .outline_mask_ids(entity_highlight.overall)
             .picking_object_id(re_renderer::PickingLayerObjectId(ent_path.hash64()));
 
-        let visitor = |instance_key: InstanceKey,
-                       half_size: Box3D,
-                       position: Option<Vec3D>,
-                       rotation: Option<Quaternion>,
-                       color: Option<ColorRGBA>,
-                       radius: Option<Radius>,
-                       label: Option<Label>,
-                       class_id: Option<ClassId>| {
+        for (instance_key, half_size, (position, rotation, color, radius, label, class_id)) in
+            entity_view.join::<(Vec3D, Quaternion, ColorRGBA, Radius, Label, ClassId)>()?
+        {
             let class_description = annotations.class_description(class_id);
             let annotation_info = class_description.annotation_info();
 
@@ -83,9 +78,9 @@ impl Boxes3DPart {
                     ),
                 });
             }
-        };
+        }
 
-        entity_view.visit7(visitor)
+        Ok(())
     }
 }
 
@@ -107,35 +102,39 @@ impl ScenePart for Boxes3DPart {
             };
             let entity_highlight = highlights.entity_outline_mask(ent_path.hash());
 
-            match query_primary_with_history::<Box3D, 8>(
-                &ctx.log_db.entity_db.data_store,
-                &query.timeline,
-                &query.latest_at,
-                &props.visible_history,
-                ent_path,
-                [
-                    Box3D::name(),
-                    InstanceKey::name(),
-                    Vec3D::name(),      // obb.position
-                    Quaternion::name(), // obb.rotation
-                    ColorRGBA::name(),
-                    Radius::name(), // stroke_width
-                    Label::name(),
-                    ClassId::name(),
-                ],
-            )
-            .and_then(|entities| {
-                for entity in entities {
-                    Self::process_entity_view(
-                        scene,
-                        &entity,
-                        ent_path,
-                        world_from_obj,
-                        entity_highlight,
-                    )?;
-                }
-                Ok(())
-            }) {
+            match ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .query_primary_with_history::<Box3D, 8>(
+                    &ctx.log_db.entity_db.data_store,
+                    &query.timeline,
+                    &query.latest_at,
+                    &props.visible_history,
+                    ent_path,
+                    [
+                        Box3D::name(),
+                        InstanceKey::name(),
+                        Vec3D::name(),      // obb.position
+                        Quaternion::name(), // obb.rotation
+                        ColorRGBA::name(),
+                        Radius::name(), // stroke_width
+                        Label::name(),
+                        ClassId::name(),
+                    ],
+                )
+                .and_then(|entities| {
+                    for entity in entities {
+                        Self::process_entity_view(
+                            scene,
+                            &entity,
+                            ent_path,
+                            world_from_obj,
+                            entity_highlight,
+                        )?;
+                    }
+                    Ok(())
+                }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
                     re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
@@ -204,9 +204,9 @@ impl ScenePart for CamerasPart {
                 &[],
             )
             .and_then(|entity_view| {
-                entity_view.visit1(|instance_key, transform| {
+                for (instance_key, transform, ()) in entity_view.join::<()>()? {
                     let Transform::Pinhole(pinhole) = transform else {
-                        return;
+                        continue;
                     };
                     let entity_highlight = highlights.entity_outline_mask(ent_path.hash());
 
@@ -227,7 +227,9 @@ impl ScenePart for CamerasPart {
                         view_coordinates,
                         entity_highlight,
                     );
-                })
+                }
+
+                Ok(())
             }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
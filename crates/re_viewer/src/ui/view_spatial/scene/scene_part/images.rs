@@ -7,7 +7,7 @@ use re_log_types::{
     component_types::{ColorRGBA, InstanceKey, Tensor, TensorData, TensorDataMeaning},
     Component, DecodedTensor, Transform,
 };
-use re_query::{query_primary_with_history, EntityView, QueryError};
+use re_query::{EntityView, QueryError};
 use re_renderer::{
     renderer::{DepthCloud, RectangleOptions},
     resource_managers::Texture2DCreationDesc,
@@ -19,7 +19,7 @@ use re_viewer_context::{
 };
 
 use crate::{
-    misc::{SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
+    misc::{QueryCaches, SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
     ui::view_spatial::{scene::EntityDepthOffsets, Image, SceneSpatial},
 };
 
@@ -401,30 +401,34 @@ impl ScenePart for ImagesPart {
                 continue;
             };
 
-            match query_primary_with_history::<Tensor, 3>(
-                &ctx.log_db.entity_db.data_store,
-                &query.timeline,
-                &query.latest_at,
-                &props.visible_history,
-                ent_path,
-                [Tensor::name(), InstanceKey::name(), ColorRGBA::name()],
-            )
-            .and_then(|entities| {
-                for entity in entities {
-                    Self::process_entity_view(
-                        &entity,
-                        scene,
-                        ctx,
-                        transforms,
-                        &props,
-                        ent_path,
-                        world_from_obj,
-                        highlights,
-                        depth_offsets.get(ent_path).unwrap_or(depth_offsets.image),
-                    )?;
-                }
-                Ok(())
-            }) {
+            match ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .query_primary_with_history::<Tensor, 3>(
+                    &ctx.log_db.entity_db.data_store,
+                    &query.timeline,
+                    &query.latest_at,
+                    &props.visible_history,
+                    ent_path,
+                    [Tensor::name(), InstanceKey::name(), ColorRGBA::name()],
+                )
+                .and_then(|entities| {
+                    for entity in entities {
+                        Self::process_entity_view(
+                            &entity,
+                            scene,
+                            ctx,
+                            transforms,
+                            &props,
+                            ent_path,
+                            world_from_obj,
+                            highlights,
+                            depth_offsets.get(ent_path).unwrap_or(depth_offsets.image),
+                        )?;
+                    }
+                    Ok(())
+                }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
                     re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
@@ -3,12 +3,12 @@ use re_log_types::{
     component_types::{ColorRGBA, InstanceKey, LineStrip2D, Radius},
     Component,
 };
-use re_query::{query_primary_with_history, EntityView, QueryError};
+use re_query::{EntityView, QueryError};
 use re_renderer::Size;
 use re_viewer_context::{DefaultColor, SceneQuery, ViewerContext};
 
 use crate::{
-    misc::{SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
+    misc::{QueryCaches, SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
     ui::view_spatial::{scene::EntityDepthOffsets, SceneSpatial},
 };
 
@@ -39,10 +39,7 @@ impl Lines2DPart {
             .outline_mask_ids(entity_highlight.overall)
             .picking_object_id(re_renderer::PickingLayerObjectId(ent_path.hash64()));
 
-        let visitor = |instance_key: InstanceKey,
-                       strip: LineStrip2D,
-                       color: Option<ColorRGBA>,
-                       radius: Option<Radius>| {
+        for (instance_key, strip, (color, radius)) in entity_view.join::<(ColorRGBA, Radius)>()? {
             // TODO(andreas): support class ids for lines
             let annotation_info = annotations.class_description(None).annotation_info();
             let radius = radius.map_or(Size::AUTO, |r| Size::new_scene(r.0));
@@ -62,9 +59,7 @@ impl Lines2DPart {
             if let Some(outline_mask_ids) = entity_highlight.instances.get(&instance_key) {
                 lines.outline_mask_ids(*outline_mask_ids);
             }
-        };
-
-        entity_view.visit3(visitor)?;
+        }
 
         Ok(())
     }
@@ -88,32 +83,36 @@ impl ScenePart for Lines2DPart {
             };
             let entity_highlight = highlights.entity_outline_mask(ent_path.hash());
 
-            match query_primary_with_history::<LineStrip2D, 4>(
-                &ctx.log_db.entity_db.data_store,
-                &query.timeline,
-                &query.latest_at,
-                &props.visible_history,
-                ent_path,
-                [
-                    LineStrip2D::name(),
-                    InstanceKey::name(),
-                    ColorRGBA::name(),
-                    Radius::name(),
-                ],
-            )
-            .and_then(|entities| {
-                for entity in entities {
-                    Self::process_entity_view(
-                        scene,
-                        &entity,
-                        ent_path,
-                        world_from_obj,
-                        entity_highlight,
-                        depth_offsets.get(ent_path).unwrap_or(depth_offsets.lines2d),
-                    )?;
-                }
-                Ok(())
-            }) {
+            match ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .query_primary_with_history::<LineStrip2D, 4>(
+                    &ctx.log_db.entity_db.data_store,
+                    &query.timeline,
+                    &query.latest_at,
+                    &props.visible_history,
+                    ent_path,
+                    [
+                        LineStrip2D::name(),
+                        InstanceKey::name(),
+                        ColorRGBA::name(),
+                        Radius::name(),
+                    ],
+                )
+                .and_then(|entities| {
+                    for entity in entities {
+                        Self::process_entity_view(
+                            scene,
+                            &entity,
+                            ent_path,
+                            world_from_obj,
+                            entity_highlight,
+                            depth_offsets.get(ent_path).unwrap_or(depth_offsets.lines2d),
+                        )?;
+                    }
+                    Ok(())
+                }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
                     re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
@@ -3,11 +3,11 @@ use re_log_types::{
     component_types::{ColorRGBA, InstanceKey},
     Component, Mesh3D,
 };
-use re_query::{query_primary_with_history, EntityView, QueryError};
+use re_query::{EntityView, QueryError};
 use re_viewer_context::{DefaultColor, SceneQuery, ViewerContext};
 
 use crate::{
-    misc::{MeshCache, SpaceViewHighlights, TransformCache},
+    misc::{MeshCache, QueryCaches, SpaceViewHighlights, TransformCache},
     ui::view_spatial::{scene::EntityDepthOffsets, MeshSource, SceneSpatial},
 };
 
@@ -29,33 +29,30 @@ impl MeshPart {
         let _default_color = DefaultColor::EntityPath(ent_path);
         let entity_highlight = highlights.entity_outline_mask(ent_path.hash());
 
-        let visitor =
-            |instance_key: InstanceKey, mesh: re_log_types::Mesh3D, _color: Option<ColorRGBA>| {
-                let picking_instance_hash = instance_path_hash_for_picking(
-                    ent_path,
-                    instance_key,
-                    entity_view,
-                    entity_highlight.any_selection_highlight,
-                );
+        for (instance_key, mesh, (_color,)) in entity_view.join::<(ColorRGBA,)>()? {
+            let picking_instance_hash = instance_path_hash_for_picking(
+                ent_path,
+                instance_key,
+                entity_view,
+                entity_highlight.any_selection_highlight,
+            );
 
-                let outline_mask_ids = entity_highlight.index_outline_mask(instance_key);
+            let outline_mask_ids = entity_highlight.index_outline_mask(instance_key);
 
-                if let Some(mesh) = ctx
-                    .cache
-                    .entry::<MeshCache>()
-                    .entry(&ent_path.to_string(), &mesh, ctx.render_ctx)
-                    .map(|cpu_mesh| MeshSource {
-                        picking_instance_hash,
-                        world_from_mesh: world_from_obj,
-                        mesh: cpu_mesh,
-                        outline_mask_ids,
-                    })
-                {
-                    scene.primitives.meshes.push(mesh);
-                };
+            if let Some(mesh) = ctx
+                .cache
+                .entry::<MeshCache>()
+                .entry(&ent_path.to_string(), &mesh, ctx.render_ctx)
+                .map(|cpu_mesh| MeshSource {
+                    picking_instance_hash,
+                    world_from_mesh: world_from_obj,
+                    mesh: cpu_mesh,
+                    outline_mask_ids,
+                })
+            {
+                scene.primitives.meshes.push(mesh);
             };
-
-        entity_view.visit2(visitor)?;
+        }
 
         Ok(())
     }
@@ -78,27 +75,31 @@ impl ScenePart for MeshPart {
                 continue;
             };
 
-            match query_primary_with_history::<Mesh3D, 3>(
-                &ctx.log_db.entity_db.data_store,
-                &query.timeline,
-                &query.latest_at,
-                &props.visible_history,
-                ent_path,
-                [Mesh3D::name(), InstanceKey::name(), ColorRGBA::name()],
-            )
-            .and_then(|entities| {
-                for entity in entities {
-                    Self::process_entity_view(
-                        scene,
-                        &entity,
-                        ent_path,
-                        world_from_obj,
-                        ctx,
-                        highlights,
-                    )?;
-                }
-                Ok(())
-            }) {
+            match ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .query_primary_with_history::<Mesh3D, 3>(
+                    &ctx.log_db.entity_db.data_store,
+                    &query.timeline,
+                    &query.latest_at,
+                    &props.visible_history,
+                    ent_path,
+                    [Mesh3D::name(), InstanceKey::name(), ColorRGBA::name()],
+                )
+                .and_then(|entities| {
+                    for entity in entities {
+                        Self::process_entity_view(
+                            scene,
+                            &entity,
+                            ent_path,
+                            world_from_obj,
+                            ctx,
+                            highlights,
+                        )?;
+                    }
+                    Ok(())
+                }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
                     re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
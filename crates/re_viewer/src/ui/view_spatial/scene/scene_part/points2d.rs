@@ -3,11 +3,11 @@ use re_log_types::{
     component_types::{ClassId, ColorRGBA, InstanceKey, KeypointId, Label, Point2D, Radius},
     Component,
 };
-use re_query::{query_primary_with_history, EntityView, QueryError};
+use re_query::{EntityView, QueryError};
 use re_viewer_context::{ResolvedAnnotationInfo, SceneQuery, ViewerContext};
 
 use crate::{
-    misc::{SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
+    misc::{QueryCaches, SpaceViewHighlights, SpaceViewOutlineMasks, TransformCache},
     ui::view_spatial::{scene::EntityDepthOffsets, SceneSpatial, UiLabel, UiLabelTarget},
 };
 
@@ -183,36 +183,40 @@ impl ScenePart for Points2DPart {
             };
             let entity_highlight = highlights.entity_outline_mask(ent_path.hash());
 
-            match query_primary_with_history::<Point2D, 7>(
-                &ctx.log_db.entity_db.data_store,
-                &query.timeline,
-                &query.latest_at,
-                &props.visible_history,
-                ent_path,
-                [
-                    Point2D::name(),
-                    InstanceKey::name(),
-                    ColorRGBA::name(),
-                    Radius::name(),
-                    Label::name(),
-                    ClassId::name(),
-                    KeypointId::name(),
-                ],
-            )
-            .and_then(|entities| {
-                for entity in entities {
-                    self.process_entity_view(
-                        scene,
-                        query,
-                        &entity,
-                        ent_path,
-                        world_from_obj,
-                        entity_highlight,
-                        depth_offsets.get(ent_path).unwrap_or(depth_offsets.points),
-                    )?;
-                }
-                Ok(())
-            }) {
+            match ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .query_primary_with_history::<Point2D, 7>(
+                    &ctx.log_db.entity_db.data_store,
+                    &query.timeline,
+                    &query.latest_at,
+                    &props.visible_history,
+                    ent_path,
+                    [
+                        Point2D::name(),
+                        InstanceKey::name(),
+                        ColorRGBA::name(),
+                        Radius::name(),
+                        Label::name(),
+                        ClassId::name(),
+                        KeypointId::name(),
+                    ],
+                )
+                .and_then(|entities| {
+                    for entity in entities {
+                        self.process_entity_view(
+                            scene,
+                            query,
+                            &entity,
+                            ent_path,
+                            world_from_obj,
+                            entity_highlight,
+                            depth_offsets.get(ent_path).unwrap_or(depth_offsets.points),
+                        )?;
+                    }
+                    Ok(())
+                }) {
                 Ok(_) | Err(QueryError::PrimaryNotFound) => {}
                 Err(err) => {
                     re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
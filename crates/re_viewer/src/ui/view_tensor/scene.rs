@@ -1,9 +1,6 @@
 use re_arrow_store::LatestAtQuery;
 use re_data_store::{EntityPath, EntityProperties, InstancePath};
-use re_log_types::{
-    component_types::{InstanceKey, Tensor},
-    DecodedTensor,
-};
+use re_log_types::{component_types::Tensor, DecodedTensor};
 use re_query::{query_entity_with_primary, EntityView, QueryError};
 use re_viewer_context::{SceneQuery, TensorDecodeCache, ViewerContext};
 
@@ -44,7 +41,7 @@ impl SceneTensor {
         _props: &EntityProperties,
         entity_view: &EntityView<Tensor>,
     ) -> Result<(), QueryError> {
-        entity_view.visit1(|instance_key: InstanceKey, tensor: Tensor| {
+        for (instance_key, tensor, ()) in entity_view.join::<()>()? {
             if !tensor.is_shaped_like_an_image() {
                 match ctx.cache.entry::<TensorDecodeCache>().entry(tensor) {
                     Ok(tensor) => {
@@ -58,6 +55,8 @@ impl SceneTensor {
                     }
                 }
             }
-        })
+        }
+
+        Ok(())
     }
 }
@@ -4,9 +4,11 @@ use re_log_types::{
     component_types::{self, InstanceKey},
     Component, RowId,
 };
-use re_query::{range_entity_with_primary, QueryError};
+use re_query::QueryError;
 use re_viewer_context::{SceneQuery, ViewerContext};
 
+use crate::misc::QueryCaches;
+
 use super::ui::ViewTextFilters;
 
 // ---
@@ -38,7 +40,7 @@ impl SceneText {
     /// Loads all text components into the scene according to the given query.
     pub(crate) fn load(
         &mut self,
-        ctx: &ViewerContext<'_>,
+        ctx: &mut ViewerContext<'_>,
         query: &SceneQuery<'_>,
         filters: &ViewTextFilters,
     ) {
@@ -65,35 +67,38 @@ impl SceneText {
                 component_types::TextEntry::name(),
                 component_types::ColorRGBA::name(),
             ];
-            let ent_views = range_entity_with_primary::<component_types::TextEntry, 3>(
-                store, &query, ent_path, components,
-            );
+            let ent_views = ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .range_entity_with_primary::<component_types::TextEntry, 3>(
+                    store, &query, ent_path, components,
+                );
 
             for (time, ent_view) in ent_views {
-                match ent_view.visit2(
-                    |_instance,
-                     text_entry: component_types::TextEntry,
-                     color: Option<component_types::ColorRGBA>| {
-                        let component_types::TextEntry { body, level } = text_entry;
-
-                        // Early filtering once more, see above.
-                        let is_visible = level
-                            .as_ref()
-                            .map_or(true, |lvl| filters.is_log_level_visible(lvl));
-
-                        if is_visible {
-                            self.text_entries.push(TextEntry {
-                                row_id: ent_view.row_id(),
-                                entity_path: entity_path.clone(),
-                                time: time.map(|time| time.as_i64()),
-                                color: color.map(|c| c.to_array()),
-                                level,
-                                body,
-                            });
+                match ent_view.join::<(component_types::ColorRGBA,)>() {
+                    Ok(rows) => {
+                        for (_instance, text_entry, (color,)) in rows {
+                            let component_types::TextEntry { body, level } = text_entry;
+
+                            // Early filtering once more, see above.
+                            let is_visible = level
+                                .as_ref()
+                                .map_or(true, |lvl| filters.is_log_level_visible(lvl));
+
+                            if is_visible {
+                                self.text_entries.push(TextEntry {
+                                    row_id: ent_view.row_id(),
+                                    entity_path: entity_path.clone(),
+                                    time: time.map(|time| time.as_i64()),
+                                    color: color.map(|c| c.to_array()),
+                                    level,
+                                    body,
+                                });
+                            }
                         }
-                    },
-                ) {
-                    Ok(_) | Err(QueryError::PrimaryNotFound) => {}
+                    }
+                    Err(QueryError::PrimaryNotFound) => {}
                     Err(err) => {
                         re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
                     }
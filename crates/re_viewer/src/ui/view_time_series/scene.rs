@@ -1,13 +1,24 @@
-use re_arrow_store::TimeRange;
+use re_arrow_store::{DataStore, LatestAtQuery, TimeInt, TimeRange};
+use re_data_store::EntityPath;
 use re_log_types::{
     component_types::{self, InstanceKey},
-    Component,
+    Component, Timeline,
 };
-use re_query::{range_entity_with_primary, QueryError};
-use re_viewer_context::{AnnotationMap, DefaultColor, SceneQuery, ViewerContext};
+use re_query::QueryError;
+use re_viewer_context::{
+    AnnotationMap, DefaultColor, ResolvedAnnotationInfo, SceneQuery, ViewerContext,
+};
+
+use super::ViewTimeSeriesState;
+use crate::misc::QueryCaches;
 
 // ---
 
+const DEFAULT_RADIUS: f32 = 0.75;
+
+/// Series that have at least that many points per pixel, on average, get aggregated.
+const MIN_POINTS_PER_PIXEL: u64 = 4;
+
 #[derive(Clone, Debug)]
 pub struct PlotPointAttrs {
     pub label: Option<String>,
@@ -64,19 +75,36 @@ pub struct SceneTimeSeries {
 
 impl SceneTimeSeries {
     /// Loads all plots into the scene according to the given query.
-    pub(crate) fn load(&mut self, ctx: &mut ViewerContext<'_>, query: &SceneQuery<'_>) {
+    ///
+    /// Series that are too dense to be plotted point by point at the resolution the plot was
+    /// last shown at are aggregated instead: every pixel then only shows the range of the values
+    /// that fall within it, see [`re_query::aggregate_scalars`].
+    pub(crate) fn load(
+        &mut self,
+        ctx: &mut ViewerContext<'_>,
+        query: &SceneQuery<'_>,
+        state: &ViewTimeSeriesState,
+    ) {
         crate::profile_function!();
 
         self.annotation_map.load(ctx, query);
 
-        self.load_scalars(ctx, query);
+        self.load_scalars(ctx, query, state);
     }
 
     #[inline(never)] // Better callstacks on crashes
-    fn load_scalars(&mut self, ctx: &mut ViewerContext<'_>, query: &SceneQuery<'_>) {
+    fn load_scalars(
+        &mut self,
+        ctx: &mut ViewerContext<'_>,
+        query: &SceneQuery<'_>,
+        state: &ViewTimeSeriesState,
+    ) {
         crate::profile_function!();
 
         let store = &ctx.log_db.entity_db.data_store;
+        let bucket_width = state
+            .time_per_pixel
+            .map(|time_per_pixel| (time_per_pixel.ceil() as u64).max(1));
 
         for entity_path in query.entity_paths {
             let ent_path = entity_path;
@@ -86,11 +114,47 @@ impl SceneTimeSeries {
             let annotation_info = annotations.class_description(None).annotation_info();
             let default_color = DefaultColor::EntityPath(ent_path);
 
+            let timeline = query.timeline;
             let query = re_arrow_store::RangeQuery::new(
-                query.timeline,
+                timeline,
                 TimeRange::new(i64::MIN.into(), i64::MAX.into()),
             );
 
+            if let Some(bucket_width) = bucket_width {
+                let aggregates = re_query::aggregate_scalars(
+                    store,
+                    &query,
+                    ent_path,
+                    component_types::Scalar::name(),
+                    bucket_width,
+                );
+                let num_points: u64 = aggregates.iter().map(|aggregate| aggregate.count).sum();
+                if !aggregates.is_empty()
+                    && num_points >= MIN_POINTS_PER_PIXEL * aggregates.len() as u64
+                {
+                    // The attributes of individual points cannot be told apart anyway.
+                    let attrs =
+                        latest_attrs(store, timeline, ent_path, &annotation_info, default_color);
+                    let line_label = attrs
+                        .label
+                        .clone()
+                        .unwrap_or_else(|| entity_path.to_string());
+                    let points = aggregates
+                        .iter()
+                        .flat_map(|aggregate| {
+                            let time = aggregate.time_range.min.as_i64();
+                            [aggregate.min, aggregate.max].map(|value| PlotPoint {
+                                time,
+                                value,
+                                attrs: attrs.clone(),
+                            })
+                        })
+                        .collect();
+                    self.add_line_segments(&line_label, points);
+                    continue;
+                }
+            }
+
             let components = [
                 InstanceKey::name(),
                 component_types::Scalar::name(),
@@ -99,40 +163,43 @@ impl SceneTimeSeries {
                 component_types::Radius::name(),
                 component_types::Label::name(),
             ];
-            let ent_views = range_entity_with_primary::<component_types::Scalar, 6>(
-                store, &query, ent_path, components,
-            );
+            let ent_views = ctx
+                .cache
+                .entry::<QueryCaches>()
+                .get(ctx.log_db.recording_id())
+                .range_entity_with_primary::<component_types::Scalar, 6>(
+                    store, &query, ent_path, components,
+                );
 
             for (time, ent_view) in ent_views {
                 let Some(time) = time else { continue; }; // scalars cannot be timeless
 
-                match ent_view.visit5(
-                    |_instance,
-                     scalar: component_types::Scalar,
-                     props: Option<component_types::ScalarPlotProps>,
-                     color: Option<component_types::ColorRGBA>,
-                     radius: Option<component_types::Radius>,
-                     label: Option<component_types::Label>| {
-                        // TODO(andreas): Support entity path
-                        let color = annotation_info
-                            .color(color.map(|c| c.to_array()).as_ref(), default_color);
-                        let label = annotation_info.label(label.map(|l| l.into()).as_ref());
-
-                        const DEFAULT_RADIUS: f32 = 0.75;
-
-                        points.push(PlotPoint {
-                            time: time.as_i64(),
-                            value: scalar.into(),
-                            attrs: PlotPointAttrs {
-                                label,
-                                color,
-                                radius: radius.map_or(DEFAULT_RADIUS, |r| r.0),
-                                scattered: props.map_or(false, |props| props.scattered),
-                            },
-                        });
-                    },
-                ) {
-                    Ok(_) | Err(QueryError::PrimaryNotFound) => {}
+                match ent_view.join::<(
+                    component_types::ScalarPlotProps,
+                    component_types::ColorRGBA,
+                    component_types::Radius,
+                    component_types::Label,
+                )>() {
+                    Ok(rows) => {
+                        for (_instance, scalar, (props, color, radius, label)) in rows {
+                            // TODO(andreas): Support entity path
+                            let color = annotation_info
+                                .color(color.map(|c| c.to_array()).as_ref(), default_color);
+                            let label = annotation_info.label(label.map(|l| l.into()).as_ref());
+
+                            points.push(PlotPoint {
+                                time: time.as_i64(),
+                                value: scalar.into(),
+                                attrs: PlotPointAttrs {
+                                    label,
+                                    color,
+                                    radius: radius.map_or(DEFAULT_RADIUS, |r| r.0),
+                                    scattered: props.map_or(false, |props| props.scattered),
+                                },
+                            });
+                        }
+                    }
+                    Err(QueryError::PrimaryNotFound) => {}
                     Err(err) => {
                         re_log::error_once!("Unexpected error querying {ent_path:?}: {err}");
                     }
@@ -229,3 +296,35 @@ impl SceneTimeSeries {
         }
     }
 }
+
+/// The attributes of the most recent point of a series.
+fn latest_attrs(
+    store: &DataStore,
+    timeline: Timeline,
+    ent_path: &EntityPath,
+    annotation_info: &ResolvedAnnotationInfo,
+    default_color: DefaultColor<'_>,
+) -> PlotPointAttrs {
+    crate::profile_function!();
+
+    let query = LatestAtQuery::new(timeline, TimeInt::MAX);
+    let (props, color, radius, label) = re_query::query_entity_joined::<
+        component_types::Scalar,
+        (
+            component_types::ScalarPlotProps,
+            component_types::ColorRGBA,
+            component_types::Radius,
+            component_types::Label,
+        ),
+    >(store, &query, ent_path)
+    .ok()
+    .and_then(|mut rows| rows.next())
+    .map_or((None, None, None, None), |(_, _, values)| values);
+
+    PlotPointAttrs {
+        label: annotation_info.label(label.map(|l| l.into()).as_ref()),
+        color: annotation_info.color(color.map(|c| c.to_array()).as_ref(), default_color),
+        radius: radius.map_or(DEFAULT_RADIUS, |r| r.0),
+        scattered: props.map_or(false, |props| props.scattered),
+    }
+}
@@ -22,12 +22,18 @@ pub(crate) const HELP_TEXT: &str = "Pan by dragging, or scroll (+ shift = horizo
     Right click to move the time cursor to the current position.";
 
 #[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
-pub struct ViewTimeSeriesState;
+pub struct ViewTimeSeriesState {
+    /// How much time a single (logical) pixel of the plot spanned, the last time it was shown.
+    ///
+    /// Series that have many points per pixel get aggregated, see `SceneTimeSeries::load`.
+    #[serde(skip)]
+    pub(crate) time_per_pixel: Option<f64>,
+}
 
 pub(crate) fn view_time_series(
     ctx: &mut ViewerContext<'_>,
     ui: &mut egui::Ui,
-    _state: &mut ViewTimeSeriesState,
+    state: &mut ViewTimeSeriesState,
     scene: &SceneTimeSeries,
 ) -> egui::Response {
     crate::profile_function!();
@@ -86,7 +92,7 @@ pub(crate) fn view_time_series(
     let egui::plot::PlotResponse {
         inner: time_x,
         response,
-        transform: _,
+        transform,
     } = plot.show(ui, |plot_ui| {
         if plot_ui.plot_secondary_clicked() {
             let timeline = ctx.rec_cfg.time_ctrl.timeline();
@@ -129,6 +135,10 @@ pub(crate) fn view_time_series(
         })
     });
 
+    let plot_width = transform.frame().width();
+    state.time_per_pixel =
+        (plot_width > 0.0).then(|| transform.bounds().width() / plot_width as f64);
+
     if let Some(time_x) = time_x {
         // TODO(emilk): allow interacting with the timeline (may require `egui::Plot` to return the `plot_from_screen` transform)
         let stroke = ui.visuals().widgets.inactive.fg_stroke;
@@ -0,0 +1,77 @@
+//! Tells the SDKs connected to us what the user is doing, see [`re_sdk_comms::ViewerEvent`].
+
+use re_log_types::{RecordingId, TimeInt, Timeline};
+use re_sdk_comms::{ConnectedPeers, EntityInstance, ViewerEvent, ViewerEventKind};
+use re_viewer_context::{Item, ItemCollection, RecordingConfig};
+
+/// Keeps track of what the SDKs were last told, so as to only send them changes.
+#[derive(Default)]
+pub struct ViewerEventSender {
+    recording_id: Option<RecordingId>,
+    selection: Vec<EntityInstance>,
+    time: Option<(Timeline, TimeInt)>,
+}
+
+impl ViewerEventSender {
+    /// Call once per frame, once the whole UI has been shown.
+    pub fn update(
+        &mut self,
+        peers: &ConnectedPeers,
+        recording_id: RecordingId,
+        rec_cfg: &RecordingConfig,
+    ) {
+        crate::profile_function!();
+
+        let selection = entity_instances(rec_cfg.selection_state.current());
+        let time = rec_cfg
+            .time_ctrl
+            .time_int()
+            .map(|time| (*rec_cfg.time_ctrl.timeline(), time));
+
+        // Switching to another recording is not something the user did to that recording.
+        if self.recording_id != Some(recording_id) {
+            self.recording_id = Some(recording_id);
+            self.selection = selection;
+            self.time = time;
+            return;
+        }
+
+        let send = |kind| {
+            peers.send_event(&ViewerEvent {
+                recording_id: Some(recording_id),
+                kind,
+            });
+        };
+
+        if selection != self.selection {
+            send(ViewerEventKind::SelectionChanged(selection.clone()));
+            self.selection = selection;
+        }
+
+        let clicked = entity_instances(rec_cfg.selection_state.clicked());
+        if !clicked.is_empty() {
+            send(ViewerEventKind::Clicked(clicked));
+        }
+
+        if time != self.time {
+            if let Some((timeline, time)) = time {
+                send(ViewerEventKind::TimeChanged { timeline, time });
+            }
+            self.time = time;
+        }
+    }
+}
+
+/// The entity instances among the given items, ignoring everything else (e.g. space views).
+fn entity_instances(items: &ItemCollection) -> Vec<EntityInstance> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::InstancePath(_, instance_path) => Some(EntityInstance {
+                entity_path: instance_path.entity_path.clone(),
+                instance_key: instance_path.instance_key,
+            }),
+            Item::ComponentPath(_) | Item::SpaceView(_) | Item::DataBlueprintGroup(_, _) => None,
+        })
+        .collect()
+}
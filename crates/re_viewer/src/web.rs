@@ -53,6 +53,8 @@ impl WebHandle {
                         limit: Some(2_500_000_000),
                     },
                     persist_state,
+                    retention: Default::default(),
+                    spill_dir: None,
                 };
                 let re_ui = crate::customize_eframe(cc);
                 let url = url.unwrap_or_else(|| get_url(&cc.integration_info));
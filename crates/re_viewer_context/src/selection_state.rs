@@ -107,6 +107,10 @@ pub struct SelectionState {
     /// What space is the pointer hovering over? Write to this.
     #[serde(skip)]
     hovered_space_this_frame: HoveredSpace,
+
+    /// What objects were clicked during this frame, if any?
+    #[serde(skip)]
+    clicked_this_frame: ItemCollection,
 }
 
 impl SelectionState {
@@ -119,6 +123,7 @@ impl SelectionState {
         self.hovered_space_previous_frame =
             std::mem::replace(&mut self.hovered_space_this_frame, HoveredSpace::None);
         self.hovered_previous_frame = std::mem::take(&mut self.hovered_this_frame);
+        self.clicked_this_frame = ItemCollection::default();
     }
 
     /// Selects the previous element in the history if any.
@@ -193,6 +198,17 @@ impl SelectionState {
         self.set_multi_selection(new_selection.into_iter());
     }
 
+    /// What objects were clicked during this frame, e.g. in a space view.
+    ///
+    /// Only complete once the whole UI has been shown.
+    pub fn clicked(&self) -> &ItemCollection {
+        &self.clicked_this_frame
+    }
+
+    pub fn set_clicked(&mut self, items: impl Iterator<Item = Item>) {
+        self.clicked_this_frame = ItemCollection::new(items);
+    }
+
     pub fn hovered_space(&self) -> &HoveredSpace {
         &self.hovered_space_previous_frame
     }
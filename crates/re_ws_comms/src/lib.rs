@@ -14,7 +14,7 @@ pub use client::Connection;
 #[cfg(feature = "server")]
 mod server;
 #[cfg(feature = "server")]
-pub use server::{RerunServer, RerunServerHandle};
+pub use server::{RerunServer, RerunServerHandle, RerunServerOptions};
 
 use re_log_types::LogMsg;
 
@@ -6,22 +6,86 @@
 //! In the future thing will be changed to a protocol where the clients can query
 //! for specific data based on e.g. time.
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    borrow::Cow,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
 
 use futures_util::{SinkExt, StreamExt};
 use parking_lot::Mutex;
-use tokio::net::{TcpListener, TcpStream};
-use tokio_tungstenite::{accept_async, tungstenite::Error};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
+use tokio_tungstenite::{
+    accept_hdr_async,
+    tungstenite::{
+        handshake::server::{ErrorResponse, Request, Response},
+        http::{header::AUTHORIZATION, StatusCode},
+        Error,
+    },
+};
 
 use re_log_types::LogMsg;
 use re_smart_channel::Receiver;
 
 use crate::{server_url, RerunServerError, RerunServerPort};
 
+/// Options for a [`RerunServer`].
+#[derive(Clone)]
+pub struct RerunServerOptions {
+    /// The IP address to listen on, e.g. `127.0.0.1` to only accept connections from this
+    /// machine.
+    ///
+    /// Defaults to `0.0.0.0`, i.e. all interfaces.
+    pub bind_ip: IpAddr,
+
+    /// If set, only the web viewers that present this exact token are served.
+    ///
+    /// The token is either sent in an `Authorization: Bearer <token>` header, or as the `token`
+    /// query parameter of the websocket url, which must then be url-safe.
+    pub auth_token: Option<String>,
+
+    /// Serve over TLS (`wss://`).
+    #[cfg(feature = "server_tls")]
+    pub tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl Default for RerunServerOptions {
+    fn default() -> Self {
+        Self {
+            bind_ip: Ipv4Addr::UNSPECIFIED.into(),
+            auth_token: None,
+            #[cfg(feature = "server_tls")]
+            tls: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for RerunServerOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            bind_ip,
+            auth_token,
+            #[cfg(feature = "server_tls")]
+            tls,
+        } = self;
+
+        let mut s = f.debug_struct("RerunServerOptions");
+        s.field("bind_ip", bind_ip)
+            .field("auth_token", &auth_token.as_ref().map(|_| "<redacted>"));
+        #[cfg(feature = "server_tls")]
+        s.field("tls", &tls.is_some());
+        s.finish()
+    }
+}
+
 /// Websocket host for relaying [`LogMsg`]s to a web viewer.
 pub struct RerunServer {
     listener: TcpListener,
     port: RerunServerPort,
+    options: Arc<RerunServerOptions>,
 }
 
 impl RerunServer {
@@ -30,7 +94,18 @@ impl RerunServer {
     ///
     /// A port of 0 will let the OS choose a free port.
     pub async fn new(port: RerunServerPort) -> Result<Self, RerunServerError> {
-        let bind_addr = format!("0.0.0.0:{port}");
+        Self::new_with_options(port, Default::default()).await
+    }
+
+    /// Create new [`RerunServer`] to relay [`LogMsg`]s to a websocket, with the given options.
+    /// The websocket will be available at `port`.
+    ///
+    /// A port of 0 will let the OS choose a free port.
+    pub async fn new_with_options(
+        port: RerunServerPort,
+        options: RerunServerOptions,
+    ) -> Result<Self, RerunServerError> {
+        let bind_addr = SocketAddr::new(options.bind_ip, port.0);
 
         let listener = TcpListener::bind(&bind_addr)
             .await
@@ -43,7 +118,11 @@ impl RerunServer {
             listener.local_addr()?
         );
 
-        Ok(Self { listener, port })
+        Ok(Self {
+            listener,
+            port,
+            options: Arc::new(options),
+        })
     }
 
     /// Accept new connections until we get a message on `shutdown_rx`
@@ -70,11 +149,17 @@ impl RerunServer {
                 peer,
                 tcp_stream,
                 history.clone(),
+                self.options.clone(),
             ));
         }
     }
 
     pub fn server_url(&self) -> String {
+        #[cfg(feature = "server_tls")]
+        if self.options.tls.is_some() {
+            return format!("wss://localhost:{}", self.port);
+        }
+
         server_url("localhost", self.port)
     }
 }
@@ -155,6 +240,7 @@ async fn accept_connection(
     _peer: SocketAddr,
     tcp_stream: TcpStream,
     history: Arc<Mutex<Vec<Arc<[u8]>>>>,
+    options: Arc<RerunServerOptions>,
 ) {
     // let span = re_log::span!(
     //     re_log::Level::INFO,
@@ -165,7 +251,27 @@ async fn accept_connection(
 
     re_log::debug!("New WebSocket connection");
 
-    if let Err(err) = handle_connection(log_stream, tcp_stream, history).await {
+    #[cfg(feature = "server_tls")]
+    if let Some(tls) = options.tls.clone() {
+        match tokio_rustls::TlsAcceptor::from(tls)
+            .accept(tcp_stream)
+            .await
+        {
+            Ok(tls_stream) => {
+                let result = handle_connection(log_stream, tls_stream, history, &options).await;
+                log_connection_error(result);
+            }
+            Err(err) => re_log::debug!("TLS handshake failed: {err}"),
+        }
+        return;
+    }
+
+    let result = handle_connection(log_stream, tcp_stream, history, &options).await;
+    log_connection_error(result);
+}
+
+fn log_connection_error(result: tungstenite::Result<()>) {
+    if let Err(err) = result {
         match err {
             Error::ConnectionClosed | Error::Protocol(_) | Error::Utf8 => (),
             err => re_log::error!("Error processing connection: {err}"),
@@ -173,12 +279,54 @@ async fn accept_connection(
     }
 }
 
+/// Rejects the websocket handshake of clients that don't present the expected auth token.
+fn authenticate(
+    expected_auth_token: Option<&str>,
+    request: &Request,
+    response: Response,
+) -> Result<Response, ErrorResponse> {
+    let Some(expected_auth_token) = expected_auth_token else {
+        return Ok(response);
+    };
+
+    let auth_token = request_auth_token(request).unwrap_or_default();
+    // Constant time, so as to not leak how much of a guess was right.
+    if constant_time_eq::constant_time_eq(auth_token.as_bytes(), expected_auth_token.as_bytes()) {
+        Ok(response)
+    } else {
+        re_log::warn!("Rejected a web viewer with a missing or invalid auth token");
+        let mut error = ErrorResponse::new(Some("Invalid auth token".to_owned()));
+        *error.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(error)
+    }
+}
+
+/// The token from either the `Authorization` header or the (percent-encoded) `token` query
+/// parameter.
+fn request_auth_token(request: &Request) -> Option<Cow<'_, str>> {
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if let Some(bearer) = bearer {
+        return Some(Cow::Borrowed(bearer));
+    }
+
+    form_urlencoded::parse(request.uri().query()?.as_bytes())
+        .find_map(|(key, value)| (key == "token").then_some(value))
+}
+
 async fn handle_connection(
     log_stream: tokio::sync::broadcast::Sender<Arc<[u8]>>,
-    tcp_stream: TcpStream,
+    stream: impl AsyncRead + AsyncWrite + Unpin,
     history: Arc<Mutex<Vec<Arc<[u8]>>>>,
+    options: &RerunServerOptions,
 ) -> tungstenite::Result<()> {
-    let ws_stream = accept_async(tcp_stream).await?;
+    let ws_stream = accept_hdr_async(stream, |request: &Request, response| {
+        authenticate(options.auth_token.as_deref(), request, response)
+    })
+    .await?;
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
     // Re-sending packet history - this is not water tight, but better than nothing.
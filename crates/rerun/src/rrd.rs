@@ -0,0 +1,810 @@
+//! The `rerun rrd` subcommands, for inspecting and rewriting `.rrd` files offline.
+//!
+//! Most of these load the recordings into a [`re_arrow_store::DataStore`], and write back what
+//! [`re_arrow_store::DataStore::to_rows`] dumps out of it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use itertools::Itertools as _;
+
+use re_arrow_store::{
+    ComponentColumns, EntityPathSource, ExportGrouping, ImportConfig, ImportFormat, TimelineColumn,
+};
+use re_data_store::LogDb;
+use re_log_encoding::{
+    decoder::Decoder, encoder::Encoder, migration::MigrationRegistry, Compression, EncodingOptions,
+};
+use re_log_types::{
+    ComponentName, DataRow, DataTable, EntityPath, EntityPathPattern, LogMsg, RecordingId,
+    SizeBytes as _, TableId, TimeInt, TimeRange, TimeType, Timeline,
+};
+
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum RrdCommands {
+    /// Print the recordings, timelines, entities and components of `.rrd` files.
+    Info {
+        /// The `.rrd` files to inspect.
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+
+    /// Merge several `.rrd` files into one.
+    ///
+    /// Data from the same recording is merged into a single recording.
+    Merge {
+        /// The `.rrd` files to merge.
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Only keep the data of some entities and/or some time range.
+    Filter {
+        /// The `.rrd` file to filter.
+        path: PathBuf,
+
+        /// Only keep these entities, e.g. `world/points`.
+        ///
+        /// `*` matches any single part of a path, and `**` any number of parts, e.g.
+        /// `robots/*/lidar` or `/world/**`. Can be given several times.
+        #[clap(long)]
+        entity: Vec<String>,
+
+        /// Only keep data within this (inclusive) time range, e.g. `frame_nr=10..20`.
+        ///
+        /// Either end of the range may be left out, e.g. `log_time=..1680000000000000000`.
+        /// Timeless data is always kept.
+        #[clap(long)]
+        time_range: Option<String>,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Rebatch the rows of an `.rrd` file into bigger tables, which makes it faster to load.
+    Compact {
+        /// The `.rrd` file to compact.
+        path: PathBuf,
+
+        /// The maximum number of rows in each table.
+        #[clap(long, default_value_t = 4096)]
+        max_rows: usize,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Export the data of an `.rrd` file to Parquet or Arrow IPC files, e.g. for use with
+    /// pandas or DuckDB.
+    ///
+    /// Writes one file per entity path (or per set of components) of each recording.
+    /// Every row keeps its row id and the times of all its timelines.
+    Export {
+        /// The `.rrd` file to export.
+        path: PathBuf,
+
+        /// The directory to write the files to.
+        #[clap(long, short)]
+        output: PathBuf,
+
+        #[clap(long, value_enum, default_value_t = ExportFormat::Parquet)]
+        format: ExportFormat,
+
+        /// Write one file per entity path, or one per set of components.
+        #[clap(long, value_enum, default_value_t = ExportGroupBy::Entity)]
+        group_by: ExportGroupBy,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+
+    /// Import a table from a CSV, Parquet or Arrow IPC file as a new recording.
+    ///
+    /// Unless told otherwise, timestamp and date columns become timelines, `x`/`y`/`z` columns
+    /// become a 3D point, and all other numeric and string columns become scalars and labels.
+    Import {
+        /// The `.csv`, `.parquet` or `.arrow` file to import.
+        path: PathBuf,
+
+        /// Where to log the rows to, e.g. `sensors/{sensor_id}` to log each row to the entity
+        /// named after the value of its `sensor_id` column.
+        ///
+        /// Defaults to the name of the file.
+        #[clap(long)]
+        entity_path: Option<String>,
+
+        /// Read the full entity path of each row from this column instead.
+        #[clap(long, conflicts_with = "entity_path")]
+        entity_path_column: Option<String>,
+
+        /// Use this timestamp, date or integer (nanoseconds since the Unix epoch) column as a time
+        /// timeline. Can be given several times.
+        #[clap(long)]
+        time: Vec<String>,
+
+        /// Use this integer column as a sequence timeline, e.g. `frame_nr`.
+        /// Can be given several times.
+        #[clap(long)]
+        sequence: Vec<String>,
+
+        /// Import this numeric column as a scalar. Can be given several times.
+        #[clap(long)]
+        scalar: Vec<String>,
+
+        /// Import these three numeric columns as a 3D point, e.g. `pos_x,pos_y,pos_z`.
+        #[clap(long)]
+        point3d: Option<String>,
+
+        /// Import this string column as a label.
+        #[clap(long)]
+        label: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Upgrade an `.rrd` file written by an older version of Rerun to the current schema.
+    ///
+    /// Every message is kept as is, apart from the migration.
+    Migrate {
+        /// The `.rrd` file to migrate.
+        path: PathBuf,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct InputArgs {
+    /// Skip over the corrupt parts of damaged or truncated `.rrd` files instead of failing,
+    /// e.g. to recover what a crashed logger managed to write.
+    ///
+    /// What was skipped is reported as a warning.
+    #[clap(long)]
+    salvage: bool,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct OutputArgs {
+    /// Where to write the resulting `.rrd` file.
+    #[clap(long, short)]
+    output: PathBuf,
+
+    /// How to compress the resulting `.rrd` file: `off`, `lz4` or `zstd`.
+    #[clap(long, default_value_t = Compression::Zstd)]
+    compression: Compression,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Arrow IPC files (`.arrow`).
+    Arrow,
+
+    /// Parquet files (`.parquet`).
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportGroupBy {
+    Entity,
+    Components,
+}
+
+/// Default number of rows in each table written by the commands that don't rebatch explicitly.
+const DEFAULT_MAX_ROWS: usize = 4096;
+
+pub fn run_rrd_commands(cmd: &RrdCommands) -> anyhow::Result<()> {
+    match cmd {
+        RrdCommands::Info { paths, input } => {
+            for path in paths {
+                print_info(path, input)?;
+            }
+            Ok(())
+        }
+        RrdCommands::Merge {
+            paths,
+            input,
+            output,
+        } => {
+            let recordings = load_recordings(paths, input)?;
+            write_recordings(output, &recordings, &RowFilter::default(), DEFAULT_MAX_ROWS)
+        }
+        RrdCommands::Filter {
+            path,
+            entity,
+            time_range,
+            input,
+            output,
+        } => {
+            let recordings = load_recordings(std::slice::from_ref(path), input)?;
+            let filter = RowFilter {
+                entities: entity
+                    .iter()
+                    .map(|entity| parse_entity_filter(entity))
+                    .collect::<anyhow::Result<_>>()?,
+                time_range: time_range
+                    .as_deref()
+                    .map(|time_range| parse_time_range(time_range, &recordings))
+                    .transpose()?,
+            };
+            write_recordings(output, &recordings, &filter, DEFAULT_MAX_ROWS)
+        }
+        RrdCommands::Compact {
+            path,
+            max_rows,
+            input,
+            output,
+        } => {
+            anyhow::ensure!(*max_rows > 0, "--max-rows must be positive");
+            let recordings = load_recordings(std::slice::from_ref(path), input)?;
+            write_recordings(output, &recordings, &RowFilter::default(), *max_rows)
+        }
+        RrdCommands::Export {
+            path,
+            output,
+            format,
+            group_by,
+            input,
+        } => export(path, output, *format, *group_by, input),
+        RrdCommands::Import {
+            path,
+            entity_path,
+            entity_path_column,
+            time,
+            sequence,
+            scalar,
+            point3d,
+            label,
+            output,
+        } => {
+            let entity_path = match (entity_path, entity_path_column) {
+                (_, Some(column)) => EntityPathSource::Column(column.clone()),
+                (Some(template), None) => EntityPathSource::Template(template.clone()),
+                (None, None) => EntityPathSource::Template(file_stem(path)?),
+            };
+            let mut config = ImportConfig::new(entity_path);
+            config.timelines = time
+                .iter()
+                .map(|column| (column, TimeType::Time))
+                .chain(sequence.iter().map(|column| (column, TimeType::Sequence)))
+                .map(|(column, time_type)| TimelineColumn {
+                    column: column.clone(),
+                    time_type,
+                })
+                .collect();
+            config.components = scalar
+                .iter()
+                .map(|column| ComponentColumns::Scalar(column.clone()))
+                .collect();
+            if let Some(point3d) = point3d {
+                let Some((x, y, z)) = point3d.split(',').map(str::trim).collect_tuple() else {
+                    anyhow::bail!("Expected --point3d X,Y,Z, got {point3d:?}");
+                };
+                config.components.push(ComponentColumns::Point3D {
+                    x: x.to_owned(),
+                    y: y.to_owned(),
+                    z: z.to_owned(),
+                });
+            }
+            if let Some(label) = label {
+                config
+                    .components
+                    .push(ComponentColumns::Label(label.clone()));
+            }
+            import(path, &config, output)
+        }
+        RrdCommands::Migrate { path, output } => migrate(path, output),
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// A recording, as loaded from one or more `.rrd` files.
+struct Recording {
+    id: RecordingId,
+    log_db: LogDb,
+}
+
+/// Loads all the recordings in the given files, in order of first appearance.
+fn load_recordings(paths: &[PathBuf], input: &InputArgs) -> anyhow::Result<Vec<Recording>> {
+    let mut recordings: Vec<Recording> = vec![];
+
+    for path in paths {
+        re_log::info!("Loading {path:?}…");
+
+        let file = std::fs::File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+        let mut decoder =
+            Decoder::new(file).with_context(|| format!("Failed to decode {path:?}"))?;
+        if input.salvage {
+            decoder = decoder.salvaging();
+        }
+
+        for msg in &mut decoder {
+            let msg = msg.with_context(|| {
+                format!("Failed to decode {path:?}. Use --salvage to load what can be recovered")
+            })?;
+            let Some(&id) = msg.recording_id() else {
+                continue; // Goodbye
+            };
+
+            let idx = if let Some(idx) = recordings.iter().position(|r| r.id == id) {
+                idx
+            } else {
+                recordings.push(Recording {
+                    id,
+                    log_db: Default::default(),
+                });
+                recordings.len() - 1
+            };
+            recordings[idx]
+                .log_db
+                .add(&msg)
+                .with_context(|| format!("Failed to load {path:?}"))?;
+        }
+
+        let report = decoder.report();
+        if !report.is_intact() {
+            re_log::warn!("{path:?} is damaged: {report}");
+        }
+    }
+
+    Ok(recordings)
+}
+
+// ----------------------------------------------------------------------------
+
+/// Which rows to keep.
+#[derive(Default)]
+struct RowFilter {
+    /// Keep all entities if empty.
+    entities: Vec<EntityPathPattern>,
+    time_range: Option<(Timeline, TimeRange)>,
+}
+
+impl RowFilter {
+    fn keeps(&self, entity_path: &EntityPath, timepoint: &re_log_types::TimePoint) -> bool {
+        let entity_ok = self.entities.is_empty()
+            || self
+                .entities
+                .iter()
+                .any(|filter| filter.matches(entity_path));
+
+        let time_ok = match self.time_range {
+            None => true,
+            Some((timeline, time_range)) => {
+                timepoint.is_timeless()
+                    || timepoint
+                        .get(&timeline)
+                        .map_or(false, |time| time_range.contains(*time))
+            }
+        };
+
+        entity_ok && time_ok
+    }
+}
+
+/// Parses an `--entity` filter, e.g. `world/points`, `/world/**` or `robots/*/lidar`.
+fn parse_entity_filter(filter: &str) -> anyhow::Result<EntityPathPattern> {
+    filter
+        .parse()
+        .with_context(|| format!("Bad entity path pattern {filter:?}"))
+}
+
+/// Parses e.g. `frame_nr=10..20`, looking up the timeline in the loaded recordings.
+fn parse_time_range(
+    time_range: &str,
+    recordings: &[Recording],
+) -> anyhow::Result<(Timeline, TimeRange)> {
+    let (name, range) = time_range
+        .split_once('=')
+        .with_context(|| format!("Expected TIMELINE=MIN..MAX, got {time_range:?}"))?;
+    let (min, max) = range
+        .split_once("..")
+        .with_context(|| format!("Expected TIMELINE=MIN..MAX, got {time_range:?}"))?;
+
+    let parse_time = |time: &str, default: TimeInt| -> anyhow::Result<TimeInt> {
+        if time.is_empty() {
+            Ok(default)
+        } else {
+            let time: i64 = time
+                .trim()
+                .parse()
+                .with_context(|| format!("Bad time {time:?}"))?;
+            Ok(time.into())
+        }
+    };
+    let min = parse_time(min, TimeInt::MIN)?;
+    let max = parse_time(max, TimeInt::MAX)?;
+
+    let timeline = recordings
+        .iter()
+        .flat_map(|recording| recording.log_db.timelines())
+        .find(|timeline| timeline.name().as_str() == name)
+        .copied()
+        .with_context(|| format!("No timeline named {name:?}"))?;
+
+    Ok((timeline, TimeRange::new(min, max)))
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_encoder(
+    path: &Path,
+    compression: Compression,
+) -> anyhow::Result<Encoder<std::io::BufWriter<std::fs::File>>> {
+    let options = match compression {
+        Compression::Off => EncodingOptions::UNCOMPRESSED,
+        Compression::Lz4 => EncodingOptions::LZ4,
+        Compression::Zstd => EncodingOptions::ZSTD,
+    };
+
+    let file = std::fs::File::create(path).with_context(|| format!("Failed to create {path:?}"))?;
+    Ok(Encoder::new(options, std::io::BufWriter::new(file))?)
+}
+
+fn write_recordings(
+    output: &OutputArgs,
+    recordings: &[Recording],
+    filter: &RowFilter,
+    max_rows: usize,
+) -> anyhow::Result<()> {
+    let OutputArgs {
+        output: path,
+        compression,
+    } = output;
+
+    let mut encoder = create_encoder(path, *compression)?;
+
+    let mut num_rows = 0;
+    let mut num_tables = 0;
+
+    for Recording { id, log_db } in recordings {
+        if let Some(recording_msg) = log_db.recording_msg() {
+            encoder.append(&LogMsg::BeginRecordingMsg(recording_msg.clone()))?;
+        }
+
+        // The clears generated by a path operation share its row id, and are generated again
+        // when the operation is replayed: write the operation instead of its clears.
+        let ops = log_db
+            .iter_entity_op_msgs()
+            .filter(|msg| filter.keeps(msg.path_op.entity_path(), &msg.time_point))
+            .collect::<Vec<_>>();
+        let op_row_ids = ops
+            .iter()
+            .map(|msg| msg.row_id)
+            .collect::<std::collections::HashSet<_>>();
+
+        let rows = log_db
+            .entity_db
+            .data_store
+            .to_rows()
+            .into_iter()
+            .filter(|row| {
+                !op_row_ids.contains(&row.row_id())
+                    && filter.keeps(row.entity_path(), row.timepoint())
+            });
+
+        // Both rows and operations are sorted by row id, i.e. in log order: keep it that way.
+        let mut ops = ops.into_iter().peekable();
+        let mut table_rows = Vec::with_capacity(max_rows);
+        for row in rows {
+            while let Some(op) = ops.next_if(|op| op.row_id < row.row_id()) {
+                append_rows(
+                    &mut encoder,
+                    *id,
+                    &mut table_rows,
+                    &mut num_rows,
+                    &mut num_tables,
+                )?;
+                encoder.append(&LogMsg::EntityPathOpMsg(*id, op.clone()))?;
+            }
+
+            table_rows.push(row);
+            if table_rows.len() >= max_rows {
+                append_rows(
+                    &mut encoder,
+                    *id,
+                    &mut table_rows,
+                    &mut num_rows,
+                    &mut num_tables,
+                )?;
+            }
+        }
+        append_rows(
+            &mut encoder,
+            *id,
+            &mut table_rows,
+            &mut num_rows,
+            &mut num_tables,
+        )?;
+        for op in ops {
+            encoder.append(&LogMsg::EntityPathOpMsg(*id, op.clone()))?;
+        }
+    }
+
+    encoder.finish()?;
+
+    re_log::info!(
+        "Wrote {} rows in {} tables to {path:?}",
+        re_format::format_number(num_rows),
+        re_format::format_number(num_tables),
+    );
+
+    Ok(())
+}
+
+/// Writes the rows as a single table, if there are any, and clears them.
+fn append_rows(
+    encoder: &mut Encoder<impl std::io::Write>,
+    recording_id: RecordingId,
+    rows: &mut Vec<DataRow>,
+    num_rows: &mut usize,
+    num_tables: &mut usize,
+) -> anyhow::Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let table = DataTable::from_rows(TableId::random(), rows.drain(..));
+    *num_rows += table.num_rows() as usize;
+    *num_tables += 1;
+    encoder.append(&LogMsg::ArrowMsg(recording_id, table.to_arrow_msg()?))?;
+
+    Ok(())
+}
+
+fn export(
+    path: &Path,
+    output: &Path,
+    format: ExportFormat,
+    group_by: ExportGroupBy,
+    input: &InputArgs,
+) -> anyhow::Result<()> {
+    let recordings = load_recordings(&[path.to_owned()], input)?;
+
+    let grouping = match group_by {
+        ExportGroupBy::Entity => ExportGrouping::EntityPath,
+        ExportGroupBy::Components => ExportGrouping::ComponentSet,
+    };
+    let extension = match format {
+        ExportFormat::Arrow => "arrow",
+        ExportFormat::Parquet => "parquet",
+    };
+
+    let mut num_files = 0;
+    for Recording { id, log_db } in &recordings {
+        // Keep the recordings apart if there are several:
+        let dir = if recordings.len() == 1 {
+            output.to_owned()
+        } else {
+            output.join(id.to_string())
+        };
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {dir:?}"))?;
+
+        let mut used_stems = std::collections::HashSet::new();
+        for table in log_db.entity_db.data_store.to_export_tables(grouping)? {
+            // Different entity paths can map to the same file name, e.g. `a/b` and `a.b`:
+            let mut stem = table.file_stem();
+            for i in 1.. {
+                if used_stems.insert(stem.clone()) {
+                    break;
+                }
+                stem = format!("{}_{i}", table.file_stem());
+            }
+
+            let file_path = dir.join(format!("{stem}.{extension}"));
+            let file = std::fs::File::create(&file_path)
+                .with_context(|| format!("Failed to create {file_path:?}"))?;
+            let file = std::io::BufWriter::new(file);
+            match format {
+                ExportFormat::Arrow => table.write_arrow_ipc(file)?,
+                ExportFormat::Parquet => table.write_parquet(file)?,
+            }
+
+            re_log::debug!(
+                "Wrote {} rows of {:?} to {file_path:?}",
+                re_format::format_number(table.num_rows()),
+                table.name
+            );
+            num_files += 1;
+        }
+    }
+
+    re_log::info!("Wrote {num_files} {extension} files to {output:?}");
+
+    Ok(())
+}
+
+/// Converts a CSV, Parquet or Arrow IPC table into a new recording, written as an `.rrd` file.
+fn import(path: &Path, config: &ImportConfig, output: &OutputArgs) -> anyhow::Result<()> {
+    let format = path
+        .to_str()
+        .and_then(ImportFormat::from_file_name)
+        .with_context(|| format!("Expected a .csv, .parquet or .arrow file, got {path:?}"))?;
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let tables = re_arrow_store::import_file(format, std::io::BufReader::new(file), config)
+        .with_context(|| format!("Failed to import {path:?}"))?;
+
+    let mut encoder = create_encoder(&output.output, output.compression)?;
+    for msg in re_arrow_store::import_to_log_msgs(file_stem(path)?.into(), &tables)? {
+        encoder.append(&msg)?;
+    }
+    encoder.finish()?;
+
+    re_log::info!(
+        "Imported {} rows from {path:?}, and wrote them to {:?}",
+        re_format::format_number(tables.iter().map(|table| table.num_rows() as usize).sum()),
+        output.output
+    );
+
+    Ok(())
+}
+
+fn file_stem(path: &Path) -> anyhow::Result<String> {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .with_context(|| format!("No file name in {path:?}"))
+}
+
+/// Rewrites the file with the current version, migrating each message as it is decoded.
+fn migrate(path: &Path, output: &OutputArgs) -> anyhow::Result<()> {
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let decoder = Decoder::new(file).with_context(|| format!("Failed to decode {path:?}"))?;
+    let version = decoder.version();
+
+    MigrationRegistry::builtin()
+        .plan(version)
+        .with_context(|| format!("Can't migrate {path:?}"))?;
+
+    let mut encoder = create_encoder(&output.output, output.compression)?;
+    let mut num_messages = 0;
+    for msg in decoder {
+        let msg = msg.with_context(|| format!("Failed to decode {path:?}"))?;
+        encoder.append(&msg)?;
+        num_messages += 1;
+    }
+    encoder.finish()?;
+
+    re_log::info!(
+        "Migrated {} messages from Rerun {version} to Rerun {}, and wrote them to {:?}",
+        re_format::format_number(num_messages),
+        env!("CARGO_PKG_VERSION"),
+        output.output
+    );
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Default)]
+struct ComponentInfo {
+    num_rows: usize,
+    num_bytes: u64,
+}
+
+fn print_info(path: &Path, input: &InputArgs) -> anyhow::Result<()> {
+    let file_size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to open {path:?}"))?
+        .len();
+    let recordings = load_recordings(&[path.to_owned()], input)?;
+
+    println!(
+        "{}: {}, {} recording(s)",
+        path.display(),
+        re_format::format_bytes(file_size as _),
+        recordings.len()
+    );
+
+    for Recording { id, log_db } in &recordings {
+        println!();
+        println!("Recording {id}");
+        if let Some(info) = log_db.recording_info() {
+            println!("  application:  {}", info.application_id);
+            println!("  started:      {}", info.started.format());
+            println!("  source:       {}", info.recording_source);
+        }
+
+        let rows = log_db.entity_db.data_store.to_rows();
+
+        let mut entities: BTreeMap<&EntityPath, usize> = Default::default();
+        let mut components: BTreeMap<ComponentName, ComponentInfo> = Default::default();
+        let mut num_timeless_rows = 0;
+        for row in &rows {
+            *entities.entry(row.entity_path()).or_default() += 1;
+            if row.timepoint().is_timeless() {
+                num_timeless_rows += 1;
+            }
+            for cell in row.cells().iter() {
+                let component = components.entry(cell.component_name()).or_default();
+                component.num_rows += 1;
+                component.num_bytes += cell.as_arrow_ref().heap_size_bytes();
+            }
+        }
+
+        println!(
+            "  rows:         {} ({} timeless)",
+            re_format::format_number(rows.len()),
+            re_format::format_number(num_timeless_rows)
+        );
+
+        println!("  timelines:");
+        for (timeline, times) in log_db.times_per_timeline().iter() {
+            let (Some(min), Some(max)) = (times.first(), times.last()) else {
+                continue;
+            };
+            println!(
+                "    {:<24} {}",
+                timeline.name().as_str(),
+                timeline.typ().format_range(TimeRange::new(*min, *max))
+            );
+        }
+
+        println!("  entities:");
+        for (entity_path, num_rows) in entities {
+            println!(
+                "    {:<48} {:>10} rows",
+                entity_path.to_string(),
+                re_format::format_number(num_rows)
+            );
+        }
+
+        println!("  components:");
+        for (
+            name,
+            ComponentInfo {
+                num_rows,
+                num_bytes,
+            },
+        ) in components
+        {
+            println!(
+                "    {:<48} {:>10} rows {:>12}",
+                name.full_name(),
+                re_format::format_number(num_rows),
+                re_format::format_bytes(num_bytes as _)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_entity_filter() {
+    let matches = |filter: &str, path: &str| {
+        parse_entity_filter(filter)
+            .unwrap()
+            .matches(&EntityPath::from(path))
+    };
+
+    assert!(matches("world/points", "world/points"));
+    assert!(matches("/world/points", "world/points"));
+    assert!(!matches("world", "world/points"));
+    assert!(matches("/world/**", "world"));
+    assert!(matches("/world/**", "world/points"));
+    assert!(!matches("/world/**", "camera/image"));
+    assert!(matches("/**", "camera/image"));
+    assert!(matches("robots/*/lidar", "robots/r2d2/lidar"));
+    assert!(!matches("robots/*/lidar", "robots/r2d2/arm/lidar"));
+    assert!(parse_entity_filter("world//points").is_err());
+}
@@ -13,6 +13,8 @@ use re_ws_comms::RerunServerPort;
 #[cfg(feature = "web_viewer")]
 use crate::web_viewer::host_web_viewer;
 
+use crate::rrd::RrdCommands;
+
 // Note the extra blank lines between the point-lists below: it is required by `clap`.
 
 /// The Rerun Viewer and Server
@@ -41,6 +43,18 @@ use crate::web_viewer::host_web_viewer;
 #[clap(author, about)]
 struct Args {
     // Note: arguments are sorted lexicographically for nicer `--help` message:
+    /// Only accept the SDKs and web viewers that authenticate with this token.
+    ///
+    /// SDKs pass it via `RecordingStreamBuilder::auth_token`, web viewers as the `token` query
+    /// parameter of the websocket url (which the hosted web viewer does automatically), or as
+    /// an `Authorization: Bearer` header.
+    ///
+    /// The token is sent in the clear unless `--tls-cert` is set. With `--web-viewer`, it is also
+    /// part of the link opened in the browser, and thus ends up in its history: use a token
+    /// dedicated to this session.
+    #[clap(long)]
+    auth_token: Option<String>,
+
     #[command(subcommand)]
     commands: Option<Commands>,
 
@@ -54,9 +68,18 @@ struct Args {
     #[clap(long)]
     drop_at_latency: Option<String>,
 
+    /// Keep the data of an entity, and of all its descendants, under the given size.
+    ///
+    /// When over quota, the Rerun Viewer purges the oldest data of that entity first.
+    /// Can be repeated.
+    ///
+    /// Example: `--entity-quota world/camera=500MB`
+    #[clap(long, value_name = "ENTITY_PATH=SIZE")]
+    entity_quota: Vec<String>,
+
     /// An upper limit on how much memory the Rerun Viewer should use.
     ///
-    /// When this limit is used, Rerun will purge the oldest data.
+    /// When this limit is used, Rerun will purge the oldest data, unless `--spill-dir` is set.
     ///
     /// Example: `16GB`
     #[clap(long)]
@@ -80,10 +103,40 @@ struct Args {
     #[clap(long)]
     profile: bool,
 
+    /// Never purge the data of this entity, nor of its descendants, e.g. when running low on
+    /// memory. Can be repeated.
+    ///
+    /// Example: `--protect-entity world/map`
+    #[clap(long, value_name = "ENTITY_PATH")]
+    protect_entity: Vec<String>,
+
+    /// Only keep the most recent part of a timeline, e.g. to watch a live stream.
+    ///
+    /// The window is a duration with a unit (`ms`, `s`, `m` or `h`) for temporal timelines, and
+    /// a plain number for sequence timelines. Can be repeated.
+    ///
+    /// Example: `--retain-time log_time=10s --retain-time frame_nr=1000`
+    #[clap(long, value_name = "TIMELINE=WINDOW")]
+    retain_time: Vec<String>,
+
+    /// When loading an `.rrd` file, skip over its corrupt parts instead of stopping at the
+    /// first one, e.g. to recover what a crashed logger managed to write.
+    ///
+    /// What was skipped is reported as a warning.
+    #[clap(long)]
+    salvage: bool,
+
     /// Stream incoming log events to an .rrd file at the given path.
     #[clap(long)]
     save: Option<String>,
 
+    /// Spill the oldest data to this directory rather than purging it when reaching
+    /// `--memory-limit`, so that recordings larger than RAM can still be browsed in full.
+    ///
+    /// Example: `--spill-dir /tmp/rerun`
+    #[clap(long, value_name = "DIR")]
+    spill_dir: Option<std::path::PathBuf>,
+
     /// Exit with a non-zero exit code if any warning or error is logged. Useful for tests.
     #[clap(long)]
     strict: bool,
@@ -96,6 +149,19 @@ struct Args {
     #[clap(long)]
     test_receive: bool,
 
+    /// Encrypt the connections of the SDK and websocket servers with TLS, using the certificate
+    /// chain in this PEM file.
+    ///
+    /// Requires `--tls-key`.
+    #[cfg(feature = "tls")]
+    #[clap(long, value_name = "PEM", requires = "tls_key")]
+    tls_cert: Option<std::path::PathBuf>,
+
+    /// The private key (PEM) that goes with `--tls-cert`.
+    #[cfg(feature = "tls")]
+    #[clap(long, value_name = "PEM", requires = "tls_cert")]
+    tls_key: Option<std::path::PathBuf>,
+
     /// Either a path to a `.rrd` file to load, an http url to an `.rrd` file,
     /// or a websocket url to a Rerun Server from which to read data
     ///
@@ -117,6 +183,12 @@ struct Args {
     #[clap(long, default_value_t = Default::default())]
     web_viewer_port: WebViewerServerPort,
 
+    /// What IP address do we listen to for incoming websocket connections from the viewer,
+    /// e.g. `127.0.0.1` to only accept connections from this machine.
+    #[cfg(feature = "web_viewer")]
+    #[clap(long, default_value = "0.0.0.0")]
+    ws_server_bind: std::net::IpAddr,
+
     /// What port do we listen to for incoming websocket connections from the viewer
     /// A port of 0 will pick a random port.
     #[cfg(feature = "web_viewer")]
@@ -130,6 +202,10 @@ enum Commands {
     #[cfg(all(feature = "analytics"))]
     #[command(subcommand)]
     Analytics(AnalyticsCommands),
+
+    /// Inspect, merge, filter and compact `.rrd` files.
+    #[command(subcommand)]
+    Rrd(RrdCommands),
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -235,8 +311,7 @@ where
         match commands {
             #[cfg(all(feature = "analytics"))]
             Commands::Analytics(analytics) => run_analytics(analytics).map_err(Into::into),
-            #[cfg(not(all(feature = "analytics")))]
-            _ => Ok(()),
+            Commands::Rrd(rrd) => crate::rrd::run_rrd_commands(rrd),
         }
     } else {
         run_impl(build_info, call_source, args).await
@@ -293,6 +368,29 @@ async fn run_impl(
     #[cfg(feature = "native_viewer")]
     let profiler = profiler(&args);
 
+    #[cfg(feature = "tls")]
+    let tls = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert_path), Some(key_path)) => {
+            Some(re_sdk_comms::load_server_config(cert_path, key_path)?)
+        }
+        _ => None,
+    };
+
+    #[cfg(feature = "tls")]
+    let encrypted = tls.is_some();
+    #[cfg(not(feature = "tls"))]
+    let encrypted = false;
+    if args.auth_token.is_some() && !encrypted {
+        re_log::warn!(
+            "--auth-token is set without --tls-cert: the token is sent unencrypted, so anyone \
+            on the network can read it and use it"
+        );
+    }
+
+    // The SDKs connected to our server, if any.
+    #[cfg(feature = "server")]
+    let sdk_peers = re_sdk_comms::ConnectedPeers::default();
+
     #[cfg(feature = "native_viewer")]
     let startup_options = re_viewer::StartupOptions {
         memory_limit: args.memory_limit.as_ref().map_or(Default::default(), |l| {
@@ -300,6 +398,12 @@ async fn run_impl(
                 .unwrap_or_else(|err| panic!("Bad --memory-limit: {err}"))
         }),
         persist_state: args.persist_state,
+        retention: parse_retention_options(&args)?,
+        spill_dir: args.spill_dir.clone(),
+        #[cfg(feature = "server")]
+        sdk_peers: Some(sdk_peers.clone()),
+        #[cfg(not(feature = "server"))]
+        sdk_peers: None,
     };
 
     let (shutdown_rx, shutdown_bool) = setup_ctrl_c_handler();
@@ -312,7 +416,7 @@ async fn run_impl(
             }
             ArgumentCategory::RrdFilePath(path) => {
                 re_log::info!("Loading {path:?}…");
-                load_file_to_channel(&path).with_context(|| format!("{path:?}"))?
+                load_file_to_channel(&path, args.salvage).with_context(|| format!("{path:?}"))?
             }
             ArgumentCategory::WebSocketAddr(rerun_server_ws_url) => {
                 // We are connecting to a server at a websocket address:
@@ -361,6 +465,13 @@ async fn run_impl(
 
                 // `rerun.spawn()` doesn't need to log that a connection has been made
                 quiet: call_source.is_python(),
+
+                peers: sdk_peers,
+
+                auth_token: args.auth_token.clone(),
+
+                #[cfg(feature = "tls")]
+                tls: tls.clone(),
             };
             re_sdk_comms::serve(args.port, server_options, shutdown_rx.resubscribe()).await?
         }
@@ -394,8 +505,22 @@ async fn run_impl(
             let shutdown_web_viewer = shutdown_rx.resubscribe();
 
             // This is the server which the web viewer will talk to:
-            let ws_server = re_ws_comms::RerunServer::new(args.ws_server_port).await?;
-            let ws_server_url = ws_server.server_url();
+            let ws_server_options = re_ws_comms::RerunServerOptions {
+                bind_ip: args.ws_server_bind,
+                auth_token: args.auth_token.clone(),
+                #[cfg(feature = "tls")]
+                tls,
+            };
+            let ws_server =
+                re_ws_comms::RerunServer::new_with_options(args.ws_server_port, ws_server_options)
+                    .await?;
+            let mut ws_server_url = ws_server.server_url();
+            if let Some(auth_token) = &args.auth_token {
+                let query = form_urlencoded::Serializer::new(String::new())
+                    .append_pair("token", auth_token)
+                    .finish();
+                ws_server_url = format!("{ws_server_url}?{query}");
+            }
             let ws_server_handle = tokio::spawn(ws_server.listen(rx, shutdown_ws_server));
 
             // This is the server that serves the Wasm+HTML:
@@ -547,10 +672,13 @@ fn native_viewer_connect_to_ws_url(
     Ok(())
 }
 
-fn load_file_to_channel(path: &std::path::Path) -> anyhow::Result<Receiver<LogMsg>> {
+fn load_file_to_channel(path: &std::path::Path, salvage: bool) -> anyhow::Result<Receiver<LogMsg>> {
     use anyhow::Context as _;
     let file = std::fs::File::open(path).context("Failed to open file")?;
-    let decoder = re_log_encoding::decoder::Decoder::new(file)?;
+    let mut decoder = re_log_encoding::decoder::Decoder::new(file)?;
+    if salvage {
+        decoder = decoder.salvaging();
+    }
 
     let (tx, rx) = re_smart_channel::smart_channel(re_smart_channel::Source::File {
         path: path.to_owned(),
@@ -560,7 +688,7 @@ fn load_file_to_channel(path: &std::path::Path) -> anyhow::Result<Receiver<LogMs
     std::thread::Builder::new()
         .name("rrd_file_reader".into())
         .spawn(move || {
-            for msg in decoder {
+            for msg in &mut decoder {
                 match msg {
                     Ok(msg) => {
                         tx.send(msg).ok();
@@ -570,6 +698,11 @@ fn load_file_to_channel(path: &std::path::Path) -> anyhow::Result<Receiver<LogMs
                     }
                 }
             }
+
+            let report = decoder.report();
+            if !report.is_intact() {
+                re_log::warn!("{path:?} is damaged: {report}");
+            }
         })
         .expect("Failed to spawn thread");
 
@@ -592,7 +725,7 @@ fn stream_to_rrd(
 
     let file =
         std::fs::File::create(path).map_err(|err| FileSinkError::CreateFile(path.clone(), err))?;
-    let mut encoder = re_log_encoding::encoder::Encoder::new(file)?;
+    let mut encoder = re_log_encoding::encoder::Encoder::new(Default::default(), file)?;
 
     while !shutdown_bool.load(std::sync::atomic::Ordering::Relaxed) {
         // We wake up and poll shutdown_bool every now and then.
@@ -624,6 +757,73 @@ fn parse_max_latency(max_latency: Option<&String>) -> f32 {
     })
 }
 
+#[cfg(feature = "native_viewer")]
+fn parse_retention_options(args: &Args) -> anyhow::Result<re_viewer::RetentionOptions> {
+    use re_log_types::{EntityPath, EntityPathPattern, EntityPathPatternPart};
+
+    fn split_once<'a>(arg: &'a str, flag: &str) -> anyhow::Result<(&'a str, &'a str)> {
+        arg.split_once('=')
+            .with_context(|| format!("Bad --{flag} {arg:?}: expected KEY=VALUE"))
+    }
+
+    /// Quotas and protection always cover whole subtrees, so accept `world/points` as well as
+    /// `world/points/**`, but no other wildcards.
+    fn parse_entity_path(path: &str) -> anyhow::Result<EntityPath> {
+        let pattern = path
+            .parse::<EntityPathPattern>()
+            .with_context(|| format!("Bad entity path {path:?}"))?;
+        let parts = match pattern.parts() {
+            [parts @ .., EntityPathPatternPart::AnyDepth] => parts,
+            parts => parts,
+        };
+        EntityPathPattern::new(parts.to_vec())
+            .as_exact()
+            .with_context(|| format!("Bad entity path {path:?}: only a trailing `/**` is allowed"))
+    }
+
+    let time_windows = args
+        .retain_time
+        .iter()
+        .map(|arg| {
+            let (timeline, window) = split_once(arg, "retain-time")?;
+            // Whether this is a duration or a number of steps must match the type of the
+            // timeline, which the viewer checks once the data arrives.
+            let window = if let Ok(window) = window.parse::<i64>() {
+                re_viewer::TimeWindow::Sequence(window)
+            } else {
+                let secs = re_format::parse_duration(window)
+                    .map_err(|err| anyhow::anyhow!("Bad --retain-time {arg:?}: {err}"))?;
+                re_viewer::TimeWindow::Duration(re_log_types::Duration::from_secs(secs).as_nanos())
+            };
+            Ok((timeline.into(), window))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let entity_quotas = args
+        .entity_quota
+        .iter()
+        .map(|arg| {
+            let (path, size) = split_once(arg, "entity-quota")?;
+            let size = re_format::parse_bytes(size)
+                .and_then(|size| u64::try_from(size).ok())
+                .with_context(|| format!("Bad --entity-quota {arg:?}: expected e.g. 500MB"))?;
+            Ok((parse_entity_path(path)?, size))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let protected_paths = args
+        .protect_entity
+        .iter()
+        .map(|path| parse_entity_path(path))
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(re_viewer::RetentionOptions {
+        time_windows,
+        entity_quotas,
+        protected_paths,
+    })
+}
+
 pub fn setup_ctrl_c_handler() -> (tokio::sync::broadcast::Receiver<()>, Arc<AtomicBool>) {
     let (sender, receiver) = tokio::sync::broadcast::channel(1);
     let shutdown_return = Arc::new(AtomicBool::new(false));
@@ -62,6 +62,7 @@ pub async fn host_web_viewer(
     let port = web_server.port();
     let web_server_handle = web_server.serve(shutdown_rx);
 
+    let source_url: String = form_urlencoded::byte_serialize(source_url.as_bytes()).collect();
     let viewer_url = format!("http://127.0.0.1:{port}?url={source_url}");
 
     re_log::info!("Web server is running - view it at {viewer_url}");